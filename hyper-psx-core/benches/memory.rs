@@ -0,0 +1,49 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use hyper_psx_core::Psx;
+
+use std::time::Instant;
+
+/// The frames emulated for each measurement
+const FRAMES: u32 = 120;
+
+/// A BIOS looping over a `lw`/`sw` pair on RAM
+fn bios() -> Vec<u8> {
+    let program = [
+        0x3c088001, // lui $t0, 0x8001
+        0x8d090000, // lw $t1, 0($t0)
+        0x25290001, // addiu $t1, $t1, 1
+        0xad090004, // sw $t1, 4($t0)
+        0x0bf00001, // j 0xbfc00004
+        0x00000000, // nop
+    ];
+
+    let mut bios = vec![0; 512 * 1024];
+    for (index, word) in program.into_iter().enumerate() {
+        bios[index * 4..index * 4 + 4].copy_from_slice(&u32::to_le_bytes(word));
+    }
+
+    bios
+}
+
+fn main() {
+    let mut psx = Psx::from_bios_bytes(bios()).unwrap();
+
+    // Warm up the caches before measuring
+    psx.run_headless(10, |_| {});
+
+    let start = Instant::now();
+    psx.run_headless(FRAMES, |_| {});
+    let elapsed = start.elapsed();
+
+    println!(
+        "lw/sw loop: {} frames in {:.3?} ({:.1} frames/s)",
+        FRAMES,
+        elapsed,
+        FRAMES as f64 / elapsed.as_secs_f64()
+    );
+}
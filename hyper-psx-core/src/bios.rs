@@ -31,16 +31,29 @@ pub enum CreationError {
     /// If the BIOS file failed to be read from
     #[error("failed to read bios: '{1}'")]
     ReadingFailure(#[source] io::Error, String),
+
+    /// If the BIOS data doesn't have the size of the BIOS region
+    #[error("invalid bios size: {actual} bytes, expected {expected} bytes")]
+    InvalidSize {
+        /// The size of the BIOS region
+        expected: usize,
+
+        /// The size of the given BIOS data
+        actual: usize,
+    },
 }
 
 /// The BIOS component
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub(crate) struct Bios {
     /// The data vector containing the bios
     data: Vec<u8>,
 }
 
 impl Bios {
+    /// The size of the BIOS region (512KB)
+    const SIZE: usize = 512 * 1024;
+
     /// Creates a BIOS Component
     ///
     /// # Arguments:
@@ -53,14 +66,31 @@ impl Bios {
         }
 
         let buffer = Self::read_file(path)?;
+        let bios = Self::from_bytes(buffer)?;
 
         log::info!(
             "Loaded BIOS from '{}' ({} bytes)",
             path_display,
-            buffer.len()
+            bios.data.len()
         );
 
-        Ok(Self { data: buffer })
+        Ok(bios)
+    }
+
+    /// Creates a BIOS Component from the BIOS data
+    ///
+    /// # Arguments:
+    ///
+    /// * `data`: The BIOS data
+    pub(crate) fn from_bytes(data: Vec<u8>) -> Result<Self, CreationError> {
+        if data.len() != Self::SIZE {
+            return Err(CreationError::InvalidSize {
+                expected: Self::SIZE,
+                actual: data.len(),
+            });
+        }
+
+        Ok(Self { data })
     }
 
     /// Reads a file into a vector of bytes
@@ -70,20 +100,47 @@ impl Bios {
     /// * `path`: The path of the BIOS
     fn read_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, CreationError> {
         let path_display = path.as_ref().display().to_string();
-        let mut file = File::open(path)
+        let file = File::open(path)
             .map_err(|error| CreationError::OpenFailure(error, path_display.clone()))?;
         let metadata = file
             .metadata()
             .map_err(|error| CreationError::FetchingFailure(error, path_display.clone()))?;
 
-        let mut buffer = vec![0x00; metadata.len() as usize];
-        file.read(&mut buffer)
+        Self::read_data(file, metadata.len() as usize, path_display)
+    }
+
+    /// Reads the given amount of bytes, failing if the reader ends before
+    ///
+    /// # Arguments:
+    ///
+    /// * `reader`: The reader of the BIOS data
+    /// * `length`: The amount of bytes to read
+    /// * `path_display`: The path of the BIOS for the error
+    fn read_data<R: Read>(
+        mut reader: R,
+        length: usize,
+        path_display: String,
+    ) -> Result<Vec<u8>, CreationError> {
+        // A single read may return early, so a partially read BIOS fails here
+        let mut buffer = vec![0x00; length];
+        reader
+            .read_exact(&mut buffer)
             .map_err(|error| CreationError::ReadingFailure(error, path_display))?;
 
         Ok(buffer)
     }
 }
 
+#[cfg(test)]
+impl Bios {
+    /// Creates a zeroed BIOS Component without a file
+    pub(crate) fn zeroed() -> Self {
+        Self {
+            data: vec![0x00; 512 * 1024],
+        }
+    }
+}
+
 impl Memory for Bios {
     fn write_u8(&mut self, offset: u32, _value: u8) {
         debug_assert!((offset as usize) < self.data.len());
@@ -96,3 +153,66 @@ impl Memory for Bios {
         self.data[offset as usize]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undersized_bios_is_rejected() {
+        let error = Bios::from_bytes(vec![0x00; 256 * 1024]).unwrap_err();
+        assert!(matches!(
+            error,
+            CreationError::InvalidSize {
+                expected: 0x80000,
+                actual: 0x40000,
+            }
+        ));
+    }
+
+    #[test]
+    fn oversized_bios_is_rejected() {
+        let error = Bios::from_bytes(vec![0x00; 1024 * 1024]).unwrap_err();
+        assert!(matches!(
+            error,
+            CreationError::InvalidSize {
+                expected: 0x80000,
+                actual: 0x100000,
+            }
+        ));
+    }
+
+    #[test]
+    fn bios_of_the_region_size_is_accepted() {
+        assert!(Bios::from_bytes(vec![0x00; 512 * 1024]).is_ok());
+    }
+
+    /// A reader returning at most 1 byte per read
+    struct ByteReader<'a>(&'a [u8]);
+
+    impl Read for ByteReader<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            let length = buffer.len().min(self.0.len()).min(1);
+            buffer[..length].copy_from_slice(&self.0[..length]);
+            self.0 = &self.0[length..];
+            Ok(length)
+        }
+    }
+
+    #[test]
+    fn short_reads_are_continued() {
+        let data = (0..64).collect::<Vec<u8>>();
+        let buffer = Bios::read_data(ByteReader(&data), 64, "bios".to_owned()).unwrap();
+        assert_eq!(buffer, data);
+    }
+
+    #[test]
+    fn truncated_data_is_a_reading_failure() {
+        let data = [0x00; 32];
+        let error = Bios::read_data(ByteReader(&data), 64, "bios".to_owned()).unwrap_err();
+        assert!(matches!(
+            error,
+            CreationError::ReadingFailure(error, _) if error.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+}
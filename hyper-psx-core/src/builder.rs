@@ -0,0 +1,171 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::{
+    bios::Bios, bus::ram::RamSize, region::Region, renderer::software_renderer::SoftwareRenderer,
+    CreationError, Psx,
+};
+
+use std::{
+    fmt::{self, Debug, Formatter},
+    path::{Path, PathBuf},
+};
+
+/// The function receiving the width, the height and the RGBA pixels of a frame
+type FrameFn = Box<dyn FnMut(u32, u32, &[u8])>;
+
+/// The callback receiving each presented frame
+pub(crate) struct FrameCallback(FrameFn);
+
+impl FrameCallback {
+    /// Hands a presented frame to the callback
+    ///
+    /// # Arguments:
+    ///
+    /// * `width`: The width of the frame
+    /// * `height`: The height of the frame
+    /// * `pixels`: The RGBA pixels of the frame
+    pub(crate) fn call(&mut self, width: u32, height: u32, pixels: &[u8]) {
+        (self.0)(width, height, pixels);
+    }
+}
+
+impl Debug for FrameCallback {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("FrameCallback").finish_non_exhaustive()
+    }
+}
+
+/// Where the builder loads the BIOS from
+#[derive(Debug)]
+enum BiosSource {
+    /// A BIOS file
+    Path(PathBuf),
+
+    /// The BIOS data
+    Bytes(Vec<u8>),
+}
+
+/// The builder of a PSX Emulator which renders offscreen, for embedding it without a window
+#[derive(Debug, Default)]
+pub struct PsxBuilder {
+    /// The source of the BIOS, which is required
+    bios: Option<BiosSource>,
+
+    /// The optional path to a CUE sheet or BIN disc image
+    disc_path: Option<PathBuf>,
+
+    /// The optional path to a memory card file
+    memory_card_path: Option<PathBuf>,
+
+    /// The amount of installed RAM
+    ram_size: RamSize,
+
+    /// The region deciding the video standard
+    region: Region,
+
+    /// The callback receiving each presented frame
+    on_frame: Option<FrameCallback>,
+}
+
+impl PsxBuilder {
+    /// Loads the BIOS from a file
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path to the BIOS
+    pub fn bios_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.bios = Some(BiosSource::Path(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Uses the BIOS data instead of a file
+    ///
+    /// # Arguments:
+    ///
+    /// * `bytes`: The 512KB BIOS data
+    pub fn bios_bytes(mut self, bytes: Vec<u8>) -> Self {
+        self.bios = Some(BiosSource::Bytes(bytes));
+        self
+    }
+
+    /// Inserts a disc
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path to a CUE sheet or BIN disc image
+    pub fn disc_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.disc_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Inserts a memory card into slot 1
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path to the memory card file, created if missing
+    pub fn memory_card_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.memory_card_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Changes the amount of installed RAM
+    ///
+    /// # Arguments:
+    ///
+    /// * `ram_size`: The amount of installed RAM
+    pub fn ram_size(mut self, ram_size: RamSize) -> Self {
+        self.ram_size = ram_size;
+        self
+    }
+
+    /// Changes the region, which decides the video standard
+    ///
+    /// # Arguments:
+    ///
+    /// * `region`: The region of the console
+    pub fn region(mut self, region: Region) -> Self {
+        self.region = region;
+        self
+    }
+
+    /// Sets the callback receiving the width, the height and the RGBA pixels of the display area
+    /// after each emulated frame
+    ///
+    /// # Arguments:
+    ///
+    /// * `on_frame`: The frame callback
+    pub fn on_frame<F: FnMut(u32, u32, &[u8]) + 'static>(mut self, on_frame: F) -> Self {
+        self.on_frame = Some(FrameCallback(Box::new(on_frame)));
+        self
+    }
+
+    /// Creates the PSX Emulator
+    ///
+    /// # Errors
+    ///
+    /// This function will throw an error if no BIOS was given or if the BIOS, the disc or the
+    /// memory card failed to load
+    pub fn build(self) -> Result<Psx, CreationError> {
+        let bios = match self.bios {
+            Some(BiosSource::Path(path)) => Bios::new(path)?,
+            Some(BiosSource::Bytes(bytes)) => Bios::from_bytes(bytes)?,
+            None => return Err(CreationError::MissingBios),
+        };
+
+        let mut psx = Psx::create(
+            bios,
+            self.disc_path,
+            self.memory_card_path,
+            self.ram_size,
+            Box::new(SoftwareRenderer::new_headless()),
+            self.region,
+        )?;
+        psx.on_frame = self.on_frame;
+
+        Ok(psx)
+    }
+}
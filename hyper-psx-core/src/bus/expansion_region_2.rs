@@ -0,0 +1,59 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::bus::memory::Memory;
+
+use serde::{Deserialize, Serialize};
+
+/// The Expansion Region 2 component, holding the DUART and the POST register of dev boards
+///
+/// <https://psx-spx.consoledev.net/expansionportpio/#exp2-post-registers>
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ExpansionRegion2 {
+    /// The last boot status written to the POST register
+    post: u8,
+}
+
+impl ExpansionRegion2 {
+    /// The DUART status register of channel A
+    const DUART_STATUS_A: u32 = 0x21;
+
+    /// The DUART status register of channel B
+    const DUART_STATUS_B: u32 = 0x31;
+
+    /// The POST register showing the boot status on the 7-segment display
+    const POST: u32 = 0x41;
+
+    /// The DUART status with the transmitter ready and empty, so polling never stalls
+    const DUART_STATUS_IDLE: u8 = 0x0c;
+
+    /// Creates an Expansion Region 2 component
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the last boot status written to the POST register
+    pub(crate) fn post(&self) -> u8 {
+        self.post
+    }
+}
+
+impl Memory for ExpansionRegion2 {
+    fn write_u8(&mut self, offset: u32, value: u8) {
+        if offset == Self::POST {
+            log::debug!(target: "bus", "POST {:#04x}", value);
+            self.post = value;
+        }
+    }
+
+    fn read_u8(&self, offset: u32) -> u8 {
+        match offset {
+            Self::DUART_STATUS_A | Self::DUART_STATUS_B => Self::DUART_STATUS_IDLE,
+            Self::POST => self.post,
+            _ => 0x00,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::bus::memory::Memory;
+
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+
+/// The instruction cache (I-Cache)
+///
+/// Instructions are fetched uncached, so the cache is only reachable through
+/// the loads and stores issued while the cache is isolated
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct InstructionCache {
+    /// The data array containing the cache lines
+    #[serde(with = "BigArray")]
+    data: [u8; Self::SIZE],
+}
+
+impl InstructionCache {
+    const SIZE: usize = 4 * 1024;
+
+    /// Creates an instruction cache component
+    pub(crate) fn new() -> Self {
+        Self {
+            data: [0x00; Self::SIZE],
+        }
+    }
+}
+
+impl Memory for InstructionCache {
+    fn write_u8(&mut self, offset: u32, value: u8) {
+        // The cache is indexed by the lower address bits only
+        self.data[offset as usize & (Self::SIZE - 1)] = value;
+    }
+
+    fn read_u8(&self, offset: u32) -> u8 {
+        self.data[offset as usize & (Self::SIZE - 1)]
+    }
+}
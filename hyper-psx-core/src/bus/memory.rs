@@ -28,6 +28,56 @@ pub trait Memory {
     ///
     /// The function should panic if the given offset is out of range
     fn read_u8(&self, offset: u32) -> u8;
+
+    /// Allows writing little-endian halfwords at a relative offset
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative address offset
+    /// * `value`: The value to be written
+    fn write_u16(&mut self, offset: u32, value: u16) {
+        self.write_u8(offset, value as u8);
+        self.write_u8(offset + 1, (value >> 8) as u8);
+    }
+
+    /// Allows writing little-endian words at a relative offset
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative address offset
+    /// * `value`: The value to be written
+    fn write_u32(&mut self, offset: u32, value: u32) {
+        self.write_u8(offset, value as u8);
+        self.write_u8(offset + 1, (value >> 8) as u8);
+        self.write_u8(offset + 2, (value >> 16) as u8);
+        self.write_u8(offset + 3, (value >> 24) as u8);
+    }
+
+    /// Allows reading little-endian halfwords from a relative offset
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative address offset
+    fn read_u16(&self, offset: u32) -> u16 {
+        let byte_0 = self.read_u8(offset) as u16;
+        let byte_1 = self.read_u8(offset + 1) as u16;
+
+        (byte_1 << 8) | byte_0
+    }
+
+    /// Allows reading little-endian words from a relative offset
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative address offset
+    fn read_u32(&self, offset: u32) -> u32 {
+        let byte_0 = self.read_u8(offset) as u32;
+        let byte_1 = self.read_u8(offset + 1) as u32;
+        let byte_2 = self.read_u8(offset + 2) as u32;
+        let byte_3 = self.read_u8(offset + 3) as u32;
+
+        (byte_3 << 24) | (byte_2 << 16) | (byte_1 << 8) | byte_0
+    }
 }
 
 impl Memory for u16 {
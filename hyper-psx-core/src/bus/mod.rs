@@ -4,25 +4,77 @@
  * SPDX-License-Identifier: MIT
  */
 
+pub(crate) mod expansion_region_2;
+pub(crate) mod instruction_cache;
 pub(crate) mod memory;
 pub(crate) mod ram;
 pub(crate) mod range;
+pub(crate) mod scratchpad;
 
 use crate::{
     bios::Bios,
-    bus::{memory::Memory, ram::Ram, range::Range},
+    bus::{
+        expansion_region_2::ExpansionRegion2, instruction_cache::InstructionCache, memory::Memory,
+        ram::Ram, range::Range, scratchpad::Scratchpad,
+    },
+    cdrom::Cdrom,
     dma::Dma,
     gpu::Gpu,
+    interrupt::{Interrupt, InterruptController},
+    mdec::Mdec,
+    sio::{controller::Button, Sio},
+    spu::{audio::AudioSink, Spu},
 };
 
+use serde::{Deserialize, Serialize};
+use std::mem;
+use thiserror::Error;
+
+/// The error type of a bus access
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub(crate) enum BusError {
+    /// If the address isn't aligned to the access size
+    #[error("unaligned access at {0:#010x}")]
+    Unaligned(u32),
+
+    /// If no component is mapped at the address
+    #[error("unmapped access at {0:#010x}")]
+    Unmapped(u32),
+}
+
 /// The BUS component connecting everything
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct Bus {
     /// The BIOS component
+    #[serde(skip)]
     bios: Bios,
 
     /// The RAM component
     ram: Ram,
+
+    /// The Scratchpad component
+    scratchpad: Scratchpad,
+
+    /// The instruction cache component
+    instruction_cache: InstructionCache,
+
+    /// The interrupt controller component
+    interrupt_controller: InterruptController,
+
+    /// The CDROM component
+    cdrom: Cdrom,
+
+    /// The controller and memory card serial port
+    sio: Sio,
+
+    /// The MDEC component
+    mdec: Mdec,
+
+    /// The SPU component
+    spu: Spu,
+
+    /// The Expansion Region 2 component
+    expansion_region_2: ExpansionRegion2,
 }
 
 impl Bus {
@@ -34,8 +86,8 @@ impl Bus {
         0xffffffff, 0xffffffff, // KSEG2 (1GB)
     ];
 
-    /// RAM Range
-    const RAM_RANGE: Range = Range::new(0x00000000, 0x200000);
+    /// RAM Range, the installed RAM is mirrored across the 8MB window
+    const RAM_RANGE: Range = Range::new(0x00000000, 0x800000);
 
     /// Expansion Region 1 Range
     const EXPANSION_REGION_1_RANGE: Range = Range::new(0x1f000000, 0x800000);
@@ -46,8 +98,11 @@ impl Bus {
     /// Memory Control 1 Range
     const MEMORY_CONTROL_1_RANGE: Range = Range::new(0x1f801000, 0x24);
 
-    /// Peripheral I/O Ports Range
-    const PERIPHERAL_IO_PORTS_RANGE: Range = Range::new(0x1f801040, 0x20);
+    /// Controller and Memory Card I/O Ports Range
+    const SIO0_RANGE: Range = Range::new(0x1f801040, 0x10);
+
+    /// Serial I/O Ports Range
+    const SIO1_RANGE: Range = Range::new(0x1f801050, 0x10);
 
     /// Memory Control 2 Range
     const MEMORY_CONTROL_2_RANGE: Range = Range::new(0x1f801060, 0x4);
@@ -91,8 +146,185 @@ impl Bus {
     ///
     /// * `bios`: The BIOS component
     /// * `ram`: The RAM component
-    pub(crate) fn new(bios: Bios, ram: Ram) -> Self {
-        Self { bios, ram }
+    /// * `interrupt_controller`: The interrupt controller component
+    /// * `cdrom`: The CDROM component
+    /// * `sio`: The controller and memory card serial port
+    pub(crate) fn new(
+        bios: Bios,
+        ram: Ram,
+        interrupt_controller: InterruptController,
+        cdrom: Cdrom,
+        sio: Sio,
+    ) -> Self {
+        Self {
+            bios,
+            ram,
+            scratchpad: Scratchpad::new(),
+            instruction_cache: InstructionCache::new(),
+            interrupt_controller,
+            cdrom,
+            sio,
+            mdec: Mdec::new(),
+            spu: Spu::new(),
+            expansion_region_2: ExpansionRegion2::new(),
+        }
+    }
+
+    /// Moves the parts excluded from save states from the previous bus into this restored one
+    ///
+    /// # Arguments:
+    ///
+    /// * `previous`: The bus which gets replaced
+    pub(crate) fn reattach(&mut self, previous: &mut Self) {
+        mem::swap(&mut self.bios, &mut previous.bios);
+        self.cdrom.reattach(&mut previous.cdrom);
+        self.sio.reattach(&mut previous.sio);
+        self.spu.reattach(&mut previous.spu);
+    }
+
+    /// Restores the power-on state of the bus and its components, keeping the BIOS, the disc, the
+    /// memory card and the audio sink
+    ///
+    /// # Arguments:
+    ///
+    /// * `clear_ram`: If RAM gets cleared, otherwise its contents are kept
+    pub(crate) fn reset(&mut self, clear_ram: bool) {
+        let ram = Ram::with_size(self.ram.size());
+        let mut bus = Self::new(
+            Bios::default(),
+            ram,
+            InterruptController::new(),
+            Cdrom::new(None),
+            Sio::new(None),
+        );
+        bus.reattach(self);
+        if !clear_ram {
+            mem::swap(&mut bus.ram, &mut self.ram);
+        }
+
+        *self = bus;
+    }
+
+    /// Executes a number of cycles on the components attached to the bus
+    ///
+    /// # Arguments:
+    ///
+    /// * `cycles`: The amount of cycles which passed
+    pub(crate) fn step(&mut self, cycles: u32) {
+        if self.cdrom.step(cycles) {
+            self.interrupt_controller.request(Interrupt::Cdrom);
+        }
+
+        if self.sio.step(cycles) {
+            self.interrupt_controller.request(Interrupt::Controller);
+        }
+
+        self.spu.step(cycles);
+    }
+
+    /// Executes 1 DMA cycle with access to the components on the bus
+    ///
+    /// # Arguments:
+    ///
+    /// * `dma`: The DMA component
+    /// * `gpu`: The GPU component
+    pub(crate) fn step_dma(&mut self, dma: &mut Dma, gpu: &mut Gpu) {
+        if dma.step(
+            &mut self.ram,
+            gpu,
+            &mut self.cdrom,
+            &mut self.mdec,
+            &mut self.spu,
+        ) {
+            self.interrupt_controller.request(Interrupt::Dma);
+        }
+    }
+
+    /// Copies a block of data directly into RAM
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The absolute destination address
+    /// * `data`: The data to be copied
+    pub(crate) fn load_ram(&mut self, address: u32, data: &[u8]) {
+        let physical_adddress = Self::mask_address(address);
+        for (index, &value) in data.iter().enumerate() {
+            let offset = physical_adddress.wrapping_add(index as u32);
+            let Some(offset) = Self::RAM_RANGE.contains(offset) else {
+                log::warn!(target: "bus", "Load to ram stopped outside of ram at {:#010x}", offset);
+                return;
+            };
+
+            self.ram.write_u8(offset, value);
+        }
+    }
+
+    /// Reads an u8 from memory without any side effects
+    ///
+    /// Only RAM, the scratchpad and the BIOS are visible, everything else reads as none
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The absolute address
+    pub(crate) fn peek_u8(&self, address: u32) -> Option<u8> {
+        let physical_adddress = Self::mask_address(address);
+
+        if let Some(offset) = Self::RAM_RANGE.contains(physical_adddress) {
+            return Some(self.ram.read_u8(offset));
+        }
+
+        if let Some(offset) = Self::SCRATCHPAD_RANGE.contains(physical_adddress) {
+            return Some(self.scratchpad.read_u8(offset));
+        }
+
+        if let Some(offset) = Self::BIOS_RANGE.contains(physical_adddress) {
+            return Some(self.bios.read_u8(offset));
+        }
+
+        None
+    }
+
+    /// Reads an u32 from memory without any side effects
+    ///
+    /// Only RAM, the scratchpad and the BIOS are visible, everything else reads as none
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The absolute address
+    pub(crate) fn peek_u32(&self, address: u32) -> Option<u32> {
+        let mut value = 0;
+        for index in 0..4 {
+            let byte = self.peek_u8(address.wrapping_add(index))? as u32;
+            value |= byte << (index * 8);
+        }
+
+        Some(value)
+    }
+
+    /// Checks if a block of data fits into RAM, including its mirrors
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The absolute start address
+    /// * `length`: The length of the block in bytes
+    pub(crate) fn ram_contains(address: u32, length: usize) -> bool {
+        // The RAM range starts at 0, so only the end of the block has to be checked
+        let end = Self::mask_address(address) as u64 + length as u64;
+        end <= 0x800000
+    }
+
+    /// Masks a virtual address to a physical address and folds the RAM mirrors onto the
+    /// installed RAM, so each mirror of an address maps to the same one
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The virtual address
+    pub(crate) fn mirror_address(&self, address: u32) -> u32 {
+        let physical_adddress = Self::mask_address(address);
+        match Self::RAM_RANGE.contains(physical_adddress) {
+            Some(offset) => offset & (self.ram.size() as u32 - 1),
+            None => physical_adddress,
+        }
     }
 
     /// Masks a virtual address to a phyiscal address
@@ -100,27 +332,34 @@ impl Bus {
     /// # Arguments:
     ///
     /// * `address`: The virtual address
-    fn mask_address(address: u32) -> u32 {
+    pub(crate) fn mask_address(address: u32) -> u32 {
         let region_bits = address >> 29;
         let mask = Self::REGION_MASKS[region_bits as usize];
         address & mask
     }
 
-    /// Reads an u8 from a specific address
+    /// Writes an u8 to a specific address
     ///
     /// # Arguments:
     ///
     /// * `address`: The absolute address
+    /// * `value`: The value to be written
     ///
-    /// # Panics:
+    /// # Errors
     ///
-    /// This functions panics if the address is not valid
-    pub(crate) fn write_u8(&mut self, address: u32, value: u8, dma: &mut Dma, gpu: &mut Gpu) {
+    /// This function will throw an error if no component is mapped at the address
+    pub(crate) fn write_u8(
+        &mut self,
+        address: u32,
+        value: u8,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) -> Result<(), BusError> {
         let physical_adddress = Self::mask_address(address);
 
         if let Some(offset) = Self::RAM_RANGE.contains(physical_adddress) {
             self.ram.write_u8(offset, value);
-            return;
+            return Ok(());
         }
 
         if let Some(_offset) = Self::EXPANSION_REGION_1_RANGE.contains(physical_adddress) {
@@ -131,18 +370,12 @@ impl Bus {
                 offset
             );
             */
-            return;
+            return Ok(());
         }
 
-        if let Some(_offset) = Self::SCRATCHPAD_RANGE.contains(physical_adddress) {
-            /*
-            log::warn!(
-                "Unhandled write to Scratchpad: {:#010x} ({:#x})",
-                address,
-                offset
-            );
-            */
-            return;
+        if let Some(offset) = Self::SCRATCHPAD_RANGE.contains(physical_adddress) {
+            self.scratchpad.write_u8(offset, value);
+            return Ok(());
         }
 
         if let Some(_offset) = Self::MEMORY_CONTROL_1_RANGE.contains(physical_adddress) {
@@ -153,18 +386,23 @@ impl Bus {
                 offset
             );
             */
-            return;
+            return Ok(());
         }
 
-        if let Some(_offset) = Self::PERIPHERAL_IO_PORTS_RANGE.contains(physical_adddress) {
+        if let Some(offset) = Self::SIO0_RANGE.contains(physical_adddress) {
+            self.sio.write_u8(offset, value);
+            return Ok(());
+        }
+
+        if let Some(_offset) = Self::SIO1_RANGE.contains(physical_adddress) {
             /*
             log::warn!(
-                "Unhandled write to Peripheral I/O Ports: {:#010x} ({:#x})",
+                "Unhandled write to Serial I/O Ports: {:#010x} ({:#x})",
                 address,
                 offset
             );
             */
-            return;
+            return Ok(());
         }
 
         if let Some(_offset) = Self::MEMORY_CONTROL_2_RANGE.contains(physical_adddress) {
@@ -175,23 +413,17 @@ impl Bus {
                 offset
             );
             */
-            return;
+            return Ok(());
         }
 
-        if let Some(_offset) = Self::INTERRUPT_CONTROL_RANGE.contains(physical_adddress) {
-            /*
-            log::warn!(
-                "Unhandled write to Interrupt Control: {:#010x} ({:#x})",
-                address,
-                offset
-            );
-            */
-            return;
+        if let Some(offset) = Self::INTERRUPT_CONTROL_RANGE.contains(physical_adddress) {
+            self.interrupt_controller.write_u8(offset, value);
+            return Ok(());
         }
 
         if let Some(offset) = Self::DMA_REGISTERS_RANGE.contains(physical_adddress) {
             dma.write_u8(offset, value);
-            return;
+            return Ok(());
         }
 
         if let Some(_offset) = Self::TIMERS_RANGE.contains(physical_adddress) {
@@ -202,52 +434,32 @@ impl Bus {
                 offset
             );
             */
-            return;
+            return Ok(());
         }
 
-        if let Some(_offset) = Self::CDROM_REGISTERS_RANGE.contains(physical_adddress) {
-            /*
-            log::warn!(
-                "Unhandled write to CDROM Registers: {:#010x} ({:#x})",
-                address,
-                offset
-            );
-            */
-            return;
+        if let Some(offset) = Self::CDROM_REGISTERS_RANGE.contains(physical_adddress) {
+            self.cdrom.write_u8(offset, value);
+            return Ok(());
         }
 
         if let Some(offset) = Self::GPU_REGISTERS_RANGE.contains(physical_adddress) {
             gpu.write_u8(offset, value);
-            return;
+            return Ok(());
         }
 
-        if let Some(_offset) = Self::MDEC_REGISTERS_RANGE.contains(physical_adddress) {
-            /*
-            log::warn!(
-                "Unhandled write to MDEC Registers: {:#010x} ({:#x})",
-                address,
-                offset
-            );
-            */
-            return;
+        if let Some(offset) = Self::MDEC_REGISTERS_RANGE.contains(physical_adddress) {
+            self.mdec.write_u8(offset, value);
+            return Ok(());
         }
 
-        if let Some(_offset) = Self::SPU_RANGE.contains(physical_adddress) {
-            /*
-            log::warn!("Unhandled write to SPU: {:#010x} ({:#x})", address, offset);
-            */
-            return;
+        if let Some(offset) = Self::SPU_RANGE.contains(physical_adddress) {
+            self.spu.write_u8(offset, value);
+            return Ok(());
         }
 
-        if let Some(_offset) = Self::EXPANSION_REGION_2_RANGE.contains(physical_adddress) {
-            /*
-            log::warn!(
-                "Unhandled write to Expansion Region 2: {:#010x} ({:#x})",
-                address,
-                offset
-            );
-            */
-            return;
+        if let Some(offset) = Self::EXPANSION_REGION_2_RANGE.contains(physical_adddress) {
+            self.expansion_region_2.write_u8(offset, value);
+            return Ok(());
         }
 
         if let Some(_offset) = Self::EXPANSION_REGION_3_RANGE.contains(physical_adddress) {
@@ -258,12 +470,12 @@ impl Bus {
                 offset
             );
             */
-            return;
+            return Ok(());
         }
 
         if let Some(offset) = Self::BIOS_RANGE.contains(physical_adddress) {
             self.bios.write_u8(offset, value);
-            return;
+            return Ok(());
         }
 
         if let Some(_offset) = Self::MEMORY_CONTROL_3_RANGE.contains(physical_adddress) {
@@ -274,48 +486,98 @@ impl Bus {
                 offset
             );
             */
-            return;
+            return Ok(());
         }
 
-        panic!(
-            "access write violation at address: {:#010x} ({:#010x})",
-            physical_adddress, address
-        );
+        Err(BusError::Unmapped(address))
     }
 
-    /// Reads an u16 from a specific address
+    /// Writes an u16 to a specific address
     ///
     /// # Arguments:
     ///
     /// * `address`: The absolute address
+    /// * `value`: The value to be written
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// This functions panics if the address is not aligned to 16-bits
-    pub(crate) fn write_u16(&mut self, address: u32, value: u16, dma: &mut Dma, gpu: &mut Gpu) {
+    /// This function will throw an error if the address is not aligned to 16-bits or not mapped
+    pub(crate) fn write_u16(
+        &mut self,
+        address: u32,
+        value: u16,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) -> Result<(), BusError> {
         if address % 2 != 0 {
-            panic!("unaligned write access at {:#010x}", address);
+            return Err(BusError::Unaligned(address));
+        }
+
+        if let Some(offset) = Self::RAM_RANGE.contains(Self::mask_address(address)) {
+            self.ram.write_u16(offset, value);
+            return Ok(());
+        }
+
+        // The SPU registers are 16-bit wide and must not be split into bytes
+        if let Some(offset) = Self::SPU_RANGE.contains(Self::mask_address(address)) {
+            self.spu.write_u16(offset, value);
+            return Ok(());
+        }
+
+        if let Some(offset) = Self::GPU_REGISTERS_RANGE.contains(Self::mask_address(address)) {
+            gpu.write_u16(offset, value);
+            return Ok(());
         }
 
         let byte_0 = (value & 0xff) as u8;
         let byte_1 = ((value >> 8) & 0xff) as u8;
 
-        self.write_u8(address, byte_0, dma, gpu);
-        self.write_u8(address + 1, byte_1, dma, gpu);
+        self.write_u8(address, byte_0, dma, gpu)?;
+        self.write_u8(address + 1, byte_1, dma, gpu)?;
+
+        Ok(())
     }
 
-    /// Reads an u32 from a specific address
+    /// Writes an u32 to a specific address
     ///
     /// # Arguments:
     ///
     /// * `address`: The absolute address
+    /// * `value`: The value to be written
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// This functions panics if the address is not aligned to 16-bits
-    pub(crate) fn write_u32(&mut self, address: u32, value: u32, dma: &mut Dma, gpu: &mut Gpu) {
+    /// This function will throw an error if the address is not aligned to 32-bits or not mapped
+    pub(crate) fn write_u32(
+        &mut self,
+        address: u32,
+        value: u32,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) -> Result<(), BusError> {
         if address % 4 != 0 {
-            panic!("unaligned write access at {:#010x}", address);
+            return Err(BusError::Unaligned(address));
+        }
+
+        if let Some(offset) = Self::RAM_RANGE.contains(Self::mask_address(address)) {
+            self.ram.write_u32(offset, value);
+            return Ok(());
+        }
+
+        if let Some(offset) = Self::SPU_RANGE.contains(Self::mask_address(address)) {
+            self.spu.write_u16(offset, value as u16);
+            self.spu.write_u16(offset + 2, (value >> 16) as u16);
+            return Ok(());
+        }
+
+        if let Some(offset) = Self::MDEC_REGISTERS_RANGE.contains(Self::mask_address(address)) {
+            self.mdec.write_u32(offset, value);
+            return Ok(());
+        }
+
+        if let Some(offset) = Self::GPU_REGISTERS_RANGE.contains(Self::mask_address(address)) {
+            gpu.write_u32(offset, value);
+            return Ok(());
         }
 
         let byte_0 = (value & 0xff) as u8;
@@ -323,10 +585,12 @@ impl Bus {
         let byte_2 = ((value >> 16) & 0xff) as u8;
         let byte_3 = ((value >> 24) & 0xff) as u8;
 
-        self.write_u8(address, byte_0, dma, gpu);
-        self.write_u8(address + 1, byte_1, dma, gpu);
-        self.write_u8(address + 2, byte_2, dma, gpu);
-        self.write_u8(address + 3, byte_3, dma, gpu);
+        self.write_u8(address, byte_0, dma, gpu)?;
+        self.write_u8(address + 1, byte_1, dma, gpu)?;
+        self.write_u8(address + 2, byte_2, dma, gpu)?;
+        self.write_u8(address + 3, byte_3, dma, gpu)?;
+
+        Ok(())
     }
 
     /// Reads an u8 from a specific address
@@ -335,14 +599,19 @@ impl Bus {
     ///
     /// * `address`: The absolute address
     ///
-    /// # Panics:
+    /// # Errors
     ///
-    /// This functions panics if the address is not valid
-    pub(crate) fn read_u8(&self, address: u32, dma: &mut Dma, gpu: &mut Gpu) -> u8 {
+    /// This function will throw an error if no component is mapped at the address
+    pub(crate) fn read_u8(
+        &mut self,
+        address: u32,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) -> Result<u8, BusError> {
         let physical_adddress = Self::mask_address(address);
 
         if let Some(offset) = Self::RAM_RANGE.contains(physical_adddress) {
-            return self.ram.read_u8(offset);
+            return Ok(self.ram.read_u8(offset));
         }
 
         if let Some(_offset) = Self::EXPANSION_REGION_1_RANGE.contains(physical_adddress) {
@@ -353,18 +622,11 @@ impl Bus {
                 offset
             );
             */
-            return 0xff;
+            return Ok(0xff);
         }
 
-        if let Some(_offset) = Self::SCRATCHPAD_RANGE.contains(physical_adddress) {
-            /*
-            log::warn!(
-                "Unhandled read from Scratchpad: {:#010x} ({:#x})",
-                address,
-                offset
-            );
-            */
-            return 0x00;
+        if let Some(offset) = Self::SCRATCHPAD_RANGE.contains(physical_adddress) {
+            return Ok(self.scratchpad.read_u8(offset));
         }
 
         if let Some(_offset) = Self::MEMORY_CONTROL_1_RANGE.contains(physical_adddress) {
@@ -375,18 +637,22 @@ impl Bus {
                 offset
             );
             */
-            return 0x00;
+            return Ok(0x00);
+        }
+
+        if let Some(offset) = Self::SIO0_RANGE.contains(physical_adddress) {
+            return Ok(self.sio.read_u8(offset));
         }
 
-        if let Some(_offset) = Self::PERIPHERAL_IO_PORTS_RANGE.contains(physical_adddress) {
+        if let Some(_offset) = Self::SIO1_RANGE.contains(physical_adddress) {
             /*
             log::warn!(
-                "Unhandled read from Peripheral I/O Ports: {:#010x} ({:#x})",
+                "Unhandled read from Serial I/O Ports: {:#010x} ({:#x})",
                 address,
                 offset
             );
             */
-            return 0x00;
+            return Ok(0x00);
         }
 
         if let Some(_offset) = Self::MEMORY_CONTROL_2_RANGE.contains(physical_adddress) {
@@ -397,22 +663,15 @@ impl Bus {
                 offset
             );
             */
-            return 0x00;
+            return Ok(0x00);
         }
 
-        if let Some(_offset) = Self::INTERRUPT_CONTROL_RANGE.contains(physical_adddress) {
-            /*
-            log::warn!(
-                "Unhandled read from Interrupt Control: {:#010x} ({:#x})",
-                address,
-                offset
-            );
-            */
-            return 0x00;
+        if let Some(offset) = Self::INTERRUPT_CONTROL_RANGE.contains(physical_adddress) {
+            return Ok(self.interrupt_controller.read_u8(offset));
         }
 
         if let Some(offset) = Self::DMA_REGISTERS_RANGE.contains(physical_adddress) {
-            return dma.read_u8(offset);
+            return Ok(dma.read_u8(offset));
         }
 
         if let Some(_offset) = Self::TIMERS_RANGE.contains(physical_adddress) {
@@ -423,51 +682,27 @@ impl Bus {
                 offset
             );
             */
-            return 0x00;
+            return Ok(0x00);
         }
 
-        if let Some(_offset) = Self::CDROM_REGISTERS_RANGE.contains(physical_adddress) {
-            /*
-            log::warn!(
-                "Unhandled read from CDROM Registers: {:#010x} ({:#x})",
-                address,
-                offset
-            );
-            */
-            return 0x00;
+        if let Some(offset) = Self::CDROM_REGISTERS_RANGE.contains(physical_adddress) {
+            return Ok(self.cdrom.read_u8(offset));
         }
 
         if let Some(offset) = Self::GPU_REGISTERS_RANGE.contains(physical_adddress) {
-            return gpu.read_u8(offset);
+            return Ok(gpu.read_u8(offset));
         }
 
-        if let Some(_offset) = Self::MDEC_REGISTERS_RANGE.contains(physical_adddress) {
-            /*
-            log::warn!(
-                "Unhandled read from MDEC Registers: {:#010x} ({:#x})",
-                address,
-                offset
-            );
-            */
-            return 0x00;
+        if let Some(offset) = Self::MDEC_REGISTERS_RANGE.contains(physical_adddress) {
+            return Ok(self.mdec.read_u8(offset));
         }
 
-        if let Some(_offset) = Self::SPU_RANGE.contains(physical_adddress) {
-            /*
-            log::warn!("Unhandled read from SPU: {:#010x} ({:#x})", address, offset);
-            */
-            return 0x00;
+        if let Some(offset) = Self::SPU_RANGE.contains(physical_adddress) {
+            return Ok(self.spu.read_u8(offset));
         }
 
-        if let Some(_offset) = Self::EXPANSION_REGION_2_RANGE.contains(physical_adddress) {
-            /*
-            log::warn!(
-                "Unhandled read from Expansion Region 2: {:#010x} ({:#x})",
-                address,
-                offset
-            );
-            */
-            return 0x00;
+        if let Some(offset) = Self::EXPANSION_REGION_2_RANGE.contains(physical_adddress) {
+            return Ok(self.expansion_region_2.read_u8(offset));
         }
 
         if let Some(_offset) = Self::EXPANSION_REGION_3_RANGE.contains(physical_adddress) {
@@ -478,11 +713,11 @@ impl Bus {
                 offset
             );
             */
-            return 0x00;
+            return Ok(0x00);
         }
 
         if let Some(offset) = Self::BIOS_RANGE.contains(physical_adddress) {
-            return self.bios.read_u8(offset);
+            return Ok(self.bios.read_u8(offset));
         }
 
         if let Some(_offset) = Self::MEMORY_CONTROL_3_RANGE.contains(physical_adddress) {
@@ -493,13 +728,10 @@ impl Bus {
                 offset
             );
             */
-            return 0x00;
+            return Ok(0x00);
         }
 
-        panic!(
-            "access read violation at address: {:#010x} ({:#010x})",
-            physical_adddress, address
-        );
+        Err(BusError::Unmapped(address))
     }
 
     /// Reads an u16 from a specific address
@@ -508,18 +740,31 @@ impl Bus {
     ///
     /// * `address`: The absolute address
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// This functions panics if the address is not aligned to 16-bits
-    pub(crate) fn read_u16(&self, address: u32, dma: &mut Dma, gpu: &mut Gpu) -> u16 {
+    /// This function will throw an error if the address is not aligned to 16-bits or not mapped
+    pub(crate) fn read_u16(
+        &mut self,
+        address: u32,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) -> Result<u16, BusError> {
         if address % 2 != 0 {
-            panic!("unaligned read access at {:#010x}", address);
+            return Err(BusError::Unaligned(address));
         }
 
-        let byte_0 = self.read_u8(address, dma, gpu) as u16;
-        let byte_1 = self.read_u8(address + 1, dma, gpu) as u16;
+        if let Some(offset) = Self::RAM_RANGE.contains(Self::mask_address(address)) {
+            return Ok(self.ram.read_u16(offset));
+        }
 
-        (byte_1 << 8) | byte_0
+        if let Some(offset) = Self::SPU_RANGE.contains(Self::mask_address(address)) {
+            return Ok(self.spu.read_u16(offset));
+        }
+
+        let byte_0 = self.read_u8(address, dma, gpu)? as u16;
+        let byte_1 = self.read_u8(address + 1, dma, gpu)? as u16;
+
+        Ok((byte_1 << 8) | byte_0)
     }
 
     /// Reads an u32 from a specific address
@@ -528,24 +773,328 @@ impl Bus {
     ///
     /// * `address`: The absolute address
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// This functions panics if the address is not aligned to 32-bits
-    pub(crate) fn read_u32(&self, address: u32, dma: &mut Dma, gpu: &mut Gpu) -> u32 {
+    /// This function will throw an error if the address is not aligned to 32-bits or not mapped
+    pub(crate) fn read_u32(
+        &mut self,
+        address: u32,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) -> Result<u32, BusError> {
         if address % 4 != 0 {
-            panic!("unaligned read access at {:#010x}", address);
+            return Err(BusError::Unaligned(address));
+        }
+
+        if let Some(offset) = Self::RAM_RANGE.contains(Self::mask_address(address)) {
+            return Ok(self.ram.read_u32(offset));
+        }
+
+        if let Some(offset) = Self::SPU_RANGE.contains(Self::mask_address(address)) {
+            let low = self.spu.read_u16(offset) as u32;
+            let high = self.spu.read_u16(offset + 2) as u32;
+            return Ok((high << 16) | low);
+        }
+
+        if let Some(offset) = Self::MDEC_REGISTERS_RANGE.contains(Self::mask_address(address)) {
+            return Ok(self.mdec.read_u32(offset));
         }
 
-        let byte_0 = self.read_u8(address, dma, gpu) as u32;
-        let byte_1 = self.read_u8(address + 1, dma, gpu) as u32;
-        let byte_2 = self.read_u8(address + 2, dma, gpu) as u32;
-        let byte_3 = self.read_u8(address + 3, dma, gpu) as u32;
+        let byte_0 = self.read_u8(address, dma, gpu)? as u32;
+        let byte_1 = self.read_u8(address + 1, dma, gpu)? as u32;
+        let byte_2 = self.read_u8(address + 2, dma, gpu)? as u32;
+        let byte_3 = self.read_u8(address + 3, dma, gpu)? as u32;
+
+        Ok((byte_3 << 24) | (byte_2 << 16) | (byte_1 << 8) | byte_0)
+    }
+
+    /// Writes an u8 into the instruction cache, which isolated stores target
+    /// instead of the memory
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The absolute address
+    pub(crate) fn write_cache_u8(&mut self, address: u32, value: u8) {
+        self.instruction_cache.write_u8(address, value);
+    }
+
+    /// Writes an u16 into the instruction cache, which isolated stores target
+    /// instead of the memory
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The absolute address
+    pub(crate) fn write_cache_u16(&mut self, address: u32, value: u16) {
+        let byte_0 = (value & 0xff) as u8;
+        let byte_1 = ((value >> 8) & 0xff) as u8;
+
+        self.write_cache_u8(address, byte_0);
+        self.write_cache_u8(address + 1, byte_1);
+    }
+
+    /// Writes an u32 into the instruction cache, which isolated stores target
+    /// instead of the memory
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The absolute address
+    pub(crate) fn write_cache_u32(&mut self, address: u32, value: u32) {
+        let byte_0 = (value & 0xff) as u8;
+        let byte_1 = ((value >> 8) & 0xff) as u8;
+        let byte_2 = ((value >> 16) & 0xff) as u8;
+        let byte_3 = ((value >> 24) & 0xff) as u8;
+
+        self.write_cache_u8(address, byte_0);
+        self.write_cache_u8(address + 1, byte_1);
+        self.write_cache_u8(address + 2, byte_2);
+        self.write_cache_u8(address + 3, byte_3);
+    }
+
+    /// Reads an u8 from the instruction cache, which isolated loads target
+    /// instead of the memory
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The absolute address
+    pub(crate) fn read_cache_u8(&self, address: u32) -> u8 {
+        self.instruction_cache.read_u8(address)
+    }
+
+    /// Reads an u16 from the instruction cache, which isolated loads target
+    /// instead of the memory
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The absolute address
+    pub(crate) fn read_cache_u16(&self, address: u32) -> u16 {
+        let byte_0 = self.read_cache_u8(address) as u16;
+        let byte_1 = self.read_cache_u8(address + 1) as u16;
+
+        (byte_1 << 8) | byte_0
+    }
+
+    /// Reads an u32 from the instruction cache, which isolated loads target
+    /// instead of the memory
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The absolute address
+    pub(crate) fn read_cache_u32(&self, address: u32) -> u32 {
+        let byte_0 = self.read_cache_u8(address) as u32;
+        let byte_1 = self.read_cache_u8(address + 1) as u32;
+        let byte_2 = self.read_cache_u8(address + 2) as u32;
+        let byte_3 = self.read_cache_u8(address + 3) as u32;
 
         (byte_3 << 24) | (byte_2 << 16) | (byte_1 << 8) | byte_0
     }
 
-    /// Returns the RAM
-    pub(crate) fn ram(&mut self) -> &mut Ram {
-        &mut self.ram
+    /// Pushes the samples the SPU produced since the last call to its sink
+    pub(crate) fn flush_audio(&mut self) {
+        self.spu.flush_samples();
+    }
+
+    /// Replaces the sink receiving the SPU samples
+    ///
+    /// Returns the previous sink
+    ///
+    /// # Arguments:
+    ///
+    /// * `sink`: The new sink
+    pub(crate) fn set_audio_sink(&mut self, sink: Box<dyn AudioSink>) -> Box<dyn AudioSink> {
+        self.spu.set_sink(sink)
+    }
+
+    /// Presses or releases a button of the digital pad
+    ///
+    /// # Arguments:
+    ///
+    /// * `button`: The button
+    /// * `pressed`: If the button is held down
+    pub(crate) fn set_button(&mut self, button: Button, pressed: bool) {
+        self.sio.set_button(button, pressed);
+    }
+
+    /// Returns the last boot status written to the POST register
+    pub(crate) fn post(&self) -> u8 {
+        self.expansion_region_2.post()
+    }
+
+    /// Returns the interrupt controller
+    pub(crate) fn interrupt_controller(&mut self) -> &mut InterruptController {
+        &mut self.interrupt_controller
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bus::ram::RamSize, region::Region, renderer::mock_renderer::MockRenderer};
+
+    fn components() -> (Bus, Dma, Gpu) {
+        components_with_ram(RamSize::Retail)
+    }
+
+    fn components_with_ram(ram_size: RamSize) -> (Bus, Dma, Gpu) {
+        let bus = Bus::new(
+            Bios::zeroed(),
+            Ram::with_size(ram_size.bytes()),
+            InterruptController::new(),
+            Cdrom::new(None),
+            Sio::new(None),
+        );
+        let (renderer, _) = MockRenderer::new();
+
+        (bus, Dma::new(), Gpu::new(Box::new(renderer), Region::Ntsc))
+    }
+
+    #[test]
+    fn scratchpad_keeps_words() {
+        let (mut bus, mut dma, mut gpu) = components();
+
+        bus.write_u32(0x1f800000, 0x12345678, &mut dma, &mut gpu)
+            .unwrap();
+        bus.write_u32(0x1f8003fc, 0x9abcdef0, &mut dma, &mut gpu)
+            .unwrap();
+        bus.write_u16(0x1f800100, 0xbeef, &mut dma, &mut gpu)
+            .unwrap();
+
+        assert_eq!(
+            bus.read_u32(0x1f800000, &mut dma, &mut gpu).unwrap(),
+            0x12345678
+        );
+        assert_eq!(
+            bus.read_u32(0x1f8003fc, &mut dma, &mut gpu).unwrap(),
+            0x9abcdef0
+        );
+        assert_eq!(
+            bus.read_u32(0x1f800100, &mut dma, &mut gpu).unwrap(),
+            0x0000beef
+        );
+        assert_eq!(bus.read_u8(0x9f800002, &mut dma, &mut gpu).unwrap(), 0x34);
+
+        // The scratchpad is separate from the RAM
+        assert_eq!(bus.read_u32(0x00000000, &mut dma, &mut gpu).unwrap(), 0);
+    }
+
+    #[test]
+    fn retail_ram_is_mirrored_across_the_window() {
+        let (mut bus, mut dma, mut gpu) = components();
+
+        bus.write_u32(0x00000100, 0x12345678, &mut dma, &mut gpu)
+            .unwrap();
+        bus.write_u16(0xa0600202, 0xbeef, &mut dma, &mut gpu)
+            .unwrap();
+
+        for mirror in [0x00200000, 0x80400000, 0xa0600000] {
+            assert_eq!(
+                bus.read_u32(mirror + 0x100, &mut dma, &mut gpu).unwrap(),
+                0x12345678
+            );
+        }
+        assert_eq!(
+            bus.read_u32(0x00000200, &mut dma, &mut gpu).unwrap(),
+            0xbeef0000
+        );
+    }
+
+    #[test]
+    fn dev_kit_ram_fills_the_window() {
+        let (mut bus, mut dma, mut gpu) = components_with_ram(RamSize::DevKit);
+
+        bus.write_u32(0x00600100, 0x12345678, &mut dma, &mut gpu)
+            .unwrap();
+
+        assert_eq!(
+            bus.read_u32(0x80600100, &mut dma, &mut gpu).unwrap(),
+            0x12345678
+        );
+        assert_eq!(bus.read_u32(0x00000100, &mut dma, &mut gpu).unwrap(), 0);
+        assert_eq!(bus.read_u32(0x00200100, &mut dma, &mut gpu).unwrap(), 0);
+    }
+
+    #[test]
+    fn gpustat_reports_the_dma_direction() {
+        let (mut bus, mut dma, mut gpu) = components();
+
+        for direction in 0..4 {
+            bus.write_u32(0x1f801814, 0x04000000 | direction, &mut dma, &mut gpu)
+                .unwrap();
+
+            let status = bus.read_u32(0x1f801814, &mut dma, &mut gpu).unwrap();
+            assert_eq!((status >> 29) & 0x3, direction);
+        }
+    }
+
+    #[test]
+    fn gpu_register_writes_reach_gp0_and_gp1() {
+        let (mut bus, mut dma, mut gpu) = components();
+
+        // GP0(E1h) sets the texture page, GP1(00h) resets it again
+        bus.write_u32(0x1f801810, 0xe1000005, &mut dma, &mut gpu)
+            .unwrap();
+        assert_eq!(
+            bus.read_u32(0x1f801814, &mut dma, &mut gpu).unwrap() & 0xf,
+            5
+        );
+
+        bus.write_u32(0x1f801814, 0x00000000, &mut dma, &mut gpu)
+            .unwrap();
+        assert_eq!(
+            bus.read_u32(0x1f801814, &mut dma, &mut gpu).unwrap() & 0xf,
+            0
+        );
+    }
+
+    #[test]
+    fn cache_is_separate_from_memory() {
+        let (mut bus, mut dma, mut gpu) = components();
+
+        bus.write_cache_u32(0x00000010, 0x12345678);
+        bus.write_cache_u16(0x00000ffe, 0xbeef);
+
+        assert_eq!(bus.read_cache_u32(0x00000010), 0x12345678);
+        assert_eq!(bus.read_cache_u16(0x80000ffe), 0xbeef);
+        assert_eq!(bus.read_u32(0x00000010, &mut dma, &mut gpu).unwrap(), 0);
+        assert_eq!(bus.read_u32(0x1f800010, &mut dma, &mut gpu).unwrap(), 0);
+    }
+
+    #[test]
+    fn mdec_byte_accesses_reach_the_mdec() {
+        let (mut bus, mut dma, mut gpu) = components();
+        assert_eq!(bus.read_u8(0x1f801827, &mut dma, &mut gpu).unwrap(), 0x80);
+
+        // MDEC(3) - Set Scale Table, written as two halfwords
+        bus.write_u16(0x1f801820, 0x0000, &mut dma, &mut gpu)
+            .unwrap();
+        bus.write_u16(0x1f801822, 0x6000, &mut dma, &mut gpu)
+            .unwrap();
+
+        assert_eq!(bus.read_u8(0x1f801827, &mut dma, &mut gpu).unwrap(), 0xa0);
+        assert_eq!(bus.read_u16(0x1f801824, &mut dma, &mut gpu).unwrap(), 31);
+    }
+
+    #[test]
+    fn completed_dma_requests_the_interrupt() {
+        let (mut bus, mut dma, mut gpu) = components();
+
+        // OTC channel IRQ enable and master enable, then start a 4 entry table
+        bus.write_u32(0x1f8010f4, 0x00c0_0000, &mut dma, &mut gpu)
+            .unwrap();
+        bus.write_u32(0x1f8010e0, 0x0000_100c, &mut dma, &mut gpu)
+            .unwrap();
+        bus.write_u32(0x1f8010e4, 0x0000_0004, &mut dma, &mut gpu)
+            .unwrap();
+        bus.write_u32(0x1f8010e8, 0x1100_0002, &mut dma, &mut gpu)
+            .unwrap();
+        bus.step_dma(&mut dma, &mut gpu);
+
+        assert_eq!(
+            bus.read_u32(0x1f801070, &mut dma, &mut gpu).unwrap() & (1 << Interrupt::Dma as u32),
+            1 << Interrupt::Dma as u32
+        );
+        assert_eq!(
+            bus.read_u32(0x1f8010f4, &mut dma, &mut gpu).unwrap() >> 24,
+            0xc0
+        );
     }
 }
@@ -6,37 +6,157 @@
 
 use crate::bus::memory::Memory;
 
+use serde::{Deserialize, Serialize};
+
+/// The amount of installed RAM
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RamSize {
+    /// The 2MB of retail units
+    #[default]
+    Retail,
+
+    /// The 8MB of development units
+    DevKit,
+}
+
+impl RamSize {
+    /// Returns the size in bytes
+    pub(crate) fn bytes(self) -> usize {
+        match self {
+            Self::Retail => 2 * 1024 * 1024,
+            Self::DevKit => 8 * 1024 * 1024,
+        }
+    }
+}
+
 /// The RAM component
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub(crate) struct Ram {
     /// The data vector containing the RAM
-    data: Box<[u8; Self::SIZE]>,
+    data: Box<[u8]>,
 }
 
 impl Ram {
-    const SIZE: usize = 1024 * (2 * 1024);
-
     /// Creates a RAM Component
-    pub(crate) fn new() -> Self {
-        let buffer = vec![0x00; Self::SIZE]
-            .into_boxed_slice()
-            .try_into()
-            .unwrap();
+    ///
+    /// # Arguments:
+    ///
+    /// * `size`: The size in bytes, a power of two
+    pub(crate) fn with_size(size: usize) -> Self {
+        debug_assert!(size.is_power_of_two());
+
+        Self {
+            data: vec![0x00; size].into_boxed_slice(),
+        }
+    }
+
+    /// Returns the size in bytes
+    pub(crate) fn size(&self) -> usize {
+        self.data.len()
+    }
 
-        Self { data: buffer }
+    /// Mirrors an offset onto the installed RAM, so every access stays in bounds
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative address offset
+    fn mirror(&self, offset: u32) -> usize {
+        (offset & (self.data.len() as u32 - 1)) as usize
     }
 }
 
 impl Memory for Ram {
     fn write_u8(&mut self, offset: u32, value: u8) {
-        debug_assert!((offset as usize) < self.data.len());
-
-        self.data[offset as usize] = value;
+        let offset = self.mirror(offset);
+        self.data[offset] = value;
     }
 
     fn read_u8(&self, offset: u32) -> u8 {
-        debug_assert!((offset as usize) < self.data.len());
+        self.data[self.mirror(offset)]
+    }
+
+    fn write_u16(&mut self, offset: u32, value: u16) {
+        let offset = self.mirror(offset);
+        self.data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, offset: u32, value: u32) {
+        let offset = self.mirror(offset);
+        self.data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn read_u16(&self, offset: u32) -> u16 {
+        let offset = self.mirror(offset);
+        u16::from_le_bytes([self.data[offset], self.data[offset + 1]])
+    }
+
+    fn read_u32(&self, offset: u32) -> u32 {
+        let offset = self.mirror(offset);
+        u32::from_le_bytes([
+            self.data[offset],
+            self.data[offset + 1],
+            self.data[offset + 2],
+            self.data[offset + 3],
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RAM which only exposes the byte accessors, so the wide ones fall back to the defaults
+    struct ByteRam(Ram);
+
+    impl Memory for ByteRam {
+        fn write_u8(&mut self, offset: u32, value: u8) {
+            self.0.write_u8(offset, value);
+        }
+
+        fn read_u8(&self, offset: u32) -> u8 {
+            self.0.read_u8(offset)
+        }
+    }
+
+    #[test]
+    fn wide_accesses_match_byte_accesses() {
+        let mut ram = Ram::with_size(2 * 1024 * 1024);
+        let mut byte_ram = ByteRam(Ram::with_size(2 * 1024 * 1024));
+
+        for (offset, value) in [
+            (0x00, 0x12345678),
+            (0x1ffffc, 0x9abcdef0),
+            (0x1000, 0xfeedface),
+        ] {
+            ram.write_u32(offset, value);
+            byte_ram.write_u32(offset, value);
+        }
+
+        for (offset, value) in [(0x02, 0xbeef), (0x1ffffe, 0xcafe), (0x1005, 0x0102)] {
+            ram.write_u16(offset, value);
+            byte_ram.write_u16(offset, value);
+        }
+
+        for offset in (0x00..0x10).chain(0x1000..0x1010).chain(0x1ffff0..0x200000) {
+            assert_eq!(ram.read_u8(offset), byte_ram.read_u8(offset));
+        }
+
+        for offset in [0x00, 0x02, 0x1000, 0x1004, 0x1ffffc] {
+            assert_eq!(ram.read_u32(offset), byte_ram.read_u32(offset));
+            assert_eq!(ram.read_u16(offset), byte_ram.read_u16(offset));
+        }
+    }
+
+    #[test]
+    fn offsets_mirror_onto_the_installed_ram() {
+        let mut ram = Ram::with_size(2 * 1024 * 1024);
+
+        ram.write_u32(0x000000, 0x12345678);
+        ram.write_u16(0x7ffffe, 0xbeef);
 
-        self.data[offset as usize]
+        assert_eq!(ram.read_u32(0x200000), 0x12345678);
+        assert_eq!(ram.read_u32(0x600000), 0x12345678);
+        assert_eq!(ram.read_u16(0x1ffffe), 0xbeef);
+        assert_eq!(ram.read_u8(0xffffffff), 0xbe);
     }
 }
@@ -0,0 +1,43 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::bus::memory::Memory;
+
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+
+/// The Scratchpad component (D-Cache used as fast RAM)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Scratchpad {
+    /// The data array containing the scratchpad
+    #[serde(with = "BigArray")]
+    data: [u8; Self::SIZE],
+}
+
+impl Scratchpad {
+    const SIZE: usize = 1024;
+
+    /// Creates a Scratchpad Component
+    pub(crate) fn new() -> Self {
+        Self {
+            data: [0x00; Self::SIZE],
+        }
+    }
+}
+
+impl Memory for Scratchpad {
+    fn write_u8(&mut self, offset: u32, value: u8) {
+        debug_assert!((offset as usize) < self.data.len());
+
+        self.data[offset as usize] = value;
+    }
+
+    fn read_u8(&self, offset: u32) -> u8 {
+        debug_assert!((offset as usize) < self.data.len());
+
+        self.data[offset as usize]
+    }
+}
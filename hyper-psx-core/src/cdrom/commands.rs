@@ -0,0 +1,353 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::cdrom::{disc::TrackType, from_bcd, to_bcd, Cdrom, DriveState, ResponseInterrupt};
+
+impl Cdrom {
+    /// The sectors in front of the first track (00:02:00)
+    const PREGAP_SECTORS: u32 = 150;
+
+    /// Command 01h - Getstat
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#cdrom-control-commands>
+    pub(super) fn op_getstat(&mut self) {
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 02h - Setloc amm,ass,asect
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#seek-commands>
+    pub(super) fn op_setloc(&mut self) {
+        if self.parameters.len() < 3 {
+            self.error(0x20);
+            return;
+        }
+
+        let minutes = from_bcd(self.parameters[0]) as u32;
+        let seconds = from_bcd(self.parameters[1]) as u32;
+        let frames = from_bcd(self.parameters[2]) as u32;
+
+        let sector = (minutes * 60 + seconds) * 75 + frames;
+        self.seek_target = sector.saturating_sub(Self::PREGAP_SECTORS);
+
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 06h - ReadN and Command 1Bh - ReadS
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#read-commands>
+    pub(super) fn op_read(&mut self) {
+        if self.disc.is_none() {
+            self.error(0x80);
+            return;
+        }
+
+        self.position = self.seek_target;
+        self.drive_state = DriveState::Reading;
+        self.read_countdown = self.sector_cycles();
+
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 08h - Stop
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#cdrom-control-commands>
+    pub(super) fn op_stop(&mut self) {
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+
+        self.drive_state = DriveState::Idle;
+
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Complete,
+            vec![status],
+            Self::SECOND_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 09h - Pause
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#cdrom-control-commands>
+    pub(super) fn op_pause(&mut self) {
+        // The first response still reports the reading state
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+
+        self.drive_state = DriveState::Idle;
+        self.pending_responses
+            .retain(|response| response.interrupt != ResponseInterrupt::DataReady);
+
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Complete,
+            vec![status],
+            Self::SECOND_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 0Ah - Init
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#cdrom-control-commands>
+    pub(super) fn op_init(&mut self) {
+        self.mode = 0;
+        self.drive_state = DriveState::Idle;
+        self.pending_responses.clear();
+
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+        self.push_response(
+            ResponseInterrupt::Complete,
+            vec![status],
+            Self::SECOND_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 0Bh - Mute
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#cdrom-control-commands>
+    pub(super) fn op_mute(&mut self) {
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 0Ch - Demute
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#cdrom-control-commands>
+    pub(super) fn op_demute(&mut self) {
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 0Dh - Setfilter file,channel
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#cdrom-control-commands>
+    pub(super) fn op_setfilter(&mut self) {
+        // TODO: Implement XA-ADPCM filtering
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 0Eh - Setmode mode
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#cdrom-control-commands>
+    pub(super) fn op_setmode(&mut self) {
+        let Some(&mode) = self.parameters.front() else {
+            self.error(0x20);
+            return;
+        };
+
+        self.mode = mode;
+
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 13h - GetTN
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#status-commands>
+    pub(super) fn op_get_tn(&mut self) {
+        let Some(disc) = &self.disc else {
+            self.error(0x80);
+            return;
+        };
+
+        let tracks = disc.tracks();
+        let first = tracks.first().map(|track| track.number).unwrap_or(1);
+        let last = tracks.last().map(|track| track.number).unwrap_or(1);
+
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status, to_bcd(first), to_bcd(last)],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 14h - GetTD track
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#status-commands>
+    pub(super) fn op_get_td(&mut self) {
+        let Some(disc) = &self.disc else {
+            self.error(0x80);
+            return;
+        };
+
+        let track_number = self.parameters.front().copied().map(from_bcd).unwrap_or(0);
+
+        // Track 0 stands for the end of the last track
+        let sector = if track_number == 0 {
+            Some(disc.sector_count())
+        } else {
+            disc.tracks()
+                .iter()
+                .find(|track| track.number == track_number)
+                .map(|track| track.start)
+        };
+
+        let Some(sector) = sector else {
+            self.error(0x10);
+            return;
+        };
+
+        let sector = sector + Self::PREGAP_SECTORS;
+        let minutes = (sector / 75 / 60) as u8;
+        let seconds = (sector / 75 % 60) as u8;
+
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status, to_bcd(minutes), to_bcd(seconds)],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 15h - SeekL and Command 16h - SeekP
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#seek-commands>
+    pub(super) fn op_seek(&mut self) {
+        self.position = self.seek_target;
+        self.drive_state = DriveState::Seeking;
+
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+
+        self.drive_state = DriveState::Idle;
+
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Complete,
+            vec![status],
+            Self::SECOND_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 19h - Test sub_function
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#cdrom-test-commands>
+    pub(super) fn op_test(&mut self) {
+        match self.parameters.front() {
+            Some(0x20) => {
+                // Get CDROM BIOS date/version (yy,mm,dd,ver) of the PU-7/PU-8 boards
+                self.push_response(
+                    ResponseInterrupt::Acknowledge,
+                    vec![0x94, 0x09, 0x19, 0xc0],
+                    Self::FIRST_RESPONSE_DELAY,
+                );
+            }
+            sub_function => {
+                log::warn!(
+                    target: "cdrom",
+                    "Unhandled test sub function {:02x?}",
+                    sub_function
+                );
+                self.error(0x10);
+            }
+        }
+    }
+
+    /// Command 1Ah - GetID
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#status-commands>
+    pub(super) fn op_get_id(&mut self) {
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+
+        let Some(disc) = &self.disc else {
+            self.push_response(
+                ResponseInterrupt::Error,
+                vec![0x08, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+                Self::SECOND_RESPONSE_DELAY,
+            );
+            return;
+        };
+
+        let first_track = disc.tracks().first().map(|track| track.track_type);
+        if first_track == Some(TrackType::Audio) {
+            self.push_response(
+                ResponseInterrupt::Error,
+                vec![status | 0x08, 0x90, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+                Self::SECOND_RESPONSE_DELAY,
+            );
+            return;
+        }
+
+        // Licensed Mode 2 disc with the "SCEA" region string
+        self.push_response(
+            ResponseInterrupt::Complete,
+            vec![status, 0x00, 0x20, 0x00, b'S', b'C', b'E', b'A'],
+            Self::SECOND_RESPONSE_DELAY,
+        );
+    }
+
+    /// Command 1Eh - ReadTOC
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#status-commands>
+    pub(super) fn op_read_toc(&mut self) {
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Acknowledge,
+            vec![status],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+        self.push_response(
+            ResponseInterrupt::Complete,
+            vec![status],
+            Self::SECOND_RESPONSE_DELAY,
+        );
+    }
+}
@@ -0,0 +1,404 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// The error type of the creation process of the disc
+#[derive(Debug, Error)]
+pub enum CreationError {
+    /// If the disc image was not found
+    #[error("failed to find disc image: '{0}'")]
+    MissingFile(String),
+
+    /// If the disc image failed to open
+    #[error("failed to open disc image: '{1}'")]
+    OpenFailure(#[source] io::Error, String),
+
+    /// If the CUE sheet failed to be read from
+    #[error("failed to read cue sheet: '{1}'")]
+    ReadingFailure(#[source] io::Error, String),
+
+    /// If the CUE sheet contains an invalid line
+    #[error("failed to parse cue sheet line {1}: '{0}'")]
+    ParsingFailure(String, usize),
+
+    /// If the CUE sheet doesn't reference any track
+    #[error("cue sheet contains no tracks: '{0}'")]
+    MissingTracks(String),
+}
+
+/// The error type of the sector reading process
+#[derive(Debug, Error)]
+pub(crate) enum ReadError {
+    /// If the sector lies outside of the disc
+    #[error("sector {0} lies outside of the disc")]
+    OutOfRange(u32),
+
+    /// If the backing file failed to be read from
+    #[error("failed to read sector {1}")]
+    ReadingFailure(#[source] io::Error, u32),
+}
+
+/// The track type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TrackType {
+    /// Mode 1 data track with raw 2352 bytes sectors
+    Mode1,
+
+    /// Mode 2 data track with raw 2352 bytes sectors
+    Mode2,
+
+    /// CD-DA audio track
+    Audio,
+}
+
+/// A track of the disc
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Track {
+    /// The track number
+    pub(crate) number: u8,
+
+    /// The track type
+    pub(crate) track_type: TrackType,
+
+    /// The first sector of the track (index 01) on the disc
+    pub(crate) start: u32,
+}
+
+/// A backing BIN file of the disc
+#[derive(Debug)]
+struct BinFile {
+    /// The file handle
+    file: File,
+
+    /// The first sector of the file on the disc
+    start: u32,
+
+    /// The amount of sectors in the file
+    sector_count: u32,
+}
+
+/// A disc image assembled from a CUE sheet and its BIN files
+#[derive(Debug)]
+pub(crate) struct Disc {
+    /// The backing BIN files in disc order
+    files: Vec<BinFile>,
+
+    /// The tracks in disc order
+    tracks: Vec<Track>,
+}
+
+impl Disc {
+    /// The size of a raw sector
+    pub(crate) const SECTOR_SIZE: usize = 2352;
+
+    /// Creates a disc from a CUE sheet or a single raw BIN file
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path of the CUE sheet or BIN file
+    pub(crate) fn new<P: AsRef<Path>>(path: P) -> Result<Self, CreationError> {
+        let path = path.as_ref();
+        let path_display = path.display().to_string();
+        if !path.exists() {
+            return Err(CreationError::MissingFile(path_display));
+        }
+
+        let is_cue = path
+            .extension()
+            .map(|extension| extension.eq_ignore_ascii_case("cue"))
+            .unwrap_or(false);
+
+        let disc = if is_cue {
+            Self::from_cue(path)?
+        } else {
+            let file = Self::open_bin(path, 0)?;
+            let tracks = vec![Track {
+                number: 1,
+                track_type: TrackType::Mode2,
+                start: 0,
+            }];
+
+            Self {
+                files: vec![file],
+                tracks,
+            }
+        };
+
+        log::info!(
+            "Loaded disc from '{}' ({} tracks)",
+            path_display,
+            disc.tracks.len()
+        );
+
+        Ok(disc)
+    }
+
+    /// Parses a CUE sheet and opens the referenced BIN files
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path of the CUE sheet
+    fn from_cue(path: &Path) -> Result<Self, CreationError> {
+        let path_display = path.display().to_string();
+        let sheet = fs::read_to_string(path)
+            .map_err(|error| CreationError::ReadingFailure(error, path_display.clone()))?;
+
+        let directory = path.parent().unwrap_or_else(|| Path::new("."));
+
+        let mut files: Vec<BinFile> = Vec::new();
+        let mut tracks = Vec::new();
+        let mut current_track: Option<(u8, TrackType)> = None;
+
+        for (line_index, line) in sheet.lines().enumerate() {
+            let line = line.trim();
+            let parsing_failure = || CreationError::ParsingFailure(line.to_owned(), line_index + 1);
+
+            let (keyword, arguments) = line.split_once(' ').unwrap_or((line, ""));
+            match keyword.to_ascii_uppercase().as_str() {
+                "FILE" => {
+                    let file_name = Self::parse_file_name(arguments).ok_or_else(parsing_failure)?;
+                    let file_path: PathBuf = directory.join(file_name);
+
+                    let start = files
+                        .last()
+                        .map(|file| file.start + file.sector_count)
+                        .unwrap_or(0);
+
+                    files.push(Self::open_bin(&file_path, start)?);
+                }
+                "TRACK" => {
+                    let mut arguments = arguments.split_whitespace();
+                    let number = arguments
+                        .next()
+                        .and_then(|number| number.parse::<u8>().ok())
+                        .ok_or_else(parsing_failure)?;
+                    let track_type = match arguments.next().map(|mode| mode.to_ascii_uppercase()) {
+                        Some(mode) if mode == "MODE1/2352" => TrackType::Mode1,
+                        Some(mode) if mode == "MODE2/2352" => TrackType::Mode2,
+                        Some(mode) if mode == "AUDIO" => TrackType::Audio,
+                        _ => return Err(parsing_failure()),
+                    };
+
+                    current_track = Some((number, track_type));
+                }
+                "INDEX" => {
+                    let mut arguments = arguments.split_whitespace();
+                    let index = arguments.next().ok_or_else(parsing_failure)?;
+                    if index != "01" {
+                        continue;
+                    }
+
+                    let (number, track_type) = current_track.ok_or_else(parsing_failure)?;
+                    let file = files.last().ok_or_else(parsing_failure)?;
+                    let offset = arguments
+                        .next()
+                        .and_then(Self::parse_msf)
+                        .ok_or_else(parsing_failure)?;
+
+                    tracks.push(Track {
+                        number,
+                        track_type,
+                        start: file.start + offset,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        if tracks.is_empty() {
+            return Err(CreationError::MissingTracks(path_display));
+        }
+
+        Ok(Self { files, tracks })
+    }
+
+    /// Opens a BIN file
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path of the BIN file
+    /// * `start`: The first sector of the file on the disc
+    fn open_bin(path: &Path, start: u32) -> Result<BinFile, CreationError> {
+        let path_display = path.display().to_string();
+        if !path.exists() {
+            return Err(CreationError::MissingFile(path_display));
+        }
+
+        let file = File::open(path)
+            .map_err(|error| CreationError::OpenFailure(error, path_display.clone()))?;
+        let metadata = file
+            .metadata()
+            .map_err(|error| CreationError::OpenFailure(error, path_display))?;
+
+        let sector_count = (metadata.len() / Self::SECTOR_SIZE as u64) as u32;
+
+        Ok(BinFile {
+            file,
+            start,
+            sector_count,
+        })
+    }
+
+    /// Parses the (optionally quoted) file name of a FILE line
+    ///
+    /// # Arguments:
+    ///
+    /// * `arguments`: The arguments after the FILE keyword
+    fn parse_file_name(arguments: &str) -> Option<&str> {
+        let arguments = arguments.trim();
+        if let Some(quoted) = arguments.strip_prefix('"') {
+            let end = quoted.find('"')?;
+            return Some(&quoted[..end]);
+        }
+
+        arguments.split_whitespace().next()
+    }
+
+    /// Parses a `mm:ss:ff` timestamp into a sector count
+    ///
+    /// # Arguments:
+    ///
+    /// * `timestamp`: The timestamp
+    fn parse_msf(timestamp: &str) -> Option<u32> {
+        let mut parts = timestamp.split(':').map(|part| part.parse::<u32>().ok());
+        let minutes = parts.next()??;
+        let seconds = parts.next()??;
+        let frames = parts.next()??;
+
+        Some((minutes * 60 + seconds) * 75 + frames)
+    }
+
+    /// Returns the tracks of the disc
+    pub(crate) fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    /// Returns the total amount of sectors of the disc
+    pub(crate) fn sector_count(&self) -> u32 {
+        self.files
+            .last()
+            .map(|file| file.start + file.sector_count)
+            .unwrap_or(0)
+    }
+
+    /// Reads a raw 2352 bytes sector
+    ///
+    /// # Arguments:
+    ///
+    /// * `sector`: The sector relative to the start of the disc data (00:02:00)
+    /// * `buffer`: The buffer receiving the raw sector
+    pub(crate) fn read_sector(
+        &mut self,
+        sector: u32,
+        buffer: &mut [u8; Self::SECTOR_SIZE],
+    ) -> Result<(), ReadError> {
+        let bin_file = self
+            .files
+            .iter_mut()
+            .find(|file| sector >= file.start && sector < file.start + file.sector_count)
+            .ok_or(ReadError::OutOfRange(sector))?;
+
+        let offset = (sector - bin_file.start) as u64 * Self::SECTOR_SIZE as u64;
+        bin_file
+            .file
+            .seek(SeekFrom::Start(offset))
+            .map_err(|error| ReadError::ReadingFailure(error, sector))?;
+        bin_file
+            .file
+            .read_exact(buffer)
+            .map_err(|error| ReadError::ReadingFailure(error, sector))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an empty directory for the disc images of a test
+    fn test_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("hyper-psx-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn cue_sheet_tracks_span_their_files() {
+        let directory = test_directory("cue");
+        fs::write(
+            directory.join("data.bin"),
+            vec![0x00; Disc::SECTOR_SIZE * 20],
+        )
+        .unwrap();
+
+        let mut audio = vec![0x00; Disc::SECTOR_SIZE * 10];
+        audio[Disc::SECTOR_SIZE * 2] = 0xab;
+        fs::write(directory.join("Track 2.bin"), audio).unwrap();
+
+        let cue = directory.join("game.cue");
+        fs::write(
+            &cue,
+            "FILE \"data.bin\" BINARY\n\
+             \x20 TRACK 01 MODE2/2352\n\
+             \x20   INDEX 01 00:00:00\n\
+             FILE \"Track 2.bin\" BINARY\n\
+             \x20 track 02 audio\n\
+             \x20   INDEX 00 00:00:00\n\
+             \x20   INDEX 01 00:00:02\n",
+        )
+        .unwrap();
+
+        let mut disc = Disc::new(&cue).unwrap();
+        let tracks = disc.tracks();
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].number, 1);
+        assert_eq!(tracks[0].track_type, TrackType::Mode2);
+        assert_eq!(tracks[0].start, 0);
+        assert_eq!(tracks[1].number, 2);
+        assert_eq!(tracks[1].track_type, TrackType::Audio);
+        assert_eq!(tracks[1].start, 22);
+        assert_eq!(disc.sector_count(), 30);
+
+        let mut buffer = [0xff; Disc::SECTOR_SIZE];
+        disc.read_sector(22, &mut buffer).unwrap();
+        assert_eq!(buffer[0], 0xab);
+        assert!(matches!(
+            disc.read_sector(30, &mut buffer),
+            Err(ReadError::OutOfRange(30))
+        ));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn invalid_cue_lines_are_reported() {
+        let directory = test_directory("invalid-cue");
+        fs::write(directory.join("data.bin"), vec![0x00; Disc::SECTOR_SIZE]).unwrap();
+
+        let cue = directory.join("game.cue");
+        fs::write(&cue, "FILE data.bin BINARY\nTRACK 01 MODE3/2048\n").unwrap();
+        assert!(matches!(
+            Disc::new(&cue),
+            Err(CreationError::ParsingFailure(_, 2))
+        ));
+
+        fs::write(&cue, "FILE data.bin BINARY\n").unwrap();
+        assert!(matches!(
+            Disc::new(&cue),
+            Err(CreationError::MissingTracks(_))
+        ));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
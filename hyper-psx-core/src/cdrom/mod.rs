@@ -0,0 +1,494 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+mod commands;
+pub(crate) mod disc;
+
+use crate::cdrom::disc::Disc;
+
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, mem};
+
+/// The interrupt types the controller responds with
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) enum ResponseInterrupt {
+    /// INT1 - Received SECOND (or further) response to ReadS/ReadN (and Play+Report)
+    DataReady = 1,
+
+    /// INT2 - Received SECOND response (to various commands)
+    Complete = 2,
+
+    /// INT3 - Received FIRST response (to any command)
+    Acknowledge = 3,
+
+    /// INT4 - DataEnd (when Play/Forward reaches end of disk) (maybe also for Read?)
+    DataEnd = 4,
+
+    /// INT5 - Received error-code (in FIRST or SECOND response)
+    Error = 5,
+}
+
+/// The drive state
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) enum DriveState {
+    /// The drive is idle
+    #[default]
+    Idle,
+
+    /// The drive is seeking
+    Seeking,
+
+    /// The drive is reading data sectors
+    Reading,
+}
+
+/// A queued response of the controller
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Response {
+    /// The interrupt raised with the response
+    interrupt: ResponseInterrupt,
+
+    /// The response bytes
+    data: Vec<u8>,
+
+    /// The cycles until the response is delivered
+    delay: u32,
+}
+
+/// The CDROM component
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Cdrom {
+    /// The index selecting the register bank
+    index: u8,
+
+    /// The parameter FIFO
+    parameters: VecDeque<u8>,
+
+    /// The response FIFO
+    response: VecDeque<u8>,
+
+    /// The data FIFO
+    data: VecDeque<u8>,
+
+    /// The interrupt enable register
+    interrupt_enable: u8,
+
+    /// The interrupt flag register
+    interrupt_flag: u8,
+
+    /// The responses waiting to be delivered
+    pending_responses: VecDeque<Response>,
+
+    /// The drive mode set by Setmode
+    mode: u8,
+
+    /// The drive state
+    drive_state: DriveState,
+
+    /// The target sector set by Setloc
+    seek_target: u32,
+
+    /// The current sector of the drive head
+    position: u32,
+
+    /// The cycles until the next sector is read
+    read_countdown: u32,
+
+    /// The data of the last read sector
+    sector: Vec<u8>,
+
+    /// The inserted disc
+    #[serde(skip)]
+    disc: Option<Disc>,
+}
+
+impl Cdrom {
+    /// The cycles until the first response (INT3) of a command is delivered
+    const FIRST_RESPONSE_DELAY: u32 = 50_000;
+
+    /// The cycles until the second response (INT2) of a command is delivered
+    const SECOND_RESPONSE_DELAY: u32 = 100_000;
+
+    /// The cycles to read one sector at single speed (33868800 / 75)
+    const SINGLE_SPEED_SECTOR_CYCLES: u32 = 451_584;
+
+    /// The size of the response FIFO
+    const FIFO_SIZE: usize = 16;
+
+    /// Creates a CDROM component
+    ///
+    /// # Arguments:
+    ///
+    /// * `disc`: The inserted disc
+    pub(crate) fn new(disc: Option<Disc>) -> Self {
+        Self {
+            index: 0,
+            parameters: VecDeque::with_capacity(Self::FIFO_SIZE),
+            response: VecDeque::with_capacity(Self::FIFO_SIZE),
+            data: VecDeque::new(),
+            interrupt_enable: 0,
+            interrupt_flag: 0,
+            pending_responses: VecDeque::new(),
+            mode: 0,
+            drive_state: DriveState::default(),
+            seek_target: 0,
+            position: 0,
+            read_countdown: 0,
+            sector: Vec::new(),
+            disc,
+        }
+    }
+
+    /// Moves the inserted disc from the previous CDROM into this restored one
+    ///
+    /// # Arguments:
+    ///
+    /// * `previous`: The CDROM which gets replaced
+    pub(crate) fn reattach(&mut self, previous: &mut Self) {
+        mem::swap(&mut self.disc, &mut previous.disc);
+    }
+
+    /// Executes a number of cycles
+    ///
+    /// Arguments:
+    ///
+    /// * `cycles`: The amount of cycles which passed
+    ///
+    /// Returns if the CDROM interrupt (IRQ2) should be requested
+    pub(crate) fn step(&mut self, cycles: u32) -> bool {
+        if self.drive_state == DriveState::Reading {
+            if self.read_countdown > cycles {
+                self.read_countdown -= cycles;
+            } else {
+                self.read_countdown = self.sector_cycles();
+                self.read_sector();
+            }
+        }
+
+        let Some(response) = self.pending_responses.front_mut() else {
+            return false;
+        };
+
+        response.delay = response.delay.saturating_sub(cycles);
+
+        // A new response is only delivered after the previous one was acknowledged
+        if response.delay != 0 || (self.interrupt_flag & 0x07) != 0 {
+            return false;
+        }
+
+        let response = self.pending_responses.pop_front().unwrap();
+
+        log::debug!(
+            target: "cdrom",
+            "Delivering {:?} with {:02x?}",
+            response.interrupt,
+            response.data
+        );
+
+        self.response.clear();
+        self.response.extend(response.data);
+        self.interrupt_flag = response.interrupt as u8;
+
+        (self.interrupt_flag & self.interrupt_enable & 0x1f) != 0
+    }
+
+    /// Returns the cycles to read one sector in the current mode
+    fn sector_cycles(&self) -> u32 {
+        // Setmode bit 7 selects double speed
+        if self.mode & 0x80 != 0 {
+            Self::SINGLE_SPEED_SECTOR_CYCLES / 2
+        } else {
+            Self::SINGLE_SPEED_SECTOR_CYCLES
+        }
+    }
+
+    /// Reads the sector under the drive head and queues the data ready response
+    fn read_sector(&mut self) {
+        let Some(disc) = &mut self.disc else {
+            return;
+        };
+
+        let mut raw_sector = [0x00; Disc::SECTOR_SIZE];
+        if let Err(error) = disc.read_sector(self.position, &mut raw_sector) {
+            log::warn!(target: "cdrom", "Failed to read sector: {}", error);
+            self.drive_state = DriveState::Idle;
+
+            let status = self.status();
+            self.push_response(ResponseInterrupt::Error, vec![status | 0x01, 0x40], 0);
+            return;
+        }
+
+        // Setmode bit 5 selects the whole sector (0x924 bytes) instead of the data (0x800 bytes)
+        self.sector = if self.mode & 0x20 != 0 {
+            raw_sector[12..12 + 0x924].to_vec()
+        } else {
+            raw_sector[24..24 + 0x800].to_vec()
+        };
+
+        self.position += 1;
+
+        // Replace a data ready response which wasn't delivered yet
+        self.pending_responses
+            .retain(|response| response.interrupt != ResponseInterrupt::DataReady);
+
+        let status = self.status();
+        self.push_response(ResponseInterrupt::DataReady, vec![status], 0);
+    }
+
+    /// Returns the drive status byte
+    ///
+    /// <https://psx-spx.consoledev.net/cdromdrive/#status-code-stat>
+    fn status(&self) -> u8 {
+        let mut status = 0;
+
+        if self.disc.is_some() {
+            // Spindle motor on
+            status |= 1 << 1;
+        } else {
+            // Shell open
+            status |= 1 << 4;
+        }
+
+        status |= match self.drive_state {
+            DriveState::Idle => 0,
+            DriveState::Seeking => 1 << 6,
+            DriveState::Reading => 1 << 5,
+        };
+
+        status
+    }
+
+    /// Queues a response
+    ///
+    /// Arguments:
+    ///
+    /// * `interrupt`: The interrupt raised with the response
+    /// * `data`: The response bytes
+    /// * `delay`: The cycles until the response is delivered
+    fn push_response(&mut self, interrupt: ResponseInterrupt, data: Vec<u8>, delay: u32) {
+        self.pending_responses.push_back(Response {
+            interrupt,
+            data,
+            delay,
+        });
+    }
+
+    /// Reads a word from the data FIFO for DMA transfers
+    pub(crate) fn dma_read_word(&mut self) -> u32 {
+        let byte_0 = self.read_data() as u32;
+        let byte_1 = self.read_data() as u32;
+        let byte_2 = self.read_data() as u32;
+        let byte_3 = self.read_data() as u32;
+
+        (byte_3 << 24) | (byte_2 << 16) | (byte_1 << 8) | byte_0
+    }
+
+    /// Pops a byte from the data FIFO
+    fn read_data(&mut self) -> u8 {
+        self.data.pop_front().unwrap_or(0x00)
+    }
+
+    /// Reads a byte from a CDROM register
+    ///
+    /// Unlike regular memory, reading the FIFOs consumes their contents
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative address offset
+    pub(crate) fn read_u8(&mut self, offset: u32) -> u8 {
+        match offset {
+            0x00 => {
+                let mut value = self.index;
+                value |= (self.parameters.is_empty() as u8) << 3;
+                value |= ((self.parameters.len() < Self::FIFO_SIZE) as u8) << 4;
+                value |= (!self.response.is_empty() as u8) << 5;
+                value |= (!self.data.is_empty() as u8) << 6;
+                value
+            }
+            0x01 => self.response.pop_front().unwrap_or(0x00),
+            0x02 => self.read_data(),
+            0x03 => match self.index {
+                0 | 2 => self.interrupt_enable | 0xe0,
+                1 | 3 => self.interrupt_flag | 0xe0,
+                _ => unreachable!(),
+            },
+            _ => unreachable!("read from cdrom at {:#04x}", offset),
+        }
+    }
+
+    /// Writes a byte to a CDROM register
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative address offset
+    /// * `value`: The value to be written
+    pub(crate) fn write_u8(&mut self, offset: u32, value: u8) {
+        match (offset, self.index) {
+            (0x00, _) => {
+                self.index = value & 0x3;
+            }
+            (0x01, 0) => self.command(value),
+            (0x02, 0) => {
+                if self.parameters.len() < Self::FIFO_SIZE {
+                    self.parameters.push_back(value);
+                }
+            }
+            (0x02, 1) => {
+                self.interrupt_enable = value & 0x1f;
+            }
+            (0x03, 0) => {
+                // Request register bit 7 loads the sector into the data FIFO
+                if value & 0x80 != 0 {
+                    if self.data.is_empty() {
+                        self.data.extend(self.sector.iter());
+                    }
+                } else {
+                    self.data.clear();
+                }
+            }
+            (0x03, 1) => {
+                // Writing 1 acknowledges the interrupt flag
+                self.interrupt_flag &= !(value & 0x1f);
+
+                if value & 0x40 != 0 {
+                    self.parameters.clear();
+                }
+            }
+            (0x01..=0x03, _) => {
+                // TODO: Implement audio volume registers
+            }
+            _ => unreachable!(
+                "write to cdrom at {:#04x} with value {:#04x}",
+                offset, value
+            ),
+        }
+    }
+
+    /// Executes a command with the current parameters
+    ///
+    /// # Arguments:
+    ///
+    /// * `command`: The command byte
+    fn command(&mut self, command: u8) {
+        log::debug!(
+            target: "cdrom",
+            "Command {:#04x} with parameters {:02x?}",
+            command,
+            self.parameters
+        );
+
+        match command {
+            0x01 => self.op_getstat(),
+            0x02 => self.op_setloc(),
+            0x06 | 0x1b => self.op_read(),
+            0x08 => self.op_stop(),
+            0x09 => self.op_pause(),
+            0x0a => self.op_init(),
+            0x0b => self.op_mute(),
+            0x0c => self.op_demute(),
+            0x0d => self.op_setfilter(),
+            0x0e => self.op_setmode(),
+            0x13 => self.op_get_tn(),
+            0x14 => self.op_get_td(),
+            0x15 | 0x16 => self.op_seek(),
+            0x19 => self.op_test(),
+            0x1a => self.op_get_id(),
+            0x1e => self.op_read_toc(),
+            _ => {
+                log::warn!(target: "cdrom", "Unhandled command {:#04x}", command);
+                self.error(0x40);
+            }
+        }
+
+        self.parameters.clear();
+    }
+
+    /// Responds with an error
+    ///
+    /// # Arguments:
+    ///
+    /// * `code`: The error code
+    fn error(&mut self, code: u8) {
+        let status = self.status();
+        self.push_response(
+            ResponseInterrupt::Error,
+            vec![status | 0x01, code],
+            Self::FIRST_RESPONSE_DELAY,
+        );
+    }
+}
+
+/// Converts a BCD byte to binary
+///
+/// # Arguments:
+///
+/// * `value`: The BCD value
+fn from_bcd(value: u8) -> u8 {
+    (value >> 4) * 10 + (value & 0xf)
+}
+
+/// Converts a binary byte to BCD
+///
+/// # Arguments:
+///
+/// * `value`: The binary value
+fn to_bcd(value: u8) -> u8 {
+    ((value / 10) << 4) | (value % 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn getstat_responds_after_the_delay() {
+        let mut cdrom = Cdrom::new(None);
+
+        // Enable every interrupt, then issue Getstat from bank 0
+        cdrom.write_u8(0x00, 0x01);
+        cdrom.write_u8(0x02, 0x1f);
+        cdrom.write_u8(0x00, 0x00);
+        cdrom.write_u8(0x01, 0x01);
+
+        assert!(!cdrom.step(Cdrom::FIRST_RESPONSE_DELAY - 1));
+        assert_eq!(cdrom.read_u8(0x00) & 0x20, 0);
+
+        assert!(cdrom.step(1));
+        assert_eq!(cdrom.read_u8(0x00) & 0x20, 0x20);
+
+        // Shell open without a disc
+        assert_eq!(cdrom.read_u8(0x01), 0x10);
+        assert_eq!(cdrom.read_u8(0x00) & 0x20, 0);
+        assert_eq!(cdrom.read_u8(0x01), 0x00);
+
+        cdrom.write_u8(0x00, 0x01);
+        assert_eq!(
+            cdrom.read_u8(0x03),
+            0xe0 | ResponseInterrupt::Acknowledge as u8
+        );
+
+        cdrom.write_u8(0x03, 0x1f);
+        assert_eq!(cdrom.read_u8(0x03), 0xe0);
+    }
+
+    #[test]
+    fn second_response_waits_for_the_acknowledge() {
+        let mut cdrom = Cdrom::new(None);
+
+        cdrom.write_u8(0x01, 0x01);
+        cdrom.write_u8(0x01, 0x01);
+
+        cdrom.step(Cdrom::FIRST_RESPONSE_DELAY);
+        cdrom.step(Cdrom::FIRST_RESPONSE_DELAY);
+        assert_eq!(cdrom.pending_responses.len(), 1);
+
+        cdrom.write_u8(0x00, 0x01);
+        cdrom.write_u8(0x03, 0x07);
+        cdrom.step(1);
+        assert!(cdrom.pending_responses.is_empty());
+    }
+}
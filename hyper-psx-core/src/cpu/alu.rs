@@ -0,0 +1,214 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! The pure arithmetic of the CPU, kept free of any CPU state and of `std`
+
+/// Adds two signed words
+///
+/// # Arguments:
+///
+/// * `a`: The first operand
+/// * `b`: The second operand
+///
+/// Returns none if the signed addition overflows
+pub(super) fn add(a: u32, b: u32) -> Option<u32> {
+    (a as i32).checked_add(b as i32).map(|result| result as u32)
+}
+
+/// Subtracts two signed words
+///
+/// # Arguments:
+///
+/// * `a`: The minuend
+/// * `b`: The subtrahend
+///
+/// Returns none if the signed subtraction overflows
+pub(super) fn sub(a: u32, b: u32) -> Option<u32> {
+    (a as i32).checked_sub(b as i32).map(|result| result as u32)
+}
+
+/// Shifts a word left, filling with zeros
+///
+/// # Arguments:
+///
+/// * `value`: The shifted word
+/// * `amount`: The shift amount, only the lower 5 bits are used
+pub(super) fn shift_left_logical(value: u32, amount: u32) -> u32 {
+    value.wrapping_shl(amount)
+}
+
+/// Shifts a word right, filling with zeros
+///
+/// # Arguments:
+///
+/// * `value`: The shifted word
+/// * `amount`: The shift amount, only the lower 5 bits are used
+pub(super) fn shift_right_logical(value: u32, amount: u32) -> u32 {
+    value.wrapping_shr(amount)
+}
+
+/// Shifts a word right, filling with the sign bit
+///
+/// # Arguments:
+///
+/// * `value`: The shifted word
+/// * `amount`: The shift amount, only the lower 5 bits are used
+pub(super) fn shift_right_arithmetic(value: u32, amount: u32) -> u32 {
+    (value as i32).wrapping_shr(amount) as u32
+}
+
+/// Compares two signed words
+///
+/// # Arguments:
+///
+/// * `a`: The first operand
+/// * `b`: The second operand
+///
+/// Returns 1 if `a` is less than `b`, otherwise 0
+pub(super) fn set_less_than(a: u32, b: u32) -> u32 {
+    ((a as i32) < (b as i32)) as u32
+}
+
+/// Compares two unsigned words
+///
+/// # Arguments:
+///
+/// * `a`: The first operand
+/// * `b`: The second operand
+///
+/// Returns 1 if `a` is less than `b`, otherwise 0
+pub(super) fn set_less_than_unsigned(a: u32, b: u32) -> u32 {
+    (a < b) as u32
+}
+
+/// Merges the upper bytes of an unaligned word into a register (LWL)
+///
+/// # Arguments:
+///
+/// * `register`: The current register value
+/// * `word`: The aligned word containing the address
+/// * `address`: The unaligned address
+pub(super) fn load_word_left(register: u32, word: u32, address: u32) -> u32 {
+    match address & 0x3 {
+        0 => (register & 0x00ffffff) | (word << 24),
+        1 => (register & 0x0000ffff) | (word << 16),
+        2 => (register & 0x000000ff) | (word << 8),
+        3 => word,
+        _ => unreachable!(),
+    }
+}
+
+/// Merges the lower bytes of an unaligned word into a register (LWR)
+///
+/// # Arguments:
+///
+/// * `register`: The current register value
+/// * `word`: The aligned word containing the address
+/// * `address`: The unaligned address
+pub(super) fn load_word_right(register: u32, word: u32, address: u32) -> u32 {
+    match address & 0x3 {
+        0 => word,
+        1 => (register & 0xff000000) | (word >> 8),
+        2 => (register & 0xffff0000) | (word >> 16),
+        3 => (register & 0xffffff00) | (word >> 24),
+        _ => unreachable!(),
+    }
+}
+
+/// Merges the upper bytes of a register into an aligned word of memory (SWL)
+///
+/// # Arguments:
+///
+/// * `word`: The aligned word containing the address
+/// * `register`: The stored register value
+/// * `address`: The unaligned address
+pub(super) fn store_word_left(word: u32, register: u32, address: u32) -> u32 {
+    match address & 0x3 {
+        0 => (word & 0xffffff00) | (register >> 24),
+        1 => (word & 0xffff0000) | (register >> 16),
+        2 => (word & 0xff000000) | (register >> 8),
+        3 => register,
+        _ => unreachable!(),
+    }
+}
+
+/// Merges the lower bytes of a register into an aligned word of memory (SWR)
+///
+/// # Arguments:
+///
+/// * `word`: The aligned word containing the address
+/// * `register`: The stored register value
+/// * `address`: The unaligned address
+pub(super) fn store_word_right(word: u32, register: u32, address: u32) -> u32 {
+    match address & 0x3 {
+        0 => register,
+        1 => (word & 0x000000ff) | (register << 8),
+        2 => (word & 0x0000ffff) | (register << 16),
+        3 => (word & 0x00ffffff) | (register << 24),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_and_sub_detect_signed_overflow() {
+        assert_eq!(add(0x7fffffff, 1), None);
+        assert_eq!(add(0xffffffff, 1), Some(0));
+        assert_eq!(sub(0x80000000, 1), None);
+        assert_eq!(sub(0, 1), Some(0xffffffff));
+    }
+
+    #[test]
+    fn shifts_only_use_the_lower_5_bits_of_the_amount() {
+        assert_eq!(shift_left_logical(0x00000001, 32), 0x00000001);
+        assert_eq!(shift_left_logical(0x00000001, 33), 0x00000002);
+        assert_eq!(shift_right_logical(0x80000000, 63), 0x00000001);
+        assert_eq!(shift_right_arithmetic(0x80000000, 36), 0xf8000000);
+    }
+
+    #[test]
+    fn unaligned_loads_merge_every_offset() {
+        let register = 0x11223344;
+        let word = 0xaabbccdd;
+
+        let left = [0xdd223344, 0xccdd3344, 0xbbccdd44, 0xaabbccdd];
+        let right = [0xaabbccdd, 0x11aabbcc, 0x1122aabb, 0x112233aa];
+        for offset in 0..4 {
+            let address = 0x80001000 + offset;
+            assert_eq!(
+                load_word_left(register, word, address),
+                left[offset as usize]
+            );
+            assert_eq!(
+                load_word_right(register, word, address),
+                right[offset as usize]
+            );
+        }
+    }
+
+    #[test]
+    fn unaligned_stores_merge_every_offset() {
+        let word = 0xaabbccdd;
+        let register = 0x11223344;
+
+        let left = [0xaabbcc11, 0xaabb1122, 0xaa112233, 0x11223344];
+        let right = [0x11223344, 0x223344dd, 0x3344ccdd, 0x44bbccdd];
+        for offset in 0..4 {
+            let address = 0x80001000 + offset;
+            assert_eq!(
+                store_word_left(word, register, address),
+                left[offset as usize]
+            );
+            assert_eq!(
+                store_word_right(word, register, address),
+                right[offset as usize]
+            );
+        }
+    }
+}
@@ -0,0 +1,244 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+/// Returns the name of a BIOS function, none if it is unknown
+///
+/// <https://psx-spx.consoledev.net/kernelbios/#bios-function-summary>
+///
+/// # Arguments:
+///
+/// * `gate`: The address of the call gate, 0xa0, 0xb0 or 0xc0
+/// * `function`: The function number passed in $t1
+pub(super) fn function_name(gate: u32, function: u32) -> Option<&'static str> {
+    let name = match (gate, function) {
+        (0xa0, 0x00) => "FileOpen",
+        (0xa0, 0x01) => "FileSeek",
+        (0xa0, 0x02) => "FileRead",
+        (0xa0, 0x03) => "FileWrite",
+        (0xa0, 0x04) => "FileClose",
+        (0xa0, 0x05) => "FileIoctl",
+        (0xa0, 0x06) => "exit",
+        (0xa0, 0x07) => "FileGetDeviceFlag",
+        (0xa0, 0x08) => "FileGetc",
+        (0xa0, 0x09) => "FilePutc",
+        (0xa0, 0x13) => "SaveState",
+        (0xa0, 0x14) => "RestoreState",
+        (0xa0, 0x15) => "strcat",
+        (0xa0, 0x16) => "strncat",
+        (0xa0, 0x17) => "strcmp",
+        (0xa0, 0x18) => "strncmp",
+        (0xa0, 0x19) => "strcpy",
+        (0xa0, 0x1a) => "strncpy",
+        (0xa0, 0x1b) => "strlen",
+        (0xa0, 0x1c) => "index",
+        (0xa0, 0x1d) => "rindex",
+        (0xa0, 0x1e) => "strchr",
+        (0xa0, 0x1f) => "strrchr",
+        (0xa0, 0x25) => "toupper",
+        (0xa0, 0x26) => "tolower",
+        (0xa0, 0x28) => "bzero",
+        (0xa0, 0x2a) => "memcpy",
+        (0xa0, 0x2b) => "memset",
+        (0xa0, 0x2c) => "memmove",
+        (0xa0, 0x2f) => "rand",
+        (0xa0, 0x30) => "srand",
+        (0xa0, 0x33) => "malloc",
+        (0xa0, 0x34) => "free",
+        (0xa0, 0x37) => "calloc",
+        (0xa0, 0x38) => "realloc",
+        (0xa0, 0x39) => "InitHeap",
+        (0xa0, 0x3a) => "SystemErrorExit",
+        (0xa0, 0x3b) => "std_in_getchar",
+        (0xa0, 0x3c) => "std_out_putchar",
+        (0xa0, 0x3d) => "std_in_gets",
+        (0xa0, 0x3e) => "std_out_puts",
+        (0xa0, 0x3f) => "printf",
+        (0xa0, 0x40) => "SystemErrorUnresolvedException",
+        (0xa0, 0x41) => "LoadExeHeader",
+        (0xa0, 0x42) => "LoadExeFile",
+        (0xa0, 0x43) => "DoExecute",
+        (0xa0, 0x44) => "FlushCache",
+        (0xa0, 0x45) => "init_a0_b0_c0_vectors",
+        (0xa0, 0x46) => "GPU_dw",
+        (0xa0, 0x47) => "gpu_send_dma",
+        (0xa0, 0x48) => "SendGP1Command",
+        (0xa0, 0x49) => "GPU_cw",
+        (0xa0, 0x4a) => "GPU_cwp",
+        (0xa0, 0x4b) => "send_gpu_linked_list",
+        (0xa0, 0x4c) => "gpu_abort_dma",
+        (0xa0, 0x4d) => "GetGPUStatus",
+        (0xa0, 0x4e) => "gpu_sync",
+        (0xa0, 0x51) => "LoadAndExecute",
+        (0xa0, 0x54) => "CdInit",
+        (0xa0, 0x55) => "_bu_init",
+        (0xa0, 0x56) => "CdRemove",
+        (0xa0, 0x5b) => "dev_tty_init",
+        (0xa0, 0x5c) => "dev_tty_open",
+        (0xa0, 0x5e) => "dev_tty_ioctl",
+        (0xa0, 0x5f) => "dev_cd_open",
+        (0xa0, 0x60) => "dev_cd_read",
+        (0xa0, 0x62) => "dev_cd_firstfile",
+        (0xa0, 0x63) => "dev_cd_nextfile",
+        (0xa0, 0x64) => "dev_cd_chdir",
+        (0xa0, 0x65) => "dev_card_open",
+        (0xa0, 0x66) => "dev_card_read",
+        (0xa0, 0x67) => "dev_card_write",
+        (0xa0, 0x68) => "dev_card_close",
+        (0xa0, 0x69) => "dev_card_firstfile",
+        (0xa0, 0x6a) => "dev_card_nextfile",
+        (0xa0, 0x6b) => "dev_card_erase",
+        (0xa0, 0x6c) => "dev_card_undelete",
+        (0xa0, 0x6d) => "dev_card_format",
+        (0xa0, 0x6e) => "dev_card_rename",
+        (0xa0, 0x70) => "_bu_init",
+        (0xa0, 0x71) => "CdInit",
+        (0xa0, 0x72) => "CdRemove",
+        (0xa0, 0x78) => "CdAsyncSeekL",
+        (0xa0, 0x7c) => "CdAsyncGetStatus",
+        (0xa0, 0x7e) => "CdAsyncReadSector",
+        (0xa0, 0x81) => "CdAsyncSetMode",
+        (0xa0, 0x90) => "CdromIoIrqFunc1",
+        (0xa0, 0x91) => "CdromDmaIrqFunc1",
+        (0xa0, 0x92) => "CdromIoIrqFunc2",
+        (0xa0, 0x93) => "CdromDmaIrqFunc2",
+        (0xa0, 0x94) => "CdromGetInt5errCode",
+        (0xa0, 0x95) => "CdInitSubFunc",
+        (0xa0, 0x96) => "AddCDROMDevice",
+        (0xa0, 0x97) => "AddMemCardDevice",
+        (0xa0, 0x98) => "AddDuartTtyDevice",
+        (0xa0, 0x99) => "AddDummyTtyDevice",
+        (0xa0, 0x9c) => "SetConf",
+        (0xa0, 0x9d) => "GetConf",
+        (0xa0, 0x9e) => "SetCdromIrqAutoAbort",
+        (0xa0, 0x9f) => "SetMemSize",
+        (0xa0, 0xa0) => "WarmBoot",
+        (0xa0, 0xa1) => "SystemErrorBootOrDiskFailure",
+        (0xa0, 0xa2) => "EnqueueCdIntr",
+        (0xa0, 0xa3) => "DequeueCdIntr",
+        (0xa0, 0xa4) => "CdGetLbn",
+        (0xa0, 0xa5) => "CdReadSector",
+        (0xa0, 0xa6) => "CdGetStatus",
+        (0xa0, 0xa7) => "bufs_cb_0",
+        (0xa0, 0xab) => "_card_info",
+        (0xa0, 0xac) => "_card_load",
+        (0xa0, 0xad) => "set_card_auto_format",
+        (0xa0, 0xaf) => "card_write_test",
+        (0xa0, 0xb4) => "GetSystemInfo",
+        (0xb0, 0x00) => "alloc_kernel_memory",
+        (0xb0, 0x01) => "free_kernel_memory",
+        (0xb0, 0x02) => "init_timer",
+        (0xb0, 0x03) => "get_timer",
+        (0xb0, 0x04) => "enable_timer_irq",
+        (0xb0, 0x05) => "disable_timer_irq",
+        (0xb0, 0x06) => "restart_timer",
+        (0xb0, 0x07) => "DeliverEvent",
+        (0xb0, 0x08) => "OpenEvent",
+        (0xb0, 0x09) => "CloseEvent",
+        (0xb0, 0x0a) => "WaitEvent",
+        (0xb0, 0x0b) => "TestEvent",
+        (0xb0, 0x0c) => "EnableEvent",
+        (0xb0, 0x0d) => "DisableEvent",
+        (0xb0, 0x0e) => "OpenThread",
+        (0xb0, 0x0f) => "CloseThread",
+        (0xb0, 0x10) => "ChangeThread",
+        (0xb0, 0x12) => "InitPad",
+        (0xb0, 0x13) => "StartPad",
+        (0xb0, 0x14) => "StopPad",
+        (0xb0, 0x15) => "OutdatedPadInitAndStart",
+        (0xb0, 0x16) => "OutdatedPadGetButtons",
+        (0xb0, 0x17) => "ReturnFromException",
+        (0xb0, 0x18) => "SetDefaultExitFromException",
+        (0xb0, 0x19) => "SetCustomExitFromException",
+        (0xb0, 0x20) => "UnDeliverEvent",
+        (0xb0, 0x32) => "FileOpen",
+        (0xb0, 0x33) => "FileSeek",
+        (0xb0, 0x34) => "FileRead",
+        (0xb0, 0x35) => "FileWrite",
+        (0xb0, 0x36) => "FileClose",
+        (0xb0, 0x37) => "FileIoctl",
+        (0xb0, 0x38) => "exit",
+        (0xb0, 0x39) => "FileGetDeviceFlag",
+        (0xb0, 0x3a) => "FileGetc",
+        (0xb0, 0x3b) => "FilePutc",
+        (0xb0, 0x3c) => "std_in_getchar",
+        (0xb0, 0x3d) => "std_out_putchar",
+        (0xb0, 0x3e) => "std_in_gets",
+        (0xb0, 0x3f) => "std_out_puts",
+        (0xb0, 0x40) => "chdir",
+        (0xb0, 0x41) => "FormatDevice",
+        (0xb0, 0x42) => "firstfile",
+        (0xb0, 0x43) => "nextfile",
+        (0xb0, 0x44) => "FileRename",
+        (0xb0, 0x45) => "FileDelete",
+        (0xb0, 0x46) => "FileUndelete",
+        (0xb0, 0x47) => "AddDevice",
+        (0xb0, 0x48) => "RemoveDevice",
+        (0xb0, 0x49) => "PrintInstalledDevices",
+        (0xb0, 0x4a) => "InitCard",
+        (0xb0, 0x4b) => "StartCard",
+        (0xb0, 0x4c) => "StopCard",
+        (0xb0, 0x4d) => "_card_info_subfunc",
+        (0xb0, 0x4e) => "write_card_sector",
+        (0xb0, 0x4f) => "read_card_sector",
+        (0xb0, 0x50) => "allow_new_card",
+        (0xb0, 0x51) => "Krom2RawAdd",
+        (0xb0, 0x53) => "Krom2Offset",
+        (0xb0, 0x54) => "GetLastError",
+        (0xb0, 0x55) => "GetLastFileError",
+        (0xb0, 0x56) => "GetC0Table",
+        (0xb0, 0x57) => "GetB0Table",
+        (0xb0, 0x58) => "get_bu_callback_port",
+        (0xb0, 0x59) => "testdevice",
+        (0xb0, 0x5b) => "ChangeClearPad",
+        (0xb0, 0x5c) => "get_card_status",
+        (0xb0, 0x5d) => "wait_card_status",
+        (0xc0, 0x00) => "EnqueueTimerAndVblankIrqs",
+        (0xc0, 0x01) => "EnqueueSyscallHandler",
+        (0xc0, 0x02) => "SysEnqIntRP",
+        (0xc0, 0x03) => "SysDeqIntRP",
+        (0xc0, 0x04) => "get_free_EvCB_slot",
+        (0xc0, 0x05) => "get_free_TCB_slot",
+        (0xc0, 0x06) => "ExceptionHandler",
+        (0xc0, 0x07) => "InstallExceptionHandlers",
+        (0xc0, 0x08) => "SysInitMemory",
+        (0xc0, 0x09) => "SysInitKernelVariables",
+        (0xc0, 0x0a) => "ChangeClearRCnt",
+        (0xc0, 0x0c) => "InitDefInt",
+        (0xc0, 0x0d) => "SetIrqAutoAck",
+        (0xc0, 0x12) => "InstallDevices",
+        (0xc0, 0x13) => "FlushStdInOutPut",
+        (0xc0, 0x15) => "tty_cdevinput",
+        (0xc0, 0x16) => "tty_cdevscan",
+        (0xc0, 0x17) => "tty_circgetc",
+        (0xc0, 0x18) => "tty_circputc",
+        (0xc0, 0x19) => "ioabort",
+        (0xc0, 0x1a) => "set_card_find_mode",
+        (0xc0, 0x1b) => "KernelRedirect",
+        (0xc0, 0x1c) => "AdjustA0Table",
+        (0xc0, 0x1d) => "get_card_find_mode",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// Returns the name of a BIOS SYSCALL function
+///
+/// <https://psx-spx.consoledev.net/kernelbios/#syscall-functions>
+///
+/// # Arguments:
+///
+/// * `function`: The function number passed in $a0
+pub(super) fn syscall_name(function: u32) -> &'static str {
+    match function {
+        0x00 => "NoFunction",
+        0x01 => "EnterCriticalSection",
+        0x02 => "ExitCriticalSection",
+        0x03 => "ChangeThreadSubFunction",
+        // Every other function number delivers an event
+        _ => "DeliverEvent",
+    }
+}
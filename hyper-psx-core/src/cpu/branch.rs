@@ -24,15 +24,6 @@ impl Cpu {
         let s = self.register(rs) as i32;
         let address_offset = offset.sign_extend() << 2;
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: BLTZ {}, {}",
-            self.n,
-            instruction.1,
-            rs,
-            address_offset as i32
-        );
-
         if s < 0 {
             self.branch(address_offset);
         }
@@ -52,15 +43,6 @@ impl Cpu {
         let s = self.register(rs) as i32;
         let address_offset = offset.sign_extend() << 2;
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: BGEZ {}, {}",
-            self.n,
-            instruction.1,
-            rs,
-            address_offset as i32
-        );
-
         if s >= 0 {
             self.branch(address_offset);
         }
@@ -80,15 +62,6 @@ impl Cpu {
         let s = self.register(rs) as i32;
         let address_offset = offset.sign_extend() << 2;
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: BLTZAL {}, {}",
-            self.n,
-            instruction.1,
-            rs,
-            address_offset as i32
-        );
-
         self.set_register(Register::Ra, self.pc + 4);
 
         if s < 0 {
@@ -110,15 +83,6 @@ impl Cpu {
         let s = self.register(rs) as i32;
         let address_offset = offset.sign_extend() << 2;
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: BGEZ {}, {}",
-            self.n,
-            instruction.1,
-            rs,
-            address_offset as i32
-        );
-
         self.set_register(Register::Ra, self.pc + 4);
 
         if s >= 0 {
@@ -0,0 +1,130 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::cpu::{
+    exception::{Access, Exception},
+    instruction::Instruction,
+    register::Cop0Register,
+    Cpu,
+};
+
+/// DCIC status bit of any break
+const STATUS_ANY_BREAK: u32 = 1 << 0;
+
+/// DCIC status bit of a BPC code break
+const STATUS_CODE_BREAK: u32 = 1 << 1;
+
+/// DCIC status bit of a BDA data break
+const STATUS_DATA_BREAK: u32 = 1 << 2;
+
+/// DCIC status bit of a BDA data-read break
+const STATUS_DATA_READ_BREAK: u32 = 1 << 3;
+
+/// DCIC status bit of a BDA data-write break
+const STATUS_DATA_WRITE_BREAK: u32 = 1 << 4;
+
+/// DCIC enable bit of the execution breakpoint
+const ENABLE_CODE_BREAK: u32 = 1 << 24;
+
+/// DCIC enable bit of the data access breakpoint
+const ENABLE_DATA_BREAK: u32 = 1 << 25;
+
+/// DCIC enable bit of breaking on data reads
+const ENABLE_DATA_READ_BREAK: u32 = 1 << 26;
+
+/// DCIC enable bit of breaking on data writes
+const ENABLE_DATA_WRITE_BREAK: u32 = 1 << 27;
+
+/// DCIC super-master enables (bit 23 and 31) and master enable (bit 30) of bits 24-27
+const MASTER_ENABLE: u32 = (1 << 23) | (1 << 30) | (1 << 31);
+
+impl Cpu {
+    /// Raises the debug exception if the execution breakpoint matches the instruction
+    ///
+    /// # Arguments:
+    ///
+    /// * `instruction`: The instruction about to be executed
+    ///
+    /// Returns if the breakpoint was hit
+    ///
+    /// <https://psx-spx.consoledev.net/cpuspecifications/#cop0-debug-registers>
+    pub(super) fn code_breakpoint(&mut self, instruction: Instruction) -> bool {
+        let dcic = self.cop0_register(Cop0Register::Dcic);
+        if dcic & (MASTER_ENABLE | ENABLE_CODE_BREAK) != MASTER_ENABLE | ENABLE_CODE_BREAK {
+            return false;
+        }
+
+        let bpc = self.cop0_register(Cop0Register::Bpc);
+        let bpcm = self.cop0_register(Cop0Register::Bpcm);
+        if (instruction.1 ^ bpc) & bpcm != 0 {
+            return false;
+        }
+
+        self.raise_debug_exception(instruction, STATUS_CODE_BREAK);
+        true
+    }
+
+    /// Raises the debug exception if the data access breakpoint matches a load or store
+    ///
+    /// # Arguments:
+    ///
+    /// * `instruction`: The instruction causing the access
+    /// * `address`: The absolute address of the access
+    /// * `access`: The kind of the access
+    ///
+    /// Returns if the breakpoint was hit
+    pub(super) fn data_breakpoint(
+        &mut self,
+        instruction: Instruction,
+        address: u32,
+        access: Access,
+    ) -> bool {
+        let dcic = self.cop0_register(Cop0Register::Dcic);
+        if dcic & (MASTER_ENABLE | ENABLE_DATA_BREAK) != MASTER_ENABLE | ENABLE_DATA_BREAK {
+            return false;
+        }
+
+        let (enable, status) = match access {
+            Access::Load => (ENABLE_DATA_READ_BREAK, STATUS_DATA_READ_BREAK),
+            Access::Store => (ENABLE_DATA_WRITE_BREAK, STATUS_DATA_WRITE_BREAK),
+            Access::Fetch => return false,
+        };
+
+        if dcic & enable == 0 {
+            return false;
+        }
+
+        let bda = self.cop0_register(Cop0Register::Bda);
+        let bdam = self.cop0_register(Cop0Register::Bdam);
+        if (address ^ bda) & bdam != 0 {
+            return false;
+        }
+
+        self.raise_debug_exception(instruction, STATUS_DATA_BREAK | status);
+        true
+    }
+
+    /// Records the break in DCIC and enters the debug exception vector
+    ///
+    /// # Arguments:
+    ///
+    /// * `instruction`: The instruction causing the break
+    /// * `status`: The DCIC status bits of the break
+    fn raise_debug_exception(&mut self, instruction: Instruction, status: u32) {
+        let dcic = self.cop0_register(Cop0Register::Dcic);
+        self.set_cop0_register(Cop0Register::Dcic, dcic | STATUS_ANY_BREAK | status);
+
+        log::debug!(
+            target: "cpu",
+            "{}: {:#010x}: hit breakpoint (DCIC status {:#04x})",
+            self.n,
+            instruction.1,
+            status
+        );
+
+        self.enter_exception(instruction.1, Exception::Bp, Self::DEBUG_VECTOR_OFFSET);
+    }
+}
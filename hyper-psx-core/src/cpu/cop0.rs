@@ -7,6 +7,34 @@
 use crate::cpu::{instruction::Instruction, register::Cop0Register, Cpu};
 
 impl Cpu {
+    /// The bits of CAUSE writable through MTC0
+    const CAUSE_WRITABLE_MASK: u32 = 0x00000300;
+
+    /// The processor ID read from PRID
+    pub(super) const PROCESSOR_ID: u32 = 0x00000002;
+
+    /// Returns the bits of a COP0 register which are writable through MTC0
+    ///
+    /// # Arguments:
+    ///
+    /// * `cop0_register`: The register to be written
+    fn cop0_write_mask(cop0_register: Cop0Register) -> u32 {
+        match cop0_register {
+            Cop0Register::Bpc
+            | Cop0Register::Bda
+            | Cop0Register::Dcic
+            | Cop0Register::Bdam
+            | Cop0Register::Bpcm
+            | Cop0Register::Sr => 0xffffffff,
+            // Only the software interrupt bits IP0 and IP1 of CAUSE are writable
+            Cop0Register::Cause => Self::CAUSE_WRITABLE_MASK,
+            Cop0Register::Jumpdest
+            | Cop0Register::Badvaddr
+            | Cop0Register::Epc
+            | Cop0Register::Prid => 0x00000000,
+        }
+    }
+
     /// Opcode MFC0 - Move From Coprocessor (0b00000)
     ///
     /// # Arguments:
@@ -20,17 +48,18 @@ impl Cpu {
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=257>
     pub(super) fn op_mfc0(&mut self, instruction: Instruction) {
         let rt = instruction.rt();
-        let rd = instruction.cop_rd();
-
-        let d = self.cop0_register(rd);
 
-        log::debug!(target: "cpu", "{}: {:#010x}: MFC0 {}, {}", self.n, instruction.1, rt, rd);
+        // Registers which don't exist read as zero
+        let d = instruction.cop_rd().map_or(0, |rd| self.cop0_register(rd));
 
         self.set_register(rt, d);
     }
 
     /// Opcode MTC0 - Move To Coprocessor (0b00100)
     ///
+    /// Interrupts are checked before each instruction, so a pending interrupt unmasked through
+    /// SR or raised through the CAUSE software bits is taken right after this instruction
+    ///
     /// # Arguments:
     ///
     /// * `instruction`: The current instruction data
@@ -42,13 +71,19 @@ impl Cpu {
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=260>
     pub(super) fn op_mtc0(&mut self, instruction: Instruction) {
         let rt = instruction.rt();
-        let rd = instruction.cop_rd();
+
+        // Writes to registers which don't exist get dropped
+        let Some(rd) = instruction.cop_rd() else {
+            return;
+        };
 
         let t = self.register(rt);
 
-        log::debug!(target: "cpu", "{}: {:#010x}: MTC0 {}, {}", self.n, instruction.1, rt, rd);
+        // Read-only bits keep their value
+        let mask = Self::cop0_write_mask(rd);
+        let value = (self.cop0_register(rd) & !mask) | (t & mask);
 
-        self.set_cop0_register(rd, t);
+        self.set_cop0_register(rd, value);
     }
 
     /// Opcode RFE - Restore from Exception (0b10000/0b010000)
@@ -62,11 +97,9 @@ impl Cpu {
     /// * Coprocessor unusable exception
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=325>
-    pub(super) fn op_rfe(&mut self, instruction: Instruction) {
+    pub(super) fn op_rfe(&mut self, _instruction: Instruction) {
         let mut sr = self.cop0_register(Cop0Register::Sr);
 
-        log::debug!(target: "cpu", "{}: {:#010x}: RFE", self.n, instruction.1);
-
         let mode = sr & 0x3f;
         sr &= !0x3f;
         sr |= mode >> 2;
@@ -35,16 +35,6 @@ impl Cpu {
         let address_offset = offset.sign_extend();
         let _address = self.register(base).wrapping_add(address_offset);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: LWC2 {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
         todo!();
     }
 
@@ -73,16 +63,6 @@ impl Cpu {
         let address_offset = offset.sign_extend();
         let _address = self.register(base).wrapping_add(address_offset);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SWC2 {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
         todo!();
     }
 }
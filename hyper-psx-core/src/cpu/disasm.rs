@@ -0,0 +1,269 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::{
+    cpu::{instruction::Instruction, register::Cop0Register},
+    utils::sext::SextExt,
+};
+
+/// Disassembles an instruction into its textual form
+///
+/// Branch and jump targets are resolved to absolute addresses, unknown encodings are shown as
+/// a raw `.word`
+///
+/// # Arguments:
+///
+/// * `instruction`: The instruction word
+/// * `pc`: The address of the instruction
+pub fn disassemble(instruction: u32, pc: u32) -> String {
+    let instruction = Instruction(instruction, pc);
+
+    match instruction.op() {
+        0b000000 => disassemble_special(instruction),
+        0b000001 => {
+            let mnemonic = match instruction.branch_op() {
+                0b00000 => "BLTZ",
+                0b00001 => "BGEZ",
+                0b10000 => "BLTZAL",
+                0b10001 => "BGEZAL",
+                _ => return word(instruction),
+            };
+
+            format!(
+                "{} {}, {:#x}",
+                mnemonic,
+                instruction.rs(),
+                branch_target(instruction)
+            )
+        }
+        0b000010 => format!("J {:#x}", jump_target(instruction)),
+        0b000011 => format!("JAL {:#x}", jump_target(instruction)),
+        0b000100 => format!(
+            "BEQ {}, {}, {:#x}",
+            instruction.rs(),
+            instruction.rt(),
+            branch_target(instruction)
+        ),
+        0b000101 => format!(
+            "BNE {}, {}, {:#x}",
+            instruction.rs(),
+            instruction.rt(),
+            branch_target(instruction)
+        ),
+        0b000110 => format!(
+            "BLEZ {}, {:#x}",
+            instruction.rs(),
+            branch_target(instruction)
+        ),
+        0b000111 => format!(
+            "BGTZ {}, {:#x}",
+            instruction.rs(),
+            branch_target(instruction)
+        ),
+        0b001000 => signed_immediate("ADDI", instruction),
+        0b001001 => signed_immediate("ADDIU", instruction),
+        0b001010 => signed_immediate("SLTI", instruction),
+        0b001011 => signed_immediate("SLTIU", instruction),
+        0b001100 => unsigned_immediate("ANDI", instruction),
+        0b001101 => unsigned_immediate("ORI", instruction),
+        0b001110 => unsigned_immediate("XORI", instruction),
+        0b001111 => format!("LUI {}, {:#x}", instruction.rt(), instruction.imm()),
+        0b010000 => match instruction.cop_op() {
+            0b00000 => format!("MFC0 {}, {}", instruction.rt(), cop0_register(instruction)),
+            0b00100 => format!("MTC0 {}, {}", instruction.rt(), cop0_register(instruction)),
+            0b10000 if instruction.funct() == 0b010000 => String::from("RFE"),
+            _ => word(instruction),
+        },
+        0b100000 => memory("LB", instruction),
+        0b100001 => memory("LH", instruction),
+        0b100010 => memory("LWL", instruction),
+        0b100011 => memory("LW", instruction),
+        0b100100 => memory("LBU", instruction),
+        0b100101 => memory("LHU", instruction),
+        0b100110 => memory("LWR", instruction),
+        0b101000 => memory("SB", instruction),
+        0b101001 => memory("SH", instruction),
+        0b101010 => memory("SWL", instruction),
+        0b101011 => memory("SW", instruction),
+        0b101110 => memory("SWR", instruction),
+        0b110010 => cop2_memory("LWC2", instruction),
+        0b111010 => cop2_memory("SWC2", instruction),
+        _ => word(instruction),
+    }
+}
+
+/// Disassembles an instruction of the SPECIAL opcode group
+///
+/// # Arguments:
+///
+/// * `instruction`: The instruction
+fn disassemble_special(instruction: Instruction) -> String {
+    let rs = instruction.rs();
+    let rt = instruction.rt();
+    let rd = instruction.rd();
+
+    match instruction.funct() {
+        0b000000 if instruction.0 == 0 => String::from("NOP"),
+        0b000000 => format!("SLL {}, {}, {}", rd, rt, instruction.shamt()),
+        0b000010 => format!("SRL {}, {}, {}", rd, rt, instruction.shamt()),
+        0b000011 => format!("SRA {}, {}, {}", rd, rt, instruction.shamt()),
+        0b000100 => format!("SLLV {}, {}, {}", rd, rt, rs),
+        0b000110 => format!("SRLV {}, {}, {}", rd, rt, rs),
+        0b000111 => format!("SRAV {}, {}, {}", rd, rt, rs),
+        0b001000 => format!("JR {}", rs),
+        0b001001 => format!("JALR {}, {}", rd, rs),
+        0b001100 => format!("SYSCALL {:#x}", instruction.code()),
+        0b001101 => format!("BREAK {:#x}", instruction.code()),
+        0b010000 => format!("MFHI {}", rd),
+        0b010001 => format!("MTHI {}", rs),
+        0b010010 => format!("MFLO {}", rd),
+        0b010011 => format!("MTLO {}", rs),
+        0b011000 => format!("MULT {}, {}", rs, rt),
+        0b011001 => format!("MULTU {}, {}", rs, rt),
+        0b011010 => format!("DIV {}, {}", rs, rt),
+        0b011011 => format!("DIVU {}, {}", rs, rt),
+        0b100000 => format!("ADD {}, {}, {}", rd, rs, rt),
+        0b100001 => format!("ADDU {}, {}, {}", rd, rs, rt),
+        0b100010 => format!("SUB {}, {}, {}", rd, rs, rt),
+        0b100011 => format!("SUBU {}, {}, {}", rd, rs, rt),
+        0b100100 => format!("AND {}, {}, {}", rd, rs, rt),
+        0b100101 => format!("OR {}, {}, {}", rd, rs, rt),
+        0b100110 => format!("XOR {}, {}, {}", rd, rs, rt),
+        0b100111 => format!("NOR {}, {}, {}", rd, rs, rt),
+        0b101010 => format!("SLT {}, {}, {}", rd, rs, rt),
+        0b101011 => format!("SLTU {}, {}, {}", rd, rs, rt),
+        _ => word(instruction),
+    }
+}
+
+/// Formats an instruction with a sign-extended immediate
+///
+/// # Arguments:
+///
+/// * `mnemonic`: The name of the instruction
+/// * `instruction`: The instruction
+fn signed_immediate(mnemonic: &str, instruction: Instruction) -> String {
+    format!(
+        "{} {}, {}, {}",
+        mnemonic,
+        instruction.rt(),
+        instruction.rs(),
+        instruction.imm() as i16
+    )
+}
+
+/// Formats an instruction with a zero-extended immediate
+///
+/// # Arguments:
+///
+/// * `mnemonic`: The name of the instruction
+/// * `instruction`: The instruction
+fn unsigned_immediate(mnemonic: &str, instruction: Instruction) -> String {
+    format!(
+        "{} {}, {}, {:#x}",
+        mnemonic,
+        instruction.rt(),
+        instruction.rs(),
+        instruction.imm()
+    )
+}
+
+/// Formats a load or store with its base register and offset
+///
+/// # Arguments:
+///
+/// * `mnemonic`: The name of the instruction
+/// * `instruction`: The instruction
+fn memory(mnemonic: &str, instruction: Instruction) -> String {
+    format!(
+        "{} {}, {}({})",
+        mnemonic,
+        instruction.rt(),
+        instruction.imm() as i16,
+        instruction.rs()
+    )
+}
+
+/// Formats a GTE load or store, which targets a GTE data register
+///
+/// # Arguments:
+///
+/// * `mnemonic`: The name of the instruction
+/// * `instruction`: The instruction
+fn cop2_memory(mnemonic: &str, instruction: Instruction) -> String {
+    format!(
+        "{} ${}, {}({})",
+        mnemonic,
+        (instruction.0 >> 16) & 0x1f,
+        instruction.imm() as i16,
+        instruction.rs()
+    )
+}
+
+/// Formats the COP0 register of an instruction, falling back to its number if it's unknown
+///
+/// # Arguments:
+///
+/// * `instruction`: The instruction
+fn cop0_register(instruction: Instruction) -> String {
+    let index = ((instruction.0 >> 11) & 0x1f) as u8;
+    match Cop0Register::try_from(index) {
+        Ok(cop0_register) => cop0_register.to_string(),
+        Err(index) => format!("${}", index),
+    }
+}
+
+/// Resolves the absolute target of a branch, relative to the delay slot
+///
+/// # Arguments:
+///
+/// * `instruction`: The instruction
+fn branch_target(instruction: Instruction) -> u32 {
+    let offset = instruction.imm().sign_extend() << 2;
+    instruction.1.wrapping_add(4).wrapping_add(offset)
+}
+
+/// Resolves the absolute target of a jump inside the region of the delay slot
+///
+/// # Arguments:
+///
+/// * `instruction`: The instruction
+fn jump_target(instruction: Instruction) -> u32 {
+    (instruction.1.wrapping_add(4) & 0xf0000000) | (instruction.target() << 2)
+}
+
+/// Formats an unknown encoding as a raw word
+///
+/// # Arguments:
+///
+/// * `instruction`: The instruction
+fn word(instruction: Instruction) -> String {
+    format!(".word {:#010x}", instruction.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_word_is_nop() {
+        assert_eq!(disassemble(0x00000000, 0x80010000), "NOP");
+    }
+
+    #[test]
+    fn shift_is_not_nop() {
+        // sll $t0, $t1, 2
+        assert_eq!(disassemble(0x00094080, 0x80010000), "SLL $t0, $t1, 2");
+    }
+
+    #[test]
+    fn syscall_and_break_decode_their_code() {
+        // syscall 0x12345 / break 0xfffff
+        assert_eq!(Instruction(0x0048d14c, 0x80010000).code(), 0x12345);
+        assert_eq!(disassemble(0x0048d14c, 0x80010000), "SYSCALL 0x12345");
+        assert_eq!(disassemble(0x03ffffcd, 0x80010000), "BREAK 0xfffff");
+    }
+}
@@ -4,7 +4,10 @@
  * SPDX-License-Identifier: MIT
  */
 
-use crate::cpu::{instruction::Instruction, register::Cop0Register, Cpu};
+use crate::{
+    bus::BusError,
+    cpu::{instruction::Instruction, register::Cop0Register, Cpu},
+};
 
 /// The exception types of the PSX
 ///
@@ -48,29 +51,124 @@ pub(super) enum Exception {
     /// Coprocessor unusable
     Cpu = 0x0b,
 
-    /// Arithmetic overflow
+    /// Arithmetic overflow, raised by ADD, ADDI and SUB before the destination gets written
     Ov = 0x0c,
 }
 
+/// The kind of memory access
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Access {
+    /// Instruction fetch
+    Fetch,
+
+    /// Data load
+    Load,
+
+    /// Data store
+    Store,
+}
+
 impl Cpu {
-    /// Raises an exception immediately
+    /// Raises the exception matching a failed bus access
     ///
     /// # Arguments:
     ///
+    /// * `instruction`: The instruction causing the access
+    /// * `error`: The error of the bus
+    /// * `access`: The kind of the access
+    pub(super) fn raise_bus_exception(
+        &mut self,
+        instruction: Instruction,
+        error: BusError,
+        access: Access,
+    ) {
+        let exception = match (error, access) {
+            (BusError::Unaligned(address), _) => {
+                self.set_cop0_register(Cop0Register::Badvaddr, address);
+
+                if access == Access::Store {
+                    Exception::Ades
+                } else {
+                    Exception::Adel
+                }
+            }
+            (BusError::Unmapped(_), Access::Fetch) => Exception::Ibe,
+            (BusError::Unmapped(_), _) => Exception::Dbe,
+        };
+
+        log::debug!(target: "cpu", "{} raised {:?}", error, exception);
+
+        self.raise_exception(instruction, exception);
+    }
+
+    /// The offset of the general exception vector
+    const GENERAL_VECTOR_OFFSET: u32 = 0x80;
+
+    /// The offset of the debug exception vector, entered by the COP0 breakpoints
+    pub(super) const DEBUG_VECTOR_OFFSET: u32 = 0x40;
+
+    /// Raises a synchronous exception caused by an instruction
+    ///
+    /// # Arguments:
+    ///
+    /// * `instruction`: The instruction causing the exception
     /// * `exception`: The exception to raise
     pub(super) fn raise_exception(&mut self, instruction: Instruction, exception: Exception) {
+        self.enter_exception(instruction.1, exception, Self::GENERAL_VECTOR_OFFSET);
+    }
+
+    /// Raises the coprocessor unusable exception
+    ///
+    /// # Arguments:
+    ///
+    /// * `instruction`: The instruction accessing the coprocessor
+    /// * `coprocessor`: The number of the accessed coprocessor, reported in CAUSE bits 28-29
+    pub(super) fn raise_coprocessor_unusable(&mut self, instruction: Instruction, coprocessor: u8) {
+        let mut cause = self.cop0_register(Cop0Register::Cause);
+        cause &= !(0x3 << 28);
+        cause |= (coprocessor as u32 & 0x3) << 28;
+        self.set_cop0_register(Cop0Register::Cause, cause);
+
+        self.raise_exception(instruction, Exception::Cpu);
+    }
+
+    /// Raises an asynchronous interrupt before the current instruction gets executed
+    ///
+    /// The interrupted instruction is executed again on return, so EPC points at it
+    pub(super) fn raise_interrupt(&mut self) {
+        self.enter_exception(self.current_pc, Exception::Int, Self::GENERAL_VECTOR_OFFSET);
+    }
+
+    /// Enters an exception vector
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The address of the instruction the exception is taken on
+    /// * `exception`: The exception to raise
+    /// * `vector_offset`: The offset of the handler inside the exception vector region
+    pub(super) fn enter_exception(
+        &mut self,
+        address: u32,
+        exception: Exception,
+        vector_offset: u32,
+    ) {
         let mut cause = self.cop0_register(Cop0Register::Cause);
 
-        // Set BD if in branch delay
-        let bd = instruction.1 != (self.pc - 4);
-        cause |= 1 << 31;
+        // Set BD if in branch delay, the branch gets executed again on return
+        let bd = self.delay_slot;
+        if bd {
+            cause |= 1 << 31;
+        } else {
+            cause &= !(1 << 31);
+        }
 
-        let pc = instruction.1 - if bd { 4 } else { 0 };
+        let pc = if bd { address.wrapping_sub(4) } else { address };
 
         // Set EPC to PC
         self.set_cop0_register(Cop0Register::Epc, pc);
 
         // Set Exception ID in CAUSE
+        cause &= !0x7c;
         cause |= (exception as u32) << 2;
         self.set_cop0_register(Cop0Register::Cause, cause);
 
@@ -85,8 +183,15 @@ impl Cpu {
         self.set_cop0_register(Cop0Register::Sr, sr);
 
         // Call the exception handler
-        let handler = if bev { 0xbfc00180 } else { 0x80000080 };
-
+        let handler = if bev {
+            0xbfc00100 + vector_offset
+        } else {
+            0x80000000 + vector_offset
+        };
+
+        // The handler is not in a delay slot, even if a branch raised the exception
+        self.branch = false;
+        self.branch_delay_pc = None;
         self.pc = handler;
     }
 }
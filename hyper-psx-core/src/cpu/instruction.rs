@@ -34,6 +34,28 @@ impl Instruction {
     pub(super) fn branch_op(&self) -> u8 {
         ((self.0 >> 16) & 0x1f) as u8
     }
+
+    /// Returns if the instruction is a branch or jump, which makes the next one a delay slot
+    /// whether it is taken or not
+    pub(super) fn is_branch(&self) -> bool {
+        match self.op() {
+            // JR, JALR
+            0b000000 => matches!(self.0 & 0x3f, 0b001000 | 0b001001),
+            // BcondZ, J, JAL, BEQ, BNE, BLEZ, BGTZ
+            0b000001..=0b000111 => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the number of the coprocessor accessed by a COPz, LWCz or SWCz instruction
+    pub(super) fn coprocessor(&self) -> Option<u8> {
+        match self.op() {
+            0b010000..=0b010011 | 0b110000..=0b110011 | 0b111000..=0b111011 => {
+                Some(self.op() & 0x3)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Instruction {
@@ -64,13 +86,14 @@ impl Instruction {
         value.into()
     }
 
-    /// Returns the 5-bit cop destination register specifier (15-11)
+    /// Returns the 5-bit cop destination register specifier (15-11), none if the register doesn't
+    /// exist
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=214>
     #[inline(always)]
-    pub(super) fn cop_rd(&self) -> Cop0Register {
+    pub(super) fn cop_rd(&self) -> Option<Cop0Register> {
         let value = ((self.0 >> 11) & 0x1f) as u8;
-        value.into()
+        Cop0Register::try_from(value).ok()
     }
 }
 
@@ -99,6 +122,16 @@ impl Instruction {
         ((self.0 >> 6) & 0x1f) as u8
     }
 
+    /// Returns the 20-bit code field of SYSCALL and BREAK (25-6)
+    ///
+    /// The CPU ignores it, handlers read it from the instruction at EPC
+    ///
+    /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=288>
+    #[inline(always)]
+    pub(super) fn code(&self) -> u32 {
+        (self.0 >> 6) & 0xfffff
+    }
+
     /// Returns the 6-bit function field (5-0)
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=214>
@@ -5,10 +5,12 @@
  */
 
 use crate::{
+    bus::{Bus, BusError},
     cpu::{
-        exception::Exception,
+        alu,
+        exception::{Access, Exception},
         instruction::Instruction,
-        register::{Cop0Register, Register},
+        register::Register,
         Cpu,
     },
     dma::Dma,
@@ -29,8 +31,6 @@ impl Cpu {
 
         let address = target << 2 | (self.pc & 0xf0000000);
 
-        log::debug!(target: "cpu", "{}: {:#010x}: J {:#x}", self.n, instruction.1, address);
-
         self.branch_delay_pc = Some(address);
     }
 
@@ -46,8 +46,6 @@ impl Cpu {
 
         let address = target << 2 | (self.pc & 0xf0000000);
 
-        log::debug!(target: "cpu", "{}: {:#010x}: JAL {:#x}", self.n, instruction.1, address);
-
         self.set_register(Register::Ra, self.pc + 4);
         self.branch_delay_pc = Some(address);
     }
@@ -68,16 +66,6 @@ impl Cpu {
         let t = self.register(rt);
         let address_offset = offset.sign_extend() << 2;
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: BEQ {}, {}, {}",
-            self.n,
-            instruction.1,
-            rs,
-            rt,
-            address_offset as i32
-        );
-
         if s == t {
             self.branch(address_offset);
         }
@@ -99,16 +87,6 @@ impl Cpu {
         let t = self.register(rt);
         let address_offset = offset.sign_extend() << 2;
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: BNE {}, {}, {}",
-            self.n,
-            instruction.1,
-            rs,
-            rt,
-            address_offset as i32
-        );
-
         if s != t {
             self.branch(address_offset);
         }
@@ -128,15 +106,6 @@ impl Cpu {
         let s = self.register(rs) as i32;
         let address_offset = offset.sign_extend() << 2;
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: BGTZ {}, {}",
-            self.n,
-            instruction.1,
-            rs,
-            address_offset as i32
-        );
-
         if s <= 0 {
             self.branch(address_offset);
         }
@@ -156,15 +125,6 @@ impl Cpu {
         let s = self.register(rs) as i32;
         let address_offset = offset.sign_extend() << 2;
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: BGTZ {}, {}",
-            self.n,
-            instruction.1,
-            rs,
-            address_offset as i32
-        );
-
         if s > 0 {
             self.branch(address_offset);
         }
@@ -189,23 +149,11 @@ impl Cpu {
         let s = self.register(rs);
         let value = imm.sign_extend();
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: ADDI {}, {}, {}",
-            self.n,
-            instruction.1,
-            rt,
-            rs,
-            value as i32
-        );
-
-        let Some(result) = (s as i32).checked_add(value as i32) else {
+        let Some(result) = alu::add(s, value) else {
             self.raise_exception(instruction, Exception::Ov);
             return;
         };
 
-        let result = result as u32;
-
         self.set_register(rt, result);
     }
 
@@ -224,16 +172,6 @@ impl Cpu {
         let s = self.register(rs);
         let value = imm.sign_extend();
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: ADDIU {}, {}, {}",
-            self.n,
-            instruction.1,
-            rt,
-            rs,
-            value as i32
-        );
-
         let result = s.wrapping_add(value);
 
         self.set_register(rt, result);
@@ -254,17 +192,7 @@ impl Cpu {
         let s = self.register(rs);
         let value = imm.sign_extend();
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SLTI {}, {}, {}",
-            self.n,
-            instruction.1,
-            rt,
-            rs,
-            value as i32
-        );
-
-        let result = ((s as i32) < value as i32) as u32;
+        let result = alu::set_less_than(s, value);
 
         self.set_register(rt, result);
     }
@@ -284,17 +212,7 @@ impl Cpu {
         let s = self.register(rs);
         let value = imm.sign_extend();
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SLTIU {}, {}, {}",
-            self.n,
-            instruction.1,
-            rt,
-            rs,
-            value as i32
-        );
-
-        let result = (s < value) as u32;
+        let result = alu::set_less_than_unsigned(s, value);
 
         self.set_register(rt, result);
     }
@@ -314,16 +232,6 @@ impl Cpu {
         let s = self.register(rs);
         let value = imm.zero_extend();
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: ANDI {}, {}, {:#x}",
-            self.n,
-            instruction.1,
-            rt,
-            rs,
-            value
-        );
-
         let result = s & value;
 
         self.set_register(rt, result);
@@ -344,16 +252,6 @@ impl Cpu {
         let s = self.register(rs);
         let value = imm.zero_extend();
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: ORI {}, {}, {:#x}",
-            self.n,
-            instruction.1,
-            rs,
-            rt,
-            value
-        );
-
         let result = s | value;
 
         self.set_register(rt, result);
@@ -374,16 +272,6 @@ impl Cpu {
         let s = self.register(rs);
         let value = imm.zero_extend();
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: XORI {}, {}, {:#x}",
-            self.n,
-            instruction.1,
-            rs,
-            rt,
-            value
-        );
-
         let result = s ^ value;
 
         self.set_register(rt, result);
@@ -402,15 +290,6 @@ impl Cpu {
 
         let value = imm.zero_extend();
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: LUI {}, {:#x}",
-            self.n,
-            instruction.1,
-            rt,
-            value
-        );
-
         let result = value << 16;
 
         self.set_register(rt, result);
@@ -430,7 +309,13 @@ impl Cpu {
     /// * Address error exception
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=244>
-    pub(super) fn op_lb(&mut self, instruction: Instruction, dma: &mut Dma, gpu: &mut Gpu) {
+    pub(super) fn op_lb(
+        &mut self,
+        instruction: Instruction,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
         let base = instruction.rs();
         let rt = instruction.rt();
         let offset = instruction.imm();
@@ -438,22 +323,11 @@ impl Cpu {
         let address_offset = offset.sign_extend();
         let address = self.register(base).wrapping_add(address_offset);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: LB {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
-        if self.cop0_register(Cop0Register::Sr) & 0x10000 != 0 {
-            // log::warn!("Tried to read from memory, while cache is isolated");
+        let Some(value) = self.load_u8(instruction, address, bus, dma, gpu) else {
             return;
-        }
+        };
 
-        let result = self.bus.read_u8(address, dma, gpu).sign_extend() as u32;
+        let result = value.sign_extend() as u32;
 
         self.load_delay_register = Some((rt, result));
     }
@@ -472,7 +346,13 @@ impl Cpu {
     /// * Address error exception
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=252>
-    pub(super) fn op_lwl(&mut self, instruction: Instruction, dma: &mut Dma, gpu: &mut Gpu) {
+    pub(super) fn op_lwl(
+        &mut self,
+        instruction: Instruction,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
         let base = instruction.rs();
         let rt = instruction.rt();
         let offset = instruction.imm();
@@ -483,26 +363,12 @@ impl Cpu {
         let value = self.out_registers[rt as usize];
 
         let aligned_address = address & !3;
-        let aligned_word = self.bus.read_u32(aligned_address, dma, gpu);
-
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: LWL {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
-        let result = match address & 3 {
-            0 => (value & 0x00ffffff) | (aligned_word << 24),
-            1 => (value & 0x0000ffff) | (aligned_word << 16),
-            2 => (value & 0x000000ff) | (aligned_word << 8),
-            3 => aligned_word,
-            _ => unreachable!(),
+        let Some(aligned_word) = self.load_u32(instruction, aligned_address, bus, dma, gpu) else {
+            return;
         };
 
+        let result = alu::load_word_left(value, aligned_word, address);
+
         self.load_delay_register = Some((rt, result));
     }
 
@@ -520,7 +386,13 @@ impl Cpu {
     /// * Address error exception
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=246>
-    pub(super) fn op_lh(&mut self, instruction: Instruction, dma: &mut Dma, gpu: &mut Gpu) {
+    pub(super) fn op_lh(
+        &mut self,
+        instruction: Instruction,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
         let base = instruction.rs();
         let rt = instruction.rt();
         let offset = instruction.imm();
@@ -528,27 +400,16 @@ impl Cpu {
         let address_offset = offset.sign_extend();
         let address = self.register(base).wrapping_add(address_offset);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: LH {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
-        if self.cop0_register(Cop0Register::Sr) & 0x10000 != 0 {
-            // log::warn!("Tried to read from memory, while cache is isolated");
+        if address % 2 != 0 {
+            self.raise_bus_exception(instruction, BusError::Unaligned(address), Access::Load);
             return;
         }
 
-        if address % 2 != 0 {
-            self.raise_exception(instruction, Exception::Adel);
+        let Some(value) = self.load_u16(instruction, address, bus, dma, gpu) else {
             return;
-        }
+        };
 
-        let result = self.bus.read_u16(address, dma, gpu).sign_extend();
+        let result = value.sign_extend();
 
         self.load_delay_register = Some((rt, result));
     }
@@ -567,7 +428,13 @@ impl Cpu {
     /// * Address error exception
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=249>
-    pub(super) fn op_lw(&mut self, instruction: Instruction, dma: &mut Dma, gpu: &mut Gpu) {
+    pub(super) fn op_lw(
+        &mut self,
+        instruction: Instruction,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
         let base = instruction.rs();
         let rt = instruction.rt();
         let offset = instruction.imm();
@@ -575,27 +442,14 @@ impl Cpu {
         let address_offset = offset.sign_extend();
         let address = self.register(base).wrapping_add(address_offset);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: LW {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
-        if self.cop0_register(Cop0Register::Sr) & 0x10000 != 0 {
-            // log::warn!("Tried to read from memory, while cache is isolated");
-            return;
-        }
-
         if address % 4 != 0 {
-            self.raise_exception(instruction, Exception::Adel);
+            self.raise_bus_exception(instruction, BusError::Unaligned(address), Access::Load);
             return;
         }
 
-        let result = self.bus.read_u32(address, dma, gpu);
+        let Some(result) = self.load_u32(instruction, address, bus, dma, gpu) else {
+            return;
+        };
 
         self.load_delay_register = Some((rt, result));
     }
@@ -614,7 +468,13 @@ impl Cpu {
     /// * Address error exception
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=245>
-    pub(super) fn op_lbu(&mut self, instruction: Instruction, dma: &mut Dma, gpu: &mut Gpu) {
+    pub(super) fn op_lbu(
+        &mut self,
+        instruction: Instruction,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
         let base = instruction.rs();
         let rt = instruction.rt();
         let offset = instruction.imm();
@@ -622,22 +482,11 @@ impl Cpu {
         let address_offset = offset.sign_extend();
         let address = self.register(base).wrapping_add(address_offset);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: LBU {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
-        if self.cop0_register(Cop0Register::Sr) & 0x10000 != 0 {
-            // log::warn!("Tried to read from memory, while cache is isolated");
+        let Some(value) = self.load_u8(instruction, address, bus, dma, gpu) else {
             return;
-        }
+        };
 
-        let result = self.bus.read_u8(address, dma, gpu) as u32;
+        let result = value as u32;
 
         self.load_delay_register = Some((rt, result));
     }
@@ -656,7 +505,13 @@ impl Cpu {
     /// * Address error exception
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=247>
-    pub(super) fn op_lhu(&mut self, instruction: Instruction, dma: &mut Dma, gpu: &mut Gpu) {
+    pub(super) fn op_lhu(
+        &mut self,
+        instruction: Instruction,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
         let base = instruction.rs();
         let rt = instruction.rt();
         let offset = instruction.imm();
@@ -664,27 +519,16 @@ impl Cpu {
         let address_offset = offset.sign_extend();
         let address = self.register(base).wrapping_add(address_offset);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: LHU {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
-        if self.cop0_register(Cop0Register::Sr) & 0x10000 != 0 {
-            // log::warn!("Tried to read from memory, while cache is isolated");
+        if address % 2 != 0 {
+            self.raise_bus_exception(instruction, BusError::Unaligned(address), Access::Load);
             return;
         }
 
-        if address % 2 != 0 {
-            self.raise_exception(instruction, Exception::Adel);
+        let Some(value) = self.load_u16(instruction, address, bus, dma, gpu) else {
             return;
-        }
+        };
 
-        let result = self.bus.read_u16(address, dma, gpu) as u32;
+        let result = value as u32;
 
         self.load_delay_register = Some((rt, result));
     }
@@ -703,7 +547,13 @@ impl Cpu {
     /// * Address error exception
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=255>
-    pub(super) fn op_lwr(&mut self, instruction: Instruction, dma: &mut Dma, gpu: &mut Gpu) {
+    pub(super) fn op_lwr(
+        &mut self,
+        instruction: Instruction,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
         let base = instruction.rs();
         let rt = instruction.rt();
         let offset = instruction.imm();
@@ -714,26 +564,12 @@ impl Cpu {
         let value = self.out_registers[rt as usize];
 
         let aligned_address = address & !3;
-        let aligned_word = self.bus.read_u32(aligned_address, dma, gpu);
-
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: LWR {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
-        let result = match address & 3 {
-            0 => aligned_word,
-            1 => (value & 0xff000000) | (aligned_word >> 8),
-            2 => (value & 0xffff0000) | (aligned_word >> 16),
-            3 => (value & 0xffffff00) | (aligned_word >> 24),
-            _ => unreachable!(),
+        let Some(aligned_word) = self.load_u32(instruction, aligned_address, bus, dma, gpu) else {
+            return;
         };
 
+        let result = alu::load_word_right(value, aligned_word, address);
+
         self.load_delay_register = Some((rt, result));
     }
 
@@ -752,7 +588,13 @@ impl Cpu {
     /// * Address error exception
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=268>
-    pub(super) fn op_sb(&mut self, instruction: Instruction, dma: &mut Dma, gpu: &mut Gpu) {
+    pub(super) fn op_sb(
+        &mut self,
+        instruction: Instruction,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
         let base = instruction.rs();
         let rt = instruction.rt();
         let offset = instruction.imm();
@@ -761,24 +603,9 @@ impl Cpu {
         let address_offset = offset.sign_extend();
         let address = self.register(base).wrapping_add(address_offset);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SB {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
-        if self.cop0_register(Cop0Register::Sr) & 0x10000 != 0 {
-            // log::warn!("Tried to write into memory, while cache is isolated");
-            return;
-        }
-
         let result = t as u8;
 
-        self.bus.write_u8(address, result, dma, gpu);
+        self.store_u8(instruction, address, result, bus, dma, gpu);
     }
 
     /// Opcode SH - Store Halfword (0b101001)
@@ -796,7 +623,13 @@ impl Cpu {
     /// * Address error exception
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=269>
-    pub(super) fn op_sh(&mut self, instruction: Instruction, dma: &mut Dma, gpu: &mut Gpu) {
+    pub(super) fn op_sh(
+        &mut self,
+        instruction: Instruction,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
         let base = instruction.rs();
         let rt = instruction.rt();
         let offset = instruction.imm();
@@ -805,29 +638,14 @@ impl Cpu {
         let address_offset = offset.sign_extend();
         let address = self.register(base).wrapping_add(address_offset);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SH {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
-        if self.cop0_register(Cop0Register::Sr) & 0x10000 != 0 {
-            // log::warn!("Tried to write into memory, while cache is isolated");
-            return;
-        }
-
         if address % 2 != 0 {
-            self.raise_exception(instruction, Exception::Ades);
+            self.raise_bus_exception(instruction, BusError::Unaligned(address), Access::Store);
             return;
         }
 
         let result = t as u16;
 
-        self.bus.write_u16(address, result, dma, gpu);
+        self.store_u16(instruction, address, result, bus, dma, gpu);
     }
 
     /// Opcode SWL - Store Word Left (0b101010)
@@ -845,7 +663,13 @@ impl Cpu {
     /// * Address error exception
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=284>
-    pub(super) fn op_swl(&mut self, instruction: Instruction, dma: &mut Dma, gpu: &mut Gpu) {
+    pub(super) fn op_swl(
+        &mut self,
+        instruction: Instruction,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
         let base = instruction.rs();
         let rt = instruction.rt();
         let offset = instruction.imm();
@@ -856,27 +680,13 @@ impl Cpu {
 
         let aligned_address = address & !3;
 
-        let value = self.bus.read_u32(aligned_address, dma, gpu);
-
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SWL {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
-        let result = match address & 3 {
-            0 => (value & 0xffffff00) | (t >> 24),
-            1 => (value & 0xffff0000) | (t >> 16),
-            2 => (value & 0xff000000) | (t >> 8),
-            3 => value | t,
-            _ => unreachable!(),
+        let Some(value) = self.load_u32(instruction, aligned_address, bus, dma, gpu) else {
+            return;
         };
 
-        self.bus.write_u32(aligned_address, result, dma, gpu);
+        let result = alu::store_word_left(value, t, address);
+
+        self.store_u32(instruction, aligned_address, result, bus, dma, gpu);
     }
 
     /// Opcode SW - Store Word (0b101011)
@@ -894,7 +704,13 @@ impl Cpu {
     /// * Address error exception
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=282>
-    pub(super) fn op_sw(&mut self, instruction: Instruction, dma: &mut Dma, gpu: &mut Gpu) {
+    pub(super) fn op_sw(
+        &mut self,
+        instruction: Instruction,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
         let base = instruction.rs();
         let rt = instruction.rt();
         let offset = instruction.imm();
@@ -903,29 +719,14 @@ impl Cpu {
         let address_offset = offset.sign_extend();
         let address = self.register(base).wrapping_add(address_offset);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SW {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
-        if self.cop0_register(Cop0Register::Sr) & 0x10000 != 0 {
-            // log::warn!("Tried to write into memory, while cache is isolated");
-            return;
-        }
-
         if address % 4 != 0 {
-            self.raise_exception(instruction, Exception::Ades);
+            self.raise_bus_exception(instruction, BusError::Unaligned(address), Access::Store);
             return;
         }
 
         let result = t;
 
-        self.bus.write_u32(address, result, dma, gpu);
+        self.store_u32(instruction, address, result, bus, dma, gpu);
     }
 
     /// Opcode SWR - Store Word Right (0b101110)
@@ -943,7 +744,13 @@ impl Cpu {
     /// * Address error exception
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=286>
-    pub(super) fn op_swr(&mut self, instruction: Instruction, dma: &mut Dma, gpu: &mut Gpu) {
+    pub(super) fn op_swr(
+        &mut self,
+        instruction: Instruction,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
         let base = instruction.rs();
         let rt = instruction.rt();
         let offset = instruction.imm();
@@ -954,26 +761,12 @@ impl Cpu {
 
         let aligned_address = address & !3;
 
-        let value = self.bus.read_u32(aligned_address, dma, gpu);
-
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SWL {}, {}({})",
-            self.n,
-            instruction.1,
-            rt,
-            address_offset as i32,
-            base
-        );
-
-        let result = match address & 3 {
-            0 => value | t,
-            1 => (value & 0x000000ff) | (t << 8),
-            2 => (value & 0x0000ffff) | (t << 16),
-            3 => (value & 0x00ffffff) | (t << 24),
-            _ => unreachable!(),
+        let Some(value) = self.load_u32(instruction, aligned_address, bus, dma, gpu) else {
+            return;
         };
 
-        self.bus.write_u32(aligned_address, result, dma, gpu);
+        let result = alu::store_word_right(value, t, address);
+
+        self.store_u32(instruction, aligned_address, result, bus, dma, gpu);
     }
 }
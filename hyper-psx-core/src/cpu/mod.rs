@@ -4,28 +4,44 @@
  * SPDX-License-Identifier: MIT
  */
 
+mod alu;
+mod bios_call;
 mod branch;
+mod breakpoint;
 mod cop0;
 mod cop2;
+pub(crate) mod disasm;
 mod exception;
 mod instruction;
 mod instructions;
 mod register;
 mod special;
+#[cfg(test)]
+mod tests;
+pub(crate) mod trace;
+mod tty;
+pub(crate) mod watchpoint;
 
 use crate::{
     bus::Bus,
     cpu::{
-        exception::Exception,
+        exception::{Access, Exception},
         instruction::Instruction,
         register::{Cop0Register, Register},
+        tty::{Tty, TtySink},
+        watchpoint::{WatchCallback, WatchKind, Watchpoints},
     },
     dma::Dma,
+    exe::Exe,
     gpu::Gpu,
 };
 
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+use std::mem;
+
 /// The CPU component
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct Cpu {
     /// The 32 general purpose registers
     registers: [u32; 32],
@@ -43,6 +59,7 @@ pub(crate) struct Cpu {
     lo: u32,
 
     /// The 64 cop registers
+    #[serde(with = "BigArray")]
     cop0_registers: [u32; 64],
 
     /// The program counter
@@ -51,40 +68,86 @@ pub(crate) struct Cpu {
     /// The branch delay program counter
     branch_delay_pc: Option<u32>,
 
-    /// The Bus component
-    bus: Bus,
+    /// The address of the instruction being executed
+    current_pc: u32,
+
+    /// If the instruction being executed is in a branch delay slot
+    delay_slot: bool,
+
+    /// If the instruction being executed is a branch or jump
+    branch: bool,
+
+    /// The TTY capturing the BIOS text output
+    #[serde(skip, default = "Tty::new")]
+    tty: Tty,
+
+    /// The watchpoints reporting loads and stores to tooling
+    #[serde(skip, default = "Watchpoints::new")]
+    watchpoints: Watchpoints,
+
+    /// The cycles executed so far
+    cycles: u64,
+
+    /// The cycle at which the result of the last multiplication or division is in HI and LO
+    hilo_ready_at: u64,
+
+    /// The cycles the current instruction stalls for
+    stall_cycles: u32,
+
+    /// If calls through the BIOS call gates get logged
+    #[serde(skip)]
+    log_bios_calls: bool,
 
     n: usize,
 }
 
 impl Cpu {
+    /// The cycles an instruction is assumed to take without stalls
+    pub(crate) const CYCLES_PER_INSTRUCTION: u32 = 2;
+
     /// Creates a CPU Component
-    ///
-    /// # Arguments:
-    ///
-    /// * `bus`: The Bus component
-    pub(crate) fn new(bus: Bus) -> Self {
+    pub(crate) fn new() -> Self {
+        let mut cop0_registers = [0x00000000; 64];
+        cop0_registers[Cop0Register::Prid as usize] = Self::PROCESSOR_ID;
+
         Self {
             registers: [0x00000000; 32],
             out_registers: [0x00000000; 32],
             hi: 0x00000000,
             lo: 0x00000000,
             load_delay_register: None,
-            cop0_registers: [0x00000000; 64],
+            cop0_registers,
             pc: 0xbfc00000,
             branch_delay_pc: None,
-            bus,
+            current_pc: 0xbfc00000,
+            delay_slot: false,
+            branch: false,
+            tty: Tty::new(),
+            watchpoints: Watchpoints::new(),
+            cycles: 0,
+            hilo_ready_at: 0,
+            stall_cycles: 0,
+            log_bios_calls: false,
             n: 0,
         }
     }
 
     /// Steps the next instruction
-    pub(crate) fn step(&mut self, dma: &mut Dma, gpu: &mut Gpu) {
-        if self.pc % 4 != 0 {
-            panic!("unaligned pc");
-        }
-
-        let instruction = Instruction(self.bus.read_u32(self.pc, dma, gpu), self.pc);
+    ///
+    /// # Arguments:
+    ///
+    /// * `bus`: The Bus component
+    /// * `dma`: The DMA component
+    /// * `gpu`: The GPU component
+    ///
+    /// Returns the cycles the instruction took
+    pub(crate) fn step(&mut self, bus: &mut Bus, dma: &mut Dma, gpu: &mut Gpu) -> u32 {
+        self.hook_bios_function(bus);
+
+        let word = bus.read_u32(self.pc, dma, gpu);
+        let instruction = Instruction(word.unwrap_or(0x00000000), self.pc);
+        self.current_pc = self.pc;
+        self.delay_slot = mem::take(&mut self.branch);
         self.pc += 4;
         self.n += 1;
 
@@ -93,14 +156,207 @@ impl Cpu {
             self.pc = branch_pc;
         }
 
-        if self.load_delay_register.is_some() {
-            let load_register = self.load_delay_register.take().unwrap();
-            self.set_register(load_register.0, load_register.1);
+        // The pending load lands before this instruction writes back, so a write to the same
+        // register in the load delay slot wins over the load
+        let pending_load = self.load_delay_register.take();
+        if let Some((register, value)) = pending_load {
+            self.set_register(register, value);
         }
 
-        self.execute(instruction, dma, gpu);
+        if let Err(error) = word {
+            self.raise_bus_exception(instruction, error, Access::Fetch);
+        } else if self.interrupt_pending(bus) {
+            self.raise_interrupt();
+        } else if !self.code_breakpoint(instruction) {
+            self.branch = instruction.is_branch();
+            self.execute(instruction, bus, dma, gpu);
+        }
+
+        // A load in the load delay slot targeting the same register cancels the pending load
+        if let (Some((pending, _)), Some((next, _))) = (pending_load, self.load_delay_register) {
+            if pending as usize == next as usize {
+                self.out_registers[pending as usize] = self.registers[pending as usize];
+            }
+        }
+
+        self.registers = self.out_registers;
+
+        let cycles = Self::CYCLES_PER_INSTRUCTION + mem::take(&mut self.stall_cycles);
+        self.cycles += cycles as u64;
+        cycles
+    }
+
+    /// Copies a PSX-EXE into RAM and jumps to its entry point
+    ///
+    /// # Arguments:
+    ///
+    /// * `exe`: The PSX-EXE to be sideloaded
+    /// * `bus`: The Bus component
+    pub(crate) fn sideload(&mut self, exe: &Exe, bus: &mut Bus) {
+        bus.load_ram(exe.load_address(), exe.text());
+
+        self.set_register(Register::Gp, exe.gp());
+        if exe.sp() != 0 {
+            self.set_register(Register::Sp, exe.sp());
+            self.set_register(Register::Fp, exe.sp());
+        }
 
         self.registers = self.out_registers;
+        self.load_delay_register = None;
+        self.branch_delay_pc = None;
+        self.branch = false;
+        self.pc = exe.pc();
+    }
+
+    /// Returns from the current function to the address in $ra, as if it had finished
+    pub(crate) fn return_to_caller(&mut self) {
+        self.branch_delay_pc = None;
+        self.branch = false;
+        self.pc = self.register(Register::Ra);
+    }
+
+    /// Returns the program counter
+    pub(crate) fn pc(&self) -> u32 {
+        self.pc
+    }
+
+    /// Returns the general purpose registers
+    pub(crate) fn registers(&self) -> [u32; 32] {
+        self.registers
+    }
+
+    /// Returns the cop0 registers
+    pub(crate) fn cop0_registers(&self) -> &[u32; 64] {
+        &self.cop0_registers
+    }
+
+    /// Intercepts the BIOS text output functions and TTY device setup before they get executed
+    ///
+    /// <https://psx-spx.consoledev.net/kernelbios/#bios-function-summary>
+    fn hook_bios_function(&mut self, bus: &Bus) {
+        let function = self.register(Register::T1);
+        if self.log_bios_calls && matches!(self.pc, 0xa0 | 0xb0 | 0xc0) {
+            self.log_bios_call(function);
+        }
+
+        match (self.pc, function) {
+            // A(3Ch) / B(3Dh) - putchar(char)
+            (0xa0, 0x3c) | (0xb0, 0x3d) => {
+                let character = self.register(Register::A0) as u8;
+                self.tty.putchar(character);
+            }
+            // A(3Eh) / B(3Fh) - puts(src)
+            (0xa0, 0x3e) | (0xb0, 0x3f) => {
+                let address = self.register(Register::A0);
+                for index in 0..Tty::MAX_STRING_LENGTH {
+                    let character = match bus.peek_u8(address.wrapping_add(index)) {
+                        Some(0) | None => break,
+                        Some(character) => character,
+                    };
+
+                    self.tty.putchar(character);
+                }
+                self.tty.putchar(b'\n');
+            }
+            // A(98h) - AddDuartTtyDevice()
+            (0xa0, 0x98) => self.tty.install_device("DUART"),
+            // A(99h) - AddDummyTtyDevice()
+            (0xa0, 0x99) => self.tty.install_device("dummy"),
+            _ => {}
+        }
+    }
+
+    /// Logs a call through a BIOS call gate with its decoded name and arguments
+    ///
+    /// # Arguments:
+    ///
+    /// * `function`: The function number passed in $t1
+    fn log_bios_call(&self, function: u32) {
+        let gate = match self.pc {
+            0xa0 => 'A',
+            0xb0 => 'B',
+            _ => 'C',
+        };
+
+        let name = bios_call::function_name(self.pc, function).unwrap_or("unknown");
+        log::debug!(
+            target: "bios",
+            "{}({:02X}h) {}({:#010x}, {:#010x}, {:#010x}, {:#010x}) ra={:#010x}",
+            gate,
+            function,
+            name,
+            self.register(Register::A0),
+            self.register(Register::A1),
+            self.register(Register::A2),
+            self.register(Register::A3),
+            self.register(Register::Ra)
+        );
+    }
+
+    /// Logs a SYSCALL with its code and the BIOS function selected in $a0
+    ///
+    /// # Arguments:
+    ///
+    /// * `instruction`: The SYSCALL instruction
+    fn log_syscall(&self, instruction: Instruction) {
+        let function = self.register(Register::A0);
+        log::debug!(
+            target: "bios",
+            "SYSCALL({:02X}h) {}({:#010x}) code={:#x} ra={:#010x}",
+            function,
+            bios_call::syscall_name(function),
+            self.register(Register::A1),
+            instruction.code(),
+            self.register(Register::Ra)
+        );
+    }
+
+    /// Enables logging of the calls through the BIOS call gates
+    ///
+    /// # Arguments:
+    ///
+    /// * `enabled`: If the calls should be logged
+    pub(crate) fn set_bios_call_logging(&mut self, enabled: bool) {
+        self.log_bios_calls = enabled;
+    }
+
+    /// Replaces the sink receiving the TTY lines
+    ///
+    /// # Arguments:
+    ///
+    /// * `sink`: The new sink
+    pub(crate) fn set_tty_sink(&mut self, sink: TtySink) {
+        self.tty.set_sink(sink);
+    }
+
+    /// Takes the TTY text printed since the last call
+    pub(crate) fn take_tty_output(&mut self) -> String {
+        self.tty.take_output()
+    }
+
+    /// Updates the hardware interrupt bit in CAUSE and checks if an interrupt
+    /// should be taken
+    ///
+    /// # Arguments:
+    ///
+    /// * `bus`: The Bus component
+    fn interrupt_pending(&mut self, bus: &mut Bus) -> bool {
+        let hardware_interrupt = bus.interrupt_controller().pending();
+
+        // Cause IP2 reflects the interrupt controller line
+        let mut cause = self.cop0_register(Cop0Register::Cause);
+        cause &= !(1 << 10);
+        cause |= (hardware_interrupt as u32) << 10;
+        self.set_cop0_register(Cop0Register::Cause, cause);
+
+        let sr = self.cop0_register(Cop0Register::Sr);
+
+        // SR IEc enables interrupts, SR IM masks the CAUSE IP bits
+        let interrupts_enabled = (sr & 0x1) != 0;
+        let interrupt_mask = (sr >> 8) & 0xff;
+        let interrupt_pending = (cause >> 8) & 0xff;
+
+        interrupts_enabled && (interrupt_mask & interrupt_pending) != 0
     }
 
     /// Executes an instruction
@@ -108,7 +364,22 @@ impl Cpu {
     /// # Arguments:
     ///
     /// * `instruction`: The instruction to be executed
-    fn execute(&mut self, instruction: Instruction, dma: &mut Dma, gpu: &mut Gpu) {
+    fn execute(&mut self, instruction: Instruction, bus: &mut Bus, dma: &mut Dma, gpu: &mut Gpu) {
+        log::debug!(
+            target: "cpu",
+            "{}: {:#010x}: {}",
+            self.n,
+            instruction.1,
+            disasm::disassemble(instruction.0, instruction.1)
+        );
+
+        if let Some(coprocessor) = instruction.coprocessor() {
+            if !self.coprocessor_usable(coprocessor) {
+                self.raise_coprocessor_unusable(instruction, coprocessor);
+                return;
+            }
+        }
+
         match instruction.op() {
             0b000000 => match instruction.funct() {
                 0b000000 => self.op_sll(instruction),
@@ -183,7 +454,7 @@ impl Cpu {
                     instruction.cop_op()
                 ),
             },
-            0b010001 => self.raise_exception(instruction, Exception::Cpu),
+            0b010001 => self.raise_coprocessor_unusable(instruction, instruction.op() & 0x3),
             0b010010 => {
                 // GTE
                 unimplemented!(
@@ -192,27 +463,27 @@ impl Cpu {
                     instruction.cop_op()
                 )
             }
-            0b010011 => self.raise_exception(instruction, Exception::Cpu),
-            0b100000 => self.op_lb(instruction, dma, gpu),
-            0b100001 => self.op_lh(instruction, dma, gpu),
-            0b100010 => self.op_lwl(instruction, dma, gpu),
-            0b100011 => self.op_lw(instruction, dma, gpu),
-            0b100100 => self.op_lbu(instruction, dma, gpu),
-            0b100101 => self.op_lhu(instruction, dma, gpu),
-            0b100110 => self.op_lwr(instruction, dma, gpu),
-            0b101000 => self.op_sb(instruction, dma, gpu),
-            0b101001 => self.op_sh(instruction, dma, gpu),
-            0b101010 => self.op_swl(instruction, dma, gpu),
-            0b101011 => self.op_sw(instruction, dma, gpu),
-            0b101110 => self.op_swr(instruction, dma, gpu),
-            0b110000 => self.raise_exception(instruction, Exception::Cpu),
-            0b110001 => self.raise_exception(instruction, Exception::Cpu),
+            0b010011 => self.raise_coprocessor_unusable(instruction, instruction.op() & 0x3),
+            0b100000 => self.op_lb(instruction, bus, dma, gpu),
+            0b100001 => self.op_lh(instruction, bus, dma, gpu),
+            0b100010 => self.op_lwl(instruction, bus, dma, gpu),
+            0b100011 => self.op_lw(instruction, bus, dma, gpu),
+            0b100100 => self.op_lbu(instruction, bus, dma, gpu),
+            0b100101 => self.op_lhu(instruction, bus, dma, gpu),
+            0b100110 => self.op_lwr(instruction, bus, dma, gpu),
+            0b101000 => self.op_sb(instruction, bus, dma, gpu),
+            0b101001 => self.op_sh(instruction, bus, dma, gpu),
+            0b101010 => self.op_swl(instruction, bus, dma, gpu),
+            0b101011 => self.op_sw(instruction, bus, dma, gpu),
+            0b101110 => self.op_swr(instruction, bus, dma, gpu),
+            0b110000 => self.raise_coprocessor_unusable(instruction, instruction.op() & 0x3),
+            0b110001 => self.raise_coprocessor_unusable(instruction, instruction.op() & 0x3),
             0b110010 => self.op_lwc2(instruction),
-            0b110011 => self.raise_exception(instruction, Exception::Cpu),
-            0b111000 => self.raise_exception(instruction, Exception::Cpu),
-            0b111001 => self.raise_exception(instruction, Exception::Cpu),
+            0b110011 => self.raise_coprocessor_unusable(instruction, instruction.op() & 0x3),
+            0b111000 => self.raise_coprocessor_unusable(instruction, instruction.op() & 0x3),
+            0b111001 => self.raise_coprocessor_unusable(instruction, instruction.op() & 0x3),
             0b111010 => self.op_swc2(instruction),
-            0b111011 => self.raise_exception(instruction, Exception::Cpu),
+            0b111011 => self.raise_coprocessor_unusable(instruction, instruction.op() & 0x3),
             _ => {
                 log::warn!(
                     "{}: {:#010x}: unimplemented instruction {:#010x} with opcode {:#08b}",
@@ -236,6 +507,280 @@ impl Cpu {
         self.branch_delay_pc = Some(address);
     }
 
+    /// Checks if the cache is isolated from the memory (SR bit 16)
+    fn cache_isolated(&self) -> bool {
+        self.cop0_register(Cop0Register::Sr) & 0x10000 != 0
+    }
+
+    /// Loads an u8, raising the matching exception if the bus access fails or hits a breakpoint
+    ///
+    /// # Arguments:
+    ///
+    /// * `instruction`: The instruction causing the load
+    /// * `address`: The absolute address
+    fn load_u8(
+        &mut self,
+        instruction: Instruction,
+        address: u32,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) -> Option<u8> {
+        if self.data_breakpoint(instruction, address, Access::Load) {
+            return None;
+        }
+
+        if self.cache_isolated() {
+            // Isolated loads are served by the cache instead of the memory
+            return Some(bus.read_cache_u8(address));
+        }
+
+        let value = bus
+            .read_u8(address, dma, gpu)
+            .map_err(|error| self.raise_bus_exception(instruction, error, Access::Load))
+            .ok()?;
+
+        self.watchpoints.check(
+            address,
+            bus.mirror_address(address),
+            1,
+            value as u32,
+            WatchKind::Read,
+        );
+
+        Some(value)
+    }
+
+    /// Loads an u16, raising the matching exception if the bus access fails or hits a breakpoint
+    ///
+    /// # Arguments:
+    ///
+    /// * `instruction`: The instruction causing the load
+    /// * `address`: The absolute address
+    fn load_u16(
+        &mut self,
+        instruction: Instruction,
+        address: u32,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) -> Option<u16> {
+        if self.data_breakpoint(instruction, address, Access::Load) {
+            return None;
+        }
+
+        if self.cache_isolated() {
+            // Isolated loads are served by the cache instead of the memory
+            return Some(bus.read_cache_u16(address));
+        }
+
+        let value = bus
+            .read_u16(address, dma, gpu)
+            .map_err(|error| self.raise_bus_exception(instruction, error, Access::Load))
+            .ok()?;
+
+        self.watchpoints.check(
+            address,
+            bus.mirror_address(address),
+            2,
+            value as u32,
+            WatchKind::Read,
+        );
+
+        Some(value)
+    }
+
+    /// Loads an u32, raising the matching exception if the bus access fails or hits a breakpoint
+    ///
+    /// # Arguments:
+    ///
+    /// * `instruction`: The instruction causing the load
+    /// * `address`: The absolute address
+    fn load_u32(
+        &mut self,
+        instruction: Instruction,
+        address: u32,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) -> Option<u32> {
+        if self.data_breakpoint(instruction, address, Access::Load) {
+            return None;
+        }
+
+        if self.cache_isolated() {
+            // Isolated loads are served by the cache instead of the memory
+            return Some(bus.read_cache_u32(address));
+        }
+
+        let value = bus
+            .read_u32(address, dma, gpu)
+            .map_err(|error| self.raise_bus_exception(instruction, error, Access::Load))
+            .ok()?;
+
+        self.watchpoints.check(
+            address,
+            bus.mirror_address(address),
+            4,
+            value,
+            WatchKind::Read,
+        );
+
+        Some(value)
+    }
+
+    /// Stores an u8, raising the matching exception if the bus access fails or hits a breakpoint
+    ///
+    /// # Arguments:
+    ///
+    /// * `instruction`: The instruction causing the store
+    /// * `address`: The absolute address
+    /// * `value`: The value to be stored
+    fn store_u8(
+        &mut self,
+        instruction: Instruction,
+        address: u32,
+        value: u8,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
+        if self.data_breakpoint(instruction, address, Access::Store) {
+            return;
+        }
+
+        if self.cache_isolated() {
+            // Isolated stores land in the cache and never reach the memory
+            bus.write_cache_u8(address, value);
+            return;
+        }
+
+        if let Err(error) = bus.write_u8(address, value, dma, gpu) {
+            self.raise_bus_exception(instruction, error, Access::Store);
+            return;
+        }
+
+        self.watchpoints.check(
+            address,
+            bus.mirror_address(address),
+            1,
+            value as u32,
+            WatchKind::Write,
+        );
+    }
+
+    /// Stores an u16, raising the matching exception if the bus access fails or hits a breakpoint
+    ///
+    /// # Arguments:
+    ///
+    /// * `instruction`: The instruction causing the store
+    /// * `address`: The absolute address
+    /// * `value`: The value to be stored
+    fn store_u16(
+        &mut self,
+        instruction: Instruction,
+        address: u32,
+        value: u16,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
+        if self.data_breakpoint(instruction, address, Access::Store) {
+            return;
+        }
+
+        if self.cache_isolated() {
+            // Isolated stores land in the cache and never reach the memory
+            bus.write_cache_u16(address, value);
+            return;
+        }
+
+        if let Err(error) = bus.write_u16(address, value, dma, gpu) {
+            self.raise_bus_exception(instruction, error, Access::Store);
+            return;
+        }
+
+        self.watchpoints.check(
+            address,
+            bus.mirror_address(address),
+            2,
+            value as u32,
+            WatchKind::Write,
+        );
+    }
+
+    /// Stores an u32, raising the matching exception if the bus access fails or hits a breakpoint
+    ///
+    /// # Arguments:
+    ///
+    /// * `instruction`: The instruction causing the store
+    /// * `address`: The absolute address
+    /// * `value`: The value to be stored
+    fn store_u32(
+        &mut self,
+        instruction: Instruction,
+        address: u32,
+        value: u32,
+        bus: &mut Bus,
+        dma: &mut Dma,
+        gpu: &mut Gpu,
+    ) {
+        if self.data_breakpoint(instruction, address, Access::Store) {
+            return;
+        }
+
+        if self.cache_isolated() {
+            // Isolated stores land in the cache and never reach the memory
+            bus.write_cache_u32(address, value);
+            return;
+        }
+
+        if let Err(error) = bus.write_u32(address, value, dma, gpu) {
+            self.raise_bus_exception(instruction, error, Access::Store);
+            return;
+        }
+
+        self.watchpoints.check(
+            address,
+            bus.mirror_address(address),
+            4,
+            value,
+            WatchKind::Write,
+        );
+    }
+
+    /// Watches a range of memory for loads and stores of the CPU
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The physical start address with the RAM mirrors folded onto the installed RAM
+    /// * `length`: The length in bytes
+    /// * `on_read`: If loads get reported
+    /// * `on_write`: If stores get reported
+    pub(crate) fn add_watchpoint(
+        &mut self,
+        address: u32,
+        length: u32,
+        on_read: bool,
+        on_write: bool,
+    ) {
+        self.watchpoints.add(address, length, on_read, on_write);
+    }
+
+    /// Removes every watchpoint
+    pub(crate) fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+
+    /// Replaces the callback receiving the accesses hitting a watchpoint
+    ///
+    /// # Arguments:
+    ///
+    /// * `callback`: The new callback
+    pub(crate) fn set_watchpoint_callback(&mut self, callback: WatchCallback) {
+        self.watchpoints.set_callback(callback);
+    }
+
     /// Sets a register to a value
     ///
     /// # Arguments:
@@ -265,6 +810,21 @@ impl Cpu {
         self.registers[register_value]
     }
 
+    /// Returns if a coprocessor is enabled by its CU bit in SR
+    ///
+    /// COP0 is always usable in kernel mode
+    ///
+    /// # Arguments:
+    ///
+    /// * `coprocessor`: The number of the coprocessor
+    fn coprocessor_usable(&self, coprocessor: u8) -> bool {
+        let sr = self.cop0_register(Cop0Register::Sr);
+        let kernel_mode = sr & (1 << 1) == 0;
+        let enabled = sr & (1 << (28 + coprocessor as u32)) != 0;
+
+        enabled || (coprocessor == 0 && kernel_mode)
+    }
+
     /// Sets a cop register to a value
     ///
     /// # Arguments:
@@ -292,9 +852,21 @@ impl Cpu {
         self.cop0_registers[cop0_register_value]
     }
 
-    /// Returns the Bus
-    pub(crate) fn bus(&mut self) -> &mut Bus {
-        // TODO: Move bus to application
-        &mut self.bus
+    /// Moves the parts excluded from save states from the previous CPU into this restored one
+    ///
+    /// # Arguments:
+    ///
+    /// * `previous`: The CPU which gets replaced
+    pub(crate) fn reattach(&mut self, previous: &mut Self) {
+        mem::swap(&mut self.tty, &mut previous.tty);
+        mem::swap(&mut self.watchpoints, &mut previous.watchpoints);
+        self.log_bios_calls = previous.log_bios_calls;
+    }
+
+    /// Restores the power-on state, keeping the TTY, the watchpoints and the BIOS call logging
+    pub(crate) fn reset(&mut self) {
+        let mut cpu = Self::new();
+        cpu.reattach(self);
+        *self = cpu;
     }
 }
@@ -4,11 +4,12 @@
  * SPDX-License-Identifier: MIT
  */
 
+use serde::{Deserialize, Serialize};
 use std::fmt::{self, Debug, Display, Formatter};
 
 /// General register wrapper
 #[repr(u8)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub(super) enum Register {
     /// Constant (always 0)
     Zero = 0,
@@ -289,9 +290,11 @@ impl Display for Cop0Register {
     }
 }
 
-impl From<u8> for Cop0Register {
-    fn from(value: u8) -> Self {
-        match value {
+impl TryFrom<u8> for Cop0Register {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        let cop0_register = match value {
             3 => Self::Bpc,
             5 => Self::Bda,
             6 => Self::Jumpdest,
@@ -303,7 +306,9 @@ impl From<u8> for Cop0Register {
             13 => Self::Cause,
             14 => Self::Epc,
             15 => Self::Prid,
-            _ => unreachable!("unknown cop0 register: {}", value),
-        }
+            _ => return Err(value),
+        };
+
+        Ok(cop0_register)
     }
 }
@@ -4,9 +4,42 @@
  * SPDX-License-Identifier: MIT
  */
 
-use crate::cpu::{exception::Exception, instruction::Instruction, Cpu};
+use crate::cpu::{alu, exception::Exception, instruction::Instruction, Cpu};
 
 impl Cpu {
+    /// The cycles a division takes until its result is in HI and LO
+    const DIVISION_CYCLES: u32 = 36;
+
+    /// Returns the cycles a multiplication takes until its result is in HI and LO
+    ///
+    /// <https://psx-spx.consoledev.net/cpuspecifications/#multiplydivide-execution-time>
+    ///
+    /// # Arguments:
+    ///
+    /// * `significant_bits`: The significant bits of the first operand
+    fn multiplication_cycles(significant_bits: u32) -> u32 {
+        match significant_bits {
+            0..=11 => 6,
+            12..=20 => 9,
+            _ => 13,
+        }
+    }
+
+    /// Starts a multiplication or division which writes HI and LO after a latency
+    ///
+    /// # Arguments:
+    ///
+    /// * `cycles`: The cycles until the result is ready
+    fn start_hilo_operation(&mut self, cycles: u32) {
+        self.hilo_ready_at = self.cycles + cycles as u64;
+    }
+
+    /// Stalls the current instruction until the result of the last multiplication or division is
+    /// ready
+    fn wait_for_hilo(&mut self) {
+        self.stall_cycles += self.hilo_ready_at.saturating_sub(self.cycles) as u32;
+    }
+
     /// Opcode SLL - Shift Word Left Logical (0b000000)
     ///
     /// # Arguments:
@@ -21,17 +54,8 @@ impl Cpu {
 
         let t = self.register(rt);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SLL {}, {}, {:#x}",
-            self.n,
-            instruction.1,
-            rd,
-            rt,
-            sa
-        );
-
-        let result = t << sa;
+        // SLL $zero, $zero, 0 is the canonical NOP, writes to $zero get discarded
+        let result = alu::shift_left_logical(t, sa as u32);
 
         self.set_register(rd, result);
     }
@@ -50,17 +74,7 @@ impl Cpu {
 
         let t = self.register(rt);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SRL {}, {}, {:#x}",
-            self.n,
-            instruction.1,
-            rd,
-            rt,
-            sa
-        );
-
-        let result = t >> sa;
+        let result = alu::shift_right_logical(t, sa as u32);
 
         self.set_register(rd, result);
     }
@@ -77,25 +91,17 @@ impl Cpu {
         let rd = instruction.rd();
         let sa = instruction.shamt();
 
-        let t = self.register(rt) as i32;
-
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SRA {}, {}, {:#x}",
-            self.n,
-            instruction.1,
-            rd,
-            rt,
-            sa
-        );
+        let t = self.register(rt);
 
-        let result = (t >> sa) as u32;
+        let result = alu::shift_right_arithmetic(t, sa as u32);
 
         self.set_register(rd, result);
     }
 
     /// Opcode SLLV - Shift Word Left Logical Variable (0b000100)
     ///
+    /// Only the lower 5 bits of $rs are used as the shift amount
+    ///
     /// # Arguments:
     ///
     /// * `instruction`: The current instruction data
@@ -109,23 +115,15 @@ impl Cpu {
         let t = self.register(rt);
         let s = self.register(rs);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SLLV {}, {}, {}",
-            self.n,
-            instruction.1,
-            rd,
-            rt,
-            rs
-        );
-
-        let result = t << (s & 0x1f);
+        let result = alu::shift_left_logical(t, s);
 
         self.set_register(rd, result);
     }
 
     /// Opcode SRLV - Shift Word Right Logical Variable (0b000110)
     ///
+    /// Only the lower 5 bits of $rs are used as the shift amount
+    ///
     /// # Arguments:
     ///
     /// * `instruction`: The current instruction data
@@ -139,22 +137,14 @@ impl Cpu {
         let t = self.register(rt);
         let s = self.register(rs);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SRLV {}, {}, {}",
-            self.n,
-            instruction.1,
-            rd,
-            rt,
-            rs
-        );
-
-        let result = t >> (s & 0x1f);
+        let result = alu::shift_right_logical(t, s);
 
         self.set_register(rd, result);
     }
 
-    /// Opcode SRAV - Shift Word Right Arithmetic Variable (0b000100)
+    /// Opcode SRAV - Shift Word Right Arithmetic Variable (0b000111)
+    ///
+    /// Only the lower 5 bits of $rs are used as the shift amount
     ///
     /// # Arguments:
     ///
@@ -166,20 +156,10 @@ impl Cpu {
         let rt = instruction.rt();
         let rd = instruction.rd();
 
-        let t = self.register(rt) as i32;
+        let t = self.register(rt);
         let s = self.register(rs);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SLLV {}, {}, {}",
-            self.n,
-            instruction.1,
-            rd,
-            rt,
-            rs
-        );
-
-        let result = (t >> (s & 0x1f)) as u32;
+        let result = alu::shift_right_arithmetic(t, s);
 
         self.set_register(rd, result);
     }
@@ -198,8 +178,6 @@ impl Cpu {
     pub(super) fn op_jr(&mut self, instruction: Instruction) {
         let rs = instruction.rs();
 
-        log::debug!(target: "cpu", "{}: {:#010x}: JR {}", self.n, instruction.1, rs);
-
         let address = self.register(rs);
 
         self.branch_delay_pc = Some(address);
@@ -220,8 +198,6 @@ impl Cpu {
         let rs = instruction.rs();
         let rd = instruction.rd();
 
-        log::debug!(target: "cpu", "{}: {:#010x}: JALR {}", self.n, instruction.1, rs);
-
         let address = self.register(rs);
 
         self.set_register(rd, self.pc);
@@ -240,7 +216,9 @@ impl Cpu {
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=288>
     pub(super) fn op_syscall(&mut self, instruction: Instruction) {
-        log::debug!(target: "cpu", "{}: {:#010x}: SYSCALL", self.n, instruction.1);
+        if self.log_bios_calls {
+            self.log_syscall(instruction);
+        }
 
         self.raise_exception(instruction, Exception::Syscall);
     }
@@ -257,8 +235,6 @@ impl Cpu {
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=233>
     pub(super) fn op_break(&mut self, instruction: Instruction) {
-        log::debug!(target: "cpu", "{}: {:#010x}: BREAK", self.n, instruction.1);
-
         self.raise_exception(instruction, Exception::Bp);
     }
 
@@ -272,7 +248,7 @@ impl Cpu {
     pub(super) fn op_mfhi(&mut self, instruction: Instruction) {
         let rd = instruction.rd();
 
-        log::debug!(target: "cpu", "{}: {:#010x}: MFHI {}", self.n, instruction.1, rd);
+        self.wait_for_hilo();
 
         let result = self.hi;
 
@@ -289,8 +265,6 @@ impl Cpu {
     pub(super) fn op_mthi(&mut self, instruction: Instruction) {
         let rs = instruction.rs();
 
-        log::debug!(target: "cpu", "{}: {:#010x}: MTHI {}", self.n, instruction.1, rs);
-
         let result = self.register(rs);
 
         self.hi = result;
@@ -306,7 +280,7 @@ impl Cpu {
     pub(super) fn op_mflo(&mut self, instruction: Instruction) {
         let rd = instruction.rd();
 
-        log::debug!(target: "cpu", "{}: {:#010x}: MFLO {}", self.n, instruction.1, rd);
+        self.wait_for_hilo();
 
         let result = self.lo;
 
@@ -323,8 +297,6 @@ impl Cpu {
     pub(super) fn op_mtlo(&mut self, instruction: Instruction) {
         let rs = instruction.rs();
 
-        log::debug!(target: "cpu", "{}: {:#010x}: MTLO {}", self.n, instruction.1, rs);
-
         let result = self.register(rs);
 
         self.lo = result;
@@ -344,7 +316,9 @@ impl Cpu {
         let s = self.register(rs) as i32 as i64;
         let t = self.register(rt) as i32 as i64;
 
-        log::debug!(target: "cpu", "{}: {:#010x}: MULT {}, {}", self.n, instruction.1, rs, rt);
+        // Operands with more leading sign bits finish early
+        let significant_bits = 32 - (s as i32 ^ (s as i32 >> 31)).leading_zeros();
+        self.start_hilo_operation(Self::multiplication_cycles(significant_bits));
 
         let result = (s * t) as u64;
 
@@ -366,7 +340,9 @@ impl Cpu {
         let s = self.register(rs) as u64;
         let t = self.register(rt) as u64;
 
-        log::debug!(target: "cpu", "{}: {:#010x}: MULTU {}, {}", self.n, instruction.1, rs, rt);
+        // Operands with more leading zeros finish early
+        let significant_bits = 32 - (s as u32).leading_zeros();
+        self.start_hilo_operation(Self::multiplication_cycles(significant_bits));
 
         let result = s * t;
 
@@ -382,7 +358,7 @@ impl Cpu {
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=237>
     pub(super) fn op_div(&mut self, instruction: Instruction) {
-        // TODO: Implement proper timing
+        self.start_hilo_operation(Self::DIVISION_CYCLES);
 
         let rs = instruction.rs();
         let rt = instruction.rt();
@@ -393,14 +369,14 @@ impl Cpu {
         // The number to multiply with or to divide with
         let t = self.register(rt) as i32;
 
-        log::debug!(target: "cpu", "{}: {:#010x}: DIV {}, {}", self.n, instruction.1, rs, rt);
-
+        // <https://psx-spx.consoledev.net/cpuspecifications/#division-by-zero>
         if t == 0 {
-            // Division by zero
+            // Division by zero keeps the dividend in HI, LO is -1 for a non-negative and +1 for a
+            // negative dividend
             self.hi = s as u32;
             self.lo = if s >= 0 { 0xffffffff } else { 1 };
-        } else if s as u32 == 0x80000000 && t == -1 {
-            // Result is greater than u32
+        } else if s == i32::MIN && t == -1 {
+            // The quotient doesn't fit into 32 bits and wraps around to -80000000h
             self.hi = 0;
             self.lo = 0x80000000;
         } else {
@@ -417,7 +393,7 @@ impl Cpu {
     ///
     /// <https://cgi.cse.unsw.edu.au/~cs3231/doc/R3000.pdf#page=237>
     pub(super) fn op_divu(&mut self, instruction: Instruction) {
-        // TODO: Implement proper timing
+        self.start_hilo_operation(Self::DIVISION_CYCLES);
 
         let rs = instruction.rs();
         let rt = instruction.rt();
@@ -428,8 +404,6 @@ impl Cpu {
         // The number to multiply with or to divide with
         let t = self.register(rt);
 
-        log::debug!(target: "cpu", "{}: {:#010x}: DIVU {}, {}", self.n, instruction.1, rs, rt);
-
         if t == 0 {
             // Division by zero
             self.hi = s;
@@ -456,26 +430,14 @@ impl Cpu {
         let rt = instruction.rt();
         let rd = instruction.rd();
 
-        let s = self.register(rs) as i32;
-        let t = self.register(rt) as i32;
+        let s = self.register(rs);
+        let t = self.register(rt);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: ADD {}, {}, {}",
-            self.n,
-            instruction.1,
-            rd,
-            rs,
-            rt
-        );
-
-        let Some(result) = s.checked_add(t) else {
+        let Some(result) = alu::add(s, t) else {
             self.raise_exception(instruction, Exception::Ov);
             return;
         };
 
-        let result = result as u32;
-
         self.set_register(rd, result);
     }
 
@@ -494,16 +456,6 @@ impl Cpu {
         let s = self.register(rs);
         let t = self.register(rt);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: ADDU {}, {}, {}",
-            self.n,
-            instruction.1,
-            rd,
-            rs,
-            rt
-        );
-
         let result = s.wrapping_add(t);
 
         self.set_register(rd, result);
@@ -521,26 +473,14 @@ impl Cpu {
         let rt = instruction.rt();
         let rd = instruction.rd();
 
-        let s = self.register(rs) as i32;
-        let t = self.register(rt) as i32;
+        let s = self.register(rs);
+        let t = self.register(rt);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SUB {}, {}, {}",
-            self.n,
-            instruction.1,
-            rd,
-            rs,
-            rt
-        );
-
-        let Some(result) = s.checked_sub(t) else {
+        let Some(result) = alu::sub(s, t) else {
             self.raise_exception(instruction, Exception::Ov);
             return;
         };
 
-        let result = result as u32;
-
         self.set_register(rd, result);
     }
 
@@ -559,16 +499,6 @@ impl Cpu {
         let s = self.register(rs);
         let t = self.register(rt);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SUBU {}, {}, {}",
-            self.n,
-            instruction.1,
-            rd,
-            rs,
-            rt
-        );
-
         let result = s.wrapping_sub(t);
 
         self.set_register(rd, result);
@@ -589,16 +519,6 @@ impl Cpu {
         let s = self.register(rs);
         let t = self.register(rt);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: AND {}, {}, {}",
-            self.n,
-            instruction.1,
-            rd,
-            rs,
-            rt
-        );
-
         let result = s & t;
 
         self.set_register(rd, result);
@@ -619,16 +539,6 @@ impl Cpu {
         let s = self.register(rs);
         let t = self.register(rt);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: OR {}, {}, {}",
-            self.n,
-            instruction.1,
-            rd,
-            rs,
-            rt
-        );
-
         let result = s | t;
 
         self.set_register(rd, result);
@@ -649,16 +559,6 @@ impl Cpu {
         let s = self.register(rs);
         let t = self.register(rt);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: XOR {}, {}, {}",
-            self.n,
-            instruction.1,
-            rd,
-            rs,
-            rt
-        );
-
         let result = s ^ t;
 
         self.set_register(rd, result);
@@ -679,16 +579,6 @@ impl Cpu {
         let s = self.register(rs);
         let t = self.register(rt);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: NOR {}, {}, {}",
-            self.n,
-            instruction.1,
-            rd,
-            rs,
-            rt
-        );
-
         let result = !(s | t);
 
         self.set_register(rd, result);
@@ -706,20 +596,10 @@ impl Cpu {
         let rt = instruction.rt();
         let rd = instruction.rd();
 
-        let s = self.register(rs) as i32;
-        let t = self.register(rt) as i32;
-
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SLT {}, {}, {}",
-            self.n,
-            instruction.1,
-            rd,
-            rs,
-            rt
-        );
+        let s = self.register(rs);
+        let t = self.register(rt);
 
-        let result = (s < t) as u32;
+        let result = alu::set_less_than(s, t);
 
         self.set_register(rd, result);
     }
@@ -739,17 +619,7 @@ impl Cpu {
         let s = self.register(rs);
         let t = self.register(rt);
 
-        log::debug!(
-            target: "cpu",
-            "{}: {:#010x}: SLTU {}, {}, {}",
-            self.n,
-            instruction.1,
-            rd,
-            rs,
-            rt
-        );
-
-        let result = (s < t) as u32;
+        let result = alu::set_less_than_unsigned(s, t);
 
         self.set_register(rd, result);
     }
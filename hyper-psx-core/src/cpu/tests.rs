@@ -0,0 +1,812 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::{
+    bios::Bios,
+    bus::{ram::Ram, Bus},
+    cdrom::Cdrom,
+    cpu::{register::Cop0Register, Cpu},
+    dma::Dma,
+    gpu::Gpu,
+    interrupt::InterruptController,
+    region::Region,
+    renderer::mock_renderer::MockRenderer,
+    sio::Sio,
+};
+
+use std::{cell::RefCell, rc::Rc};
+
+/// A CPU with its components, running a program from RAM
+struct Harness {
+    cpu: Cpu,
+    bus: Bus,
+    dma: Dma,
+    gpu: Gpu,
+}
+
+impl Harness {
+    /// The address the program gets loaded to
+    const PROGRAM_ADDRESS: u32 = 0x80001000;
+
+    fn new(program: &[u32]) -> Self {
+        let bus = Bus::new(
+            Bios::zeroed(),
+            Ram::with_size(2 * 1024 * 1024),
+            InterruptController::new(),
+            Cdrom::new(None),
+            Sio::new(None),
+        );
+        let (renderer, _) = MockRenderer::new();
+
+        let mut harness = Self {
+            cpu: Cpu::new(),
+            bus,
+            dma: Dma::new(),
+            gpu: Gpu::new(Box::new(renderer), Region::Ntsc),
+        };
+        harness.load(Self::PROGRAM_ADDRESS, program);
+        harness.cpu.pc = Self::PROGRAM_ADDRESS;
+        harness
+    }
+
+    fn load(&mut self, address: u32, words: &[u32]) {
+        for (index, word) in words.iter().enumerate() {
+            let address = address + index as u32 * 4;
+            self.bus
+                .write_u32(address, *word, &mut self.dma, &mut self.gpu)
+                .unwrap();
+        }
+    }
+
+    fn read_u32(&mut self, address: u32) -> u32 {
+        self.bus
+            .read_u32(address, &mut self.dma, &mut self.gpu)
+            .unwrap()
+    }
+
+    fn set_register(&mut self, index: usize, value: u32) {
+        self.cpu.registers[index] = value;
+        self.cpu.out_registers[index] = value;
+    }
+
+    fn register(&self, index: usize) -> u32 {
+        self.cpu.registers[index]
+    }
+
+    fn cop0_register(&self, register: Cop0Register) -> u32 {
+        self.cpu.cop0_registers[register as usize]
+    }
+
+    fn tty_lines(&mut self) -> Rc<RefCell<Vec<String>>> {
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink_lines = Rc::clone(&lines);
+        self.cpu.set_tty_sink(Box::new(move |line| {
+            sink_lines.borrow_mut().push(line.to_owned())
+        }));
+        lines
+    }
+
+    fn step(&mut self) -> u32 {
+        self.cpu.step(&mut self.bus, &mut self.dma, &mut self.gpu)
+    }
+
+    fn run_until(&mut self, pc: u32) {
+        for _ in 0..1000 {
+            if self.cpu.pc == pc {
+                return;
+            }
+
+            self.step();
+        }
+
+        panic!("pc {:#010x} was never reached", pc);
+    }
+
+    fn bios_call(&mut self, gate: u32, function: u32, argument: u32) {
+        self.set_register(4, argument);
+        self.set_register(9, function);
+        self.cpu.pc = gate;
+        self.step();
+    }
+}
+
+#[test]
+fn putchar_accumulates_once_a_tty_device_is_installed() {
+    let mut harness = Harness::new(&[]);
+    harness.bios_call(0xa0, 0x3c, b'-' as u32);
+    assert_eq!(harness.cpu.take_tty_output(), "");
+
+    // AddDummyTtyDevice()
+    harness.bios_call(0xa0, 0x99, 0);
+    for character in b"OK\r\n" {
+        harness.bios_call(0xb0, 0x3d, *character as u32);
+    }
+    assert_eq!(harness.cpu.take_tty_output(), "OK\n");
+    assert_eq!(harness.cpu.take_tty_output(), "");
+}
+
+#[test]
+fn isolated_stores_only_reach_the_cache() {
+    let mut harness = Harness::new(&[
+        0x40886000, // mtc0 $t0, $sr
+        0xad2a0000, // sw $t2, 0($t1)
+        0xa12a0005, // sb $t2, 5($t1)
+        0x8d2b0000, // lw $t3, 0($t1)
+        0x91240005, // lbu $a0, 5($t1)
+        0x40806000, // mtc0 $zero, $sr
+        0x8d2c0000, // lw $t4, 0($t1)
+        0x00000000, // nop
+    ]);
+    harness.set_register(8, 0x00010000);
+    harness.set_register(9, 0x80002000);
+    harness.set_register(10, 0x12345678);
+
+    for _ in 0..8 {
+        harness.step();
+    }
+
+    // The isolated loads read back what the isolated stores left in the cache
+    assert_eq!(harness.register(11), 0x12345678);
+    assert_eq!(harness.register(4), 0x78);
+
+    // The RAM never saw the stores
+    assert_eq!(harness.register(12), 0);
+    assert_eq!(harness.read_u32(0x80002000), 0);
+    assert_eq!(harness.read_u32(0x80002004), 0);
+}
+
+#[test]
+fn puts_reads_the_string_without_side_effects() {
+    let mut harness = Harness::new(&[]);
+    let lines = harness.tty_lines();
+    harness.cpu.tty.install_device("test");
+    harness.load(0x80002000, &[u32::from_le_bytes(*b"PSX\0")]);
+    harness.bios_call(0xb0, 0x3f, 0x80002000);
+
+    assert_eq!(*lines.borrow(), ["PSX"]);
+}
+
+#[test]
+fn puts_stops_at_unmapped_memory() {
+    let mut harness = Harness::new(&[]);
+    let lines = harness.tty_lines();
+    harness.cpu.tty.install_device("test");
+    harness.bios_call(0xa0, 0x3e, 0x1f801ffe);
+
+    assert_eq!(*lines.borrow(), [""]);
+}
+
+#[test]
+fn unaligned_load_raises_address_error() {
+    let mut harness = Harness::new(&[
+        0x8d2b0000, // lw $t3, 0($t1)
+    ]);
+    harness.set_register(9, 0x80002001);
+    harness.step();
+
+    assert_eq!(harness.cpu.pc, 0x80000080);
+    assert_eq!(
+        (harness.cop0_register(Cop0Register::Cause) >> 2) & 0x1f,
+        0x04
+    );
+    assert_eq!(
+        harness.cop0_register(Cop0Register::Epc),
+        Harness::PROGRAM_ADDRESS
+    );
+    assert_eq!(harness.cop0_register(Cop0Register::Badvaddr), 0x80002001);
+}
+
+#[test]
+fn unaligned_store_raises_address_error() {
+    let mut harness = Harness::new(&[
+        0xa52a0000, // sh $t2, 0($t1)
+    ]);
+    harness.set_register(9, 0x80002003);
+    harness.step();
+
+    assert_eq!(harness.cpu.pc, 0x80000080);
+    assert_eq!(
+        (harness.cop0_register(Cop0Register::Cause) >> 2) & 0x1f,
+        0x05
+    );
+    assert_eq!(harness.cop0_register(Cop0Register::Badvaddr), 0x80002003);
+}
+
+#[test]
+fn unmapped_load_raises_bus_error() {
+    let mut harness = Harness::new(&[
+        0x812b0000, // lb $t3, 0($t1)
+    ]);
+    harness.set_register(9, 0x1f800400);
+    harness.step();
+
+    assert_eq!(harness.cpu.pc, 0x80000080);
+    assert_eq!(
+        (harness.cop0_register(Cop0Register::Cause) >> 2) & 0x1f,
+        0x07
+    );
+}
+
+#[test]
+fn execution_breakpoint_breaks_at_bpc() {
+    let mut harness = Harness::new(&[
+        0x00000000, // nop
+        0x00000000, // nop
+        0x24080001, // addiu $t0, $zero, 1
+    ]);
+    let address = Harness::PROGRAM_ADDRESS + 8;
+    harness.cpu.set_cop0_register(Cop0Register::Bpc, address);
+    harness
+        .cpu
+        .set_cop0_register(Cop0Register::Bpcm, 0xffffffff);
+    harness
+        .cpu
+        .set_cop0_register(Cop0Register::Dcic, 0xc1800000);
+
+    harness.step();
+    harness.step();
+    assert_eq!(harness.cpu.pc, address);
+
+    harness.step();
+
+    assert_eq!(harness.cpu.pc, 0x80000040);
+    assert_eq!(harness.register(8), 0);
+    assert_eq!(
+        (harness.cop0_register(Cop0Register::Cause) >> 2) & 0x1f,
+        0x09
+    );
+    assert_eq!(harness.cop0_register(Cop0Register::Epc), address);
+    assert_eq!(harness.cop0_register(Cop0Register::Dcic) & 0x1f, 0x03);
+}
+
+#[test]
+fn disabled_execution_breakpoint_is_ignored() {
+    let mut harness = Harness::new(&[
+        0x24080001, // addiu $t0, $zero, 1
+    ]);
+    harness
+        .cpu
+        .set_cop0_register(Cop0Register::Bpc, Harness::PROGRAM_ADDRESS);
+    harness
+        .cpu
+        .set_cop0_register(Cop0Register::Bpcm, 0xffffffff);
+
+    // The execute enable without the master enables
+    harness
+        .cpu
+        .set_cop0_register(Cop0Register::Dcic, 0x01000000);
+    harness.step();
+
+    assert_eq!(harness.register(8), 1);
+    assert_eq!(harness.cop0_register(Cop0Register::Dcic) & 0x1f, 0x00);
+}
+
+#[test]
+fn data_write_breakpoint_stops_the_store() {
+    let mut harness = Harness::new(&[
+        0xad2a0000, // sw $t2, 0($t1)
+    ]);
+    harness.set_register(9, 0x80002000);
+    harness.set_register(10, 0x12345678);
+    harness.cpu.set_cop0_register(Cop0Register::Bda, 0x80002000);
+    harness
+        .cpu
+        .set_cop0_register(Cop0Register::Bdam, 0xfffffff0);
+    harness
+        .cpu
+        .set_cop0_register(Cop0Register::Dcic, 0xca800000);
+    harness.step();
+
+    assert_eq!(harness.cpu.pc, 0x80000040);
+    assert_eq!(harness.read_u32(0x80002000), 0);
+    assert_eq!(harness.cop0_register(Cop0Register::Dcic) & 0x1f, 0x15);
+}
+
+#[test]
+fn data_read_breakpoint_ignores_stores() {
+    let mut harness = Harness::new(&[
+        0xad2a0000, // sw $t2, 0($t1)
+        0x8d2b0000, // lw $t3, 0($t1)
+    ]);
+    harness.set_register(9, 0x80002000);
+    harness.set_register(10, 0x12345678);
+    harness.cpu.set_cop0_register(Cop0Register::Bda, 0x80002000);
+    harness
+        .cpu
+        .set_cop0_register(Cop0Register::Bdam, 0xffffffff);
+    harness
+        .cpu
+        .set_cop0_register(Cop0Register::Dcic, 0xc6800000);
+
+    harness.step();
+    assert_eq!(harness.read_u32(0x80002000), 0x12345678);
+    assert_eq!(harness.cop0_register(Cop0Register::Dcic) & 0x1f, 0x00);
+
+    harness.step();
+    assert_eq!(harness.cpu.pc, 0x80000040);
+    assert_eq!(harness.cop0_register(Cop0Register::Dcic) & 0x1f, 0x0d);
+}
+
+#[test]
+fn unaligned_jump_target_raises_address_error() {
+    let mut harness = Harness::new(&[
+        0x01000008, // jr $t0
+        0x00000000, // nop
+    ]);
+    let target = Harness::PROGRAM_ADDRESS + 0x12;
+    harness.set_register(8, target);
+
+    // A stale BD flag gets cleared outside of a delay slot
+    harness
+        .cpu
+        .set_cop0_register(Cop0Register::Cause, 0x80000000);
+
+    for _ in 0..3 {
+        harness.step();
+    }
+
+    assert_eq!(harness.cpu.pc, 0x80000080);
+    assert_eq!(harness.cop0_register(Cop0Register::Cause), 0x04 << 2);
+    assert_eq!(harness.cop0_register(Cop0Register::Epc), target);
+    assert_eq!(harness.cop0_register(Cop0Register::Badvaddr), target);
+}
+
+#[test]
+fn exception_in_delay_slot_sets_bd() {
+    let mut harness = Harness::new(&[
+        0x08000440, // j 0x80001100
+        0x8d2b0000, // lw $t3, 0($t1)
+    ]);
+    harness.set_register(9, 0x80002002);
+
+    harness.step();
+    harness.step();
+
+    assert_eq!(harness.cpu.pc, 0x80000080);
+    assert_eq!(
+        harness.cop0_register(Cop0Register::Cause),
+        (1 << 31) | (0x04 << 2)
+    );
+    assert_eq!(
+        harness.cop0_register(Cop0Register::Epc),
+        Harness::PROGRAM_ADDRESS
+    );
+    assert_eq!(harness.cop0_register(Cop0Register::Badvaddr), 0x80002002);
+}
+
+#[test]
+fn unaligned_halfword_load_sets_badvaddr() {
+    let mut harness = Harness::new(&[
+        0x952b0000, // lhu $t3, 0($t1)
+    ]);
+    harness.set_register(9, 0x80002005);
+    harness.set_register(11, 0x1234);
+    harness.step();
+
+    assert_eq!(harness.cpu.pc, 0x80000080);
+    assert_eq!(
+        (harness.cop0_register(Cop0Register::Cause) >> 2) & 0x1f,
+        0x04
+    );
+    assert_eq!(harness.cop0_register(Cop0Register::Badvaddr), 0x80002005);
+    assert_eq!(harness.register(11), 0x1234);
+}
+
+#[test]
+fn unaligned_word_store_leaves_memory_untouched() {
+    let mut harness = Harness::new(&[
+        0xad2a0000, // sw $t2, 0($t1)
+    ]);
+    harness.load(0x80002000, &[0xdeadbeef, 0xcafebabe]);
+    harness.set_register(9, 0x80002002);
+    harness.set_register(10, 0x11223344);
+    harness.step();
+
+    assert_eq!(harness.cpu.pc, 0x80000080);
+    assert_eq!(
+        (harness.cop0_register(Cop0Register::Cause) >> 2) & 0x1f,
+        0x05
+    );
+    assert_eq!(harness.cop0_register(Cop0Register::Badvaddr), 0x80002002);
+    assert_eq!(harness.read_u32(0x80002000), 0xdeadbeef);
+    assert_eq!(harness.read_u32(0x80002004), 0xcafebabe);
+}
+
+#[test]
+fn load_delay_slot_reads_the_old_value() {
+    let mut harness = Harness::new(&[
+        0x8d280000, // lw $t0, 0($t1)
+        0x01005021, // addu $t2, $t0, $zero
+        0x00000000, // nop
+    ]);
+    harness.load(0x80002000, &[0x12345678]);
+    harness.set_register(8, 0x11);
+    harness.set_register(9, 0x80002000);
+
+    harness.step();
+    harness.step();
+    harness.step();
+
+    assert_eq!(harness.register(10), 0x11);
+    assert_eq!(harness.register(8), 0x12345678);
+}
+
+#[test]
+fn write_in_load_delay_slot_wins_over_the_load() {
+    let mut harness = Harness::new(&[
+        0x8d280000, // lw $t0, 0($t1)
+        0x25080001, // addiu $t0, $t0, 1
+        0x00000000, // nop
+    ]);
+    harness.load(0x80002000, &[0x12345678]);
+    harness.set_register(8, 0x11);
+    harness.set_register(9, 0x80002000);
+
+    harness.step();
+    harness.step();
+    harness.step();
+
+    assert_eq!(harness.register(8), 0x12);
+}
+
+#[test]
+fn load_in_load_delay_slot_cancels_the_pending_load() {
+    let mut harness = Harness::new(&[
+        0x8d280000, // lw $t0, 0($t1)
+        0x8d280004, // lw $t0, 4($t1)
+        0x01005021, // addu $t2, $t0, $zero
+        0x00000000, // nop
+    ]);
+    harness.load(0x80002000, &[0x12345678, 0x9abcdef0]);
+    harness.set_register(8, 0x11);
+    harness.set_register(9, 0x80002000);
+
+    harness.step();
+    harness.step();
+    harness.step();
+    harness.step();
+
+    // The first load never lands
+    assert_eq!(harness.register(10), 0x11);
+    assert_eq!(harness.register(8), 0x9abcdef0);
+}
+
+#[test]
+fn mtc0_only_writes_the_software_interrupt_bits_of_cause() {
+    let mut harness = Harness::new(&[
+        0x40886800, // mtc0 $t0, $13
+    ]);
+    harness
+        .cpu
+        .set_cop0_register(Cop0Register::Cause, 0x04 << 2);
+    harness.set_register(8, 0xffffffff);
+    harness.step();
+
+    assert_eq!(
+        harness.cop0_register(Cop0Register::Cause),
+        0x00000300 | (0x04 << 2)
+    );
+}
+
+#[test]
+fn software_interrupt_bit_raises_an_interrupt() {
+    let mut harness = Harness::new(&[
+        0x40886800, // mtc0 $t0, $13
+        0x00000000, // nop
+    ]);
+    harness.cpu.set_cop0_register(Cop0Register::Sr, 0x00000101);
+    harness.set_register(8, 0x00000100);
+
+    harness.step();
+    harness.step();
+
+    assert_eq!(harness.cpu.pc, 0x80000080);
+    assert_eq!(
+        (harness.cop0_register(Cop0Register::Cause) >> 2) & 0x1f,
+        0x00
+    );
+    assert_eq!(
+        harness.cop0_register(Cop0Register::Epc),
+        Harness::PROGRAM_ADDRESS + 4
+    );
+}
+
+#[test]
+fn interrupt_in_delay_slot_returns_to_the_branch() {
+    let mut harness = Harness::new(&[
+        0x08000440, // j 0x80001100
+        0x00000000, // nop
+    ]);
+    harness.step();
+
+    // The software interrupt becomes pending while the delay slot is next
+    harness.cpu.set_cop0_register(Cop0Register::Sr, 0x00000101);
+    harness
+        .cpu
+        .set_cop0_register(Cop0Register::Cause, 0x00000100);
+    harness.step();
+
+    assert_eq!(harness.cpu.pc, 0x80000080);
+    assert_eq!(
+        harness.cop0_register(Cop0Register::Cause) & 0x8000007c,
+        1 << 31
+    );
+    assert_eq!(
+        harness.cop0_register(Cop0Register::Epc),
+        Harness::PROGRAM_ADDRESS
+    );
+}
+
+#[test]
+fn overflow_leaves_the_destination_untouched() {
+    for (word, s, t) in [
+        (0x21280001, 0x7fffffff, 0), // addi $t0, $t1, 1
+        (0x012a4020, 0x7fffffff, 1), // add $t0, $t1, $t2
+        (0x012a4022, 0x80000000, 1), // sub $t0, $t1, $t2
+    ] {
+        let mut harness = Harness::new(&[word]);
+        harness.set_register(8, 0x1234);
+        harness.set_register(9, s);
+        harness.set_register(10, t);
+        harness.step();
+
+        assert_eq!(harness.register(8), 0x1234, "{:#010x}", word);
+        assert_eq!(harness.cpu.pc, 0x80000080, "{:#010x}", word);
+        assert_eq!(
+            harness.cop0_register(Cop0Register::Cause) & 0x8000007c,
+            0x0c << 2,
+            "{:#010x}",
+            word
+        );
+        assert_eq!(
+            harness.cop0_register(Cop0Register::Epc),
+            Harness::PROGRAM_ADDRESS,
+            "{:#010x}",
+            word
+        );
+    }
+}
+
+#[test]
+fn overflow_in_delay_slot_returns_to_the_branch() {
+    let mut harness = Harness::new(&[
+        0x08000440, // j 0x80001100
+        0x21280001, // addi $t0, $t1, 1
+    ]);
+    harness.set_register(8, 0x1234);
+    harness.set_register(9, 0x7fffffff);
+    harness.step();
+    harness.step();
+
+    assert_eq!(harness.register(8), 0x1234);
+    assert_eq!(
+        harness.cop0_register(Cop0Register::Cause) & 0x8000007c,
+        (1 << 31) | (0x0c << 2)
+    );
+    assert_eq!(
+        harness.cop0_register(Cop0Register::Epc),
+        Harness::PROGRAM_ADDRESS
+    );
+}
+
+#[test]
+fn variable_shifts_use_the_lower_5_bits_of_the_amount() {
+    // sllv $t2, $t1, $t0 / srlv $t3, $t1, $t0 / srav $t4, $t1, $t0
+    let mut harness = Harness::new(&[0x01095004, 0x01095806, 0x01096007]);
+    harness.set_register(8, 33);
+    harness.set_register(9, 0x80000010);
+    for _ in 0..3 {
+        harness.step();
+    }
+
+    assert_eq!(harness.register(10), 0x00000020);
+    assert_eq!(harness.register(11), 0x40000008);
+    assert_eq!(harness.register(12), 0xc0000008);
+}
+
+/// Returns the cycles of a MFLO directly behind a multiplication or division
+///
+/// The operation is encoded with $t0 as rs and $t1 as rt
+fn hilo_latency(operation: u32, s: u32, t: u32) -> u32 {
+    // <operation> $t0, $t1 / mflo $t2
+    let mut harness = Harness::new(&[operation, 0x00005012]);
+    harness.set_register(8, s);
+    harness.set_register(9, t);
+
+    assert_eq!(harness.step(), Cpu::CYCLES_PER_INSTRUCTION);
+    harness.step()
+}
+
+#[test]
+fn multiplication_latency_depends_on_the_operand_size() {
+    // mult $t0, $t1
+    assert_eq!(hilo_latency(0x01090018, 0x00000005, 3), 6);
+    assert_eq!(hilo_latency(0x01090018, 0xfffffffb, 3), 6);
+    assert_eq!(hilo_latency(0x01090018, 0x00001000, 3), 9);
+    assert_eq!(hilo_latency(0x01090018, 0x00200000, 3), 13);
+
+    // multu $t0, $t1
+    assert_eq!(hilo_latency(0x01090019, 0x00000005, 3), 6);
+    assert_eq!(hilo_latency(0x01090019, 0x000fffff, 3), 9);
+    assert_eq!(hilo_latency(0x01090019, 0xfffffffb, 3), 13);
+}
+
+#[test]
+fn division_latency_is_fixed() {
+    // div $t0, $t1 / divu $t0, $t1
+    assert_eq!(hilo_latency(0x0109001a, 100, 7), 36);
+    assert_eq!(hilo_latency(0x0109001b, 100, 0), 36);
+}
+
+#[test]
+fn hilo_reads_only_stall_until_the_result_is_ready() {
+    // div $t0, $t1 / 20 nops / mfhi $t2
+    let mut program = vec![0x0109001a];
+    program.extend([0x00000000; 20]);
+    program.push(0x00005010);
+
+    let mut harness = Harness::new(&program);
+    harness.set_register(8, 100);
+    harness.set_register(9, 7);
+    for _ in 0..21 {
+        harness.step();
+    }
+
+    // The 21 instructions took 42 cycles, so the 36 cycles of the division have passed
+    assert_eq!(harness.step(), Cpu::CYCLES_PER_INSTRUCTION);
+    assert_eq!(harness.register(10), 2);
+}
+
+#[test]
+fn hilo_reads_without_an_operation_do_not_stall() {
+    // mflo $t2
+    let mut harness = Harness::new(&[0x00005012]);
+
+    assert_eq!(harness.step(), Cpu::CYCLES_PER_INSTRUCTION);
+}
+
+#[test]
+fn code_written_to_ram_runs_after_flush_cache() {
+    let mut harness = Harness::new(&[
+        0x0c000800, // jal 0x80002000
+        0x00000000, // nop
+        0x3c088000, // lui $t0, 0x8000
+        0x3c09340a, // lui $t1, 0x340a
+        0x35290002, // ori $t1, $t1, 2
+        0xad092000, // sw $t1, 0x2000($t0) - ori $t2, $zero, 2
+        0x34090044, // ori $t1, $zero, 0x44
+        0x340b00a0, // ori $t3, $zero, 0xa0
+        0x0160f809, // jalr $t3 - FlushCache()
+        0x00000000, // nop
+        0x0c000800, // jal 0x80002000
+        0x00000000, // nop
+    ]);
+    harness.load(
+        0x80002000,
+        &[
+            0x340a0001, // ori $t2, $zero, 1
+            0x03e00008, // jr $ra
+            0x00000000, // nop
+        ],
+    );
+    harness.load(
+        0x800000a0,
+        &[
+            0x03e00008, // jr $ra
+            0x00000000, // nop
+        ],
+    );
+
+    harness.run_until(0x80001008);
+    assert_eq!(harness.register(10), 1);
+
+    harness.run_until(0x80001030);
+    assert_eq!(harness.register(10), 2);
+}
+
+/// Writes a pattern to a COP0 register through MTC0 and returns what MFC0 reads back
+///
+/// The pattern leaves the breakpoints of DCIC, the cache isolation and user mode of SR disabled
+fn cop0_round_trip(rd: u32) -> u32 {
+    // mtc0 $t0, <rd> / mfc0 $t2, <rd> / nop
+    let mut harness = Harness::new(&[0x40880000 | rd << 11, 0x400a0000 | rd << 11, 0x00000000]);
+    harness.set_register(8, 0x12345678);
+    harness.set_register(10, 0xdeadbeef);
+    for _ in 0..3 {
+        harness.step();
+    }
+
+    harness.register(10)
+}
+
+#[test]
+fn mtc0_only_writes_the_writable_bits() {
+    let cases = [
+        (3, 0x12345678),
+        (5, 0x12345678),
+        (6, 0x00000000),
+        (7, 0x12345678),
+        (8, 0x00000000),
+        (9, 0x12345678),
+        (11, 0x12345678),
+        (12, 0x12345678),
+        (13, 0x00000200),
+        (14, 0x00000000),
+        (15, Cpu::PROCESSOR_ID),
+    ];
+
+    for (rd, expected) in cases {
+        assert_eq!(cop0_round_trip(rd), expected, "cop0 register {}", rd);
+    }
+}
+
+#[test]
+fn missing_cop0_registers_read_as_zero() {
+    for rd in [0, 1, 2, 4, 10, 16, 31] {
+        assert_eq!(cop0_round_trip(rd), 0, "cop0 register {}", rd);
+    }
+}
+
+#[test]
+fn prid_reads_the_processor_id() {
+    // mfc0 $t2, $15 / nop
+    let mut harness = Harness::new(&[0x400a7800, 0x00000000]);
+    harness.step();
+    harness.step();
+
+    assert_eq!(harness.register(10), Cpu::PROCESSOR_ID);
+}
+
+/// Runs a single coprocessor instruction with a SR value and returns CAUSE, or none if it didn't
+/// raise an exception
+fn coprocessor_exception(instruction: u32, sr: u32) -> Option<u32> {
+    let mut harness = Harness::new(&[instruction, 0x00000000]);
+    harness.cpu.cop0_registers[12] = sr;
+    harness.step();
+
+    let cause = harness.cpu.cop0_registers[13];
+    if harness.cpu.pc != 0x80000080 {
+        return None;
+    }
+
+    // Coprocessor unusable exception, EPC at the faulting instruction
+    assert_eq!((cause >> 2) & 0x1f, 0x0b);
+    assert_eq!(harness.cpu.cop0_registers[14], Harness::PROGRAM_ADDRESS);
+    Some(cause)
+}
+
+#[test]
+fn cop0_is_unusable_in_user_mode_without_cu0() {
+    // mfc0 $t2, $12
+    let mfc0 = 0x400a6000;
+
+    // Kernel mode always may use COP0
+    assert_eq!(coprocessor_exception(mfc0, 0x00000000), None);
+
+    // User mode needs CU0
+    let cause = coprocessor_exception(mfc0, 0x00000002).unwrap();
+    assert_eq!((cause >> 28) & 0x3, 0);
+    assert_eq!(coprocessor_exception(mfc0, 0x10000002), None);
+}
+
+#[test]
+fn gte_operations_need_cu2() {
+    // rtps / lwc2 $0, 0($zero)
+    for instruction in [0x4a180001, 0xc8000000] {
+        let cause = coprocessor_exception(instruction, 0x00000000).unwrap();
+        assert_eq!((cause >> 28) & 0x3, 2);
+    }
+}
+
+#[test]
+fn coprocessor_error_bits_name_the_coprocessor() {
+    // mfc1 $t2, $0 / mfc3 $t2, $0
+    let cause = coprocessor_exception(0x440a0000, 0x00000000).unwrap();
+    assert_eq!((cause >> 28) & 0x3, 1);
+
+    let cause = coprocessor_exception(0x4c0a0000, 0x00000000).unwrap();
+    assert_eq!((cause >> 28) & 0x3, 3);
+}
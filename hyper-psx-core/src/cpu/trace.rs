@@ -0,0 +1,70 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::cpu::{disasm, register::Register};
+
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// The tracer writing each executed instruction with the registers it changed
+///
+/// Each line holds the address, the instruction word, the disassembly and the changed registers,
+/// e.g. `bfc00000 3c080013 LUI $t0, 0x13 $t0=00130000`
+#[derive(Debug)]
+pub(crate) struct Tracer {
+    /// The buffered trace file
+    writer: BufWriter<File>,
+}
+
+impl Tracer {
+    /// Creates a tracer writing into a new file
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path to the trace file
+    ///
+    /// # Errors
+    ///
+    /// This function will throw an error if the file failed to be created
+    pub(crate) fn new<P: AsRef<Path>>(path: P) -> Result<Self, io::Error> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    /// Writes the line of an executed instruction
+    ///
+    /// # Arguments:
+    ///
+    /// * `pc`: The address of the instruction
+    /// * `word`: The instruction word
+    /// * `before`: The registers before the instruction
+    /// * `after`: The registers after the instruction
+    ///
+    /// # Errors
+    ///
+    /// This function will throw an error if the line failed to be written
+    pub(crate) fn trace(
+        &mut self,
+        pc: u32,
+        word: u32,
+        before: &[u32; 32],
+        after: &[u32; 32],
+    ) -> Result<(), io::Error> {
+        let mut line = format!("{:08x} {:08x} {}", pc, word, disasm::disassemble(word, pc));
+        for (index, (old, new)) in before.iter().zip(after).enumerate() {
+            if old != new {
+                let _ = write!(line, " {}={:08x}", Register::from(index as u8), new);
+            }
+        }
+
+        writeln!(self.writer, "{}", line)
+    }
+}
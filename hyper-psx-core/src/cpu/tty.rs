@@ -0,0 +1,128 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::{
+    fmt::{self, Debug, Formatter},
+    mem,
+};
+
+/// The sink receiving the completed TTY lines
+pub(crate) type TtySink = Box<dyn FnMut(&str)>;
+
+/// The TTY capturing the text printed through the BIOS functions
+pub(crate) struct Tty {
+    /// The characters of the current line
+    line: String,
+
+    /// The custom sink, the lines get logged if none is set
+    sink: Option<TtySink>,
+
+    /// The text printed since it was last taken, limited to the most recent characters
+    output: String,
+
+    /// If the BIOS installed a TTY device for std_out
+    device_installed: bool,
+}
+
+impl Tty {
+    /// The longest string accepted by puts before it gets cut off
+    pub(super) const MAX_STRING_LENGTH: u32 = 1024;
+
+    /// The amount of bytes kept in the output until it gets taken
+    const MAX_OUTPUT_LENGTH: usize = 0x10000;
+
+    /// Creates a TTY logging each line
+    pub(super) fn new() -> Self {
+        Self {
+            line: String::new(),
+            sink: None,
+            output: String::new(),
+            device_installed: false,
+        }
+    }
+
+    /// Replaces the sink receiving the completed lines
+    ///
+    /// # Arguments:
+    ///
+    /// * `sink`: The new sink
+    pub(crate) fn set_sink(&mut self, sink: TtySink) {
+        self.sink = Some(sink);
+    }
+
+    /// Records the BIOS installing a TTY device, after which std_out reaches the TTY
+    ///
+    /// # Arguments:
+    ///
+    /// * `name`: The name of the installed device
+    pub(super) fn install_device(&mut self, name: &str) {
+        log::debug!(target: "tty", "BIOS installed the {} TTY device", name);
+        self.device_installed = true;
+    }
+
+    /// Appends a character, flushing the line on a newline
+    ///
+    /// The character only reaches the taken output once the BIOS installed a TTY device, like
+    /// std_out on hardware
+    ///
+    /// # Arguments:
+    ///
+    /// * `character`: The printed character
+    pub(super) fn putchar(&mut self, character: u8) {
+        if self.device_installed && character != b'\r' {
+            self.push_output(character as char);
+        }
+
+        match character {
+            b'\n' => self.flush(),
+            b'\r' => {}
+            _ => self.line.push(character as char),
+        }
+    }
+
+    /// Takes the text printed since the last call
+    pub(crate) fn take_output(&mut self) -> String {
+        mem::take(&mut self.output)
+    }
+
+    /// Appends a character to the output, dropping the oldest ones once it is full
+    ///
+    /// # Arguments:
+    ///
+    /// * `character`: The printed character
+    fn push_output(&mut self, character: char) {
+        self.output.push(character);
+
+        let length = self.output.len();
+        if length > Self::MAX_OUTPUT_LENGTH {
+            let excess = (length - Self::MAX_OUTPUT_LENGTH..length)
+                .find(|&index| self.output.is_char_boundary(index))
+                .unwrap_or(length);
+            self.output.drain(..excess);
+        }
+    }
+
+    /// Hands the current line to the sink
+    fn flush(&mut self) {
+        match &mut self.sink {
+            Some(sink) => sink(&self.line),
+            None => log::info!(target: "tty", "{}", self.line),
+        }
+
+        self.line.clear();
+    }
+}
+
+impl Debug for Tty {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Tty")
+            .field("line", &self.line)
+            .field("sink", &self.sink.is_some())
+            .field("output", &self.output.len())
+            .field("device_installed", &self.device_installed)
+            .finish()
+    }
+}
@@ -0,0 +1,139 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::fmt::{self, Debug, Formatter};
+
+/// The kind of memory access which hit a watchpoint
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchKind {
+    /// A load of the CPU
+    Read,
+
+    /// A store of the CPU
+    Write,
+}
+
+/// The callback receiving the address, the value and the kind of each watched access
+pub(crate) type WatchCallback = Box<dyn FnMut(u32, u32, WatchKind)>;
+
+/// A watched range of memory
+#[derive(Clone, Copy, Debug)]
+struct Watchpoint {
+    /// The physical start address, with the RAM mirrors folded onto the installed RAM
+    address: u32,
+
+    /// The length in bytes
+    length: u32,
+
+    /// If loads from the range are reported
+    on_read: bool,
+
+    /// If stores to the range are reported
+    on_write: bool,
+}
+
+/// The watchpoints reporting the CPU accesses to memory ranges
+pub(crate) struct Watchpoints {
+    /// The watched ranges
+    entries: Vec<Watchpoint>,
+
+    /// The callback receiving the accesses, none reports nothing
+    callback: Option<WatchCallback>,
+}
+
+impl Watchpoints {
+    /// Creates the watchpoints without any watched range
+    pub(super) fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            callback: None,
+        }
+    }
+
+    /// Watches a range of memory, matching each mirror of its address
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The physical start address with the RAM mirrors folded onto the installed RAM
+    /// * `length`: The length in bytes
+    /// * `on_read`: If loads get reported
+    /// * `on_write`: If stores get reported
+    pub(crate) fn add(&mut self, address: u32, length: u32, on_read: bool, on_write: bool) {
+        self.entries.push(Watchpoint {
+            address,
+            length,
+            on_read,
+            on_write,
+        });
+    }
+
+    /// Removes every watched range
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Replaces the callback receiving the watched accesses
+    ///
+    /// # Arguments:
+    ///
+    /// * `callback`: The new callback
+    pub(crate) fn set_callback(&mut self, callback: WatchCallback) {
+        self.callback = Some(callback);
+    }
+
+    /// Reports an access to the callback if it overlaps a watched range
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The absolute address of the access
+    /// * `physical_address`: The address of the access with the RAM mirrors folded onto the
+    ///   installed RAM
+    /// * `size`: The size of the access in bytes
+    /// * `value`: The loaded or stored value
+    /// * `kind`: The kind of the access
+    pub(super) fn check(
+        &mut self,
+        address: u32,
+        physical_address: u32,
+        size: u32,
+        value: u32,
+        kind: WatchKind,
+    ) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let Some(callback) = &mut self.callback else {
+            return;
+        };
+
+        let start = physical_address as u64;
+        let end = start + size as u64;
+        let hit = self.entries.iter().any(|watchpoint| {
+            let enabled = match kind {
+                WatchKind::Read => watchpoint.on_read,
+                WatchKind::Write => watchpoint.on_write,
+            };
+
+            let watch_start = watchpoint.address as u64;
+            let watch_end = watch_start + watchpoint.length as u64;
+            enabled && start < watch_end && watch_start < end
+        });
+
+        if hit {
+            callback(address, value, kind);
+        }
+    }
+}
+
+impl Debug for Watchpoints {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Watchpoints")
+            .field("entries", &self.entries)
+            .field("callback", &self.callback.is_some())
+            .finish()
+    }
+}
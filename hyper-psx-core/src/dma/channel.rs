@@ -6,13 +6,17 @@
 
 use crate::{
     bus::{memory::Memory, ram::Ram},
+    cdrom::Cdrom,
     gpu::Gpu,
+    mdec::Mdec,
+    spu::Spu,
 };
 
+use serde::{Deserialize, Serialize};
 use std::fmt::{self, Debug, Formatter};
 
 /// Channel id
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum Id {
     /// The macro block in (RAM to MDEC)
     #[default]
@@ -38,7 +42,7 @@ pub(super) enum Id {
 }
 
 /// Channel transfer direction
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum TransferDirection {
     /// To main RAM
     #[default]
@@ -49,7 +53,7 @@ pub(super) enum TransferDirection {
 }
 
 /// Channel memory step
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum MemoryAddressStep {
     /// Forwards +4
     #[default]
@@ -60,7 +64,7 @@ pub(super) enum MemoryAddressStep {
 }
 
 /// Channel chopping
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum ChoppingMode {
     /// Normal mode
     #[default]
@@ -71,7 +75,7 @@ pub(super) enum ChoppingMode {
 }
 
 /// Channel transfer synchronisation
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum SyncMode {
     /// Immediately and all at once
     #[default]
@@ -85,7 +89,7 @@ pub(super) enum SyncMode {
 }
 
 /// Channel start/busy
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum Busy {
     /// Completed
     #[default]
@@ -96,7 +100,7 @@ pub(super) enum Busy {
 }
 
 /// Channel start/trigger
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum Trigger {
     /// Normal
     #[default]
@@ -107,7 +111,7 @@ pub(super) enum Trigger {
 }
 
 /// Channel (unknown) pause
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum UnknownPause {
     /// Nothing
     #[default]
@@ -118,7 +122,7 @@ pub(super) enum UnknownPause {
 }
 
 /// DMA Channel
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
 pub(crate) struct Channel {
     id: Id,
 
@@ -169,8 +173,15 @@ impl Channel {
     ///
     /// * `id`: The id of the channel
     pub(super) fn new(id: Id) -> Self {
+        // The OTC channel always clears the ordering table backwards
+        let memory_address_step = match id {
+            Id::Otc => MemoryAddressStep::Backward,
+            _ => MemoryAddressStep::Forward,
+        };
+
         Self {
             id,
+            memory_address_step,
             ..Default::default()
         }
     }
@@ -181,10 +192,26 @@ impl Channel {
     ///
     /// * `ram`: The RAM component
     /// * `gpu`: The GPU component
-    pub(crate) fn step(&mut self, ram: &mut Ram, gpu: &mut Gpu) {
-        if self.ready() {
-            self.start_transfer(ram, gpu);
+    /// * `cdrom`: The CDROM component
+    /// * `mdec`: The MDEC component
+    /// * `spu`: The SPU component
+    ///
+    /// Returns if a transfer was completed during this cycle
+    pub(crate) fn step(
+        &mut self,
+        ram: &mut Ram,
+        gpu: &mut Gpu,
+        cdrom: &mut Cdrom,
+        mdec: &mut Mdec,
+        spu: &mut Spu,
+    ) -> bool {
+        if !self.ready() {
+            return false;
         }
+
+        self.start_transfer(ram, gpu, cdrom, mdec, spu);
+
+        true
     }
 
     /// Checks if the current channel is ready to transfer data by checking if
@@ -201,28 +228,86 @@ impl Channel {
         }
     }
 
+    /// Summarizes the channel setup in a single line
+    pub(crate) fn describe(&self) -> String {
+        let direction = match self.transfer_direction {
+            TransferDirection::ToRam => "to RAM",
+            TransferDirection::FromRam => "from RAM",
+        };
+
+        let sync_mode = match self.sync_mode {
+            SyncMode::Immediately => "immediate",
+            SyncMode::SyncBlocks => "blocks",
+            SyncMode::LinkedList => "linked list",
+        };
+
+        let state = match self.busy {
+            Busy::Completed => "idle",
+            Busy::Busy => "busy",
+        };
+
+        format!(
+            "{:?}: {} {} at {:#08x}, {} blocks of {:#06x} words, {}",
+            self.id,
+            sync_mode,
+            direction,
+            self.base_address,
+            self.block_count,
+            self.block_size,
+            state
+        )
+    }
+
     /// Finishes off a transfer
     fn finish(&mut self) {
         self.busy = Busy::Completed;
         self.trigger = Trigger::Normal;
-
-        // TODO: Trigger interrupt
     }
 
     /// Starts the block or linked list transfer for the DMA
-    fn start_transfer(&mut self, ram: &mut Ram, gpu: &mut Gpu) {
+    fn start_transfer(
+        &mut self,
+        ram: &mut Ram,
+        gpu: &mut Gpu,
+        cdrom: &mut Cdrom,
+        mdec: &mut Mdec,
+        spu: &mut Spu,
+    ) {
         match self.sync_mode {
-            SyncMode::Immediately => self.transfer_block(ram, gpu),
-            SyncMode::SyncBlocks => self.transfer_block(ram, gpu),
+            SyncMode::Immediately => self.transfer_block(ram, gpu, cdrom, mdec, spu),
+            SyncMode::SyncBlocks => self.transfer_block(ram, gpu, cdrom, mdec, spu),
             SyncMode::LinkedList => self.transfer_linked_list(ram, gpu),
         }
     }
 
-    /// Starts a block transfer
-    fn transfer_block(&mut self, ram: &mut Ram, gpu: &mut Gpu) {
-        log::debug!("Transfer Block: {:?}", self);
+    /// Returns the amount of words a block transfer moves
+    fn transfer_size(&self) -> u32 {
+        match self.sync_mode {
+            SyncMode::Immediately => {
+                // A block size of 0 stands for the maximum of 0x10000 words
+                if self.block_size == 0 {
+                    0x10000
+                } else {
+                    self.block_size as u32
+                }
+            }
+            SyncMode::SyncBlocks => self.block_size as u32 * self.block_count as u32,
+            SyncMode::LinkedList => unreachable!(),
+        }
+    }
 
-        let mut block_count = self.block_size;
+    /// Starts a block transfer
+    fn transfer_block(
+        &mut self,
+        ram: &mut Ram,
+        gpu: &mut Gpu,
+        cdrom: &mut Cdrom,
+        mdec: &mut Mdec,
+        spu: &mut Spu,
+    ) {
+        log::debug!(target: "dma", "Transfer block {}", self.describe());
+
+        let mut remaining_words = self.transfer_size();
         let mut address = self.base_address;
 
         let memory_address_step = match self.memory_address_step {
@@ -230,97 +315,121 @@ impl Channel {
             MemoryAddressStep::Backward => -4_i8 as u32,
         };
 
-        while block_count != 0 {
+        while remaining_words != 0 {
+            let current_address = address & 0xfffffc;
+
             match self.transfer_direction {
                 TransferDirection::ToRam => {
                     let value = match self.id {
                         Id::Otc => {
-                            if block_count == 1 {
+                            // The word count comes from BCR bits 0-15 like for every
+                            // other channel running in immediate mode
+                            if remaining_words == 1 {
                                 // End Marker
                                 0xffffff
                             } else {
                                 // Previous address
-                                address.wrapping_add(memory_address_step)
+                                address.wrapping_add(memory_address_step) & 0xffffff
                             }
                         }
+                        Id::MacroBlockOut => mdec.dma_read_word(),
+                        Id::Gpu => gpu.gpuread(),
+                        Id::Cdrom => cdrom.dma_read_word(),
+                        Id::Spu => spu.dma_read_word(),
                         _ => {
-                            unimplemented!("immediate transfer from channel '{:?}' to ram", self.id)
+                            log::warn!(target: "dma", "Unsupported block transfer from channel '{:?}' to ram", self.id);
+                            self.finish();
+                            return;
                         }
                     };
 
-                    let byte_0 = (value & 0xff) as u8;
-                    let byte_1 = ((value >> 8) & 0xff) as u8;
-                    let byte_2 = ((value >> 16) & 0xff) as u8;
-                    let byte_3 = ((value >> 24) & 0xff) as u8;
-
-                    ram.write_u8(address, byte_0);
-                    ram.write_u8(address + 1, byte_1);
-                    ram.write_u8(address + 2, byte_2);
-                    ram.write_u8(address + 3, byte_3);
+                    ram.write_u32(current_address, value);
                 }
-                TransferDirection::FromRam => match self.id {
-                    Id::Gpu => {
-                        let byte_0 = ram.read_u8(address) as u32;
-                        let byte_1 = ram.read_u8(address + 1) as u32;
-                        let byte_2 = ram.read_u8(address + 2) as u32;
-                        let byte_3 = ram.read_u8(address + 3) as u32;
-                        let command = (byte_3 << 24) | (byte_2 << 16) | (byte_1 << 8) | byte_0;
-
-                        gpu.gp0(command);
+                TransferDirection::FromRam => {
+                    let value = ram.read_u32(current_address);
+
+                    match self.id {
+                        Id::MacroBlockIn => mdec.dma_write_word(value),
+                        Id::Gpu => gpu.gp0(value),
+                        Id::Spu => spu.dma_write_word(value),
+                        _ => {
+                            log::warn!(target: "dma", "Unsupported block transfer from channel '{:?}' from ram", self.id);
+                            self.finish();
+                            return;
+                        }
                     }
-                    _ => unimplemented!("immediate transfer from channel '{:?}' from ram", self.id),
-                },
+                }
             }
 
             address = address.wrapping_add(memory_address_step);
-            block_count -= 1;
+            remaining_words -= 1;
+        }
+
+        if self.sync_mode == SyncMode::SyncBlocks {
+            // The hardware leaves the address behind the last block and counts the blocks down
+            self.base_address = address & 0xffffff;
+            self.block_count = 0;
         }
 
         self.finish();
     }
 
     /// Starts a linked list transfer
+    ///
+    /// Every command word of the list is handed to GP0, exactly like a CPU
+    /// write to the GP0 port would do
     fn transfer_linked_list(&mut self, ram: &mut Ram, gpu: &mut Gpu) {
-        log::debug!("Transfer Linked List: {:?}", self);
+        log::debug!(target: "dma", "Transfer linked list {}", self.describe());
+
+        if self.id != Id::Gpu || self.transfer_direction != TransferDirection::FromRam {
+            log::warn!(
+                target: "dma",
+                "Unsupported linked list transfer for channel '{:?}' ({:?})",
+                self.id,
+                self.transfer_direction
+            );
+            self.finish();
+            return;
+        }
 
-        let mut address = self.base_address;
+        let mut address = self.base_address & 0xfffffc;
 
-        let memory_address_step = match self.memory_address_step {
-            MemoryAddressStep::Forward => 4,
-            MemoryAddressStep::Backward => -4_i8 as u32,
-        };
+        // A list can't hold more nodes than RAM has words, anything beyond
+        // that has to be a loop which would never reach the end marker
+        let max_nodes = ram.size() / 4;
+        let mut visited_nodes = 0;
 
         loop {
-            let byte_0 = ram.read_u8(address) as u32;
-            let byte_1 = ram.read_u8(address + 1) as u32;
-            let byte_2 = ram.read_u8(address + 2) as u32;
-            let byte_3 = ram.read_u8(address + 3) as u32;
-
-            let node = (byte_3 << 24) | (byte_2 << 16) | (byte_1 << 8) | byte_0;
+            visited_nodes += 1;
+            if visited_nodes > max_nodes {
+                log::warn!(
+                    target: "dma",
+                    "Linked list transfer stopped after {} nodes without an end marker",
+                    max_nodes
+                );
+                break;
+            }
 
-            let mut node_size = (node >> 24) & 0xff;
-            while node_size != 0 {
-                address = address.wrapping_add(memory_address_step);
+            let node = ram.read_u32(address);
 
-                let byte_0 = ram.read_u8(address) as u32;
-                let byte_1 = ram.read_u8(address + 1) as u32;
-                let byte_2 = ram.read_u8(address + 2) as u32;
-                let byte_3 = ram.read_u8(address + 3) as u32;
-                let command = (byte_3 << 24) | (byte_2 << 16) | (byte_1 << 8) | byte_0;
+            let node_size = (node >> 24) & 0xff;
+            for index in 1..=node_size {
+                let command_address = address.wrapping_add(index * 4) & 0xfffffc;
+                let command = ram.read_u32(command_address);
 
                 gpu.gp0(command);
-
-                node_size -= 1;
             }
 
+            // End marker
             if node & 0x800000 != 0 {
                 break;
             }
 
-            let next_node = node & 0x00ffffff;
-            address = next_node;
+            address = node & 0xfffffc;
         }
 
+        self.base_address = 0x00ffffff;
+
         self.finish();
     }
 }
@@ -365,6 +474,10 @@ impl Memory for Channel {
             0x06..=0x07 => {
                 self.block_count.write_u8(offset - 0x06, value);
             }
+            0x08..=0x0a if self.id == Id::Otc => {
+                // The OTC channel is hardwired to an immediate backwards transfer
+                // to RAM, only the start/trigger bits are writable
+            }
             0x08 => {
                 let transfer_direction = value & 0b00000001;
                 self.transfer_direction = match transfer_direction {
@@ -408,7 +521,7 @@ impl Memory for Channel {
                     _ => unreachable!(),
                 };
 
-                let trigger = value & (0b00010000) >> 4;
+                let trigger = (value & 0b00010000) >> 4;
                 self.trigger = match trigger {
                     0 => Trigger::Normal,
                     1 => Trigger::ManualStart,
@@ -468,3 +581,222 @@ impl Memory for Channel {
         value
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        region::Region,
+        renderer::{
+            mock_renderer::{Draw, MockRenderer},
+            Color, Position,
+        },
+    };
+
+    #[test]
+    fn gpu_block_transfer_moves_every_block() {
+        let mut ram = Ram::with_size(2 * 1024 * 1024);
+        let (renderer, draws) = MockRenderer::new();
+        let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+        let mut cdrom = Cdrom::new(None);
+        let mut mdec = Mdec::new();
+        let mut spu = Spu::new();
+
+        // Shaded triangle, split in 2 blocks of 3 words
+        let words = [
+            0x300000ff, 0x00000000, 0x0000ff00, 0x00000010, 0x00ff0000, 0x00100000,
+        ];
+        for (index, word) in words.into_iter().enumerate() {
+            ram.write_u32(0x1000 + index as u32 * 4, word);
+        }
+
+        let mut channel = Channel::new(Id::Gpu);
+        channel.write_u32(0x00, 0x1000);
+        channel.write_u32(0x04, 0x0002_0003);
+        channel.write_u32(0x08, 0x0100_0201);
+        channel.step(&mut ram, &mut gpu, &mut cdrom, &mut mdec, &mut spu);
+
+        assert_eq!(
+            *draws.borrow(),
+            [Draw::Triangle(
+                [
+                    Position::new(0, 0),
+                    Position::new(16, 0),
+                    Position::new(0, 16)
+                ],
+                [
+                    Color::new(0xff, 0, 0),
+                    Color::new(0, 0xff, 0),
+                    Color::new(0, 0, 0xff)
+                ],
+                None,
+            )]
+        );
+        assert_eq!(channel.base_address, 0x1018);
+        assert_eq!(channel.block_count, 0);
+        assert_eq!(channel.busy, Busy::Completed);
+    }
+
+    #[test]
+    fn gpu_linked_list_matches_gp0_writes() {
+        let mut ram = Ram::with_size(2 * 1024 * 1024);
+        let (renderer, dma_draws) = MockRenderer::new();
+        let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+        let mut cdrom = Cdrom::new(None);
+        let mut mdec = Mdec::new();
+        let mut spu = Spu::new();
+
+        // A 2x2 texture upload followed by a shaded triangle
+        let upload = [0xa0000000, 0x00000000, 0x00020002, 0x7fff001f, 0x03e07c00];
+        let triangle = [
+            0x300000ff, 0x00000000, 0x0000ff00, 0x00000010, 0x00ff0000, 0x00100000,
+        ];
+
+        // The nodes are spread over RAM and chained through their headers
+        ram.write_u32(0x1000, ((upload.len() as u32) << 24) | 0x2000);
+        for (index, word) in upload.into_iter().enumerate() {
+            ram.write_u32(0x1004 + index as u32 * 4, word);
+        }
+
+        ram.write_u32(0x2000, ((triangle.len() as u32) << 24) | 0xffffff);
+        for (index, word) in triangle.into_iter().enumerate() {
+            ram.write_u32(0x2004 + index as u32 * 4, word);
+        }
+
+        let mut channel = Channel::new(Id::Gpu);
+        channel.write_u32(0x00, 0x1000);
+        channel.write_u32(0x08, 0x0100_0401);
+        channel.step(&mut ram, &mut gpu, &mut cdrom, &mut mdec, &mut spu);
+        assert_eq!(channel.busy, Busy::Completed);
+
+        let (renderer, cpu_draws) = MockRenderer::new();
+        let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+        for word in upload.into_iter().chain(triangle) {
+            gpu.gp0(word);
+        }
+
+        // The 4 pixels of the texture upload and the triangle
+        assert_eq!(dma_draws.borrow().len(), 5);
+        assert_eq!(*dma_draws.borrow(), *cpu_draws.borrow());
+    }
+
+    #[test]
+    fn looping_linked_list_finishes() {
+        let mut ram = Ram::with_size(2 * 1024 * 1024);
+        let (renderer, _) = MockRenderer::new();
+        let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+        let mut cdrom = Cdrom::new(None);
+        let mut mdec = Mdec::new();
+        let mut spu = Spu::new();
+
+        // 2 empty nodes pointing at each other without an end marker
+        ram.write_u32(0x1000, 0x00002000);
+        ram.write_u32(0x2000, 0x00001000);
+
+        let mut channel = Channel::new(Id::Gpu);
+        channel.write_u32(0x00, 0x1000);
+        channel.write_u32(0x08, 0x0100_0401);
+        channel.step(&mut ram, &mut gpu, &mut cdrom, &mut mdec, &mut spu);
+
+        assert_eq!(channel.busy, Busy::Completed);
+    }
+
+    #[test]
+    fn unsupported_linked_list_finishes() {
+        let mut ram = Ram::with_size(2 * 1024 * 1024);
+        let (renderer, _) = MockRenderer::new();
+        let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+        let mut cdrom = Cdrom::new(None);
+        let mut mdec = Mdec::new();
+        let mut spu = Spu::new();
+
+        let mut channel = Channel::new(Id::Spu);
+        channel.write_u32(0x00, 0x1000);
+        channel.write_u32(0x08, 0x0100_0401);
+        channel.step(&mut ram, &mut gpu, &mut cdrom, &mut mdec, &mut spu);
+
+        assert_eq!(channel.busy, Busy::Completed);
+    }
+
+    #[test]
+    fn unsupported_block_transfer_finishes() {
+        let mut ram = Ram::with_size(2 * 1024 * 1024);
+        let (renderer, _) = MockRenderer::new();
+        let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+        let mut cdrom = Cdrom::new(None);
+        let mut mdec = Mdec::new();
+        let mut spu = Spu::new();
+
+        let mut channel = Channel::new(Id::Pio);
+        channel.write_u32(0x04, 0x0001);
+        channel.write_u32(0x08, 0x1100_0001);
+        channel.step(&mut ram, &mut gpu, &mut cdrom, &mut mdec, &mut spu);
+
+        assert_eq!(channel.busy, Busy::Completed);
+    }
+
+    #[test]
+    fn trigger_is_read_from_bit_4() {
+        let mut channel = Channel::new(Id::Otc);
+        channel.write_u8(0x0b, 0b00010000);
+
+        assert_eq!(channel.trigger, Trigger::ManualStart);
+        assert_eq!(channel.busy, Busy::Completed);
+    }
+
+    #[test]
+    fn otc_builds_the_ordering_table() {
+        let mut ram = Ram::with_size(2 * 1024 * 1024);
+        let (renderer, _) = MockRenderer::new();
+        let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+        let mut cdrom = Cdrom::new(None);
+        let mut mdec = Mdec::new();
+        let mut spu = Spu::new();
+
+        const ENTRIES: u32 = 8;
+        const END_ADDRESS: u32 = 0x2000 + (ENTRIES - 1) * 4;
+
+        ram.write_u32(0x1ffc, 0xdeadbeef);
+
+        // The direction and step bits are ignored for the OTC channel
+        let mut channel = Channel::new(Id::Otc);
+        channel.write_u32(0x00, END_ADDRESS);
+        channel.write_u32(0x04, ENTRIES);
+        channel.write_u32(0x08, 0x1100_0000);
+        channel.step(&mut ram, &mut gpu, &mut cdrom, &mut mdec, &mut spu);
+
+        for entry in 1..ENTRIES {
+            let address = 0x2000 + entry * 4;
+            assert_eq!(ram.read_u32(address), address - 4);
+        }
+        assert_eq!(ram.read_u32(0x2000), 0x00ffffff);
+        assert_eq!(ram.read_u32(0x1ffc), 0xdeadbeef);
+        assert_eq!(channel.busy, Busy::Completed);
+    }
+
+    #[test]
+    fn mdec_in_block_transfer_loads_the_quant_table() {
+        let mut ram = Ram::with_size(2 * 1024 * 1024);
+        let (renderer, _) = MockRenderer::new();
+        let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+        let mut cdrom = Cdrom::new(None);
+        let mut mdec = Mdec::new();
+        let mut spu = Spu::new();
+
+        // MDEC(2) - Set Quant Table, luminance only
+        ram.write_u32(0x1000, 0x40000000);
+        for index in 1..=16 {
+            ram.write_u32(0x1000 + index * 4, 0x01010101);
+        }
+
+        let mut channel = Channel::new(Id::MacroBlockIn);
+        channel.write_u32(0x00, 0x1000);
+        channel.write_u32(0x04, 0x0001_0011);
+        channel.write_u32(0x08, 0x0100_0201);
+        channel.step(&mut ram, &mut gpu, &mut cdrom, &mut mdec, &mut spu);
+
+        // Neither busy nor waiting for parameters
+        assert_eq!(mdec.read_u32(0x04), 0x8004ffff);
+        assert_eq!(channel.read_u32(0x08) & (1 << 24), 0);
+    }
+}
@@ -0,0 +1,105 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::bus::memory::Memory;
+
+use serde::{Deserialize, Serialize};
+
+/// DICR - DMA Interrupt register
+///
+/// <https://psx-spx.consoledev.net/dmachannels/#1f8010f4h-dicr-dma-interrupt-register-rw>
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub(super) struct InterruptRegister {
+    /// The unknown read/write bits (0-5)
+    unknown: u8,
+
+    /// Force IRQ (sets bit 31)
+    force_irq: bool,
+
+    /// IRQ enable for each channel (bits 16-22)
+    channel_irq_enable: u8,
+
+    /// IRQ enable for all channels (bit 23)
+    master_irq_enable: bool,
+
+    /// IRQ flags for each channel (bits 24-30)
+    channel_irq_flags: u8,
+}
+
+impl InterruptRegister {
+    /// Creates a new DMA interrupt register
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the IRQ master flag (bit 31)
+    ///
+    /// The flag is computed as `force OR (master_enable AND (enable AND flags))`
+    pub(super) fn master_flag(&self) -> bool {
+        let channel_irq = (self.channel_irq_enable & self.channel_irq_flags) != 0;
+        self.force_irq || (self.master_irq_enable && channel_irq)
+    }
+
+    /// Sets the IRQ flag of a channel if its IRQ is enabled
+    ///
+    /// # Arguments:
+    ///
+    /// * `channel_id`: The channel which completed its transfer
+    pub(super) fn complete_channel(&mut self, channel_id: u8) {
+        let channel_bit = 1 << channel_id;
+        if self.channel_irq_enable & channel_bit != 0 {
+            self.channel_irq_flags |= channel_bit;
+        }
+    }
+}
+
+impl Memory for InterruptRegister {
+    fn write_u8(&mut self, offset: u32, value: u8) {
+        match offset {
+            0x00 => {
+                self.unknown = value & 0b00111111;
+            }
+            0x01 => {
+                self.force_irq = ((value & 0b10000000) >> 7) != 0;
+            }
+            0x02 => {
+                self.channel_irq_enable = value & 0b01111111;
+                self.master_irq_enable = ((value & 0b10000000) >> 7) != 0;
+            }
+            0x03 => {
+                // Writing 1 acknowledges the flag, the master flag is read-only
+                self.channel_irq_flags &= !(value & 0b01111111);
+            }
+            _ => unreachable!(
+                "write to dma interrupt register at {:#04x} with value {:#04x}",
+                offset, value
+            ),
+        }
+    }
+
+    fn read_u8(&self, offset: u32) -> u8 {
+        let mut value = 0;
+        match offset {
+            0x00 => {
+                value |= self.unknown;
+            }
+            0x01 => {
+                value |= (self.force_irq as u8) << 7;
+            }
+            0x02 => {
+                value |= self.channel_irq_enable;
+                value |= (self.master_irq_enable as u8) << 7;
+            }
+            0x03 => {
+                value |= self.channel_irq_flags;
+                value |= (self.master_flag() as u8) << 7;
+            }
+            _ => unreachable!("read from dma interrupt register at {:#04x}", offset),
+        }
+
+        value
+    }
+}
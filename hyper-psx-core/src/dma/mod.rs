@@ -5,22 +5,34 @@
  */
 
 pub(crate) mod channel;
+mod interrupt_register;
 
 use crate::{
     bus::{memory::Memory, ram::Ram},
-    dma::channel::{Channel, Id},
+    cdrom::Cdrom,
+    dma::{
+        channel::{Channel, Id},
+        interrupt_register::InterruptRegister,
+    },
     gpu::Gpu,
+    mdec::Mdec,
+    spu::Spu,
 };
 
+use serde::{Deserialize, Serialize};
+
 /// Direct Memory Access Component
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub(crate) struct Dma {
     // TODO: Replace registers with individual fields
     /// DPCR - Control register
     control: u32,
 
     /// DICR - Interrupt register
-    interrupt: u32,
+    interrupt: InterruptRegister,
+
+    /// The last observed IRQ master flag to detect its rising edge
+    last_master_flag: bool,
 
     /// DMA0-DMA6 - Channels
     channels: [Channel; 7],
@@ -41,7 +53,8 @@ impl Dma {
 
         Self {
             control: 0x07654321,
-            interrupt: 0,
+            interrupt: InterruptRegister::new(),
+            last_master_flag: false,
             channels,
         }
     }
@@ -52,10 +65,40 @@ impl Dma {
     ///
     /// * `ram`: The RAM component
     /// * `gpu`: The GPU component
-    pub(crate) fn step(&mut self, ram: &mut Ram, gpu: &mut Gpu) {
-        for channel in &mut self.channels {
-            channel.step(ram, gpu);
+    /// * `cdrom`: The CDROM component
+    /// * `mdec`: The MDEC component
+    /// * `spu`: The SPU component
+    ///
+    /// Returns if the DMA interrupt (IRQ3) should be requested
+    pub(crate) fn step(
+        &mut self,
+        ram: &mut Ram,
+        gpu: &mut Gpu,
+        cdrom: &mut Cdrom,
+        mdec: &mut Mdec,
+        spu: &mut Spu,
+    ) -> bool {
+        for (channel_id, channel) in self.channels.iter_mut().enumerate() {
+            if channel.step(ram, gpu, cdrom, mdec, spu) {
+                self.interrupt.complete_channel(channel_id as u8);
+            }
         }
+
+        // The interrupt is only requested when the master flag goes from 0 to 1
+        let master_flag = self.interrupt.master_flag();
+        let request_interrupt = master_flag && !self.last_master_flag;
+        self.last_master_flag = master_flag;
+
+        request_interrupt
+    }
+
+    /// Returns a channel for inspection, none if no channel has the id
+    ///
+    /// # Arguments:
+    ///
+    /// * `id`: The id of the channel, 0-6
+    pub(crate) fn channel(&self, id: u8) -> Option<&Channel> {
+        self.channels.get(id as usize)
     }
 
     /// Gives the channel id based on the offset
@@ -114,3 +157,78 @@ impl Memory for Dma {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{region::Region, renderer::mock_renderer::MockRenderer};
+
+    #[test]
+    fn completed_transfer_requests_the_interrupt() {
+        let mut ram = Ram::with_size(2 * 1024 * 1024);
+        let (renderer, _) = MockRenderer::new();
+        let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+        let mut cdrom = Cdrom::new(None);
+        let mut mdec = Mdec::new();
+        let mut spu = Spu::new();
+        let mut dma = Dma::new();
+
+        // IRQ enable for the OTC channel and the master enable
+        dma.write_u32(0x74, 0x00c0_0000);
+
+        dma.write_u32(0x60, 0x0000_100c);
+        dma.write_u32(0x64, 0x0000_0004);
+        dma.write_u32(0x68, 0x1100_0002);
+
+        // IRQ3 is only requested on the rising edge of the master flag
+        assert!(dma.step(&mut ram, &mut gpu, &mut cdrom, &mut mdec, &mut spu));
+        assert!(!dma.step(&mut ram, &mut gpu, &mut cdrom, &mut mdec, &mut spu));
+
+        let interrupt = dma.read_u32(0x74);
+        assert_eq!(
+            interrupt & (1 << (24 + Id::Otc as u32)),
+            1 << (24 + Id::Otc as u32)
+        );
+        assert_eq!(interrupt & (1 << 31), 1 << 31);
+
+        // Acknowledging the flag clears the master flag again
+        dma.write_u32(0x74, interrupt);
+        assert_eq!(dma.read_u32(0x74) & 0xff00_0000, 0);
+    }
+
+    #[test]
+    fn completed_transfer_without_enable_sets_no_flag() {
+        let mut ram = Ram::with_size(2 * 1024 * 1024);
+        let (renderer, _) = MockRenderer::new();
+        let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+        let mut cdrom = Cdrom::new(None);
+        let mut mdec = Mdec::new();
+        let mut spu = Spu::new();
+        let mut dma = Dma::new();
+
+        dma.write_u32(0x74, 0x0080_0000);
+
+        dma.write_u32(0x60, 0x0000_100c);
+        dma.write_u32(0x64, 0x0000_0004);
+        dma.write_u32(0x68, 0x1100_0002);
+
+        assert!(!dma.step(&mut ram, &mut gpu, &mut cdrom, &mut mdec, &mut spu));
+        assert_eq!(dma.read_u32(0x74) & 0xff00_0000, 0);
+    }
+
+    #[test]
+    fn channel_describes_its_setup() {
+        let mut dma = Dma::new();
+
+        // 4 blocks of 16 words from RAM to the GPU, not started yet
+        dma.write_u32(0x20, 0x001f_f000);
+        dma.write_u32(0x24, 0x0004_0010);
+        dma.write_u32(0x28, 0x0000_0201);
+
+        assert_eq!(
+            dma.channel(2).unwrap().describe(),
+            "Gpu: blocks from RAM at 0x1ff000, 4 blocks of 0x0010 words, idle"
+        );
+        assert!(dma.channel(7).is_none());
+    }
+}
@@ -0,0 +1,195 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::bus::Bus;
+
+use std::{fs, io, path::Path};
+use thiserror::Error;
+
+/// The error type of the creation process of the PSX-EXE
+#[derive(Debug, Error)]
+pub enum CreationError {
+    /// If the PSX-EXE file was not found
+    #[error("failed to find exe: '{0}'")]
+    MissingFile(String),
+
+    /// If the PSX-EXE file failed to be read from
+    #[error("failed to read exe: '{1}'")]
+    ReadingFailure(#[source] io::Error, String),
+
+    /// If the PSX-EXE file has no valid header
+    #[error("invalid exe header: '{0}'")]
+    InvalidHeader(String),
+
+    /// If the PSX-EXE file is shorter than its header claims
+    #[error("truncated exe: '{0}'")]
+    Truncated(String),
+
+    /// If the text of the PSX-EXE doesn't fit into RAM at its load address
+    #[error("exe text outside of ram: '{0}'")]
+    InvalidLoadAddress(String),
+}
+
+/// A PSX-EXE executable
+///
+/// <https://psx-spx.consoledev.net/cdromfileformats/#cdrom-file-psx-executables-exe>
+#[derive(Clone, Debug)]
+pub(crate) struct Exe {
+    /// The initial PC
+    pc: u32,
+
+    /// The initial GP/R28
+    gp: u32,
+
+    /// The destination address of the text in RAM
+    load_address: u32,
+
+    /// The initial SP/R29 and FP/R30 (base + offset), zero if unused
+    sp: u32,
+
+    /// The text copied to RAM
+    text: Vec<u8>,
+}
+
+impl Exe {
+    /// The magic identifying a PSX-EXE
+    const MAGIC: &[u8; 8] = b"PS-X EXE";
+
+    /// The size of the header in front of the text
+    const HEADER_SIZE: usize = 0x800;
+
+    /// Loads a PSX-EXE from a file
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path of the PSX-EXE
+    pub(crate) fn new<P: AsRef<Path>>(path: P) -> Result<Self, CreationError> {
+        let path_display = path.as_ref().display().to_string();
+        if !path.as_ref().exists() {
+            return Err(CreationError::MissingFile(path_display));
+        }
+
+        let buffer = fs::read(path)
+            .map_err(|error| CreationError::ReadingFailure(error, path_display.clone()))?;
+
+        let exe = Self::parse(&buffer, &path_display)?;
+
+        log::info!(
+            "Loaded EXE from '{}' ({} bytes at {:#010x})",
+            path_display,
+            exe.text.len(),
+            exe.load_address
+        );
+
+        Ok(exe)
+    }
+
+    /// Parses the header and text of a PSX-EXE
+    ///
+    /// # Arguments:
+    ///
+    /// * `buffer`: The contents of the PSX-EXE
+    /// * `path_display`: The path of the PSX-EXE used for errors
+    fn parse(buffer: &[u8], path_display: &str) -> Result<Self, CreationError> {
+        if buffer.len() < Self::HEADER_SIZE || &buffer[0x00..0x08] != Self::MAGIC {
+            return Err(CreationError::InvalidHeader(path_display.to_owned()));
+        }
+
+        let word = |offset: usize| {
+            u32::from_le_bytes([
+                buffer[offset],
+                buffer[offset + 1],
+                buffer[offset + 2],
+                buffer[offset + 3],
+            ])
+        };
+
+        let pc = word(0x10);
+        let gp = word(0x14);
+        let load_address = word(0x18);
+        let text_size = word(0x1c) as usize;
+        let sp_base = word(0x30);
+        let sp_offset = word(0x34);
+
+        let text = buffer
+            .get(Self::HEADER_SIZE..Self::HEADER_SIZE + text_size)
+            .ok_or_else(|| CreationError::Truncated(path_display.to_owned()))?
+            .to_vec();
+
+        if !Bus::ram_contains(load_address, text_size) {
+            return Err(CreationError::InvalidLoadAddress(path_display.to_owned()));
+        }
+
+        let sp = if sp_base != 0 {
+            sp_base.wrapping_add(sp_offset)
+        } else {
+            0
+        };
+
+        Ok(Self {
+            pc,
+            gp,
+            load_address,
+            sp,
+            text,
+        })
+    }
+
+    /// Returns the initial PC
+    pub(crate) fn pc(&self) -> u32 {
+        self.pc
+    }
+
+    /// Returns the initial GP
+    pub(crate) fn gp(&self) -> u32 {
+        self.gp
+    }
+
+    /// Returns the initial SP and FP, zero if the BIOS value should be kept
+    pub(crate) fn sp(&self) -> u32 {
+        self.sp
+    }
+
+    /// Returns the destination address of the text
+    pub(crate) fn load_address(&self) -> u32 {
+        self.load_address
+    }
+
+    /// Returns the text
+    pub(crate) fn text(&self) -> &[u8] {
+        &self.text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(load_address: u32, text_size: usize) -> Vec<u8> {
+        let mut buffer = vec![0; Exe::HEADER_SIZE + text_size];
+        buffer[0x00..0x08].copy_from_slice(Exe::MAGIC);
+        buffer[0x10..0x14].copy_from_slice(&0x80010000_u32.to_le_bytes());
+        buffer[0x18..0x1c].copy_from_slice(&load_address.to_le_bytes());
+        buffer[0x1c..0x20].copy_from_slice(&(text_size as u32).to_le_bytes());
+        buffer
+    }
+
+    #[test]
+    fn parse_accepts_text_inside_ram() {
+        let exe = Exe::parse(&build(0x80010000, 0x800), "test").unwrap();
+        assert_eq!(exe.load_address(), 0x80010000);
+        assert_eq!(exe.text().len(), 0x800);
+    }
+
+    #[test]
+    fn parse_rejects_text_outside_ram() {
+        let result = Exe::parse(&build(0x807ff800, 0x1000), "test");
+        assert!(matches!(result, Err(CreationError::InvalidLoadAddress(_))));
+
+        let result = Exe::parse(&build(0x1f000000, 0x800), "test");
+        assert!(matches!(result, Err(CreationError::InvalidLoadAddress(_))));
+    }
+}
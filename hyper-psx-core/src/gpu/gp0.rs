@@ -6,10 +6,10 @@
 
 use crate::{
     gpu::{
-        DisplayAreaDrawing, Dither, DrawPixels, Gpu, MaskDrawing, ReceiveMode, SemiTransparency,
-        TexturePageColors,
+        DisplayAreaDrawing, Dither, DrawPixels, Gpu, InterruptRequest, MaskDrawing, ReceiveMode,
+        VramTransfer,
     },
-    renderer::{self, Color},
+    renderer,
 };
 
 impl Gpu {
@@ -29,11 +29,68 @@ impl Gpu {
         // TODO: Implement Cache
     }
 
-    /// GP0(28h) - Monochrome four-point polygon, opaque
+    /// GP0(02h) - Fill Rectangle in VRAM
+    ///
+    /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gp002h-fill-rectangle-in-vram>
+    pub(super) fn op_fill_rectangle(&mut self) {
+        log::debug!(target: "gpu", "GP0(02h) - Fill Rectangle in VRAM");
+
+        let color = renderer::color_from_u32(self.arguments[0]);
+        let r = (color.x >> 3) as u16;
+        let g = (color.y >> 3) as u16;
+        let b = (color.z >> 3) as u16;
+        let pixel = r | (g << 5) | (b << 10);
+
+        // The horizontal position and size are in steps of 16 pixels, and the mask bit settings
+        // are ignored
+        let x = (self.arguments[1] & 0x3f0) as u16;
+        let y = ((self.arguments[1] >> 16) & 0x1ff) as u16;
+        let width = (((self.arguments[2] & 0x3ff) + 0xf) & !0xf) as u16;
+        let height = ((self.arguments[2] >> 16) & 0x1ff) as u16;
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let data = vec![pixel; width as usize * height as usize];
+        self.renderer.upload_vram(x, y, width, height, &data);
+    }
+
+    /// GP0(1Fh) - Interrupt Request (IRQ1)
+    ///
+    /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gp01fh-interrupt-request-irq1>
+    pub(super) fn op_interrupt_request(&mut self) {
+        log::debug!(target: "gpu", "GP0(1Fh) - Interrupt Request (IRQ1)");
+
+        // The request stays set until GP1(02h), so only the first one reaches I_STAT
+        if self.interrupt_request == InterruptRequest::Off {
+            self.interrupt_request = InterruptRequest::Irq;
+            self.interrupt_raised = true;
+        }
+    }
+
+    /// GP0(20h/22h) - Monochrome three-point polygon, opaque/semi-transparent
+    ///
+    /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gpu-render-polygon-commands>
+    pub(super) fn op_draw_monochrome_three_point_polygon(&mut self) {
+        log::debug!(target: "gpu", "GP0(20h/22h) - Monochrome three-point polygon");
+
+        let positions = [
+            renderer::position_from_u32(self.arguments[1]),
+            renderer::position_from_u32(self.arguments[2]),
+            renderer::position_from_u32(self.arguments[3]),
+        ];
+
+        let colors = [renderer::color_from_u32(self.arguments[0] & 0x00ffffff); 3];
+
+        self.renderer
+            .draw_triangle(positions, colors, self.blend_mode());
+    }
+
+    /// GP0(28h/2Ah) - Monochrome four-point polygon, opaque/semi-transparent
     ///
     /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gpu-render-polygon-commands>
-    pub(super) fn op_draw_monochrome_four_point_polygon_opaque(&mut self) {
-        log::debug!(target: "gpu", "GP0(28h) - Monochrome four-point polygon, opaque");
+    pub(super) fn op_draw_monochrome_four_point_polygon(&mut self) {
+        log::debug!(target: "gpu", "GP0(28h/2Ah) - Monochrome four-point polygon");
 
         let positions = [
             renderer::position_from_u32(self.arguments[1]),
@@ -44,14 +101,16 @@ impl Gpu {
 
         let colors = [renderer::color_from_u32(self.arguments[0] & 0x00ffffff); 4];
 
-        self.renderer.draw_quad(positions, colors);
+        self.renderer
+            .draw_quad(positions, colors, self.blend_mode());
     }
 
-    /// GP0(2ch) - Textured four-point polygon, opaque, texture-blending
+    /// GP0(2Ch..2Fh) - Textured four-point polygon, opaque/semi-transparent,
+    /// texture-blending/raw-texture
     ///
     /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gpu-render-polygon-commands>
-    pub(super) fn op_draw_textured_four_point_polygon_opaque_texture_blending(&mut self) {
-        log::debug!(target: "gpu", "GP0(2ch) - Textured four-point polygon, opaque, texture-blending");
+    pub(super) fn op_draw_textured_four_point_polygon(&mut self) {
+        log::debug!(target: "gpu", "GP0(2Ch..2Fh) - Textured four-point polygon");
 
         let positions = [
             renderer::position_from_u32(self.arguments[1]),
@@ -60,22 +119,33 @@ impl Gpu {
             renderer::position_from_u32(self.arguments[7]),
         ];
 
-        let colors = [Color {
-            x: 255,
-            y: 0,
-            z: 255,
-        }; 4];
+        let tex_coords = [
+            renderer::tex_coord_from_u32(self.arguments[2]),
+            renderer::tex_coord_from_u32(self.arguments[4]),
+            renderer::tex_coord_from_u32(self.arguments[6]),
+            renderer::tex_coord_from_u32(self.arguments[8]),
+        ];
+
+        // The texpage attribute replaces the texpage of GP0(E1h)
+        self.set_texture_page(self.arguments[4] >> 16);
 
-        // TODO: Implement textures
+        let colors = [renderer::color_from_u32(self.arguments[0] & 0x00ffffff); 4];
 
-        self.renderer.draw_quad(positions, colors);
+        let texture = self.texture(self.arguments[2] >> 16);
+        self.renderer.draw_textured_quad(
+            positions,
+            tex_coords,
+            self.texture_colors(colors),
+            texture,
+            self.blend_mode(),
+        );
     }
 
-    /// GP0(30h) - Shaded three-point polygon, opaque
+    /// GP0(30h/32h) - Shaded three-point polygon, opaque/semi-transparent
     ///
     /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gpu-render-polygon-commands>
-    pub(super) fn op_draw_shaded_three_point_polygon_opaque(&mut self) {
-        log::debug!(target: "gpu", "GP0(30h) - Shaded three-point polygon, opaque");
+    pub(super) fn op_draw_shaded_three_point_polygon(&mut self) {
+        log::debug!(target: "gpu", "GP0(30h/32h) - Shaded three-point polygon");
 
         let positions = [
             renderer::position_from_u32(self.arguments[1]),
@@ -89,14 +159,52 @@ impl Gpu {
             renderer::color_from_u32(self.arguments[4] & 0x00ffffff),
         ];
 
-        self.renderer.draw_triangle(positions, colors);
+        self.renderer
+            .draw_triangle(positions, colors, self.blend_mode());
+    }
+
+    /// GP0(34h..37h) - Shaded textured three-point polygon, opaque/semi-transparent,
+    /// texture-blending/raw-texture
+    ///
+    /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gpu-render-polygon-commands>
+    pub(super) fn op_draw_shaded_textured_three_point_polygon(&mut self) {
+        log::debug!(target: "gpu", "GP0(34h..37h) - Shaded textured three-point polygon");
+
+        let positions = [
+            renderer::position_from_u32(self.arguments[1]),
+            renderer::position_from_u32(self.arguments[4]),
+            renderer::position_from_u32(self.arguments[7]),
+        ];
+
+        let tex_coords = [
+            renderer::tex_coord_from_u32(self.arguments[2]),
+            renderer::tex_coord_from_u32(self.arguments[5]),
+            renderer::tex_coord_from_u32(self.arguments[8]),
+        ];
+
+        let colors = [
+            renderer::color_from_u32(self.arguments[0] & 0x00ffffff),
+            renderer::color_from_u32(self.arguments[3] & 0x00ffffff),
+            renderer::color_from_u32(self.arguments[6] & 0x00ffffff),
+        ];
+
+        self.set_texture_page(self.arguments[5] >> 16);
+
+        let texture = self.texture(self.arguments[2] >> 16);
+        self.renderer.draw_textured_triangle(
+            positions,
+            tex_coords,
+            self.texture_colors(colors),
+            texture,
+            self.blend_mode(),
+        );
     }
 
-    /// GP0(38h) - Shaded four-point polygon, opaque
+    /// GP0(38h/3Ah) - Shaded four-point polygon, opaque/semi-transparent
     ///
     /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gpu-render-polygon-commands>
-    pub(super) fn op_draw_shaded_four_point_polygon_opaque(&mut self) {
-        log::debug!(target: "gpu", "GP0(38h) - Shaded four-point polygon, opaque");
+    pub(super) fn op_draw_shaded_four_point_polygon(&mut self) {
+        log::debug!(target: "gpu", "GP0(38h/3Ah) - Shaded four-point polygon");
 
         let positions = [
             renderer::position_from_u32(self.arguments[1]),
@@ -112,7 +220,48 @@ impl Gpu {
             renderer::color_from_u32(self.arguments[6] & 0x00ffffff),
         ];
 
-        self.renderer.draw_quad(positions, colors);
+        self.renderer
+            .draw_quad(positions, colors, self.blend_mode());
+    }
+
+    /// GP0(3Ch..3Fh) - Shaded textured four-point polygon, opaque/semi-transparent,
+    /// texture-blending/raw-texture
+    ///
+    /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gpu-render-polygon-commands>
+    pub(super) fn op_draw_shaded_textured_four_point_polygon(&mut self) {
+        log::debug!(target: "gpu", "GP0(3Ch..3Fh) - Shaded textured four-point polygon");
+
+        let positions = [
+            renderer::position_from_u32(self.arguments[1]),
+            renderer::position_from_u32(self.arguments[4]),
+            renderer::position_from_u32(self.arguments[7]),
+            renderer::position_from_u32(self.arguments[10]),
+        ];
+
+        let tex_coords = [
+            renderer::tex_coord_from_u32(self.arguments[2]),
+            renderer::tex_coord_from_u32(self.arguments[5]),
+            renderer::tex_coord_from_u32(self.arguments[8]),
+            renderer::tex_coord_from_u32(self.arguments[11]),
+        ];
+
+        let colors = [
+            renderer::color_from_u32(self.arguments[0] & 0x00ffffff),
+            renderer::color_from_u32(self.arguments[3] & 0x00ffffff),
+            renderer::color_from_u32(self.arguments[6] & 0x00ffffff),
+            renderer::color_from_u32(self.arguments[9] & 0x00ffffff),
+        ];
+
+        self.set_texture_page(self.arguments[5] >> 16);
+
+        let texture = self.texture(self.arguments[2] >> 16);
+        self.renderer.draw_textured_quad(
+            positions,
+            tex_coords,
+            self.texture_colors(colors),
+            texture,
+            self.blend_mode(),
+        );
     }
 
     /// GP0(A0h) - Copy Rectangle (CPU to VRAM)
@@ -121,16 +270,22 @@ impl Gpu {
     pub(super) fn op_copy_rectangle(&mut self) {
         log::debug!(target: "gpu", "GP0(A0h) - Copy Rectangle (CPU to VRAM)");
 
-        let _destination_x = (self.arguments[1] & 0xffff) as u16;
-        let _destination_y = ((self.arguments[1] >> 16) & 0xffff) as u16;
-
-        let width = (self.arguments[2] & 0xffff) as u16;
-        let height = ((self.arguments[2] >> 16) & 0xffff) as u16;
-
-        // Align
-        let image_size = ((width * height) + 1) & !1;
-        let words = image_size / 2;
-
+        // The destination wraps around VRAM, and a size of 0 selects the maximum
+        let x = (self.arguments[1] & 0x3ff) as u16;
+        let y = ((self.arguments[1] >> 16) & 0x1ff) as u16;
+        let width = ((self.arguments[2] & 0xffff).wrapping_sub(1) & 0x3ff) as u16 + 1;
+        let height = (((self.arguments[2] >> 16) & 0xffff).wrapping_sub(1) & 0x1ff) as u16 + 1;
+
+        // Rectangles with an odd amount of pixels get padded to a whole word
+        let words = (width as u32 * height as u32).div_ceil(2);
+
+        self.vram_transfer = VramTransfer {
+            x,
+            y,
+            width,
+            height,
+            index: 0,
+        };
         self.argument_count = words;
         self.receive_mode = ReceiveMode::Data;
     }
@@ -143,25 +298,7 @@ impl Gpu {
 
         let command = self.arguments[0];
 
-        self.texture_page_x_base = (command & 0xf) as u8;
-        self.texture_page_y_base_1 = ((command >> 4) & 0x1) as u8;
-
-        let semi_transparency = ((command >> 5) & 0x3) as u8;
-        self.semi_transparency = match semi_transparency {
-            0 => SemiTransparency::First,
-            1 => SemiTransparency::Second,
-            2 => SemiTransparency::Third,
-            3 => SemiTransparency::Fourth,
-            _ => unreachable!(),
-        };
-
-        let texture_page_colors = ((command >> 7) & 0x3) as u8;
-        self.texture_page_colors = match texture_page_colors {
-            0 => TexturePageColors::Bit4,
-            1 => TexturePageColors::Bit8,
-            2 => TexturePageColors::Bit15,
-            _ => unreachable!(),
-        };
+        self.set_texture_page(command);
 
         let dither = ((command >> 9) & 0x1) as u8;
         self.dither = match dither {
@@ -176,8 +313,8 @@ impl Gpu {
             1 => DisplayAreaDrawing::Allowed,
             _ => unreachable!(),
         };
+        self.update_skipped_lines();
 
-        self.texture_page_y_base_2 = ((command >> 11) & 0x1) as u8;
         self.texture_rectangle_x_flip = ((command >> 12) & 0x1) as u8 != 0;
         self.texture_rectangle_y_flip = ((command >> 13) & 0x1) as u8 != 0;
     }
@@ -253,5 +390,7 @@ impl Gpu {
             1 => DrawPixels::Unmasked,
             _ => unreachable!(),
         };
+
+        self.update_mask_bit_setting();
     }
 }
@@ -6,7 +6,7 @@
 
 use crate::gpu::{
     ColorDepth, DisplayAreaDrawing, DisplayEnabled, Dither, DmaDirection, DrawPixels, Gpu,
-    HorizontalResolution, InterruptRequest, MaskDrawing, Reverse, SemiTransparency,
+    HorizontalResolution, InterruptRequest, MaskDrawing, ReceiveMode, Reverse, SemiTransparency,
     TexturePageColors, VerticalInterlace, VerticalResolution, VideoMode,
 };
 
@@ -18,11 +18,11 @@ impl Gpu {
     /// * `command`: The command itself
     ///
     /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gp100h-reset-gpu>
-    pub(super) fn op_reset_gpu(&mut self, _command: u32) {
+    pub(super) fn op_reset_gpu(&mut self, command: u32) {
         log::debug!(target: "gpu", "GP1(00h) - Reset GPU");
 
         // GP1(01h)
-        // TODO: Reset FIFO
+        self.op_reset_command_buffer(command);
 
         // GP1(02h)
         self.interrupt_request = InterruptRequest::Off;
@@ -38,19 +38,24 @@ impl Gpu {
         self.display_area_y_start_in_vram = 0;
 
         // GP1(06h)
-        self.display_range_horizontal_start = 0x200;
-        self.display_range_horizontal_end = 0x200 + 256 * 10;
+        (
+            self.display_range_horizontal_start,
+            self.display_range_horizontal_end,
+        ) = Self::DISPLAY_RANGE_HORIZONTAL_RESET;
 
         // GP1(07h)
-        self.display_range_vertical_start = 0x010;
-        self.display_range_vertical_end = 0x010 + 240;
+        (
+            self.display_range_vertical_start,
+            self.display_range_vertical_end,
+        ) = Self::DISPLAY_RANGE_VERTICAL_RESET;
 
         // GP1(08h)
         self.vertical_resolution = VerticalResolution::S240;
-        self.video_mode = VideoMode::Hz60;
+        self.video_mode = VideoMode::from(self.region);
         self.display_area_color_depth = ColorDepth::Bit15;
         self.vertical_interlace = VerticalInterlace::Off;
         self.horizontal_resolution = HorizontalResolution::S256;
+        self.horizontal_resolution_1 = 0;
         self.reverse = Reverse::Normal;
 
         // GP0(E1h)
@@ -63,6 +68,10 @@ impl Gpu {
         self.texture_page_y_base_2 = 0;
         self.texture_rectangle_x_flip = false;
         self.texture_rectangle_y_flip = false;
+        self.texture_disable = false;
+
+        // GP1(09h)
+        self.texture_disable_allowed = false;
 
         // GP0(E2h)
         self.texture_window_x_mask = 0;
@@ -85,6 +94,8 @@ impl Gpu {
         // GP0(E6h)
         self.mask_drawing = MaskDrawing::No;
         self.draw_pixels = DrawPixels::Always;
+        self.update_mask_bit_setting();
+        self.update_skipped_lines();
     }
 
     /// GP1(01h) - Reset Command Buffer
@@ -97,7 +108,11 @@ impl Gpu {
     pub(super) fn op_reset_command_buffer(&mut self, _command: u32) {
         log::debug!(target: "gpu", "GP1(01h) - Reset Command Buffer");
 
-        // TODO: Reset FIFO
+        self.arguments.clear();
+        self.argument_count = 0;
+        self.fifo_length = 0;
+        self.receive_mode = ReceiveMode::Command;
+        self.update_ready_flags();
     }
 
     /// GP1(02h) - Acknowledge GPU Interrupt (IRQ1)
@@ -110,6 +125,7 @@ impl Gpu {
     pub(super) fn op_acknowledge_gpu_interrupt(&mut self, _command: u32) {
         log::debug!(target: "gpu", "GP1(02h) - Acknowledge GPU Interrupt (IRQ1)");
 
+        // Only the GPU flag gets cleared, I_STAT is acknowledged separately by the CPU
         self.interrupt_request = InterruptRequest::Off;
     }
 
@@ -123,6 +139,7 @@ impl Gpu {
     pub(super) fn op_display_enable(&mut self, command: u32) {
         log::debug!(target: "gpu", "GP1(03h) - Display Enable");
 
+        // A set bit turns the display off
         let display_enabled = (command & 0x1) as u8;
         self.display_enabled = match display_enabled {
             0 => DisplayEnabled::Enabled,
@@ -141,7 +158,7 @@ impl Gpu {
     pub(super) fn op_dma_direction(&mut self, command: u32) {
         log::debug!(target: "gpu", "GP1(04h) - DMA Direction / Data Request");
 
-        let dma_direction = (command & 0x1) as u8;
+        let dma_direction = (command & 0x3) as u8;
         self.dma_direction = match dma_direction {
             0 => DmaDirection::Off,
             1 => DmaDirection::Fifo,
@@ -163,6 +180,7 @@ impl Gpu {
 
         self.display_area_x_start_in_vram = (command & 0x3ff) as u16;
         self.display_area_y_start_in_vram = ((command >> 10) & 0x3ff) as u16;
+        self.update_skipped_lines();
     }
 
     /// GP1(06h) - Horizontal Display range (on Screen)
@@ -175,8 +193,9 @@ impl Gpu {
     pub(super) fn op_horizontal_display_range_on_screen(&mut self, command: u32) {
         log::debug!(target: "gpu", "GP1(06h) - Horizontal Display range (on Screen)");
 
-        self.display_range_horizontal_start = (command & 0xfff) as u16;
-        self.display_range_horizontal_end = ((command >> 12) & 0xfff) as u16;
+        self.display_range_horizontal_start = command as u16 & Self::DISPLAY_RANGE_HORIZONTAL_MASK;
+        self.display_range_horizontal_end =
+            (command >> 12) as u16 & Self::DISPLAY_RANGE_HORIZONTAL_MASK;
     }
 
     /// GP1(07h) - Vertical Display range (on Screen)
@@ -189,8 +208,9 @@ impl Gpu {
     pub(super) fn op_vertical_display_range_on_screen(&mut self, command: u32) {
         log::debug!(target: "gpu", "GP1(07h) - Vertical Display range (on Screen)");
 
-        self.display_range_vertical_start = (command & 0x3ff) as u16;
-        self.display_range_vertical_end = ((command >> 10) & 0x3ff) as u16;
+        self.display_range_vertical_start = command as u16 & Self::DISPLAY_RANGE_VERTICAL_MASK;
+        self.display_range_vertical_end =
+            (command >> 10) as u16 & Self::DISPLAY_RANGE_VERTICAL_MASK;
     }
 
     /// GP1(08h) - Display mode
@@ -239,23 +259,68 @@ impl Gpu {
 
         let horizontal_resolution_1 = (command & 0x3) as u8;
         let horizontal_resolution_2 = ((command >> 6) & 0x1) as u8;
-        self.horizontal_resolution = match horizontal_resolution_2 {
-            0 => match horizontal_resolution_1 {
-                0 => HorizontalResolution::S256,
-                1 => HorizontalResolution::S320,
-                2 => HorizontalResolution::S512,
-                3 => HorizontalResolution::S640,
-                _ => unreachable!(),
-            },
-            1 => HorizontalResolution::S368,
-            _ => unreachable!(),
-        };
+        self.horizontal_resolution =
+            HorizontalResolution::from_bits(horizontal_resolution_1, horizontal_resolution_2);
+        self.horizontal_resolution_1 = horizontal_resolution_1;
 
         let reverse = ((command >> 7) & 0x1) as u8;
-        self.reverse = match reverse {
+        let reverse = match reverse {
             0 => Reverse::Normal,
             1 => Reverse::Distorted,
             _ => unreachable!(),
         };
+        if reverse == Reverse::Distorted && self.reverse != Reverse::Distorted {
+            log::warn!(target: "gpu", "Reverse flag enabled, the distorted display is not emulated");
+        }
+        self.reverse = reverse;
+
+        self.update_skipped_lines();
+    }
+
+    /// GP1(09h) - New Texture Disable
+    ///
+    /// Arguments:
+    ///
+    /// * `command`: The command itself
+    ///
+    /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gp109h-new-texture-disable>
+    pub(super) fn op_new_texture_disable(&mut self, command: u32) {
+        log::debug!(target: "gpu", "GP1(09h) - New Texture Disable");
+
+        self.texture_disable_allowed = (command & 0x1) != 0;
+        if !self.texture_disable_allowed {
+            self.texture_disable = false;
+        }
+    }
+
+    /// GP1(10h) - Get GPU Info
+    ///
+    /// Arguments:
+    ///
+    /// * `command`: The command itself
+    ///
+    /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gp110h-get-gpu-info>
+    pub(super) fn op_get_gpu_info(&mut self, command: u32) {
+        log::debug!(target: "gpu", "GP1(10h) - Get GPU Info");
+
+        let info = command & 0xf;
+        self.gpu_read = match info {
+            0x02 => {
+                let mut value = 0;
+                value |= self.texture_window_x_mask as u32;
+                value |= (self.texture_window_y_mask as u32) << 5;
+                value |= (self.texture_window_x_offset as u32) << 10;
+                value |= (self.texture_window_y_offset as u32) << 15;
+                value
+            }
+            0x03 => (self.drawing_area_left as u32) | ((self.drawing_area_top as u32) << 10),
+            0x04 => (self.drawing_area_right as u32) | ((self.drawing_area_bottom as u32) << 10),
+            0x05 => (self.drawing_x_offset as u32) | ((self.drawing_y_offset as u32) << 11),
+            // GPU Type
+            0x07 => 0x00000002,
+            0x08 => 0x00000000,
+            // Returns nothing, GPUREAD stays unchanged
+            _ => self.gpu_read,
+        };
     }
 }
@@ -6,14 +6,29 @@
 
 mod gp0;
 mod gp1;
-
-use crate::{bus::memory::Memory, renderer::Renderer};
-
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    bus::memory::Memory,
+    region::Region,
+    renderer::{
+        scaling::{Rect, Scaling},
+        BlendMode, Color, NullRenderer, Renderer, SkippedLines, Texture, TextureDepth,
+        TextureWindow,
+    },
+};
+
+#[cfg(feature = "window")]
 use cgmath::Vector2;
-use std::fmt::{self, Debug, Formatter};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Debug, Formatter},
+    mem,
+};
 
 /// The semi transparency mode
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum SemiTransparency {
     /// The first mode (B/2+F/2)
     #[default]
@@ -30,7 +45,7 @@ pub(super) enum SemiTransparency {
 }
 
 /// The texture page colors
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum TexturePageColors {
     /// 4 bits depth
     #[default]
@@ -44,7 +59,7 @@ pub(super) enum TexturePageColors {
 }
 
 /// The dither mode
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum Dither {
     /// Off mode
     #[default]
@@ -55,7 +70,7 @@ pub(super) enum Dither {
 }
 
 /// The display area drawing flag
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum DisplayAreaDrawing {
     /// Drawing to the prohibited area is allowed
     #[default]
@@ -66,7 +81,7 @@ pub(super) enum DisplayAreaDrawing {
 }
 
 /// The mask drawing flag
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum MaskDrawing {
     /// The mask bit will not be set
     #[default]
@@ -77,7 +92,7 @@ pub(super) enum MaskDrawing {
 }
 
 /// The draw pixels flag
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum DrawPixels {
     /// It should always be drawn
     #[default]
@@ -87,19 +102,24 @@ pub(super) enum DrawPixels {
     Unmasked = 1,
 }
 
-/// The field interlace
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// The interlace field
+///
+/// Stays odd while vertical interlace is off
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum Interlace {
-    /// Never interlace
+    /// Even field
     #[default]
-    Never = 0,
+    Even = 0,
 
-    /// Always interlace
-    Always = 1,
+    /// Odd field
+    Odd = 1,
 }
 
 /// The reverse flag
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+///
+/// The distortion depends on the console revision and is not emulated, the flag is only stored
+/// and reported in GPUSTAT
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum Reverse {
     /// Normal mode
     #[default]
@@ -110,7 +130,7 @@ pub(super) enum Reverse {
 }
 
 /// The horizontal resolution
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum HorizontalResolution {
     /// 256 Resolution
     #[default]
@@ -129,8 +149,27 @@ pub(super) enum HorizontalResolution {
     S640 = 4,
 }
 
+impl HorizontalResolution {
+    /// Decodes the horizontal resolution bits of GP1(08h)
+    ///
+    /// Arguments:
+    ///
+    /// * `horizontal_resolution_1`: The bits 0-1 selecting 256, 320, 512 or 640
+    /// * `horizontal_resolution_2`: The bit 6 selecting 368, overriding bits 0-1
+    pub(super) fn from_bits(horizontal_resolution_1: u8, horizontal_resolution_2: u8) -> Self {
+        match (horizontal_resolution_2, horizontal_resolution_1) {
+            (0, 0) => Self::S256,
+            (0, 1) => Self::S320,
+            (0, 2) => Self::S512,
+            (0, 3) => Self::S640,
+            (1, _) => Self::S368,
+            _ => unreachable!(),
+        }
+    }
+}
+
 /// The vertical resolution
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum VerticalResolution {
     /// 240 Resolution
     #[default]
@@ -141,7 +180,7 @@ pub(super) enum VerticalResolution {
 }
 
 /// The video mode
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum VideoMode {
     /// 60Hz
     #[default]
@@ -151,8 +190,17 @@ pub(super) enum VideoMode {
     Hz50 = 1,
 }
 
+impl From<Region> for VideoMode {
+    fn from(region: Region) -> Self {
+        match region {
+            Region::Ntsc => Self::Hz60,
+            Region::Pal => Self::Hz50,
+        }
+    }
+}
+
 /// The color depth
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum ColorDepth {
     /// 15 bits
     #[default]
@@ -163,7 +211,7 @@ pub(super) enum ColorDepth {
 }
 
 /// The vertical interlace
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum VerticalInterlace {
     /// Interlaced disabled
     #[default]
@@ -174,7 +222,7 @@ pub(super) enum VerticalInterlace {
 }
 
 /// The display enabled flag
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum DisplayEnabled {
     /// Display enabled
     #[default]
@@ -185,7 +233,7 @@ pub(super) enum DisplayEnabled {
 }
 
 /// The interrupt request flag
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum InterruptRequest {
     /// Interrupts disabled
     #[default]
@@ -196,7 +244,7 @@ pub(super) enum InterruptRequest {
 }
 
 /// The ready flag
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum Ready {
     /// Not ready
     #[default]
@@ -207,7 +255,7 @@ pub(super) enum Ready {
 }
 
 /// The DMA direction
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum DmaDirection {
     /// Disabled mode
     #[default]
@@ -224,7 +272,7 @@ pub(super) enum DmaDirection {
 }
 
 /// The drawing mode
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum DrawingMode {
     /// Even lines
     #[default]
@@ -235,7 +283,7 @@ pub(super) enum DrawingMode {
 }
 
 /// The receive mode
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub(super) enum ReceiveMode {
     /// Command
     #[default]
@@ -245,7 +293,27 @@ pub(super) enum ReceiveMode {
     Data = 1,
 }
 
+/// The state of a CPU to VRAM transfer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct VramTransfer {
+    /// The left edge of the destination in VRAM
+    pub(super) x: u16,
+
+    /// The top edge of the destination in VRAM
+    pub(super) y: u16,
+
+    /// The width of the rectangle in pixels
+    pub(super) width: u16,
+
+    /// The height of the rectangle in pixels
+    pub(super) height: u16,
+
+    /// The index of the next pixel inside the rectangle
+    pub(super) index: u32,
+}
+
 /// The GPU component
+#[derive(Serialize, Deserialize)]
 pub(crate) struct Gpu {
     /// The texture page x base
     texture_page_x_base: u8,
@@ -271,7 +339,7 @@ pub(crate) struct Gpu {
     /// If pixels should be drawn to masked areas
     draw_pixels: DrawPixels,
 
-    /// If field should be interlaced
+    /// The current interlace field
     interlace: Interlace,
 
     /// The reverse flag
@@ -283,6 +351,10 @@ pub(crate) struct Gpu {
     /// The horizontal resolution
     horizontal_resolution: HorizontalResolution,
 
+    /// The horizontal resolution 1 bits of GP1(08h), still reported in GPUSTAT while 368 overrides
+    /// them
+    horizontal_resolution_1: u8,
+
     /// The vertical resolution
     vertical_resolution: VerticalResolution,
 
@@ -301,6 +373,9 @@ pub(crate) struct Gpu {
     /// If interrupts should be requested
     interrupt_request: InterruptRequest,
 
+    /// If IRQ1 was raised since the last tick
+    interrupt_raised: bool,
+
     /// If it is ready to receive cmd words
     ready_receive_cmd_word: Ready,
 
@@ -322,6 +397,12 @@ pub(crate) struct Gpu {
     /// If the texture should be flipped on the y-axis
     texture_rectangle_y_flip: bool,
 
+    /// If textures are disabled for drawing
+    texture_disable: bool,
+
+    /// If GP0(E1h) is allowed to disable textures
+    texture_disable_allowed: bool,
+
     /// The display area x start in VRAM
     display_area_x_start_in_vram: u16,
 
@@ -380,18 +461,81 @@ pub(crate) struct Gpu {
     arguments: Vec<u32>,
 
     /// The remaining arguments count
-    argument_count: u16,
+    argument_count: u32,
+
+    /// The words in the GP0 FIFO, including the words of the command being received
+    fifo_length: u32,
 
     /// The receive mode
     receive_mode: ReceiveMode,
 
+    /// The CPU to VRAM transfer receiving data words
+    vram_transfer: VramTransfer,
+
+    /// The value latched into the GPUREAD register
+    gpu_read: u32,
+
+    /// The elapsed GPU cycles of the current scanline, scaled by 7
+    line_cycles: u32,
+
+    /// The current scanline
+    scanline: u16,
+
+    /// If the odd field of an interlaced frame is being displayed
+    odd_field: bool,
+
+    /// The region deciding the video mode after a reset
+    region: Region,
+
     /// The renderer
+    #[serde(skip, default = "Gpu::detached_renderer")]
     renderer: Box<dyn Renderer>,
 }
 
 impl Gpu {
+    /// The width of VRAM in pixels
+    const VRAM_WIDTH: u32 = 1024;
+
+    /// The height of VRAM in pixels
+    const VRAM_HEIGHT: u32 = 512;
+
+    /// The GPU cycles per scanline in NTSC mode
+    const CYCLES_PER_LINE_NTSC: u32 = 3413;
+
+    /// The GPU cycles per scanline in PAL mode
+    const CYCLES_PER_LINE_PAL: u32 = 3406;
+
+    /// The scanlines per frame in NTSC mode
+    const LINES_PER_FRAME_NTSC: u16 = 263;
+
+    /// The scanlines per frame in PAL mode
+    const LINES_PER_FRAME_PAL: u16 = 314;
+
+    /// The amount of words the GP0 command FIFO holds
+    const FIFO_DEPTH: u32 = 16;
+
+    /// The horizontal display range bits of GP1(06h), which are 12 bits wide
+    const DISPLAY_RANGE_HORIZONTAL_MASK: u16 = 0xfff;
+
+    /// The vertical display range bits of GP1(07h), which are 10 bits wide
+    const DISPLAY_RANGE_VERTICAL_MASK: u16 = 0x3ff;
+
+    /// The horizontal display range after a reset, starting at 200h and spanning 256 pixels of
+    /// 10 video clock cycles each
+    ///
+    /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gp100h-reset-gpu>
+    const DISPLAY_RANGE_HORIZONTAL_RESET: (u16, u16) = (0x200, 0x200 + 256 * 10);
+
+    /// The vertical display range after a reset, starting at 10h and spanning 240 lines
+    const DISPLAY_RANGE_VERTICAL_RESET: (u16, u16) = (0x010, 0x010 + 240);
+
     /// Creates a new GPU component
-    pub(crate) fn new(renderer: Box<dyn Renderer>) -> Self {
+    ///
+    /// Arguments:
+    ///
+    /// * `renderer`: The renderer drawing the primitives
+    /// * `region`: The region deciding the video mode after a reset
+    pub(crate) fn new(renderer: Box<dyn Renderer>, region: Region) -> Self {
         Self {
             texture_page_x_base: 0,
             texture_page_y_base_1: 0,
@@ -405,12 +549,14 @@ impl Gpu {
             reverse: Reverse::default(),
             texture_page_y_base_2: 0,
             horizontal_resolution: HorizontalResolution::default(),
+            horizontal_resolution_1: 0,
             vertical_resolution: VerticalResolution::default(),
-            video_mode: VideoMode::default(),
+            video_mode: VideoMode::from(region),
             display_area_color_depth: ColorDepth::default(),
             vertical_interlace: VerticalInterlace::default(),
             display_enabled: DisplayEnabled::Disabled,
             interrupt_request: InterruptRequest::default(),
+            interrupt_raised: false,
             ready_receive_cmd_word: Ready::Ready,
             ready_send_vram_to_cpu: Ready::Ready,
             ready_receive_dma_block: Ready::Ready,
@@ -418,12 +564,14 @@ impl Gpu {
             drawing_mode: DrawingMode::default(),
             texture_rectangle_x_flip: false,
             texture_rectangle_y_flip: false,
+            texture_disable: false,
+            texture_disable_allowed: false,
             display_area_x_start_in_vram: 0,
             display_area_y_start_in_vram: 0,
-            display_range_horizontal_start: 0,
-            display_range_horizontal_end: 0,
-            display_range_vertical_start: 0,
-            display_range_vertical_end: 0,
+            display_range_horizontal_start: Self::DISPLAY_RANGE_HORIZONTAL_RESET.0,
+            display_range_horizontal_end: Self::DISPLAY_RANGE_HORIZONTAL_RESET.1,
+            display_range_vertical_start: Self::DISPLAY_RANGE_VERTICAL_RESET.0,
+            display_range_vertical_end: Self::DISPLAY_RANGE_VERTICAL_RESET.1,
             texture_window_x_mask: 0,
             texture_window_y_mask: 0,
             texture_window_x_offset: 0,
@@ -438,14 +586,353 @@ impl Gpu {
             gp1_bytes: [0; 3],
             arguments: Vec::new(),
             argument_count: 0,
+            fifo_length: 0,
             receive_mode: ReceiveMode::Command,
+            vram_transfer: VramTransfer::default(),
+            gpu_read: 0,
+            line_cycles: 0,
+            scanline: 0,
+            odd_field: false,
             renderer,
+            region,
         }
     }
 
+    /// The renderer used by GPUs restored from a save state until the real one is reattached
+    fn detached_renderer() -> Box<dyn Renderer> {
+        Box::new(NullRenderer)
+    }
+
+    /// Moves the renderer from the previous GPU into this restored one
+    ///
+    /// Arguments:
+    ///
+    /// * `previous`: The GPU which gets replaced
+    pub(crate) fn reattach(&mut self, previous: &mut Self) {
+        mem::swap(&mut self.renderer, &mut previous.renderer);
+        self.update_mask_bit_setting();
+        self.update_skipped_lines();
+    }
+
+    /// Restores the power-on state, keeping the renderer with a cleared VRAM
+    pub(crate) fn reset(&mut self) {
+        let mut gpu = Self::new(Self::detached_renderer(), self.region);
+        gpu.reattach(self);
+        gpu.renderer.load_vram(&vec![
+            0x0000;
+            (Self::VRAM_WIDTH * Self::VRAM_HEIGHT) as usize
+        ]);
+        *self = gpu;
+    }
+
+    /// Sets the texture page from the bits shared by GP0(E1h) and the texpage attribute
+    ///
+    /// Arguments:
+    ///
+    /// * `texpage`: The texpage bits
+    fn set_texture_page(&mut self, texpage: u32) {
+        self.texture_page_x_base = (texpage & 0xf) as u8;
+        self.texture_page_y_base_1 = ((texpage >> 4) & 0x1) as u8;
+
+        let semi_transparency = ((texpage >> 5) & 0x3) as u8;
+        self.semi_transparency = match semi_transparency {
+            0 => SemiTransparency::First,
+            1 => SemiTransparency::Second,
+            2 => SemiTransparency::Third,
+            3 => SemiTransparency::Fourth,
+            _ => unreachable!(),
+        };
+
+        // The reserved fourth mode behaves like 15 bits
+        let texture_page_colors = ((texpage >> 7) & 0x3) as u8;
+        self.texture_page_colors = match texture_page_colors {
+            0 => TexturePageColors::Bit4,
+            1 => TexturePageColors::Bit8,
+            2 | 3 => TexturePageColors::Bit15,
+            _ => unreachable!(),
+        };
+
+        self.texture_page_y_base_2 = ((texpage >> 11) & 0x1) as u8;
+        self.texture_disable = self.texture_disable_allowed && self.texture_page_y_base_2 != 0;
+    }
+
+    /// Returns the texture textured primitives currently sample from
+    ///
+    /// Arguments:
+    ///
+    /// * `clut`: The CLUT attribute of the primitive
+    fn texture(&self, clut: u32) -> Texture {
+        let depth = match self.texture_page_colors {
+            TexturePageColors::Bit4 => TextureDepth::Bit4,
+            TexturePageColors::Bit8 => TextureDepth::Bit8,
+            TexturePageColors::Bit15 => TextureDepth::Bit15,
+        };
+
+        Texture {
+            page_x: self.texture_page_x_base as u16 * 64,
+            page_y: self.texture_page_y_base_1 as u16 * 256,
+            depth,
+            clut_x: (clut & 0x3f) as u16 * 16,
+            clut_y: ((clut >> 6) & 0x1ff) as u16,
+            window: TextureWindow {
+                x_mask: self.texture_window_x_mask,
+                y_mask: self.texture_window_y_mask,
+                x_offset: self.texture_window_x_offset,
+                y_offset: self.texture_window_y_offset,
+            },
+        }
+    }
+
+    /// Returns the colors a textured polygon blends its texels with, none if the raw-texture bit
+    /// of the command is set
+    ///
+    /// Arguments:
+    ///
+    /// * `colors`: The vertex colors of the command
+    fn texture_colors<const N: usize>(&self, colors: [Color; N]) -> Option<[Color; N]> {
+        let raw_texture = (self.arguments[0] >> 24) & 0x1 != 0;
+        (!raw_texture).then_some(colors)
+    }
+
+    /// Returns how the current polygon gets blended, none if its semi-transparency bit is clear
+    ///
+    /// Textured polygons have to update the texture page first, as its attribute carries the
+    /// semi-transparency mode
+    fn blend_mode(&self) -> Option<BlendMode> {
+        let semi_transparent = (self.arguments[0] >> 25) & 0x1 != 0;
+        if !semi_transparent {
+            return None;
+        }
+
+        let blend_mode = match self.semi_transparency {
+            SemiTransparency::First => BlendMode::Average,
+            SemiTransparency::Second => BlendMode::Add,
+            SemiTransparency::Third => BlendMode::Subtract,
+            SemiTransparency::Fourth => BlendMode::AddQuarter,
+        };
+
+        Some(blend_mode)
+    }
+
+    /// Hands the mask bit setting of GP0(E6h) to the renderer
+    fn update_mask_bit_setting(&mut self) {
+        self.renderer.set_mask_bit_setting(
+            self.mask_drawing == MaskDrawing::Yes,
+            self.draw_pixels == DrawPixels::Unmasked,
+        );
+    }
+
+    /// Hands the lines of the displayed field to the renderer while drawing to them is prohibited
+    ///
+    /// Only interlaced 480-line frames display a field while the other one is drawn, so every
+    /// line is drawn otherwise
+    fn update_skipped_lines(&mut self) {
+        let interlaced = self.vertical_interlace == VerticalInterlace::On
+            && self.vertical_resolution == VerticalResolution::S480;
+        let prohibited = self.display_area_drawing == DisplayAreaDrawing::Prohibited;
+
+        let skipped_lines = (interlaced && prohibited).then(|| {
+            let display_area = self.display_area();
+            SkippedLines {
+                top: display_area.y,
+                height: display_area.height,
+                parity: (display_area.y + self.odd_field as u32) & 0x1,
+            }
+        });
+        self.renderer.set_skipped_lines(skipped_lines);
+    }
+
     /// Executes 1 cycle
     pub(crate) fn step(&mut self) {
-        self.renderer.render();
+        let display_area =
+            (self.display_enabled == DisplayEnabled::Enabled).then(|| self.display_area());
+        self.renderer.render(display_area);
+    }
+
+    /// Advances the video timing by CPU cycles and updates the drawn line
+    ///
+    /// Arguments:
+    ///
+    /// * `cycles`: The elapsed CPU cycles
+    ///
+    /// Returns if the GPU interrupt (IRQ1) should be requested
+    pub(crate) fn tick(&mut self, cycles: u32) -> bool {
+        let (cycles_per_line, lines_per_frame) = match self.video_mode {
+            VideoMode::Hz60 => (Self::CYCLES_PER_LINE_NTSC, Self::LINES_PER_FRAME_NTSC),
+            VideoMode::Hz50 => (Self::CYCLES_PER_LINE_PAL, Self::LINES_PER_FRAME_PAL),
+        };
+
+        self.drain_fifo(cycles);
+
+        // The GPU runs at 11/7 of the CPU clock
+        self.line_cycles += cycles * 11;
+        while self.line_cycles >= cycles_per_line * 7 {
+            self.line_cycles -= cycles_per_line * 7;
+            self.scanline += 1;
+
+            if self.scanline >= lines_per_frame {
+                self.scanline = 0;
+            }
+
+            // The field flips as VBLANK begins, so it holds for the following frame
+            if self.scanline == self.display_range_vertical_end {
+                self.odd_field = !self.odd_field;
+                self.update_skipped_lines();
+            }
+        }
+
+        let in_vblank = self.scanline < self.display_range_vertical_start
+            || self.scanline >= self.display_range_vertical_end;
+        let interlaced = self.vertical_interlace == VerticalInterlace::On
+            && self.vertical_resolution == VerticalResolution::S480;
+
+        let odd = if in_vblank {
+            false
+        } else if interlaced {
+            self.odd_field
+        } else {
+            self.scanline & 0x1 != 0
+        };
+
+        self.drawing_mode = if odd {
+            DrawingMode::Odd
+        } else {
+            DrawingMode::Even
+        };
+
+        // Without vertical interlace the field bit stays set
+        self.interlace = if self.vertical_interlace == VerticalInterlace::Off || self.odd_field {
+            Interlace::Odd
+        } else {
+            Interlace::Even
+        };
+
+        mem::take(&mut self.interrupt_raised)
+    }
+
+    /// Returns the area of VRAM shown on screen at the active display resolution
+    fn display_area(&self) -> Rect {
+        let width = match self.horizontal_resolution {
+            HorizontalResolution::S256 => 256,
+            HorizontalResolution::S320 => 320,
+            HorizontalResolution::S368 => 368,
+            HorizontalResolution::S512 => 512,
+            HorizontalResolution::S640 => 640,
+        };
+
+        let height = match self.vertical_resolution {
+            VerticalResolution::S240 => 240,
+            VerticalResolution::S480 => 480,
+        };
+
+        Rect {
+            x: self.display_area_x_start_in_vram as u32,
+            y: self.display_area_y_start_in_vram as u32,
+            width,
+            height,
+        }
+    }
+
+    /// Copies the display area out of the framebuffer at the internal scale of the renderer
+    ///
+    /// Returns the width, the height and the RGBA pixels, which are black while the display is
+    /// disabled
+    pub(crate) fn capture_frame(&self) -> (u32, u32, Vec<u8>) {
+        let display_area = self.display_area();
+        let frame = self.renderer.frame();
+
+        let scale = self.renderer.internal_scale();
+        let (width, height) = (display_area.width * scale, display_area.height * scale);
+        if self.display_enabled == DisplayEnabled::Disabled {
+            return (
+                width,
+                height,
+                [0x00, 0x00, 0x00, 0xff].repeat((width * height) as usize),
+            );
+        }
+        let (frame_width, frame_height) = (Self::VRAM_WIDTH * scale, Self::VRAM_HEIGHT * scale);
+
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                // The display area wraps around the edges of VRAM
+                let frame_x = (display_area.x * scale + x) % frame_width;
+                let frame_y = (display_area.y * scale + y) % frame_height;
+
+                let index = ((frame_y * frame_width + frame_x) * 4) as usize;
+                match frame.get(index..index + 4) {
+                    Some(pixel) => pixels.extend_from_slice(pixel),
+                    None => pixels.extend_from_slice(&[0x00, 0x00, 0x00, 0xff]),
+                }
+            }
+        }
+
+        (width, height, pixels)
+    }
+
+    /// Returns a copy of the 1024x512 VRAM in BGR555 with the mask bit in bit 15
+    pub(crate) fn dump_vram(&self) -> Vec<u16> {
+        // Renderers without a VRAM read as black
+        let mut vram = self.renderer.vram().to_vec();
+        vram.resize((Self::VRAM_WIDTH * Self::VRAM_HEIGHT) as usize, 0x0000);
+        vram
+    }
+
+    /// Reads a VRAM pixel, wrapping coordinates around the VRAM edges
+    ///
+    /// Arguments:
+    ///
+    /// * `x`: The x coordinate of the pixel
+    /// * `y`: The y coordinate of the pixel
+    pub(crate) fn vram_pixel(&self, x: u32, y: u32) -> u16 {
+        let index = (y % Self::VRAM_HEIGHT) * Self::VRAM_WIDTH + x % Self::VRAM_WIDTH;
+
+        // Renderers without a VRAM read as black
+        self.renderer
+            .vram()
+            .get(index as usize)
+            .copied()
+            .unwrap_or(0x0000)
+    }
+
+    /// Writes a VRAM pixel, wrapping coordinates around the VRAM edges
+    ///
+    /// Arguments:
+    ///
+    /// * `x`: The x coordinate of the pixel
+    /// * `y`: The y coordinate of the pixel
+    /// * `pixel`: The pixel in BGR555 with the mask bit in bit 15
+    pub(crate) fn set_vram_pixel(&mut self, x: u32, y: u32, pixel: u16) {
+        let x = (x % Self::VRAM_WIDTH) as u16;
+        let y = (y % Self::VRAM_HEIGHT) as u16;
+        self.renderer.upload_vram(x, y, 1, 1, &[pixel]);
+    }
+
+    /// Replaces the VRAM
+    ///
+    /// Arguments:
+    ///
+    /// * `data`: The 1024x512 VRAM in BGR555 with the mask bit in bit 15
+    pub(crate) fn load_vram(&mut self, data: &[u16]) {
+        self.renderer.load_vram(data);
+    }
+
+    /// Changes the factor primitives are rasterized at relative to the native resolution
+    ///
+    /// Arguments:
+    ///
+    /// * `scale`: The internal scale
+    pub(crate) fn set_internal_scale(&mut self, scale: u32) {
+        self.renderer.set_internal_scale(scale);
+    }
+
+    /// Changes how the display area is fitted into the window
+    ///
+    /// Arguments:
+    ///
+    /// * `scaling`: The scaling mode
+    pub(crate) fn set_scaling(&mut self, scaling: Scaling) {
+        self.renderer.set_scaling(scaling);
     }
 
     /// Resizes the current framebuffer
@@ -453,28 +940,30 @@ impl Gpu {
     /// Arguments:
     ///
     /// * `size`: New framebuffer size
+    #[cfg(feature = "window")]
     pub(crate) fn resize(&mut self, size: Vector2<u32>) {
         self.renderer.resize(size);
     }
 
+    /// Returns the current RGBA framebuffer
+    pub(crate) fn frame(&self) -> &[u8] {
+        self.renderer.frame()
+    }
+
     /// Executes a GP0 command
     ///
     /// Arguments:
     ///
     /// * `command`: The command to execute
     pub(crate) fn gp0(&mut self, command: u32) {
+        // Words written to a full FIFO get lost on hardware, they are still executed here
+        self.fifo_length = (self.fifo_length + 1).min(Self::FIFO_DEPTH);
+
         if self.argument_count == 0 {
             let opcode = (command >> 24) as u8;
-            let bytes = match opcode {
-                0x28 => 5,
-                0x2c => 9,
-                0x30 => 6,
-                0x38 => 8,
-                0xa0 => 3,
-                _ => 1,
-            };
-
-            self.argument_count = bytes;
+            let words = Self::command_length(opcode);
+
+            self.argument_count = words;
             self.arguments.clear();
         }
 
@@ -486,13 +975,25 @@ impl Gpu {
 
                 if self.argument_count == 0 {
                     let opcode = (self.arguments[0] >> 24) as u8;
+                    if Self::is_polyline(opcode) && !self.polyline_terminated(opcode) {
+                        // The vertices of a polyline keep coming until the terminator word
+                        self.argument_count = 1;
+                        self.update_ready_flags();
+                        return;
+                    }
+
                     match opcode {
                         0x00 => self.op_nop(),
                         0x01 => self.op_clear_cache(),
-                        0x28 => self.op_draw_monochrome_four_point_polygon_opaque(),
-                        0x2c => self.op_draw_textured_four_point_polygon_opaque_texture_blending(),
-                        0x30 => self.op_draw_shaded_three_point_polygon_opaque(),
-                        0x38 => self.op_draw_shaded_four_point_polygon_opaque(),
+                        0x02 => self.op_fill_rectangle(),
+                        0x1f => self.op_interrupt_request(),
+                        0x20 | 0x22 => self.op_draw_monochrome_three_point_polygon(),
+                        0x28 | 0x2a => self.op_draw_monochrome_four_point_polygon(),
+                        0x2c..=0x2f => self.op_draw_textured_four_point_polygon(),
+                        0x30 | 0x32 => self.op_draw_shaded_three_point_polygon(),
+                        0x34..=0x37 => self.op_draw_shaded_textured_three_point_polygon(),
+                        0x38 | 0x3a => self.op_draw_shaded_four_point_polygon(),
+                        0x3c..=0x3f => self.op_draw_shaded_textured_four_point_polygon(),
                         0xa0 => self.op_copy_rectangle(),
                         0xe1 => self.op_draw_mode_setting(),
                         0xe2 => self.op_texture_window_setting(),
@@ -500,9 +1001,10 @@ impl Gpu {
                         0xe4 => self.op_set_drawing_area_bottom_right(),
                         0xe5 => self.op_set_drawing_offset(),
                         0xe6 => self.op_mask_bit_setting(),
-                        _ => unimplemented!(
-                            "gp0 command {:#010x} with opcode {:#04x} ({:#010b})",
-                            command,
+                        _ => log::warn!(
+                            target: "gpu",
+                            "Unhandled gp0 command {:#010x} with opcode {:#04x} ({:#010b})",
+                            self.arguments[0],
                             opcode,
                             opcode
                         ),
@@ -510,13 +1012,211 @@ impl Gpu {
                 }
             }
             ReceiveMode::Data => {
-                // TODO: Handle VRAM
+                self.write_vram_transfer(command as u16);
+                self.write_vram_transfer((command >> 16) as u16);
 
                 if self.argument_count == 0 {
                     self.receive_mode = ReceiveMode::Command;
                 }
             }
         }
+
+        self.update_ready_flags();
+    }
+
+    /// Writes the next pixel of a CPU to VRAM transfer
+    ///
+    /// The padding halfword of a rectangle with an odd amount of pixels gets discarded
+    ///
+    /// Arguments:
+    ///
+    /// * `pixel`: The pixel in BGR555 with the mask bit in bit 15
+    fn write_vram_transfer(&mut self, pixel: u16) {
+        let transfer = &mut self.vram_transfer;
+        let width = transfer.width as u32;
+        if transfer.index >= width * transfer.height as u32 {
+            return;
+        }
+
+        let x = transfer.x as u32 + transfer.index % width;
+        let y = transfer.y as u32 + transfer.index / width;
+        transfer.index += 1;
+
+        // Unlike fills, transfers honor the mask bit settings
+        let current = self.vram_pixel(x, y);
+        if self.draw_pixels == DrawPixels::Unmasked && current & 0x8000 != 0 {
+            return;
+        }
+
+        let mask = if self.mask_drawing == MaskDrawing::Yes {
+            0x8000
+        } else {
+            0x0000
+        };
+        self.set_vram_pixel(x, y, pixel | mask);
+    }
+
+    /// Takes the words of completed commands and transfers out of the GP0 FIFO
+    ///
+    /// The GPU is assumed to take 1 word per CPU cycle, the time spent drawing isn't modeled. The
+    /// words of a command which is still being received stay in the FIFO until it is complete.
+    ///
+    /// Arguments:
+    ///
+    /// * `cycles`: The elapsed CPU cycles
+    fn drain_fifo(&mut self, cycles: u32) {
+        let drainable_words = self.fifo_length - self.pending_command_words();
+        self.fifo_length -= drainable_words.min(cycles);
+        self.update_ready_flags();
+    }
+
+    /// Returns the words of the command being received which are waiting in the FIFO
+    ///
+    /// Polylines only wait for their first segment, the following vertices get drawn as they
+    /// arrive
+    fn pending_command_words(&self) -> u32 {
+        if self.receive_mode != ReceiveMode::Command || self.argument_count == 0 {
+            return 0;
+        }
+
+        let opcode = (self.arguments[0] >> 24) as u8;
+        let words = (self.arguments.len() as u32).min(Self::command_length(opcode));
+        words.min(self.fifo_length)
+    }
+
+    /// Updates the ready flags from the state of the GP0 command FIFO
+    pub(super) fn update_ready_flags(&mut self) {
+        let receiving_command =
+            self.receive_mode == ReceiveMode::Command && self.argument_count != 0;
+        self.ready_receive_cmd_word = if receiving_command {
+            Ready::No
+        } else {
+            Ready::Ready
+        };
+
+        self.ready_receive_dma_block = if self.fifo_full() {
+            Ready::No
+        } else {
+            Ready::Ready
+        };
+    }
+
+    /// Returns if the GP0 command FIFO can't take another word
+    fn fifo_full(&self) -> bool {
+        self.fifo_length >= Self::FIFO_DEPTH
+    }
+
+    /// Returns if the opcode starts a polyline, which ends with a terminator word
+    ///
+    /// Arguments:
+    ///
+    /// * `opcode`: The opcode of the command
+    fn is_polyline(opcode: u8) -> bool {
+        matches!(opcode, 0x48..=0x4f | 0x58..=0x5f)
+    }
+
+    /// Returns if the last received word terminates the polyline being received
+    ///
+    /// The terminator is only recognized after the first segment, it is usually 0x55555555
+    ///
+    /// Arguments:
+    ///
+    /// * `opcode`: The opcode of the polyline
+    fn polyline_terminated(&self, opcode: u8) -> bool {
+        let first_segment = Self::command_length(opcode) as usize;
+        let last_word = self.arguments[self.arguments.len() - 1];
+
+        self.arguments.len() > first_segment && last_word & 0xf000f000 == 0x50005000
+    }
+
+    /// Returns the amount of words a GP0 command consists of, including the command word
+    ///
+    /// Polygons, lines and rectangles get decoded from their primitive flags. Polylines return
+    /// the words of their first segment, the following vertices are received until the
+    /// terminator. Unknown opcodes are treated as a single word.
+    ///
+    /// Arguments:
+    ///
+    /// * `opcode`: The opcode of the command
+    fn command_length(opcode: u8) -> u32 {
+        let shaded = (opcode >> 4) & 0x1 != 0;
+        let textured = (opcode >> 2) & 0x1 != 0;
+
+        match opcode >> 5 {
+            // Fill Rectangle in VRAM
+            0b000 if opcode == 0x02 => 3,
+            // Polygons
+            0b001 => {
+                let vertices = if (opcode >> 3) & 0x1 != 0 { 4 } else { 3 };
+                let words_per_vertex = 1 + shaded as u32 + textured as u32;
+
+                1 + vertices * words_per_vertex - shaded as u32
+            }
+            // Lines
+            0b010 => {
+                if shaded {
+                    4
+                } else {
+                    3
+                }
+            }
+            // Rectangles
+            0b011 => {
+                let variable_size = (opcode >> 3) & 0x3 == 0;
+                2 + textured as u32 + variable_size as u32
+            }
+            // VRAM to VRAM
+            0b100 => 4,
+            // CPU to VRAM / VRAM to CPU
+            0b101 | 0b110 => 3,
+            _ => 1,
+        }
+    }
+
+    /// Reads a word from the GPUREAD register
+    pub(crate) fn gpuread(&self) -> u32 {
+        // TODO: Implement VRAM to CPU transfers
+        self.gpu_read
+    }
+
+    /// Assembles the GPUSTAT register from the current state
+    ///
+    /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#1f801814h-gpustat-gpu-status-register-r>
+    pub(crate) fn status_word(&self) -> u32 {
+        let data_request = match self.dma_direction {
+            DmaDirection::Off => 0,
+            DmaDirection::Fifo => !self.fifo_full() as u32,
+            DmaDirection::CpuToGpu => self.ready_receive_dma_block as u32,
+            DmaDirection::GpuToCpu => self.ready_send_vram_to_cpu as u32,
+        };
+
+        let mut value = 0;
+        value |= self.texture_page_x_base as u32;
+        value |= (self.texture_page_y_base_1 as u32) << 4;
+        value |= (self.semi_transparency as u32) << 5;
+        value |= (self.texture_page_colors as u32) << 7;
+        value |= (self.dither as u32) << 9;
+        value |= (self.display_area_drawing as u32) << 10;
+        value |= (self.mask_drawing as u32) << 11;
+        value |= (self.draw_pixels as u32) << 12;
+        value |= (self.interlace as u32) << 13;
+        value |= (self.reverse as u32) << 14;
+        value |= (self.texture_disable as u32) << 15;
+        value |= ((self.horizontal_resolution == HorizontalResolution::S368) as u32) << 16;
+        value |= (self.horizontal_resolution_1 as u32) << 17;
+        value |= (self.vertical_resolution as u32) << 19;
+        value |= (self.video_mode as u32) << 20;
+        value |= (self.display_area_color_depth as u32) << 21;
+        value |= (self.vertical_interlace as u32) << 22;
+        value |= (self.display_enabled as u32) << 23;
+        value |= (self.interrupt_request as u32) << 24;
+        value |= data_request << 25;
+        value |= (self.ready_receive_cmd_word as u32) << 26;
+        value |= (self.ready_send_vram_to_cpu as u32) << 27;
+        value |= (self.ready_receive_dma_block as u32) << 28;
+        value |= (self.dma_direction as u32) << 29;
+        value |= (self.drawing_mode as u32) << 31;
+        value
     }
 
     /// Executes a GP1 command
@@ -524,7 +1224,7 @@ impl Gpu {
     /// Arguments:
     ///
     /// * `command`: The command to execute
-    fn gp1(&mut self, command: u32) {
+    pub(crate) fn gp1(&mut self, command: u32) {
         let opcode = (command >> 24) as u8;
 
         match opcode {
@@ -537,8 +1237,11 @@ impl Gpu {
             0x06 => self.op_horizontal_display_range_on_screen(command),
             0x07 => self.op_vertical_display_range_on_screen(command),
             0x08 => self.op_display_mode(command),
-            _ => unimplemented!(
-                "gp1 command {:#010x} with opcode {:#04x} ({:#010b})",
+            0x09 => self.op_new_texture_disable(command),
+            0x10..=0x1f => self.op_get_gpu_info(command),
+            _ => log::warn!(
+                target: "gpu",
+                "Unhandled gp1 command {:#010x} with opcode {:#04x} ({:#010b})",
                 command,
                 opcode,
                 opcode
@@ -590,64 +1293,34 @@ impl Memory for Gpu {
         }
     }
 
-    fn read_u8(&self, offset: u32) -> u8 {
+    // A halfword store to the low half of a port submits a whole command word. The upper half of
+    // the CPU register doesn't reach the bus here, so it is taken as zero. A store to the upper
+    // half alone would complete a command with stale low bytes, so it gets dropped instead.
+    fn write_u16(&mut self, offset: u32, value: u16) {
         match offset {
-            0x00..=0x03 => {
-                // TODO: Implement GPUREAD regsiter
-                0x00
-            }
-            0x04 => {
-                let mut value = 0;
-                value |= self.texture_page_x_base;
-                value |= self.texture_page_y_base_1 << 4;
-                value |= (self.semi_transparency as u8) << 5;
-                value |= (self.texture_page_colors as u8 & 0b00000001) << 7;
+            0x00 => self.gp0(value as u32),
+            0x04 => self.gp1(value as u32),
+            _ => log::warn!(
+                target: "gpu",
+                "Ignored halfword write to the upper half of GP{} with value {:#06x}",
+                offset / 4,
                 value
-            }
-            0x05 => {
-                let mut value = 0;
-                value |= (self.texture_page_colors as u8 & 0b00000010) >> 1;
-                value |= (self.dither as u8) << 1;
-                value |= (self.display_area_drawing as u8) << 2;
-                value |= (self.mask_drawing as u8) << 3;
-                value |= (self.draw_pixels as u8) << 4;
-                value |= (self.interlace as u8) << 5;
-                value |= (self.reverse as u8) << 6;
-                value |= self.texture_page_y_base_2 << 7;
-                value
-            }
-            0x06 => {
-                let mut value = 0;
-                value |= match self.horizontal_resolution {
-                    HorizontalResolution::S256 => 0b00000000,
-                    HorizontalResolution::S320 => 0b00000010,
-                    HorizontalResolution::S368 => 0b00000001,
-                    HorizontalResolution::S512 => 0b00000100,
-                    HorizontalResolution::S640 => 0b00000110,
-                };
-                //value |= (self.vertical_resolution as u8) << 3;
-                value |= (self.video_mode as u8) << 4;
-                value |= (self.display_area_color_depth as u8) << 5;
-                value |= (self.vertical_interlace as u8) << 6;
-                value |= (self.display_enabled as u8) << 7;
-                value
-            }
-            0x07 => {
-                let mut value = 0;
-                value |= self.interrupt_request as u8;
-                value |= match self.dma_direction {
-                    DmaDirection::Off => 0,
-                    DmaDirection::Fifo => 1,
-                    DmaDirection::CpuToGpu => self.ready_receive_dma_block as u8,
-                    DmaDirection::GpuToCpu => self.ready_send_vram_to_cpu as u8,
-                } << 1;
-                value |= (self.ready_receive_cmd_word as u8) << 2;
-                value |= (self.ready_send_vram_to_cpu as u8) << 3;
-                value |= (self.ready_receive_dma_block as u8) << 4;
-                value |= (self.dma_direction as u8) << 5;
-                value |= (self.drawing_mode as u8) << 7;
-                value
-            }
+            ),
+        }
+    }
+
+    fn write_u32(&mut self, offset: u32, value: u32) {
+        match offset {
+            0x00 => self.gp0(value),
+            0x04 => self.gp1(value),
+            _ => unreachable!("write to gpu at {:#04x} with value {:#010x}", offset, value),
+        }
+    }
+
+    fn read_u8(&self, offset: u32) -> u8 {
+        match offset {
+            0x00..=0x03 => self.gpuread().read_u8(offset),
+            0x04..=0x07 => (self.status_word() >> ((offset - 0x04) * 8)) as u8,
             _ => unreachable!("read from gpu at {:#04x}", offset,),
         }
     }
@@ -668,12 +1341,14 @@ impl Debug for Gpu {
             .field("reverse", &self.reverse)
             .field("texture_page_y_base_2", &self.texture_page_y_base_2)
             .field("horizontal_resolution", &self.horizontal_resolution)
+            .field("horizontal_resolution_1", &self.horizontal_resolution_1)
             .field("vertical_resolution", &self.vertical_resolution)
             .field("video_mode", &self.video_mode)
             .field("display_area_color_depth", &self.display_area_color_depth)
             .field("vertical_interlace", &self.vertical_interlace)
             .field("display_enabled", &self.display_enabled)
             .field("interrupt_request", &self.interrupt_request)
+            .field("interrupt_raised", &self.interrupt_raised)
             .field("ready_receive_cmd_word", &self.ready_receive_cmd_word)
             .field("ready_send_vram_to_cpu", &self.ready_send_vram_to_cpu)
             .field("ready_receive_dma_block", &self.ready_receive_dma_block)
@@ -681,6 +1356,8 @@ impl Debug for Gpu {
             .field("drawing_mode", &self.drawing_mode)
             .field("texture_rectangle_x_flip", &self.texture_rectangle_x_flip)
             .field("texture_rectangle_y_flip", &self.texture_rectangle_y_flip)
+            .field("texture_disable", &self.texture_disable)
+            .field("texture_disable_allowed", &self.texture_disable_allowed)
             .field(
                 "display_area_x_start_in_vram",
                 &self.display_area_x_start_in_vram,
@@ -719,6 +1396,13 @@ impl Debug for Gpu {
             .field("gp1_bytes", &self.gp1_bytes)
             .field("arguments", &self.arguments)
             .field("argument_count", &self.argument_count)
+            .field("fifo_length", &self.fifo_length)
+            .field("vram_transfer", &self.vram_transfer)
+            .field("gpu_read", &self.gpu_read)
+            .field("line_cycles", &self.line_cycles)
+            .field("scanline", &self.scanline)
+            .field("odd_field", &self.odd_field)
+            .field("region", &self.region)
             .finish()
     }
 }
@@ -0,0 +1,729 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::{
+    bus::memory::Memory,
+    gpu::Gpu,
+    region::Region,
+    renderer::{
+        mock_renderer::{Draw, MockRenderer},
+        scaling::Rect,
+        software_renderer::SoftwareRenderer,
+        BlendMode, Color, Position, TexCoord, Texture, TextureDepth, TextureWindow,
+    },
+};
+
+fn gpu() -> Gpu {
+    let (renderer, _) = MockRenderer::new();
+    Gpu::new(Box::new(renderer), Region::Ntsc)
+}
+
+/// Returns the texture page x base in GPUSTAT, set through GP0(E1h)
+fn texture_page_x_base(gpu: &Gpu) -> u32 {
+    gpu.read_u32(0x04) & 0xf
+}
+
+#[test]
+fn display_area_follows_the_display_mode() {
+    let mut gpu = gpu();
+    assert_eq!(
+        gpu.display_area(),
+        Rect {
+            x: 0,
+            y: 0,
+            width: 256,
+            height: 240,
+        }
+    );
+
+    // Interlaced 368x480 starting at (64, 16)
+    gpu.gp1(0x05004040);
+    gpu.gp1(0x08000064);
+    assert_eq!(
+        gpu.display_area(),
+        Rect {
+            x: 64,
+            y: 16,
+            width: 368,
+            height: 480,
+        }
+    );
+
+    gpu.gp1(0x08000003);
+    assert_eq!(gpu.display_area().width, 640);
+    assert_eq!(gpu.display_area().height, 240);
+}
+
+#[test]
+fn gpu_info_latches_the_drawing_area() {
+    let mut gpu = gpu();
+    gpu.gp0(0xe3004410);
+    gpu.gp0(0xe407fd3f);
+
+    gpu.gp1(0x10000003);
+    assert_eq!(gpu.gpuread(), 0x004410);
+
+    gpu.gp1(0x10000004);
+    assert_eq!(gpu.gpuread(), 0x07fd3f);
+}
+
+#[test]
+fn gpu_info_latches_the_offset_and_version() {
+    let mut gpu = gpu();
+    gpu.gp0(0xe53ff801);
+
+    gpu.gp1(0x10000005);
+    assert_eq!(gpu.gpuread(), 0x3ff801);
+
+    gpu.gp1(0x10000007);
+    assert_eq!(gpu.gpuread(), 0x000002);
+
+    // Unused parameters leave GPUREAD unchanged
+    gpu.gp1(0x10000001);
+    assert_eq!(gpu.gpuread(), 0x000002);
+}
+
+#[test]
+fn texture_disable_needs_the_gp1_enable() {
+    let mut gpu = gpu();
+
+    gpu.gp0(0xe1000800);
+    assert_eq!(gpu.read_u32(0x04) & (1 << 15), 0);
+
+    gpu.gp1(0x09000001);
+    gpu.gp0(0xe1000800);
+    assert_eq!(gpu.read_u32(0x04) & (1 << 15), 1 << 15);
+
+    gpu.gp1(0x09000000);
+    assert_eq!(gpu.read_u32(0x04) & (1 << 15), 0);
+}
+
+#[test]
+fn unknown_gp1_command_is_ignored() {
+    let mut gpu = gpu();
+    let status = gpu.read_u32(0x04);
+
+    gpu.gp1(0x20000000);
+    assert_eq!(gpu.read_u32(0x04), status);
+}
+
+#[test]
+fn command_length_of_dispatched_commands() {
+    let lengths: &[(&[u8], u32)] = &[
+        (&[0x00, 0x01], 1),
+        (&[0x02], 3),
+        (&[0x1f], 1),
+        (&[0x20, 0x22], 4),
+        (&[0x28, 0x2a], 5),
+        (&[0x2c, 0x2d, 0x2e, 0x2f], 9),
+        (&[0x30, 0x32], 6),
+        (&[0x34, 0x35, 0x36, 0x37], 9),
+        (&[0x38, 0x3a], 8),
+        (&[0x3c, 0x3d, 0x3e, 0x3f], 12),
+        (&[0x48, 0x4a], 3),
+        (&[0x58, 0x5a], 4),
+        (&[0xa0], 3),
+        (&[0xe1, 0xe2, 0xe3, 0xe4, 0xe5, 0xe6], 1),
+    ];
+
+    for (opcodes, length) in lengths {
+        for &opcode in opcodes.iter() {
+            assert_eq!(
+                Gpu::command_length(opcode),
+                *length,
+                "opcode {:#04x}",
+                opcode
+            );
+        }
+    }
+}
+
+#[test]
+fn unknown_command_is_skipped() {
+    let mut gpu = gpu();
+    gpu.gp0(0x03000000);
+    gpu.gp0(0xe1000005);
+
+    assert_eq!(texture_page_x_base(&gpu), 5);
+}
+
+#[test]
+fn polyline_is_received_until_the_terminator() {
+    let mut gpu = gpu();
+
+    // Monochrome polyline with 4 vertices
+    gpu.gp0(0x48ffffff);
+    gpu.gp0(0x00000000);
+    gpu.gp0(0x00100010);
+    gpu.gp0(0x00200010);
+    gpu.gp0(0x00200020);
+    gpu.gp0(0x55555555);
+    gpu.gp0(0xe1000005);
+
+    assert_eq!(texture_page_x_base(&gpu), 5);
+}
+
+#[test]
+fn shaded_polyline_is_received_until_the_terminator() {
+    let mut gpu = gpu();
+
+    // Shaded polyline with 3 vertices, the terminator replaces the next color
+    gpu.gp0(0x58ff0000);
+    gpu.gp0(0x00000000);
+    gpu.gp0(0x0000ff00);
+    gpu.gp0(0x00100010);
+    gpu.gp0(0x000000ff);
+    gpu.gp0(0x00200010);
+    gpu.gp0(0x50005000);
+    gpu.gp0(0xe1000007);
+
+    assert_eq!(texture_page_x_base(&gpu), 7);
+}
+
+#[test]
+fn drawn_line_parity_follows_the_scanline() {
+    let mut gpu = gpu();
+    let odd_line = |gpu: &Gpu| gpu.read_u32(0x04) >> 31;
+
+    // Display lines 16-256, 11 scanlines pass per 3413 * 7 CPU cycles
+    gpu.gp1(0x07040010);
+
+    gpu.tick(3413 * 7);
+    assert_eq!(gpu.scanline, 11);
+    assert_eq!(odd_line(&gpu), 0);
+
+    gpu.tick(3413 * 7);
+    assert_eq!(gpu.scanline, 22);
+    assert_eq!(odd_line(&gpu), 0);
+
+    gpu.tick(3413 * 7);
+    assert_eq!(gpu.scanline, 33);
+    assert_eq!(odd_line(&gpu), 1);
+}
+
+#[test]
+fn status_reports_the_vertical_resolution() {
+    let mut gpu = gpu();
+
+    gpu.gp1(0x08000024);
+    assert_eq!(gpu.read_u32(0x04) & (1 << 19), 1 << 19);
+
+    gpu.gp1(0x08000000);
+    assert_eq!(gpu.read_u32(0x04) & (1 << 19), 0);
+}
+
+/// Returns if GPUSTAT reports the FIFO as ready to receive a DMA block
+fn ready_receive_dma_block(gpu: &Gpu) -> bool {
+    gpu.read_u32(0x04) & (1 << 28) != 0
+}
+
+#[test]
+fn fifo_fills_and_drains() {
+    let mut gpu = gpu();
+    for _ in 0..Gpu::FIFO_DEPTH - 1 {
+        gpu.gp0(0xe1000000);
+    }
+    assert!(ready_receive_dma_block(&gpu));
+
+    gpu.gp0(0xe1000000);
+    assert!(!ready_receive_dma_block(&gpu));
+
+    gpu.tick(1);
+    assert!(ready_receive_dma_block(&gpu));
+
+    gpu.tick(Gpu::FIFO_DEPTH);
+    assert_eq!(gpu.fifo_length, 0);
+}
+
+#[test]
+fn fifo_keeps_the_words_of_incomplete_commands() {
+    let mut gpu = gpu();
+
+    // The first 4 of the 5 words of a monochrome quad
+    gpu.gp0(0x28ffffff);
+    gpu.gp0(0x00000000);
+    gpu.gp0(0x00000010);
+    gpu.gp0(0x00100000);
+    gpu.tick(100);
+    assert_eq!(gpu.fifo_length, 4);
+
+    gpu.gp0(0x00100010);
+    gpu.tick(100);
+    assert_eq!(gpu.fifo_length, 0);
+}
+
+#[test]
+fn fifo_counts_vram_transfer_words() {
+    let mut gpu = gpu();
+
+    // CPU to VRAM copy of a 64x1 rectangle, which takes 32 data words
+    gpu.gp0(0xa0000000);
+    gpu.gp0(0x00000000);
+    gpu.gp0(0x00010040);
+    gpu.tick(3);
+    for _ in 0..Gpu::FIFO_DEPTH {
+        gpu.gp0(0x7fff7fff);
+    }
+    assert!(!ready_receive_dma_block(&gpu));
+
+    gpu.tick(Gpu::FIFO_DEPTH);
+    assert!(ready_receive_dma_block(&gpu));
+}
+
+#[test]
+fn textured_quad_uses_the_texpage_and_clut_attributes() {
+    let (renderer, draws) = MockRenderer::new();
+    let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+
+    // Mask 1 and offset 1 on both axes
+    gpu.gp0(0xe2008421);
+
+    // CLUT at x = 16, y = 257 and an 8-bit texpage at x = 3 * 64, y = 256
+    gpu.gp0(0x2c808080);
+    gpu.gp0(0x00000000);
+    gpu.gp0(0x40410000);
+    gpu.gp0(0x00000010);
+    gpu.gp0(0x00930010);
+    gpu.gp0(0x00100000);
+    gpu.gp0(0x00001000);
+    gpu.gp0(0x00100010);
+    gpu.gp0(0x00001010);
+
+    let texture = Texture {
+        page_x: 192,
+        page_y: 256,
+        depth: TextureDepth::Bit8,
+        clut_x: 16,
+        clut_y: 257,
+        window: TextureWindow {
+            x_mask: 1,
+            y_mask: 1,
+            x_offset: 1,
+            y_offset: 1,
+        },
+    };
+    assert_eq!(
+        *draws.borrow(),
+        vec![Draw::TexturedQuad(
+            [
+                Position::new(0, 0),
+                Position::new(16, 0),
+                Position::new(0, 16),
+                Position::new(16, 16),
+            ],
+            [
+                TexCoord::new(0, 0),
+                TexCoord::new(16, 0),
+                TexCoord::new(0, 16),
+                TexCoord::new(16, 16),
+            ],
+            Some([Color::new(0x80, 0x80, 0x80); 4]),
+            texture,
+            None,
+        )]
+    );
+    assert_eq!(gpu.read_u32(0x04) & 0x1f, 0x13);
+}
+
+#[test]
+fn shaded_textured_triangle_passes_its_vertex_colors() {
+    let (renderer, draws) = MockRenderer::new();
+    let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+
+    // 15-bit texpage at x = 64 in the attribute of the second vertex
+    for command in [
+        0x340000ff, 0x00000000, 0x00000000, 0x0000ff00, 0x00000010, 0x01010010, 0x00ff0000,
+        0x00100000, 0x00001000,
+    ] {
+        gpu.gp0(command);
+    }
+
+    let draws = draws.borrow();
+    let [Draw::TexturedTriangle(positions, tex_coords, colors, texture, _)] = draws.as_slice()
+    else {
+        panic!("expected a single textured triangle, got {:?}", draws);
+    };
+    assert_eq!(
+        *positions,
+        [
+            Position::new(0, 0),
+            Position::new(16, 0),
+            Position::new(0, 16),
+        ]
+    );
+    assert_eq!(
+        *tex_coords,
+        [
+            TexCoord::new(0, 0),
+            TexCoord::new(16, 0),
+            TexCoord::new(0, 16),
+        ]
+    );
+    assert_eq!(
+        *colors,
+        Some([
+            Color::new(0xff, 0x00, 0x00),
+            Color::new(0x00, 0xff, 0x00),
+            Color::new(0x00, 0x00, 0xff),
+        ])
+    );
+    assert_eq!((texture.page_x, texture.depth), (64, TextureDepth::Bit15));
+}
+
+#[test]
+fn raw_textured_quad_is_drawn_without_colors() {
+    let (renderer, draws) = MockRenderer::new();
+    let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+
+    for opcode in [0x2c, 0x2d] {
+        for command in [
+            (opcode << 24) | 0x404040,
+            0x00000000,
+            0x00000000,
+            0x00000010,
+            0x01000010,
+            0x00100000,
+            0x00001000,
+            0x00100010,
+            0x00001010,
+        ] {
+            gpu.gp0(command);
+        }
+    }
+
+    let draws = draws.borrow();
+    let colors = draws
+        .iter()
+        .map(|draw| match draw {
+            Draw::TexturedQuad(_, _, colors, _, _) => *colors,
+            _ => panic!("expected a textured quad, got {:?}", draw),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(colors, [Some([Color::new(0x40, 0x40, 0x40); 4]), None]);
+}
+
+#[test]
+fn horizontal_resolution_round_trips_through_gpustat() {
+    // GP1(08h) bits 0-1 and 6, the GPUSTAT bits 16-18 and the resulting width
+    for (bits, status, width) in [
+        (0x00, 0b000, 256),
+        (0x01, 0b010, 320),
+        (0x02, 0b100, 512),
+        (0x03, 0b110, 640),
+        (0x40, 0b001, 368),
+        (0x42, 0b101, 368),
+    ] {
+        let mut gpu = gpu();
+        gpu.gp1(0x08000000 | bits);
+
+        assert_eq!(
+            (gpu.read_u32(0x04) >> 16) & 0x7,
+            status,
+            "bits {:#04x}",
+            bits
+        );
+        assert_eq!(gpu.display_area().width, width, "bits {:#04x}", bits);
+    }
+}
+
+#[test]
+fn disabled_display_presents_a_black_screen() {
+    let mut gpu = Gpu::new(Box::new(SoftwareRenderer::new_headless()), Region::Ntsc);
+    gpu.load_vram(&[0x7fff; (Gpu::VRAM_WIDTH * Gpu::VRAM_HEIGHT) as usize]);
+
+    // The display starts disabled, GP1(03h) with bit 0 clear turns it on
+    let (_, _, pixels) = gpu.capture_frame();
+    assert!(pixels
+        .chunks_exact(4)
+        .all(|pixel| pixel == [0x00, 0x00, 0x00, 0xff]));
+
+    gpu.gp1(0x03000000);
+    let (_, _, pixels) = gpu.capture_frame();
+    assert!(pixels
+        .chunks_exact(4)
+        .all(|pixel| pixel == [0xf8, 0xf8, 0xf8, 0xff]));
+
+    gpu.gp1(0x03000001);
+    let (_, _, pixels) = gpu.capture_frame();
+    assert!(pixels
+        .chunks_exact(4)
+        .all(|pixel| pixel == [0x00, 0x00, 0x00, 0xff]));
+}
+
+#[test]
+fn polygon_commands_take_their_word_count() {
+    for (opcode, words) in [
+        (0x20, 4),
+        (0x22, 4),
+        (0x28, 5),
+        (0x2a, 5),
+        (0x2e, 9),
+        (0x30, 6),
+        (0x32, 6),
+        (0x36, 9),
+        (0x38, 8),
+        (0x3a, 8),
+        (0x3e, 12),
+    ] {
+        assert_eq!(Gpu::command_length(opcode), words, "GP0({:02x}h)", opcode);
+    }
+}
+
+#[test]
+fn semi_transparent_polygons_use_the_texpage_blend_mode() {
+    let (renderer, draws) = MockRenderer::new();
+    let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+
+    for command in [
+        // Opaque and semi-transparent monochrome triangles with the default B/2+F/2
+        0x20ff0000, 0x00000000, 0x00000010, 0x00100000, 0x22ff0000, 0x00000000, 0x00000010,
+        0x00100000, // B-F from GP0(E1h), then a semi-transparent monochrome quad
+        0xe1000040, 0x2aff0000, 0x00000000, 0x00000010, 0x00100000, 0x00100010,
+    ] {
+        gpu.gp0(command);
+    }
+
+    let blend_modes = draws
+        .borrow()
+        .iter()
+        .map(|draw| match draw {
+            Draw::Triangle(_, _, blend_mode) | Draw::Quad(_, _, blend_mode) => *blend_mode,
+            _ => panic!("expected an untextured polygon, got {:?}", draw),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        blend_modes,
+        [None, Some(BlendMode::Average), Some(BlendMode::Subtract)]
+    );
+}
+
+#[test]
+fn cpu_to_vram_copy_wraps_around_the_vram_edges() {
+    let mut gpu = Gpu::new(Box::new(SoftwareRenderer::new_headless()), Region::Ntsc);
+
+    // A 4x2 rectangle at (1022, 511), given with out of range coordinate bits
+    for command in [
+        0xa0000000, 0xfffffffe, 0x00020004, 0x00020001, 0x00040003, 0x00060005, 0x00080007,
+    ] {
+        gpu.gp0(command);
+    }
+
+    let vram = gpu.dump_vram();
+    let pixel = |x: u32, y: u32| vram[(y * Gpu::VRAM_WIDTH + x) as usize];
+    assert_eq!(
+        [
+            pixel(1022, 511),
+            pixel(1023, 511),
+            pixel(0, 511),
+            pixel(1, 511)
+        ],
+        [1, 2, 3, 4]
+    );
+    assert_eq!(
+        [pixel(1022, 0), pixel(1023, 0), pixel(0, 0), pixel(1, 0)],
+        [5, 6, 7, 8]
+    );
+}
+
+#[test]
+fn cpu_to_vram_copy_of_size_zero_covers_the_whole_vram() {
+    let mut gpu = gpu();
+    for command in [0xa0000000, 0x00000000, 0x00000000] {
+        gpu.gp0(command);
+    }
+
+    assert_eq!(gpu.argument_count, Gpu::VRAM_WIDTH * Gpu::VRAM_HEIGHT / 2);
+    assert_eq!(
+        (gpu.vram_transfer.width, gpu.vram_transfer.height),
+        (1024, 512)
+    );
+}
+
+#[test]
+fn cpu_to_vram_copy_uploads_the_rectangle() {
+    let (renderer, draws) = MockRenderer::new();
+    let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+
+    // A 3x1 rectangle, its padding halfword doesn't get uploaded
+    for command in [0xa0000000, 0x00080010, 0x00010003, 0x00020001, 0xffff0003] {
+        gpu.gp0(command);
+    }
+
+    let uploads = (0..3)
+        .map(|index| Draw::Upload {
+            x: 16 + index,
+            y: 8,
+            width: 1,
+            height: 1,
+            data: vec![index + 1],
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(*draws.borrow(), uploads);
+}
+
+#[test]
+fn fill_uploads_the_rectangle_in_steps_of_16_pixels() {
+    let (renderer, draws) = MockRenderer::new();
+    let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+
+    // Red over (16, 2) with a width of 17, rounded up to 32
+    for command in [0x020000ff, 0x0002001f, 0x00020011] {
+        gpu.gp0(command);
+    }
+
+    assert_eq!(
+        *draws.borrow(),
+        [Draw::Upload {
+            x: 16,
+            y: 2,
+            width: 32,
+            height: 2,
+            data: vec![0x001f; 64],
+        }]
+    );
+}
+
+#[test]
+fn vram_pixel_accessors_wrap_the_coordinates() {
+    let mut gpu = Gpu::new(Box::new(SoftwareRenderer::new_headless()), Region::Ntsc);
+
+    // 1024 * 5 + 3 and 512 * 7 + 9 wrap to (3, 9)
+    gpu.set_vram_pixel(5123, 3593, 0x1234);
+    assert_eq!(gpu.dump_vram()[(9 * Gpu::VRAM_WIDTH + 3) as usize], 0x1234);
+    assert_eq!(gpu.vram_pixel(3, 9), 0x1234);
+    assert_eq!(gpu.vram_pixel(u32::MAX - 1020, u32::MAX - 502), 0x1234);
+}
+
+#[test]
+fn prohibited_drawing_skips_the_displayed_field() {
+    let mut gpu = Gpu::new(Box::new(SoftwareRenderer::new_headless()), Region::Ntsc);
+
+    // Interlaced 480 lines, drawing to the display area prohibited, then a red quad crossing
+    // the bottom of the display area
+    gpu.gp1(0x08000024);
+    for command in [
+        0xe1000000, 0xe3000000, 0xe407fbff, 0x280000ff, 0x01d60000, 0x01d60010, 0x01f40000,
+        0x01f40010,
+    ] {
+        gpu.gp0(command);
+    }
+
+    // The even field is displayed first
+    let vram = gpu.dump_vram();
+    for y in 470..500 {
+        let pixel = vram[(y * Gpu::VRAM_WIDTH + 4) as usize];
+        let skipped = y < 480 && y % 2 == 0;
+        assert_eq!(pixel == 0x0000, skipped, "line {}", y);
+    }
+}
+
+#[test]
+fn interlaced_fields_alternate_every_frame() {
+    let mut gpu = Gpu::new(Box::new(SoftwareRenderer::new_headless()), Region::Ntsc);
+    let frame_cycles = Gpu::CYCLES_PER_LINE_NTSC * Gpu::LINES_PER_FRAME_NTSC as u32 * 7 / 11;
+
+    // Interlaced 480 lines with drawing to the display area prohibited, so each frame only
+    // draws the field which isn't displayed
+    gpu.gp1(0x08000024);
+    for command in [0xe1000000, 0xe3000000, 0xe407fbff] {
+        gpu.gp0(command);
+    }
+
+    // Halfway into the frame the display is active
+    gpu.tick(frame_cycles / 2);
+
+    let mut fields = Vec::new();
+    for color in [0x0000ff, 0x00ff00] {
+        let status = gpu.read_u32(0x04);
+        fields.push(((status >> 13) & 0x1, status >> 31));
+
+        for command in [
+            0x28000000 | color,
+            0x00640000,
+            0x00640010,
+            0x00680000,
+            0x00680010,
+        ] {
+            gpu.gp0(command);
+        }
+        gpu.tick(frame_cycles);
+    }
+
+    // GPUSTAT bit 13 and the drawn lines in bit 31 follow the field
+    assert_eq!(fields.len(), 2);
+    assert!(fields.iter().all(|&(field, lines)| field == lines));
+    assert_ne!(fields[0], fields[1]);
+
+    // Each frame drew the lines of the other field
+    let vram = gpu.dump_vram();
+    let lines = (100..104)
+        .map(|y| vram[(y * Gpu::VRAM_WIDTH + 4) as usize])
+        .collect::<Vec<_>>();
+    let (odd, even) = if fields[0].0 == 1 {
+        (0x03e0, 0x001f)
+    } else {
+        (0x001f, 0x03e0)
+    };
+    assert_eq!(lines, [even, odd, even, odd]);
+}
+
+#[test]
+fn status_word_assembles_every_field() {
+    let mut gpu = gpu();
+
+    // Texpage with x = 3, y = 256, B/2+F/2 swapped for B-F, 8-bit colors, dithering and drawing
+    // to the display area, then both mask bit settings
+    gpu.gp0(0xe10006d3);
+    gpu.gp0(0xe6000003);
+
+    // Interlaced 480 lines at 256, the display enabled and CPU to GP0 DMA
+    gpu.gp1(0x08000024);
+    gpu.gp1(0x03000000);
+    gpu.gp1(0x04000002);
+
+    // Bits 0-12 from GP0(E1h) and GP0(E6h), 19 and 22 from GP1(08h), 25 requesting DMA, 26-28
+    // being ready for commands, VRAM reads and DMA blocks, and 29-30 for the direction
+    let expected = 0x000006d3 | 0x00001800 | 0x00480000 | 0x02000000 | 0x1c000000 | 0x40000000;
+    assert_eq!(gpu.status_word(), expected);
+    assert_eq!(gpu.read_u32(0x04), expected);
+}
+
+#[test]
+fn fifo_direction_data_request_follows_the_fifo() {
+    let data_request = |gpu: &Gpu| gpu.status_word() & (1 << 25) != 0;
+
+    let mut gpu = gpu();
+    gpu.gp1(0x04000001);
+    assert!(data_request(&gpu));
+
+    for _ in 0..Gpu::FIFO_DEPTH {
+        gpu.gp0(0xe1000000);
+    }
+    assert!(!data_request(&gpu));
+
+    gpu.tick(Gpu::FIFO_DEPTH);
+    assert!(data_request(&gpu));
+}
+
+#[test]
+fn word_write_to_gp1_executes_the_command() {
+    let mut gpu = gpu();
+
+    // GP1(08h) with interlaced 480 lines
+    gpu.write_u32(0x04, 0x08000024);
+    assert_eq!(gpu.status_word() & 0x00480000, 0x00480000);
+}
+
+#[test]
+fn halfword_write_to_the_upper_half_submits_nothing() {
+    let mut gpu = gpu();
+    let status = gpu.status_word();
+
+    // The staged low byte must not turn this into GP1(08h) with interlaced 480 lines
+    gpu.write_u8(0x04, 0x24);
+    gpu.write_u16(0x06, 0x0800);
+    assert_eq!(gpu.status_word(), status);
+}
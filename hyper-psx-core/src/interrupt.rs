@@ -0,0 +1,113 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::bus::memory::Memory;
+
+use serde::{Deserialize, Serialize};
+
+/// The interrupt sources of the PSX
+///
+/// <https://psx-spx.consoledev.net/interrupts/>
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Interrupt {
+    /// IRQ0 VBLANK
+    Vblank = 0,
+
+    /// IRQ1 GPU
+    Gpu = 1,
+
+    /// IRQ2 CDROM
+    Cdrom = 2,
+
+    /// IRQ3 DMA
+    Dma = 3,
+
+    /// IRQ4 TMR0 - Timer 0
+    Timer0 = 4,
+
+    /// IRQ5 TMR1 - Timer 1
+    Timer1 = 5,
+
+    /// IRQ6 TMR2 - Timer 2
+    Timer2 = 6,
+
+    /// IRQ7 Controller and Memory Card
+    Controller = 7,
+
+    /// IRQ8 SIO
+    Sio = 8,
+
+    /// IRQ9 SPU
+    Spu = 9,
+
+    /// IRQ10 Controller - Lightpen Interrupt
+    Lightpen = 10,
+}
+
+/// The interrupt controller component
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct InterruptController {
+    /// I_STAT - Interrupt status register
+    status: u16,
+
+    /// I_MASK - Interrupt mask register
+    mask: u16,
+}
+
+impl InterruptController {
+    /// Creates an interrupt controller component
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests an interrupt by setting its status bit
+    ///
+    /// # Arguments:
+    ///
+    /// * `interrupt`: The interrupt source
+    pub(crate) fn request(&mut self, interrupt: Interrupt) {
+        log::debug!(target: "interrupt", "Requested interrupt {:?}", interrupt);
+
+        self.status |= 1 << interrupt as u16;
+    }
+
+    /// Checks if any unmasked interrupt is pending
+    pub(crate) fn pending(&self) -> bool {
+        (self.status & self.mask) != 0
+    }
+}
+
+impl Memory for InterruptController {
+    fn write_u8(&mut self, offset: u32, value: u8) {
+        match offset {
+            0x00..=0x01 => {
+                // Writing 0 acknowledges the interrupt, writing 1 leaves it unchanged
+                let status = self.status.read_u8(offset) & value;
+                self.status.write_u8(offset, status);
+            }
+            0x02..=0x03 => {}
+            0x04..=0x05 => {
+                self.mask.write_u8(offset - 0x04, value);
+            }
+            0x06..=0x07 => {}
+            _ => unreachable!(
+                "write to interrupt controller at {:#04x} with value {:#04x}",
+                offset, value
+            ),
+        }
+    }
+
+    fn read_u8(&self, offset: u32) -> u8 {
+        match offset {
+            0x00..=0x01 => self.status.read_u8(offset),
+            0x02..=0x03 => 0x00,
+            0x04..=0x05 => self.mask.read_u8(offset - 0x04),
+            0x06..=0x07 => 0x00,
+            _ => unreachable!("read from interrupt controller at {:#04x}", offset),
+        }
+    }
+}
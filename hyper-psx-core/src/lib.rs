@@ -5,113 +5,757 @@
  */
 
 //! The core for the PSX Emulator
+//!
+//! The default `window` feature presents through a GLFW window and plays audio through the
+//! default output device. Without it the core only runs headless, which is what wasm32 builds use.
+
+#[cfg(all(feature = "window", target_arch = "wasm32"))]
+compile_error!("the window feature is unsupported on wasm32, build without default features");
 
 mod bios;
+mod builder;
 mod bus;
+mod cdrom;
 mod cpu;
 mod dma;
+mod exe;
 mod gpu;
+mod interrupt;
+mod mdec;
+mod region;
 mod renderer;
+mod sio;
+mod spu;
+mod state;
 mod utils;
 
 use crate::{
     bios::Bios,
+    builder::FrameCallback,
     bus::{ram::Ram, Bus},
-    cpu::Cpu,
+    cdrom::{
+        disc::{self, Disc},
+        Cdrom,
+    },
+    cpu::{trace::Tracer, Cpu},
     dma::Dma,
+    exe::Exe,
     gpu::Gpu,
+    interrupt::{Interrupt, InterruptController},
+    renderer::Renderer,
+    sio::{
+        memory_card::{self, MemoryCard},
+        Sio,
+    },
+    state::{SaveState, SaveStateRef},
+};
+#[cfg(feature = "window")]
+use crate::{
     renderer::{
-        software_renderer::{self, SoftwareRenderer},
+        software_renderer,
         window::{self, Window},
-        Renderer,
     },
+    spu::audio::Audio,
 };
 
+pub use crate::{
+    builder::PsxBuilder,
+    bus::ram::RamSize,
+    cpu::{disasm::disassemble, watchpoint::WatchKind},
+    region::Region,
+    renderer::{scaling::Scaling, RendererKind},
+    sio::controller::Button,
+    spu::audio::{AudioSink, BufferSink, NullSink},
+};
+
+#[cfg(feature = "window")]
 use cgmath::Vector2;
-use glfw::WindowEvent;
-use std::{path::Path, time::Instant};
+#[cfg(feature = "window")]
+use glfw::{Action, Key, WindowEvent};
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
+};
+#[cfg(feature = "window")]
+use std::{
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 use thiserror::Error;
 
 /// The error type for the creation process of the PSX
 #[derive(Debug, Error)]
 pub enum CreationError {
+    /// If the builder was given no BIOS
+    #[error("no bios was given")]
+    MissingBios,
+
     /// If the BIOS failed to load
     #[error("failed to load bios")]
     BiosFailure(#[from] bios::CreationError),
 
+    /// If the disc image failed to load
+    #[error("failed to load disc")]
+    DiscFailure(#[from] disc::CreationError),
+
+    /// If the memory card failed to load
+    #[error("failed to load memory card")]
+    MemoryCardFailure(#[from] memory_card::CreationError),
+
+    /// If the PSX-EXE failed to load
+    #[error("failed to load exe")]
+    ExeFailure(#[from] exe::CreationError),
+
     /// If the Window failed to create
+    #[cfg(feature = "window")]
     #[error("failed to create window")]
     WindowFailure(#[from] window::CreationError),
 
     /// If the software renderer failed to create
+    #[cfg(feature = "window")]
     #[error("failed to create software renderer")]
     SoftwareRendererFailure(#[from] software_renderer::CreationError),
 }
 
+/// The error type for the loading process of a save state
+#[derive(Debug, Error)]
+pub enum StateError {
+    /// If the save state failed to be decoded
+    #[error("failed to decode save state")]
+    DecodingFailure(#[from] bincode::Error),
+}
+
+/// The error type for saving a screenshot
+#[derive(Debug, Error)]
+pub enum ScreenshotError {
+    /// If the file failed to be created
+    #[error("failed to create screenshot file")]
+    FileCreationFailure(#[from] io::Error),
+
+    /// If the PNG failed to be encoded
+    #[error("failed to encode screenshot")]
+    EncodingFailure(#[from] png::EncodingError),
+}
+
 /// The PSX Emulator containg each component
 #[derive(Debug)]
 pub struct Psx {
     /// The CPU component
     cpu: Cpu,
 
+    /// The Bus component
+    bus: Bus,
+
     /// The DMA component,
     dma: Dma,
 
     /// The GPU component,
     gpu: Gpu,
 
-    /// The window component
-    window: Window,
+    /// The PSX-EXE sideloaded once the BIOS reaches the shell, again after each reset
+    exe: Option<Exe>,
+
+    /// If the PSX-EXE is still waiting for the BIOS to reach the shell
+    exe_pending: bool,
+
+    /// If the BIOS shell gets skipped, booting the disc without the intro
+    fast_boot: bool,
+
+    /// The window component, none if running headless
+    #[cfg(feature = "window")]
+    window: Option<Window>,
+
+    /// If the audio output is disabled
+    #[cfg(feature = "window")]
+    muted: bool,
+
+    /// If frames are emulated as fast as possible instead of at the console rate
+    #[cfg(feature = "window")]
+    uncapped: bool,
+
+    /// If the emulation is paused, only advancing through `step_once`
+    #[cfg(feature = "window")]
+    paused: bool,
+
+    /// The region deciding the frame rate
+    region: Region,
+
+    /// The cycles `run_cycles` ran past its last budget, taken from the next one
+    overrun_cycles: u64,
+
+    /// The tracer writing each executed instruction, none if tracing is disabled
+    tracer: Option<Tracer>,
+
+    /// The callback receiving each presented frame
+    on_frame: Option<FrameCallback>,
 }
 
 impl Psx {
+    /// The entry point of the BIOS shell, at which the kernel is initialized
+    const SHELL_ENTRY: u32 = 0x80030000;
+
+    /// The CPU cycles per second
+    const CPU_CYCLES_PER_SECOND: f32 = 33868800.0;
+
     /// Creates a new PSX Emulator
     ///
     /// # Arguments:
     ///
     /// * `bios_path`: The path to the BIOS
+    /// * `disc_path`: The optional path to a CUE sheet or BIN disc image
+    /// * `memory_card_path`: The optional path to a memory card file, created if missing
+    /// * `ram_size`: The amount of installed RAM
+    /// * `renderer_kind`: The backend drawing the GPU primitives
+    /// * `region`: The region deciding the video standard
     ///
     /// # Errors
     ///
-    /// This function will throw an error if the BIOS failed to load
-    pub fn new<P: AsRef<Path>>(bios_path: P) -> Result<Self, CreationError> {
+    /// This function will throw an error if the BIOS, the disc or the memory card failed to load
+    #[cfg(feature = "window")]
+    pub fn new<P: AsRef<Path>>(
+        bios_path: P,
+        disc_path: Option<P>,
+        memory_card_path: Option<P>,
+        ram_size: RamSize,
+        renderer_kind: RendererKind,
+        region: Region,
+    ) -> Result<Self, CreationError> {
+        let window = Window::new()?;
+        let renderer = renderer::create_renderer(renderer_kind, &window)?;
+
         let bios = Bios::new(bios_path)?;
-        let ram = Ram::new();
+        let mut psx = Self::create(
+            bios,
+            disc_path,
+            memory_card_path,
+            ram_size,
+            renderer,
+            region,
+        )?;
+        psx.window = Some(window);
 
-        let dma = Dma::new();
+        Ok(psx)
+    }
 
-        let window = Window::new()?;
+    /// Creates a new PSX Emulator without a window, rendering offscreen
+    ///
+    /// # Arguments:
+    ///
+    /// * `bios_path`: The path to the BIOS
+    ///
+    /// # Errors
+    ///
+    /// This function will throw an error if the BIOS failed to load
+    pub fn new_headless<P: AsRef<Path>>(bios_path: P) -> Result<Self, CreationError> {
+        Self::builder().bios_path(bios_path).build()
+    }
+
+    /// Creates a new headless PSX Emulator from the BIOS data instead of a file
+    ///
+    /// # Arguments:
+    ///
+    /// * `bytes`: The 512KB BIOS data
+    ///
+    /// # Errors
+    ///
+    /// This function will throw an error if the BIOS data has the wrong size
+    pub fn from_bios_bytes(bytes: Vec<u8>) -> Result<Self, CreationError> {
+        Self::builder().bios_bytes(bytes).build()
+    }
+
+    /// Returns a builder for a PSX Emulator rendering offscreen, which hands each frame to a
+    /// callback instead of a window
+    pub fn builder() -> PsxBuilder {
+        PsxBuilder::default()
+    }
+
+    /// Creates the components of the PSX Emulator
+    ///
+    /// # Arguments:
+    ///
+    /// * `bios`: The BIOS component
+    /// * `disc_path`: The optional path to a CUE sheet or BIN disc image
+    /// * `memory_card_path`: The optional path to a memory card file
+    /// * `ram_size`: The amount of installed RAM
+    /// * `renderer`: The renderer of the GPU
+    /// * `region`: The region deciding the video standard
+    pub(crate) fn create<P: AsRef<Path>>(
+        bios: Bios,
+        disc_path: Option<P>,
+        memory_card_path: Option<P>,
+        ram_size: RamSize,
+        renderer: Box<dyn Renderer>,
+        region: Region,
+    ) -> Result<Self, CreationError> {
+        let ram = Ram::with_size(ram_size.bytes());
+        let interrupt_controller = InterruptController::new();
+
+        let disc = disc_path.map(Disc::new).transpose()?;
+        let cdrom = Cdrom::new(disc);
 
-        let renderer: Box<dyn Renderer> = Box::new(SoftwareRenderer::new(&window)?);
-        let gpu = Gpu::new(renderer);
+        let memory_card = memory_card_path.map(MemoryCard::new).transpose()?;
+        let sio = Sio::new(memory_card);
 
-        let bus = Bus::new(bios, ram);
+        let dma = Dma::new();
+
+        let gpu = Gpu::new(renderer, region);
+
+        let bus = Bus::new(bios, ram, interrupt_controller, cdrom, sio);
 
-        let cpu = Cpu::new(bus);
+        let cpu = Cpu::new();
 
         Ok(Self {
             cpu,
+            bus,
             dma,
             gpu,
-            window,
+            exe: None,
+            exe_pending: false,
+            fast_boot: false,
+            #[cfg(feature = "window")]
+            window: None,
+            #[cfg(feature = "window")]
+            muted: false,
+            #[cfg(feature = "window")]
+            uncapped: false,
+            #[cfg(feature = "window")]
+            paused: false,
+            region,
+            overrun_cycles: 0,
+            tracer: None,
+            on_frame: None,
         })
     }
 
+    /// Sideloads a PSX-EXE once the BIOS reaches the shell, skipping the intro
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path to the PSX-EXE
+    ///
+    /// # Errors
+    ///
+    /// This function will throw an error if the PSX-EXE failed to load
+    pub fn load_exe<P: AsRef<Path>>(&mut self, path: P) -> Result<(), CreationError> {
+        self.exe = Some(Exe::new(path)?);
+        self.exe_pending = true;
+        Ok(())
+    }
+
+    /// Serializes the state of every component into a save state
+    ///
+    /// The VRAM is taken from the renderer, the BIOS, the disc, the memory card, the renderer and
+    /// the window themselves are not part of the save state
+    pub fn save_state(&self) -> Vec<u8> {
+        let state = SaveStateRef {
+            cpu: &self.cpu,
+            bus: &self.bus,
+            dma: &self.dma,
+            gpu: &self.gpu,
+            overrun_cycles: self.overrun_cycles,
+            vram: self.gpu.dump_vram(),
+        };
+
+        bincode::serialize(&state).expect("components should always be serializable")
+    }
+
+    /// Restores the state of every component from a save state
+    ///
+    /// # Arguments:
+    ///
+    /// * `data`: The save state created by `save_state`
+    ///
+    /// # Errors
+    ///
+    /// This function will throw an error if the save state is corrupted, the current state is
+    /// kept in that case
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut state: SaveState = bincode::deserialize(data)?;
+        state.cpu.reattach(&mut self.cpu);
+        state.bus.reattach(&mut self.bus);
+        state.gpu.reattach(&mut self.gpu);
+        state.gpu.load_vram(&state.vram);
+
+        self.cpu = state.cpu;
+        self.bus = state.bus;
+        self.dma = state.dma;
+        self.gpu = state.gpu;
+        self.overrun_cycles = state.overrun_cycles;
+
+        Ok(())
+    }
+
+    /// Restores the power-on state of every component, as if the console was switched off and on
+    ///
+    /// RAM and VRAM are cleared, so runs after a reset are reproducible. The BIOS, the disc, the
+    /// memory card, the renderer and the window are kept, a loaded PSX-EXE gets sideloaded again.
+    pub fn reset(&mut self) {
+        self.reset_components(true);
+    }
+
+    /// Restores the power-on state of every component except RAM, like the reset button does
+    ///
+    /// VRAM is cleared. The BIOS, the disc, the memory card, the renderer and the window are
+    /// kept, a loaded PSX-EXE gets sideloaded again.
+    pub fn reset_keeping_ram(&mut self) {
+        self.reset_components(false);
+    }
+
+    /// Restores the power-on state of every component
+    ///
+    /// # Arguments:
+    ///
+    /// * `clear_ram`: If RAM gets cleared
+    fn reset_components(&mut self, clear_ram: bool) {
+        self.cpu.reset();
+        self.bus.reset(clear_ram);
+        self.dma = Dma::new();
+        self.gpu.reset();
+        self.overrun_cycles = 0;
+        self.exe_pending = self.exe.is_some();
+    }
+
+    /// Presses or releases a button of the digital pad in slot 1
+    ///
+    /// # Arguments:
+    ///
+    /// * `button`: The button
+    /// * `pressed`: If the button is held down
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        self.bus.set_button(button, pressed);
+    }
+
+    /// Replaces the sink receiving the BIOS TTY output line by line
+    ///
+    /// By default each line is logged with the `tty` target
+    ///
+    /// # Arguments:
+    ///
+    /// * `sink`: The function receiving each line
+    pub fn set_tty_sink<F: FnMut(&str) + 'static>(&mut self, sink: F) {
+        self.cpu.set_tty_sink(Box::new(sink));
+    }
+
+    /// Takes the BIOS TTY output printed since the last call
+    ///
+    /// Only the output printed after the BIOS installed its TTY device is kept. The most recent
+    /// 64KB are kept until they get taken, independent of the sink.
+    pub fn take_tty_output(&mut self) -> String {
+        self.cpu.take_tty_output()
+    }
+
+    /// Watches a range of memory, reporting each CPU load or store overlapping it to the
+    /// watchpoint callback
+    ///
+    /// The address matches through every mirror of the memory segments and of RAM
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The start address
+    /// * `length`: The length in bytes
+    /// * `on_read`: If loads get reported
+    /// * `on_write`: If stores get reported
+    pub fn add_watchpoint(&mut self, address: u32, length: u32, on_read: bool, on_write: bool) {
+        let address = self.bus.mirror_address(address);
+        self.cpu.add_watchpoint(address, length, on_read, on_write);
+    }
+
+    /// Removes every watchpoint
+    pub fn clear_watchpoints(&mut self) {
+        self.cpu.clear_watchpoints();
+    }
+
+    /// Replaces the callback receiving the accesses hitting a watchpoint
+    ///
+    /// # Arguments:
+    ///
+    /// * `callback`: The function receiving the accessed address, the loaded or stored value and
+    ///   the kind of the access
+    pub fn set_watchpoint_callback<F: FnMut(u32, u32, WatchKind) + 'static>(
+        &mut self,
+        callback: F,
+    ) {
+        self.cpu.set_watchpoint_callback(Box::new(callback));
+    }
+
+    /// Returns the currently presented display area
+    ///
+    /// Returns the width, the height and the RGBA pixels
+    pub fn capture_frame(&self) -> (u32, u32, Vec<u8>) {
+        self.gpu.capture_frame()
+    }
+
+    /// Writes the currently presented display area into a PNG file
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path of the PNG file
+    ///
+    /// # Errors
+    ///
+    /// This function will throw an error if the file failed to be created or written
+    pub fn save_screenshot<P: AsRef<Path>>(&self, path: P) -> Result<(), ScreenshotError> {
+        let (width, height, pixels) = self.capture_frame();
+        Self::write_png(path, width, height, &pixels)
+    }
+
+    /// Returns a copy of the 1024x512 VRAM in BGR555 with the mask bit in bit 15
+    pub fn dump_vram(&self) -> Vec<u16> {
+        self.gpu.dump_vram()
+    }
+
+    /// Replaces the VRAM, for example with an earlier dump
+    ///
+    /// # Arguments:
+    ///
+    /// * `data`: The 1024x512 VRAM in BGR555 with the mask bit in bit 15
+    pub fn load_vram(&mut self, data: &[u16]) {
+        self.gpu.load_vram(data);
+    }
+
+    /// Writes the whole VRAM into a 1024x512 PNG file
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path of the PNG file
+    ///
+    /// # Errors
+    ///
+    /// This function will throw an error if the file failed to be created or written
+    pub fn save_vram_dump<P: AsRef<Path>>(&self, path: P) -> Result<(), ScreenshotError> {
+        let pixels = self
+            .dump_vram()
+            .iter()
+            .flat_map(|&pixel| {
+                let r = ((pixel & 0x1f) << 3) as u8;
+                let g = (((pixel >> 5) & 0x1f) << 3) as u8;
+                let b = (((pixel >> 10) & 0x1f) << 3) as u8;
+                [r, g, b, 0xff]
+            })
+            .collect::<Vec<_>>();
+
+        Self::write_png(path, 1024, 512, &pixels)
+    }
+
+    /// Encodes RGBA pixels into a PNG file
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path of the PNG file
+    /// * `width`: The width of the image
+    /// * `height`: The height of the image
+    /// * `pixels`: The RGBA pixels
+    fn write_png<P: AsRef<Path>>(
+        path: P,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> Result<(), ScreenshotError> {
+        let file = File::create(path)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(pixels)?;
+
+        Ok(())
+    }
+
+    /// Changes how the display area is fitted into the window
+    ///
+    /// # Arguments:
+    ///
+    /// * `scaling`: The scaling mode
+    pub fn set_scaling(&mut self, scaling: Scaling) {
+        self.gpu.set_scaling(scaling);
+    }
+
+    /// Replaces the sink receiving the audio samples, which discards them by default
+    ///
+    /// Unless muted, `run` plays through the default output device instead
+    ///
+    /// # Arguments:
+    ///
+    /// * `sink`: The new sink
+    pub fn set_audio_sink<S: AudioSink + 'static>(&mut self, sink: S) {
+        self.bus.set_audio_sink(Box::new(sink));
+    }
+
+    /// Changes the factor primitives are rasterized at relative to the native resolution
+    ///
+    /// Scales above 1 give sharper polygon edges, while VRAM and textures stay at the native
+    /// resolution. The scale is clamped between 1 and 8.
+    ///
+    /// # Arguments:
+    ///
+    /// * `scale`: The internal scale
+    pub fn set_internal_scale(&mut self, scale: u32) {
+        self.gpu.set_internal_scale(scale);
+    }
+
+    /// Disables or enables the audio output of the next `run`
+    ///
+    /// # Arguments:
+    ///
+    /// * `muted`: If no audio should be played
+    #[cfg(feature = "window")]
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    /// Logs each call through the BIOS call gates with its function name and arguments at debug
+    /// level
+    ///
+    /// # Arguments:
+    ///
+    /// * `enabled`: If the BIOS calls should be logged
+    pub fn set_bios_call_logging(&mut self, enabled: bool) {
+        self.cpu.set_bios_call_logging(enabled);
+    }
+
+    /// Skips the BIOS shell, so the disc boots without the intro
+    ///
+    /// The shell is called like a function once the kernel is initialized, so returning from its
+    /// entry leaves the BIOS as if the shell had finished, without patching the BIOS
+    ///
+    /// # Arguments:
+    ///
+    /// * `fast_boot`: If the BIOS shell should be skipped
+    pub fn set_fast_boot(&mut self, fast_boot: bool) {
+        self.fast_boot = fast_boot;
+    }
+
+    /// Disables the frame limiter, so frames are emulated as fast as possible
+    ///
+    /// # Arguments:
+    ///
+    /// * `uncapped`: If the frame rate should be uncapped
+    #[cfg(feature = "window")]
+    pub fn set_uncapped(&mut self, uncapped: bool) {
+        self.uncapped = uncapped;
+    }
+
+    /// Returns the general purpose registers of the CPU
+    pub fn cpu_registers(&self) -> [u32; 32] {
+        self.cpu.registers()
+    }
+
+    /// Returns the program counter of the CPU
+    pub fn pc(&self) -> u32 {
+        self.cpu.pc()
+    }
+
+    /// Returns a cop0 register, unknown registers read as 0
+    ///
+    /// # Arguments:
+    ///
+    /// * `index`: The index of the cop0 register
+    pub fn cop0_register(&self, index: u8) -> u32 {
+        self.cpu
+            .cop0_registers()
+            .get(index as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Summarizes the setup of a DMA channel, none if no channel has the id
+    ///
+    /// # Arguments:
+    ///
+    /// * `id`: The id of the channel, 0 (MDECin) to 6 (OTC)
+    pub fn describe_dma_channel(&self, id: u8) -> Option<String> {
+        self.dma.channel(id).map(|channel| channel.describe())
+    }
+
+    /// Returns the last boot status the BIOS wrote to the POST register in Expansion Region 2
+    pub fn post_code(&self) -> u8 {
+        self.bus.post()
+    }
+
+    /// Reads a block of memory without any side effects
+    ///
+    /// Only RAM, the scratchpad and the BIOS are readable, any other address reads as 0
+    ///
+    /// # Arguments:
+    ///
+    /// * `address`: The absolute start address
+    /// * `length`: The amount of bytes to read
+    pub fn read_memory(&self, address: u32, length: usize) -> Vec<u8> {
+        (0..length)
+            .map(|index| {
+                let address = address.wrapping_add(index as u32);
+                self.bus.peek_u8(address).unwrap_or(0)
+            })
+            .collect()
+    }
+
+    /// Writes each executed instruction with the registers it changed into a trace file
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path to the trace file, which gets overwritten
+    ///
+    /// # Errors
+    ///
+    /// This function will throw an error if the trace file failed to be created
+    pub fn set_trace_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), io::Error> {
+        self.tracer = Some(Tracer::new(path)?);
+        Ok(())
+    }
+
+    /// Pauses or resumes the emulation in `run`
+    ///
+    /// # Arguments:
+    ///
+    /// * `paused`: If the emulation should be paused
+    #[cfg(feature = "window")]
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Executes exactly one instruction and logs it
+    pub fn step_once(&mut self) {
+        let pc = self.cpu.pc();
+        if let Some(word) = self.bus.peek_u32(pc) {
+            log::info!(target: "cpu", "{:#010x}: {}", pc, disassemble(word, pc));
+        }
+
+        self.step();
+    }
+
     /// Runs the PSX Emulator
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the PSX Emulator was created headless
+    #[cfg(feature = "window")]
     pub fn run(&mut self) {
-        let cpu_cycles_per_second = 33868800.0; // CPU Cyles per Second
-        let frames_per_second = 60.0_f32; // Around 59.940 for NTSC;
-        let cycles_per_frame = (cpu_cycles_per_second / frames_per_second).round() as u32;
+        let delta_time = 1.0 / self.region.frames_per_second();
+
+        let mut window = self
+            .window
+            .take()
+            .expect("headless emulators have to use run_headless");
 
-        let delta_time = 1.0 / frames_per_second;
+        // The audio output is only open while running with a window
+        let previous_sink = if self.muted {
+            None
+        } else {
+            Audio::new()
+                .map_err(|error| log::error!("Failed to open audio output: {}", error))
+                .ok()
+                .map(|audio| self.bus.set_audio_sink(Box::new(audio)))
+        };
 
         let mut last_time = Instant::now();
         let mut accumulator = 0.0;
-        while !self.window.should_close() {
-            self.window.poll_events();
-            self.window.handle_events(|event| {
-                if let WindowEvent::Size(width, height) = *event {
-                    if width == 0 || height == 0 {
+        while !window.should_close() {
+            window.poll_events();
+            window.handle_events(|event| match *event {
+                WindowEvent::Size(width, height) => {
+                    if width <= 0 || height <= 0 {
                         return;
                     }
 
@@ -121,9 +765,62 @@ impl Psx {
                     };
 
                     self.gpu.resize(size);
-                };
+                }
+                WindowEvent::Key(Key::F12, _, Action::Press, _) => {
+                    let timestamp = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_millis();
+                    let path = format!("screenshot-{}.png", timestamp);
+
+                    match self.save_screenshot(&path) {
+                        Ok(()) => log::info!("Saved screenshot to '{}'", path),
+                        Err(error) => log::error!("Failed to save screenshot: {}", error),
+                    }
+                }
+                WindowEvent::Key(Key::F11, _, Action::Press, _) => {
+                    let timestamp = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_millis();
+                    let path = format!("vram-{}.png", timestamp);
+
+                    match self.save_vram_dump(&path) {
+                        Ok(()) => log::info!("Saved VRAM dump to '{}'", path),
+                        Err(error) => log::error!("Failed to save VRAM dump: {}", error),
+                    }
+                }
+                WindowEvent::Key(Key::F9, _, Action::Press, _) => {
+                    self.paused = !self.paused;
+                    log::info!(
+                        "Emulation {}",
+                        if self.paused { "paused" } else { "resumed" }
+                    );
+                }
+                WindowEvent::Key(Key::F10, _, Action::Press | Action::Repeat, _) if self.paused => {
+                    self.step_once();
+                }
+                WindowEvent::Key(key, _, action, _) => {
+                    if let Some(button) = Window::button(key) {
+                        let pressed = action != Action::Release;
+                        self.bus.set_button(button, pressed);
+                    }
+                }
+                _ => {}
             });
 
+            if self.paused {
+                thread::sleep(Duration::from_secs_f32(delta_time));
+                last_time = Instant::now();
+                accumulator = 0.0;
+                continue;
+            }
+
+            if self.uncapped {
+                self.emulate_frame();
+                continue;
+            }
+
             let current_time = Instant::now();
             let mut elapsed_time = (current_time - last_time).as_secs_f32();
             if elapsed_time > 0.25 {
@@ -134,26 +831,283 @@ impl Psx {
             accumulator += elapsed_time;
 
             while accumulator >= delta_time {
-                self.emulate_frame(cycles_per_frame);
+                self.emulate_frame();
 
                 accumulator -= delta_time;
             }
+
+            // Sleep until the next frame is due instead of spinning
+            let remaining_time = delta_time - accumulator;
+            thread::sleep(Duration::from_secs_f32(remaining_time));
+        }
+
+        self.window = Some(window);
+        if let Some(previous_sink) = previous_sink {
+            self.bus.set_audio_sink(previous_sink);
         }
     }
 
-    /// Emulates a frame
+    /// Runs the PSX Emulator for a fixed amount of frames without presenting them
+    ///
+    /// # Arguments:
     ///
-    /// Arguments:
+    /// * `frames`: The amount of frames to emulate
+    /// * `on_frame`: The callback receiving the RGBA framebuffer at the internal scale after each
+    ///   frame
+    pub fn run_headless<F: FnMut(&[u8])>(&mut self, frames: u32, mut on_frame: F) {
+        for _ in 0..frames {
+            self.emulate_frame();
+            on_frame(self.gpu.frame());
+        }
+    }
+
+    /// Runs the PSX Emulator for an exact amount of CPU cycles without presenting frames
+    ///
+    /// Instructions can't be split, so the cycles an instruction runs past the budget are taken
+    /// from the budget of the next call
+    ///
+    /// # Arguments:
+    ///
+    /// * `cycles`: The amount of cycles to emulate
+    pub fn run_cycles(&mut self, cycles: u64) {
+        let overrun = self.overrun_cycles.min(cycles);
+        self.overrun_cycles -= overrun;
+
+        let mut remaining_cycles = cycles - overrun;
+        while remaining_cycles > 0 {
+            let (step_cycles, _) = self.step();
+
+            let step_cycles = step_cycles as u64;
+            self.overrun_cycles += step_cycles.saturating_sub(remaining_cycles);
+            remaining_cycles = remaining_cycles.saturating_sub(step_cycles);
+        }
+
+        self.bus.flush_audio();
+    }
+
+    /// Runs the BIOS headlessly until it jumps into the shell
+    ///
+    /// Returns whether the shell entry was reached within the given amount of frames, which makes
+    /// it usable as a smoke check for the whole boot path.
+    ///
+    /// # Arguments:
+    ///
+    /// * `max_frames`: The upper bound of frames to emulate
+    pub fn boot_to_shell(&mut self, max_frames: u32) -> bool {
+        for _ in 0..max_frames {
+            if self.emulate_frame() {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Emulates a single instruction
     ///
-    /// * `cycles_per_frame`: The amount of cycles this frame needs to do
-    fn emulate_frame(&mut self, cycles_per_frame: u32) {
-        for _ in 0..cycles_per_frame / 2 {
-            self.cpu.step(&mut self.dma, &mut self.gpu);
+    /// Returns the cycles the instruction took and whether the BIOS shell entry was reached
+    fn step(&mut self) -> (u32, bool) {
+        let trace_start = self
+            .tracer
+            .is_some()
+            .then(|| (self.cpu.pc(), self.cpu.registers()));
+
+        let cycles = self.cpu.step(&mut self.bus, &mut self.dma, &mut self.gpu);
+
+        if let Some((pc, registers)) = trace_start {
+            self.trace(pc, &registers);
+        }
+
+        // Transfers started by the instruction complete before the next one runs
+        self.bus.step_dma(&mut self.dma, &mut self.gpu);
+
+        self.bus.step(cycles);
+        if self.gpu.tick(cycles) {
+            self.bus.interrupt_controller().request(Interrupt::Gpu);
+        }
+
+        if self.cpu.pc() != Self::SHELL_ENTRY {
+            return (cycles, false);
         }
 
-        self.dma.step(self.cpu.bus().ram(), &mut self.gpu);
+        if let Some(exe) = self.exe.as_ref().filter(|_| self.exe_pending) {
+            self.cpu.sideload(exe, &mut self.bus);
+            self.exe_pending = false;
+        } else if self.fast_boot {
+            log::info!("Skipping the BIOS shell");
+            self.cpu.return_to_caller();
+        }
+
+        (cycles, true)
+    }
+
+    /// Writes the trace line of the instruction which was just executed
+    ///
+    /// # Arguments:
+    ///
+    /// * `pc`: The address of the instruction
+    /// * `registers`: The registers before the instruction
+    fn trace(&mut self, pc: u32, registers: &[u32; 32]) {
+        let Some(tracer) = &mut self.tracer else {
+            return;
+        };
+
+        let word = self.bus.peek_u32(pc).unwrap_or(0);
+        if let Err(error) = tracer.trace(pc, word, registers, &self.cpu.registers()) {
+            log::error!(target: "cpu", "Failed to write trace, disabling tracing: {}", error);
+            self.tracer = None;
+        }
+    }
+
+    /// Emulates a frame
+    ///
+    /// Returns whether the BIOS shell entry was reached during the frame
+    fn emulate_frame(&mut self) -> bool {
+        let mut reached_shell = false;
+        let cycles_per_frame =
+            (Self::CPU_CYCLES_PER_SECOND / self.region.frames_per_second()) as u32;
+
+        let mut cycles = 0;
+        while cycles < cycles_per_frame {
+            let (step_cycles, shell) = self.step();
+            reached_shell |= shell;
+            cycles += step_cycles;
+        }
+
+        self.bus.flush_audio();
 
         self.gpu.step();
         // TODO: Emulate GPU frames with VBLANK
+
+        if let Some(on_frame) = &mut self.on_frame {
+            let (width, height, pixels) = self.gpu.capture_frame();
+            on_frame.call(width, height, &pixels);
+        }
+
+        reached_shell
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_button_reaches_the_pad() {
+        let mut psx = Psx::from_bios_bytes(vec![0; 512 * 1024]).unwrap();
+        psx.set_button(Button::Circle, true);
+        psx.set_button(Button::Up, true);
+
+        let Psx { bus, dma, gpu, .. } = &mut psx;
+
+        // Select the pad in slot 1 through JOY_CTRL
+        bus.write_u16(0x1f80104a, 0x0002, dma, gpu).unwrap();
+
+        let mut response = Vec::new();
+        for value in [0x01, 0x42, 0x00, 0x00, 0x00] {
+            bus.write_u8(0x1f801040, value, dma, gpu).unwrap();
+            response.push(bus.read_u8(0x1f801040, dma, gpu).unwrap());
+        }
+
+        assert_eq!(response, [0xff, 0x41, 0x5a, 0xef, 0xdf]);
+    }
+
+    #[test]
+    fn captured_frame_shows_the_drawn_triangle() {
+        let mut psx = Psx::from_bios_bytes(vec![0; 512 * 1024]).unwrap();
+
+        // Drawing area over the whole display, then a red shaded triangle
+        psx.gpu.gp1(0x03000000);
+        for command in [
+            0xe3000000, 0xe403c3ff, 0x300000ff, 0x00000000, 0x000000ff, 0x00000040, 0x000000ff,
+            0x00400000,
+        ] {
+            psx.gpu.gp0(command);
+        }
+
+        let (width, height, pixels) = psx.capture_frame();
+        assert_eq!((width, height), (256, 240));
+        assert_eq!(pixels.len(), 256 * 240 * 4);
+
+        let pixel = |x: usize, y: usize| &pixels[(y * 256 + x) * 4..(y * 256 + x) * 4 + 4];
+        assert_eq!(pixel(4, 4), [0xff, 0x00, 0x00, 0xff]);
+        assert_eq!(pixel(100, 100), [0x00, 0x00, 0x00, 0xff]);
+    }
+
+    /// Creates a PSX Emulator whose BIOS enables the DMA6 interrupt, starts an ordering table
+    /// clear and spins
+    fn dma_psx() -> Psx {
+        let program: [u32; 11] = [
+            0x3c081f80, // lui $t0, 0x1f80
+            0x3c0900c0, // lui $t1, 0x00c0
+            0xad0910f4, // sw $t1, 0x10f4($t0) - DICR, DMA6 and master enable
+            0x3409100c, // ori $t1, $zero, 0x100c
+            0xad0910e0, // sw $t1, 0x10e0($t0) - D6_MADR
+            0x34090004, // ori $t1, $zero, 4
+            0xad0910e4, // sw $t1, 0x10e4($t0) - D6_BCR
+            0x3c091100, // lui $t1, 0x1100
+            0xad0910e8, // sw $t1, 0x10e8($t0) - D6_CHCR, start and trigger
+            0x0bf00009, // j 0xbfc00024
+            0x00000000, // nop
+        ];
+
+        let mut bios = program
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect::<Vec<_>>();
+        bios.resize(512 * 1024, 0);
+
+        Psx::from_bios_bytes(bios).unwrap()
+    }
+
+    /// Returns DICR, D6_CHCR and I_STAT
+    fn dma_state(psx: &mut Psx) -> (u32, u32, u32) {
+        let Psx { bus, dma, gpu, .. } = psx;
+        (
+            bus.read_u32(0x1f8010f4, dma, gpu).unwrap(),
+            bus.read_u32(0x1f8010e8, dma, gpu).unwrap(),
+            bus.read_u32(0x1f801070, dma, gpu).unwrap(),
+        )
+    }
+
+    #[test]
+    fn dma_completes_right_after_the_starting_instruction() {
+        let mut psx = dma_psx();
+        for _ in 0..8 {
+            psx.step_once();
+        }
+        assert_eq!(dma_state(&mut psx).2 & (1 << 3), 0);
+
+        psx.step_once();
+        let (dicr, chcr, i_stat) = dma_state(&mut psx);
+        assert_eq!(dicr & 0xc0000000, 0xc0000000);
+        assert_eq!(chcr & (1 << 24), 0);
+        assert_eq!(i_stat & (1 << 3), 1 << 3);
+    }
+
+    #[test]
+    fn step_once_run_cycles_and_frames_agree_on_dma() {
+        let mut frame_psx = dma_psx();
+        frame_psx.run_headless(1, |_| {});
+
+        let cycles_per_frame =
+            (Psx::CPU_CYCLES_PER_SECOND / frame_psx.region.frames_per_second()) as u32;
+        // The program has no stalling instructions
+        let instructions = cycles_per_frame.div_ceil(Cpu::CYCLES_PER_INSTRUCTION);
+
+        let mut step_psx = dma_psx();
+        for _ in 0..instructions {
+            step_psx.step_once();
+        }
+
+        let mut cycles_psx = dma_psx();
+        cycles_psx.run_cycles((instructions * Cpu::CYCLES_PER_INSTRUCTION) as u64);
+
+        let expected = dma_state(&mut frame_psx);
+        assert_eq!(expected.0 & 0xc0000000, 0xc0000000);
+        assert_eq!(dma_state(&mut step_psx), expected);
+        assert_eq!(dma_state(&mut cycles_psx), expected);
+        assert_eq!(step_psx.cpu.pc(), frame_psx.cpu.pc());
+        assert_eq!(cycles_psx.cpu.pc(), frame_psx.cpu.pc());
     }
 }
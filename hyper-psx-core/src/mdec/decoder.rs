@@ -0,0 +1,194 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+/// The zig-zag order of the run-length encoded coefficients
+const ZIGZAG: [usize; 64] = [
+    0, 1, 5, 6, 14, 15, 27, 28, //
+    2, 4, 7, 13, 16, 26, 29, 42, //
+    3, 8, 12, 17, 25, 30, 41, 43, //
+    9, 11, 18, 24, 31, 40, 44, 53, //
+    10, 19, 23, 32, 39, 45, 52, 54, //
+    20, 22, 33, 38, 46, 51, 55, 60, //
+    21, 34, 37, 47, 50, 56, 59, 61, //
+    35, 36, 48, 49, 57, 58, 62, 63, //
+];
+
+/// The padding halfword between blocks, also used as end of block marker
+const END_OF_BLOCK: u16 = 0xfe00;
+
+/// Sign extends the 10-bit coefficient of a run-length encoded halfword
+///
+/// # Arguments:
+///
+/// * `value`: The run-length encoded halfword
+fn signed_10bit(value: u16) -> i32 {
+    (((value & 0x3ff) as i32) << 22) >> 22
+}
+
+/// The compressed halfwords of a macroblock stream
+pub(super) struct Source<'a> {
+    /// The halfwords
+    data: &'a [u16],
+
+    /// The index of the next halfword
+    position: usize,
+}
+
+impl<'a> Source<'a> {
+    /// Creates a source over a macroblock stream
+    ///
+    /// # Arguments:
+    ///
+    /// * `data`: The halfwords of the stream
+    pub(super) fn new(data: &'a [u16]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    /// Tells if there are halfwords left
+    pub(super) fn has_remaining(&self) -> bool {
+        self.data[self.position..]
+            .iter()
+            .any(|&halfword| halfword != END_OF_BLOCK)
+    }
+
+    /// Returns the next halfword, a missing one ends the block
+    fn next(&mut self) -> u16 {
+        let Some(&halfword) = self.data.get(self.position) else {
+            return END_OF_BLOCK;
+        };
+
+        self.position += 1;
+        halfword
+    }
+}
+
+/// Decodes a run-length encoded block, dequantizes it and runs the inverse DCT on it
+///
+/// <https://psx-spx.consoledev.net/macroblockdecodermdec/#mdec-decompression>
+///
+/// # Arguments:
+///
+/// * `source`: The compressed macroblock stream
+/// * `quant_table`: The quant table of the block
+/// * `scale_table`: The IDCT scale table
+pub(super) fn decode_block(
+    source: &mut Source,
+    quant_table: &[u8; 64],
+    scale_table: &[i16; 64],
+) -> [i16; 64] {
+    let mut block = [0i16; 64];
+
+    let mut halfword = source.next();
+    while halfword == END_OF_BLOCK && source.position < source.data.len() {
+        halfword = source.next();
+    }
+
+    let mut index = 0;
+    let quant_scale = ((halfword >> 10) & 0x3f) as i32;
+    let mut value = signed_10bit(halfword) * quant_table[index] as i32;
+    loop {
+        if quant_scale == 0 {
+            value = signed_10bit(halfword) * 2;
+        }
+
+        let value_clamped = value.clamp(-0x400, 0x3ff) as i16;
+        if quant_scale > 0 {
+            block[ZIGZAG[index]] = value_clamped;
+        } else {
+            block[index] = value_clamped;
+        }
+
+        halfword = source.next();
+        index += ((halfword >> 10) & 0x3f) as usize + 1;
+        if index > 63 {
+            break;
+        }
+
+        value = (signed_10bit(halfword) * quant_table[index] as i32 * quant_scale + 4) / 8;
+    }
+
+    idct(&mut block, scale_table);
+
+    block
+}
+
+/// Runs the two pass inverse discrete cosine transform on a block
+///
+/// # Arguments:
+///
+/// * `block`: The dequantized coefficients, replaced by the samples
+/// * `scale_table`: The IDCT scale table
+fn idct(block: &mut [i16; 64], scale_table: &[i16; 64]) {
+    let mut source = block.map(|value| value as i32);
+    let mut destination = [0i32; 64];
+
+    for _ in 0..2 {
+        for x in 0..8 {
+            for y in 0..8 {
+                let mut sum = 0i64;
+                for z in 0..8 {
+                    let scale = (scale_table[x + z * 8] >> 3) as i64;
+                    sum += source[y + z * 8] as i64 * scale;
+                }
+
+                destination[x + y * 8] = ((sum + 0xfff) >> 13) as i32;
+            }
+        }
+
+        source = destination;
+    }
+
+    *block = source.map(|value| value.clamp(i16::MIN as i32, i16::MAX as i32) as i16);
+}
+
+/// Converts a luminance block and the shared chrominance blocks into a quadrant of the
+/// 16x16 macroblock
+///
+/// # Arguments:
+///
+/// * `macroblock`: The RGB pixels of the macroblock
+/// * `y_block`: The luminance block
+/// * `cr_block`: The red chrominance block
+/// * `cb_block`: The blue chrominance block
+/// * `quadrant_x`: The x offset of the quadrant
+/// * `quadrant_y`: The y offset of the quadrant
+pub(super) fn yuv_to_rgb(
+    macroblock: &mut [[i8; 3]; 256],
+    y_block: &[i16; 64],
+    cr_block: &[i16; 64],
+    cb_block: &[i16; 64],
+    quadrant_x: usize,
+    quadrant_y: usize,
+) {
+    for y in 0..8 {
+        for x in 0..8 {
+            let chroma = (x + quadrant_x) / 2 + ((y + quadrant_y) / 2) * 8;
+            let cr = cr_block[chroma] as i32;
+            let cb = cb_block[chroma] as i32;
+
+            // 1.402, -0.3437, -0.7143 and 1.772 in 8-bit fixed point
+            let r = (359 * cr) >> 8;
+            let g = (-88 * cb - 183 * cr) >> 8;
+            let b = (454 * cb) >> 8;
+
+            let luminance = y_block[x + y * 8] as i32;
+            macroblock[(x + quadrant_x) + (y + quadrant_y) * 16] = [
+                (luminance + r).clamp(-128, 127) as i8,
+                (luminance + g).clamp(-128, 127) as i8,
+                (luminance + b).clamp(-128, 127) as i8,
+            ];
+        }
+    }
+}
+
+/// Converts a luminance block into monochrome pixels
+///
+/// # Arguments:
+///
+/// * `y_block`: The luminance block
+pub(super) fn y_to_mono(y_block: &[i16; 64]) -> [i8; 64] {
+    y_block.map(|luminance| signed_10bit(luminance as u16).clamp(-128, 127) as i8)
+}
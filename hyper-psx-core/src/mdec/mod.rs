@@ -0,0 +1,691 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+mod decoder;
+
+use crate::mdec::decoder::Source;
+
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+use std::{collections::VecDeque, mem};
+
+/// The depth of the decoded pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum OutputDepth {
+    /// 4 bits per pixel, monochrome
+    #[default]
+    Bit4 = 0,
+
+    /// 8 bits per pixel, monochrome
+    Bit8 = 1,
+
+    /// 24 bits per pixel
+    Bit24 = 2,
+
+    /// 15 bits per pixel
+    Bit15 = 3,
+}
+
+/// The command whose parameters are being received
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum Command {
+    /// No command is active
+    #[default]
+    None,
+
+    /// MDEC(1) - Decode Macroblock(s)
+    DecodeMacroblock,
+
+    /// MDEC(2) - Set Quant Table(s)
+    SetQuantTable,
+
+    /// MDEC(3) - Set Scale Table
+    SetScaleTable,
+}
+
+/// The Macroblock Decoder
+///
+/// <https://psx-spx.consoledev.net/macroblockdecodermdec/>
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Mdec {
+    /// The command whose parameters are being received
+    command: Command,
+
+    /// The parameter words remaining for the current command
+    remaining_words: u32,
+
+    /// The index of the next parameter word of the current command
+    parameter_index: usize,
+
+    /// If the quant table command also contains the color table
+    color_quant: bool,
+
+    /// The depth of the decoded pixels
+    output_depth: OutputDepth,
+
+    /// If the decoded pixels are signed
+    output_signed: bool,
+
+    /// If bit 15 of 15-bit pixels gets set
+    output_bit15: bool,
+
+    /// The luminance quant table
+    #[serde(with = "BigArray")]
+    luminance_quant_table: [u8; 64],
+
+    /// The color quant table
+    #[serde(with = "BigArray")]
+    color_quant_table: [u8; 64],
+
+    /// The IDCT scale table
+    #[serde(with = "BigArray")]
+    scale_table: [i16; 64],
+
+    /// The halfwords of the compressed macroblock stream
+    data_in: Vec<u16>,
+
+    /// The decoded words waiting to be read
+    data_out: VecDeque<u32>,
+
+    /// If DMA channel 0 is allowed to feed data in
+    data_in_request: bool,
+
+    /// If DMA channel 1 is allowed to read data out
+    data_out_request: bool,
+
+    /// The block currently being decoded (0-3 = Y1-Y4, 4 = Cr, 5 = Cb)
+    current_block: u8,
+
+    /// The lower bytes of a command word written bytewise
+    command_bytes: [u8; 3],
+
+    /// The lower bytes of a control word written bytewise
+    control_bytes: [u8; 3],
+
+    /// The data word being read bytewise
+    data_out_latch: u32,
+}
+
+impl Mdec {
+    /// Creates a MDEC component
+    pub(crate) fn new() -> Self {
+        Self {
+            command: Command::None,
+            remaining_words: 0,
+            parameter_index: 0,
+            color_quant: false,
+            output_depth: OutputDepth::default(),
+            output_signed: false,
+            output_bit15: false,
+            luminance_quant_table: [0; 64],
+            color_quant_table: [0; 64],
+            scale_table: [0; 64],
+            data_in: Vec::new(),
+            data_out: VecDeque::new(),
+            data_in_request: false,
+            data_out_request: false,
+            current_block: 4,
+            command_bytes: [0; 3],
+            control_bytes: [0; 3],
+            data_out_latch: 0,
+        }
+    }
+
+    /// Resets the MDEC, aborting the current command
+    fn reset(&mut self) {
+        self.command = Command::None;
+        self.remaining_words = 0;
+        self.parameter_index = 0;
+        self.output_depth = OutputDepth::default();
+        self.output_signed = false;
+        self.output_bit15 = false;
+        self.data_in.clear();
+        self.data_out.clear();
+        self.data_in_request = false;
+        self.data_out_request = false;
+        self.current_block = 4;
+    }
+
+    /// Writes a word to the MDEC registers
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative register offset
+    /// * `value`: The value to be written
+    pub(crate) fn write_u32(&mut self, offset: u32, value: u32) {
+        match offset {
+            0x00 => self.write_command(value),
+            0x04 => self.write_control(value),
+            _ => unreachable!(
+                "write to mdec at {:#04x} with value {:#010x}",
+                offset, value
+            ),
+        }
+    }
+
+    /// Reads a word from the MDEC registers
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative register offset
+    pub(crate) fn read_u32(&mut self, offset: u32) -> u32 {
+        match offset {
+            0x00 => self.data_out.pop_front().unwrap_or(0),
+            0x04 => self.status(),
+            _ => unreachable!("read from mdec at {:#04x}", offset),
+        }
+    }
+
+    /// Writes a byte to the MDEC registers
+    ///
+    /// The registers only take whole words, so the lower bytes are latched until the
+    /// highest one arrives
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative register offset
+    /// * `value`: The value to be written
+    pub(crate) fn write_u8(&mut self, offset: u32, value: u8) {
+        match offset {
+            0x00..=0x02 => self.command_bytes[offset as usize] = value,
+            0x03 => {
+                let [byte_0, byte_1, byte_2] = self.command_bytes;
+                self.write_command(u32::from_le_bytes([byte_0, byte_1, byte_2, value]));
+            }
+            0x04..=0x06 => self.control_bytes[offset as usize - 4] = value,
+            0x07 => {
+                let [byte_0, byte_1, byte_2] = self.control_bytes;
+                self.write_control(u32::from_le_bytes([byte_0, byte_1, byte_2, value]));
+            }
+            _ => unreachable!("write to mdec at {:#04x} with value {:#04x}", offset, value),
+        }
+    }
+
+    /// Reads a byte from the MDEC registers
+    ///
+    /// Reading the lowest byte of the data register pops the next word, the other bytes
+    /// come from that word
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative register offset
+    pub(crate) fn read_u8(&mut self, offset: u32) -> u8 {
+        match offset {
+            0x00 => {
+                self.data_out_latch = self.data_out.pop_front().unwrap_or(0);
+                self.data_out_latch as u8
+            }
+            0x01..=0x03 => (self.data_out_latch >> (offset * 8)) as u8,
+            0x04..=0x07 => (self.status() >> ((offset - 4) * 8)) as u8,
+            _ => unreachable!("read from mdec at {:#04x}", offset),
+        }
+    }
+
+    /// Receives a word from DMA channel 0
+    ///
+    /// # Arguments:
+    ///
+    /// * `value`: The command or parameter word
+    pub(crate) fn dma_write_word(&mut self, value: u32) {
+        self.write_command(value);
+    }
+
+    /// Hands a decoded word to DMA channel 1
+    pub(crate) fn dma_read_word(&mut self) -> u32 {
+        self.data_out.pop_front().unwrap_or(0)
+    }
+
+    /// MDEC1 - Control/Reset Register
+    ///
+    /// # Arguments:
+    ///
+    /// * `value`: The value to be written
+    fn write_control(&mut self, value: u32) {
+        if value & (1 << 31) != 0 {
+            self.reset();
+        }
+
+        self.data_in_request = value & (1 << 30) != 0;
+        self.data_out_request = value & (1 << 29) != 0;
+    }
+
+    /// MDEC0 - Command/Parameter Register
+    ///
+    /// # Arguments:
+    ///
+    /// * `value`: The value to be written
+    fn write_command(&mut self, value: u32) {
+        if self.remaining_words == 0 {
+            self.start_command(value);
+        } else {
+            self.write_parameter(value);
+        }
+
+        if self.remaining_words == 0 && self.command != Command::None {
+            self.finish_command();
+        }
+    }
+
+    /// Decodes a command word and prepares for its parameters
+    ///
+    /// # Arguments:
+    ///
+    /// * `value`: The command word
+    fn start_command(&mut self, value: u32) {
+        self.output_depth = match (value >> 27) & 0x3 {
+            0 => OutputDepth::Bit4,
+            1 => OutputDepth::Bit8,
+            2 => OutputDepth::Bit24,
+            3 => OutputDepth::Bit15,
+            _ => unreachable!(),
+        };
+        self.output_signed = value & (1 << 26) != 0;
+        self.output_bit15 = value & (1 << 25) != 0;
+        self.parameter_index = 0;
+
+        let opcode = value >> 29;
+        (self.command, self.remaining_words) = match opcode {
+            1 => {
+                self.data_in.clear();
+                (Command::DecodeMacroblock, value & 0xffff)
+            }
+            2 => {
+                self.color_quant = value & 0x1 != 0;
+                let words = if self.color_quant { 32 } else { 16 };
+                (Command::SetQuantTable, words)
+            }
+            3 => (Command::SetScaleTable, 32),
+            _ => {
+                log::warn!(target: "mdec", "Unhandled command {:#010x}", value);
+                (Command::None, 0)
+            }
+        };
+    }
+
+    /// Stores a parameter word of the current command
+    ///
+    /// # Arguments:
+    ///
+    /// * `value`: The parameter word
+    fn write_parameter(&mut self, value: u32) {
+        let index = self.parameter_index;
+        match self.command {
+            Command::DecodeMacroblock => {
+                self.data_in.push(value as u16);
+                self.data_in.push((value >> 16) as u16);
+            }
+            Command::SetQuantTable => {
+                for (byte_index, byte) in value.to_le_bytes().into_iter().enumerate() {
+                    let table_index = index * 4 + byte_index;
+                    if table_index < 64 {
+                        self.luminance_quant_table[table_index] = byte;
+                    } else {
+                        self.color_quant_table[table_index - 64] = byte;
+                    }
+                }
+            }
+            Command::SetScaleTable => {
+                self.scale_table[index * 2] = value as i16;
+                self.scale_table[index * 2 + 1] = (value >> 16) as i16;
+            }
+            Command::None => unreachable!(),
+        }
+
+        self.parameter_index += 1;
+        self.remaining_words -= 1;
+    }
+
+    /// Completes the current command once all its parameters arrived
+    fn finish_command(&mut self) {
+        if self.command == Command::DecodeMacroblock {
+            self.decode_macroblocks();
+        }
+
+        self.command = Command::None;
+    }
+
+    /// Decodes the received macroblock stream into the data out FIFO
+    fn decode_macroblocks(&mut self) {
+        let data_in = mem::take(&mut self.data_in);
+        let mut source = Source::new(&data_in);
+
+        while source.has_remaining() {
+            match self.output_depth {
+                OutputDepth::Bit4 | OutputDepth::Bit8 => {
+                    self.current_block = 4;
+                    let y_block = decoder::decode_block(
+                        &mut source,
+                        &self.luminance_quant_table,
+                        &self.scale_table,
+                    );
+
+                    let pixels = decoder::y_to_mono(&y_block);
+                    self.output_mono(&pixels);
+                }
+                OutputDepth::Bit24 | OutputDepth::Bit15 => {
+                    self.current_block = 4;
+                    let cr_block = decoder::decode_block(
+                        &mut source,
+                        &self.color_quant_table,
+                        &self.scale_table,
+                    );
+
+                    self.current_block = 5;
+                    let cb_block = decoder::decode_block(
+                        &mut source,
+                        &self.color_quant_table,
+                        &self.scale_table,
+                    );
+
+                    let mut macroblock = [[0; 3]; 256];
+                    for (index, (quadrant_x, quadrant_y)) in
+                        [(0, 0), (8, 0), (0, 8), (8, 8)].into_iter().enumerate()
+                    {
+                        self.current_block = index as u8;
+                        let y_block = decoder::decode_block(
+                            &mut source,
+                            &self.luminance_quant_table,
+                            &self.scale_table,
+                        );
+
+                        decoder::yuv_to_rgb(
+                            &mut macroblock,
+                            &y_block,
+                            &cr_block,
+                            &cb_block,
+                            quadrant_x,
+                            quadrant_y,
+                        );
+                    }
+
+                    self.output_color(&macroblock);
+                }
+            }
+        }
+    }
+
+    /// Converts a sample to the output range
+    ///
+    /// # Arguments:
+    ///
+    /// * `sample`: The signed sample
+    fn output_sample(&self, sample: i8) -> u8 {
+        if self.output_signed {
+            sample as u8
+        } else {
+            (sample as u8) ^ 0x80
+        }
+    }
+
+    /// Packs a decoded monochrome block into the data out FIFO
+    ///
+    /// # Arguments:
+    ///
+    /// * `pixels`: The 8x8 pixels of the block
+    fn output_mono(&mut self, pixels: &[i8; 64]) {
+        let bytes: Vec<u8> = match self.output_depth {
+            OutputDepth::Bit4 => pixels
+                .chunks_exact(2)
+                .map(|pair| {
+                    let low = self.output_sample(pair[0]) >> 4;
+                    let high = self.output_sample(pair[1]) >> 4;
+                    (high << 4) | low
+                })
+                .collect(),
+            _ => pixels
+                .iter()
+                .map(|&pixel| self.output_sample(pixel))
+                .collect(),
+        };
+
+        self.output_bytes(&bytes);
+    }
+
+    /// Packs a decoded 16x16 color macroblock into the data out FIFO
+    ///
+    /// # Arguments:
+    ///
+    /// * `macroblock`: The RGB pixels of the macroblock
+    fn output_color(&mut self, macroblock: &[[i8; 3]; 256]) {
+        let bytes: Vec<u8> = match self.output_depth {
+            OutputDepth::Bit15 => macroblock
+                .iter()
+                .flat_map(|pixel| {
+                    let [r, g, b] = pixel.map(|sample| (self.output_sample(sample) >> 3) as u16);
+                    let color = r | (g << 5) | (b << 10) | ((self.output_bit15 as u16) << 15);
+                    color.to_le_bytes()
+                })
+                .collect(),
+            _ => macroblock
+                .iter()
+                .flat_map(|pixel| pixel.map(|sample| self.output_sample(sample)))
+                .collect(),
+        };
+
+        self.output_bytes(&bytes);
+    }
+
+    /// Pushes bytes into the data out FIFO as little-endian words
+    ///
+    /// # Arguments:
+    ///
+    /// * `bytes`: The bytes to push, a multiple of 4
+    fn output_bytes(&mut self, bytes: &[u8]) {
+        for word in bytes.chunks_exact(4) {
+            self.data_out
+                .push_back(u32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+        }
+    }
+
+    /// MDEC1 - Status Register
+    fn status(&self) -> u32 {
+        let mut value = 0;
+        value |= (self.data_out.is_empty() as u32) << 31;
+        value |= ((self.remaining_words != 0 || !self.data_out.is_empty()) as u32) << 29;
+        value |= (self.data_in_request as u32) << 28;
+        value |= ((self.data_out_request && !self.data_out.is_empty()) as u32) << 27;
+        value |= (self.output_depth as u32) << 25;
+        value |= (self.output_signed as u32) << 24;
+        value |= (self.output_bit15 as u32) << 23;
+        value |= (self.current_block as u32 & 0x7) << 16;
+        value |= self.remaining_words.wrapping_sub(1) & 0xffff;
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quant_table_command_fills_both_tables() {
+        let mut mdec = Mdec::new();
+        mdec.write_u32(0x00, 0x40000001);
+
+        for word in 0..32u32 {
+            let base = (word * 4) as u8;
+            mdec.write_u32(
+                0x00,
+                u32::from_le_bytes([base, base + 1, base + 2, base + 3]),
+            );
+        }
+
+        let expected: Vec<u8> = (0..128).collect();
+        assert_eq!(mdec.luminance_quant_table[..], expected[..64]);
+        assert_eq!(mdec.color_quant_table[..], expected[64..]);
+        assert_eq!(mdec.command, Command::None);
+    }
+
+    #[test]
+    fn luminance_only_quant_table_takes_16_words() {
+        let mut mdec = Mdec::new();
+        mdec.write_u32(0x00, 0x40000000);
+        assert_eq!(mdec.read_u32(0x04) & 0xffff, 15);
+
+        for _ in 0..16 {
+            mdec.write_u32(0x00, 0x01010101);
+        }
+
+        assert_eq!(mdec.luminance_quant_table, [1; 64]);
+        assert_eq!(mdec.color_quant_table, [0; 64]);
+        assert_eq!(mdec.command, Command::None);
+    }
+
+    #[test]
+    fn status_reports_the_command_state() {
+        let mut mdec = Mdec::new();
+        assert_eq!(mdec.read_u32(0x04), 0x8004ffff);
+
+        // Decode 2 words to signed 15-bit pixels with bit 15 set
+        mdec.write_u32(0x00, 0x3e000002);
+        let status = mdec.read_u32(0x04);
+        assert_ne!(status & (1 << 29), 0);
+        assert_eq!((status >> 25) & 0x3, 3);
+        assert_ne!(status & (1 << 24), 0);
+        assert_ne!(status & (1 << 23), 0);
+        assert_eq!(status & 0xffff, 1);
+
+        // Padding only, which decodes to nothing
+        mdec.write_u32(0x00, 0xfe00fe00);
+        mdec.write_u32(0x00, 0xfe00fe00);
+        let status = mdec.read_u32(0x04);
+        assert_eq!(status & (1 << 29), 0);
+        assert_eq!(status & 0xffff, 0xffff);
+    }
+
+    #[test]
+    fn control_sets_the_requests_and_resets() {
+        let mut mdec = Mdec::new();
+        mdec.write_u32(0x00, 0x60000000);
+        mdec.write_u32(0x04, 0x60000000);
+
+        let status = mdec.read_u32(0x04);
+        assert_ne!(status & (1 << 28), 0);
+        assert_eq!(status & (1 << 27), 0);
+        assert_ne!(status & (1 << 29), 0);
+
+        mdec.write_u32(0x04, 0x80000000);
+        assert_eq!(mdec.read_u32(0x04), 0x8004ffff);
+    }
+
+    #[test]
+    fn byte_writes_assemble_a_command() {
+        let mut mdec = Mdec::new();
+        for (offset, byte) in 0x60000000u32.to_le_bytes().into_iter().enumerate() {
+            mdec.write_u8(offset as u32, byte);
+
+            // Nothing happens until the highest byte arrives
+            if offset < 3 {
+                assert_eq!(mdec.command, Command::None);
+            }
+        }
+
+        assert_eq!(mdec.command, Command::SetScaleTable);
+        assert_eq!(mdec.read_u8(0x04), 31);
+        assert_eq!(mdec.read_u8(0x07), 0xa0);
+    }
+
+    /// Loads quant tables of 1 and a flat scale table, which turns the DC coefficient
+    /// of a block into an eighth of its value on every sample
+    fn mdec_with_tables() -> Mdec {
+        let mut mdec = Mdec::new();
+        mdec.write_u32(0x00, 0x40000001);
+        for _ in 0..32 {
+            mdec.write_u32(0x00, 0x01010101);
+        }
+
+        mdec.write_u32(0x00, 0x60000000);
+        for _ in 0..32 {
+            mdec.write_u32(0x00, 0x5a825a82);
+        }
+
+        mdec
+    }
+
+    /// Sends a decode command with its stream and reads back every decoded word
+    fn decode(mdec: &mut Mdec, command: u32, halfwords: &[u16]) -> Vec<u32> {
+        let words = halfwords.len() as u32 / 2;
+        mdec.write_u32(0x00, command | (1 << 29) | words);
+        for pair in halfwords.chunks_exact(2) {
+            mdec.write_u32(0x00, pair[0] as u32 | ((pair[1] as u32) << 16));
+        }
+
+        let mut output = Vec::new();
+        while mdec.read_u32(0x04) & (1 << 31) == 0 {
+            output.push(mdec.read_u32(0x00));
+        }
+
+        output
+    }
+
+    #[test]
+    fn dc_only_block_decodes_to_a_flat_8bit_block() {
+        let mut mdec = mdec_with_tables();
+
+        // Quant scale 1, DC 0x100, then the end of block
+        let output = decode(&mut mdec, 1 << 27, &[0x0500, 0xfe00]);
+        assert_eq!(output, vec![0xa0a0a0a0; 16]);
+
+        let output = decode(&mut mdec, (1 << 27) | (1 << 26), &[0x0500, 0xfe00]);
+        assert_eq!(output, vec![0x20202020; 16]);
+    }
+
+    #[test]
+    fn dc_only_block_decodes_to_a_flat_4bit_block() {
+        let mut mdec = mdec_with_tables();
+
+        let output = decode(&mut mdec, 0, &[0x0500, 0xfe00]);
+        assert_eq!(output, vec![0xaaaaaaaa; 8]);
+    }
+
+    #[test]
+    fn color_macroblock_decodes_to_24bit_pixels() {
+        let mut mdec = mdec_with_tables();
+
+        // Cr, Cb and Y1-Y4, each a DC coefficient followed by the end of block
+        let stream = [
+            0x0500, 0xfe00, 0x0400, 0xfe00, 0x0500, 0xfe00, 0x0500, 0xfe00, 0x0500, 0xfe00, 0x0500,
+            0xfe00,
+        ];
+        let output = decode(&mut mdec, 2 << 27, &stream);
+        assert_eq!(output.len(), 16 * 16 * 3 / 4);
+
+        // Y = 32 and Cr = 32 give R = 32 + 44 and G = 32 - 23
+        let bytes: Vec<u8> = output.iter().flat_map(|word| word.to_le_bytes()).collect();
+        for pixel in bytes.chunks_exact(3) {
+            assert_eq!(pixel, [0xcc, 0x89, 0xa0]);
+        }
+    }
+
+    #[test]
+    fn color_macroblock_decodes_to_15bit_pixels() {
+        let mut mdec = mdec_with_tables();
+
+        let stream = [
+            0x0400, 0xfe00, 0x0400, 0xfe00, 0x0500, 0xfe00, 0x0500, 0xfe00, 0x0500, 0xfe00, 0x0500,
+            0xfe00,
+        ];
+        let output = decode(&mut mdec, (3 << 27) | (1 << 25), &stream);
+        assert_eq!(output, vec![0xd294d294; 128]);
+    }
+
+    #[test]
+    fn decoded_words_reach_dma_channel_1() {
+        let mut mdec = mdec_with_tables();
+        mdec.write_u32(0x04, 0x20000000);
+
+        mdec.write_u32(0x00, 0x28000001);
+        mdec.dma_write_word(0xfe000500);
+        assert_ne!(mdec.read_u32(0x04) & (1 << 27), 0);
+
+        let words: Vec<u32> = (0..16).map(|_| mdec.dma_read_word()).collect();
+        assert_eq!(words, vec![0xa0a0a0a0; 16]);
+
+        let status = mdec.read_u32(0x04);
+        assert_ne!(status & (1 << 31), 0);
+        assert_eq!(status & (1 << 27), 0);
+    }
+}
@@ -0,0 +1,28 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use serde::{Deserialize, Serialize};
+
+/// The region of the console, deciding the video standard
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Region {
+    /// NTSC consoles of America and Japan
+    #[default]
+    Ntsc,
+
+    /// PAL consoles of Europe
+    Pal,
+}
+
+impl Region {
+    /// Returns the frames per second of the video standard
+    pub(crate) fn frames_per_second(self) -> f32 {
+        match self {
+            Self::Ntsc => 59.94,
+            Self::Pal => 50.0,
+        }
+    }
+}
@@ -0,0 +1,155 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::renderer::{
+    scaling::{Rect, Scaling},
+    BlendMode, Color, Position, Renderer, SkippedLines, TexCoord, Texture,
+};
+
+use cgmath::Vector2;
+
+use std::{cell::RefCell, rc::Rc};
+
+/// A primitive handed to the renderer
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Draw {
+    /// A rectangle uploaded into VRAM
+    Upload {
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        data: Vec<u16>,
+    },
+
+    /// A quad, with its blend mode
+    Quad([Position; 4], [Color; 4], Option<BlendMode>),
+
+    /// A triangle, with its blend mode
+    Triangle([Position; 3], [Color; 3], Option<BlendMode>),
+
+    /// A textured quad, with the vertex colors it is blended with and its blend mode
+    TexturedQuad(
+        [Position; 4],
+        [TexCoord; 4],
+        Option<[Color; 4]>,
+        Texture,
+        Option<BlendMode>,
+    ),
+
+    /// A textured triangle, with the vertex colors it is blended with and its blend mode
+    TexturedTriangle(
+        [Position; 3],
+        [TexCoord; 3],
+        Option<[Color; 3]>,
+        Texture,
+        Option<BlendMode>,
+    ),
+}
+
+/// The mock renderer, which records the primitives instead of drawing them
+pub(crate) struct MockRenderer {
+    /// The recorded primitives, shared with the test
+    draws: Rc<RefCell<Vec<Draw>>>,
+}
+
+impl MockRenderer {
+    /// Creates a new mock renderer together with its recorded primitives
+    pub(crate) fn new() -> (Self, Rc<RefCell<Vec<Draw>>>) {
+        let draws = Rc::new(RefCell::new(Vec::new()));
+        let renderer = Self {
+            draws: Rc::clone(&draws),
+        };
+
+        (renderer, draws)
+    }
+}
+
+impl Renderer for MockRenderer {
+    fn render(&mut self, _display_area: Option<Rect>) {}
+
+    fn resize(&mut self, _size: Vector2<u32>) {}
+
+    fn set_scaling(&mut self, _scaling: Scaling) {}
+
+    fn frame(&self) -> &[u8] {
+        &[]
+    }
+
+    fn vram(&self) -> &[u16] {
+        &[]
+    }
+
+    fn load_vram(&mut self, _data: &[u16]) {}
+
+    fn upload_vram(&mut self, x: u16, y: u16, width: u16, height: u16, data: &[u16]) {
+        self.draws.borrow_mut().push(Draw::Upload {
+            x,
+            y,
+            width,
+            height,
+            data: data.to_vec(),
+        });
+    }
+
+    fn set_internal_scale(&mut self, _scale: u32) {}
+
+    fn internal_scale(&self) -> u32 {
+        1
+    }
+
+    fn set_mask_bit_setting(&mut self, _set_mask: bool, _check_mask: bool) {}
+
+    fn set_skipped_lines(&mut self, _skipped_lines: Option<SkippedLines>) {}
+
+    fn draw_quad(
+        &mut self,
+        positions: [Position; 4],
+        colors: [Color; 4],
+        blend_mode: Option<BlendMode>,
+    ) {
+        self.draws
+            .borrow_mut()
+            .push(Draw::Quad(positions, colors, blend_mode));
+    }
+
+    fn draw_triangle(
+        &mut self,
+        positions: [Position; 3],
+        colors: [Color; 3],
+        blend_mode: Option<BlendMode>,
+    ) {
+        self.draws
+            .borrow_mut()
+            .push(Draw::Triangle(positions, colors, blend_mode));
+    }
+
+    fn draw_textured_quad(
+        &mut self,
+        positions: [Position; 4],
+        tex_coords: [TexCoord; 4],
+        colors: Option<[Color; 4]>,
+        texture: Texture,
+        blend_mode: Option<BlendMode>,
+    ) {
+        self.draws.borrow_mut().push(Draw::TexturedQuad(
+            positions, tex_coords, colors, texture, blend_mode,
+        ));
+    }
+
+    fn draw_textured_triangle(
+        &mut self,
+        positions: [Position; 3],
+        tex_coords: [TexCoord; 3],
+        colors: Option<[Color; 3]>,
+        texture: Texture,
+        blend_mode: Option<BlendMode>,
+    ) {
+        self.draws.borrow_mut().push(Draw::TexturedTriangle(
+            positions, tex_coords, colors, texture, blend_mode,
+        ));
+    }
+}
@@ -4,13 +4,175 @@
  * SPDX-License-Identifier: MIT
  */
 
+#[cfg(test)]
+pub(crate) mod mock_renderer;
+#[cfg(feature = "window")]
+mod presenter;
+pub(crate) mod scaling;
 pub(crate) mod software_renderer;
+#[cfg(feature = "window")]
 pub(crate) mod window;
 
+use crate::renderer::scaling::{Rect, Scaling};
+#[cfg(feature = "window")]
+use crate::renderer::{
+    software_renderer::{CreationError, SoftwareRenderer},
+    window::Window,
+};
+
 use cgmath::{Vector2, Vector3};
 
+/// The backend drawing the GPU primitives
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RendererKind {
+    /// Rasterizes on the CPU
+    #[default]
+    Software,
+
+    /// Rasterizes on the graphics card, currently mirrors the software renderer
+    Hardware,
+}
+
+/// Creates the renderer of a backend presenting into a window
+///
+/// Arguments:
+///
+/// * `kind`: The backend
+/// * `window`: The window to present into
+#[cfg(feature = "window")]
+pub(crate) fn create_renderer(
+    kind: RendererKind,
+    window: &Window,
+) -> Result<Box<dyn Renderer>, CreationError> {
+    match kind {
+        RendererKind::Software => Ok(Box::new(SoftwareRenderer::new(window)?)),
+        RendererKind::Hardware => {
+            // TODO: Implement a hardware renderer
+            log::warn!(target: "gpu", "Hardware renderer is not implemented, using the software renderer");
+            Ok(Box::new(SoftwareRenderer::new(window)?))
+        }
+    }
+}
+
 pub(crate) type Position = Vector2<i16>;
 pub(crate) type Color = Vector3<u8>;
+pub(crate) type TexCoord = Vector2<u8>;
+
+/// How semi-transparent pixels (F) are blended with the background (B) they are drawn over
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BlendMode {
+    /// B/2+F/2
+    Average,
+
+    /// B+F
+    Add,
+
+    /// B-F
+    Subtract,
+
+    /// B+F/4
+    AddQuarter,
+}
+
+/// The VRAM lines of the field being displayed, which drawing skips while drawing to the display
+/// area is prohibited
+///
+/// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gp0e1h-draw-mode-setting-aka-texpage>
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct SkippedLines {
+    /// The first line of the display area
+    pub(crate) top: u32,
+
+    /// The amount of lines of the display area
+    pub(crate) height: u32,
+
+    /// The parity of the lines of the displayed field
+    pub(crate) parity: u32,
+}
+
+impl SkippedLines {
+    /// Returns if a line is being displayed, the display area wraps around the bottom of VRAM
+    ///
+    /// Arguments:
+    ///
+    /// * `y`: The VRAM line
+    pub(crate) fn contains(&self, y: u32) -> bool {
+        let inside = y.wrapping_sub(self.top) % 512 < self.height;
+        inside && y & 0x1 == self.parity
+    }
+}
+
+/// The texture window applied to texture coordinates before sampling
+///
+/// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#gp0e2h-texture-window-setting>
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct TextureWindow {
+    /// The x mask in steps of 8 pixels
+    pub(crate) x_mask: u8,
+
+    /// The y mask in steps of 8 pixels
+    pub(crate) y_mask: u8,
+
+    /// The x offset in steps of 8 pixels
+    pub(crate) x_offset: u8,
+
+    /// The y offset in steps of 8 pixels
+    pub(crate) y_offset: u8,
+}
+
+impl TextureWindow {
+    /// Transforms a texture coordinate into the texture window
+    ///
+    /// Arguments:
+    ///
+    /// * `tex_coord`: The interpolated texture coordinate
+    pub(crate) fn apply(&self, tex_coord: TexCoord) -> TexCoord {
+        let apply = |coordinate: u8, mask: u8, offset: u8| {
+            (coordinate & !(mask << 3)) | ((offset & mask) << 3)
+        };
+
+        TexCoord {
+            x: apply(tex_coord.x, self.x_mask, self.x_offset),
+            y: apply(tex_coord.y, self.y_mask, self.y_offset),
+        }
+    }
+}
+
+/// The color depth of a texture page
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum TextureDepth {
+    /// 4-bit indices into a CLUT of 16 colors
+    #[default]
+    Bit4,
+
+    /// 8-bit indices into a CLUT of 256 colors
+    Bit8,
+
+    /// Direct 15-bit colors
+    Bit15,
+}
+
+/// The texture a primitive samples from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Texture {
+    /// The x coordinate of the texture page in VRAM
+    pub(crate) page_x: u16,
+
+    /// The y coordinate of the texture page in VRAM
+    pub(crate) page_y: u16,
+
+    /// The color depth of the texture page
+    pub(crate) depth: TextureDepth,
+
+    /// The x coordinate of the CLUT in VRAM
+    pub(crate) clut_x: u16,
+
+    /// The y coordinate of the CLUT in VRAM
+    pub(crate) clut_y: u16,
+
+    /// The texture window
+    pub(crate) window: TextureWindow,
+}
 
 pub(crate) fn position_from_u32(word: u32) -> Position {
     let x = (word & 0xffff) as i16;
@@ -19,6 +181,13 @@ pub(crate) fn position_from_u32(word: u32) -> Position {
     Position { x, y }
 }
 
+pub(crate) fn tex_coord_from_u32(word: u32) -> TexCoord {
+    let u = (word & 0xff) as u8;
+    let v = ((word >> 8) & 0xff) as u8;
+
+    TexCoord { x: u, y: v }
+}
+
 pub(crate) fn color_from_u32(word: u32) -> Color {
     let r = (word & 0xff) as u8;
     let g = ((word >> 8) & 0xff) as u8;
@@ -28,23 +197,95 @@ pub(crate) fn color_from_u32(word: u32) -> Color {
 }
 
 pub(crate) trait Renderer {
-    /// Renders the current framebuffer
-    fn render(&mut self);
+    /// Presents the display area of the current framebuffer
+    ///
+    /// The framebuffer is left untouched, so draws persist until they get overwritten
+    ///
+    /// Arguments:
+    ///
+    /// * `display_area`: The area of the framebuffer shown on screen, none presents a black screen
+    ///   while the display is disabled
+    fn render(&mut self, display_area: Option<Rect>);
 
-    /// Resizes the current framebuffer
+    /// Resizes the window buffer the framebuffer is presented into
     ///
     /// Arguments:
     ///
-    /// * `size`: New framebuffer size
+    /// * `size`: New window size
+    #[cfg(feature = "window")]
     fn resize(&mut self, size: Vector2<u32>);
 
+    /// Changes how the display area is fitted into the window
+    ///
+    /// Arguments:
+    ///
+    /// * `scaling`: The scaling mode
+    fn set_scaling(&mut self, scaling: Scaling);
+
+    /// Returns the current RGBA framebuffer
+    fn frame(&self) -> &[u8];
+
+    /// Returns the 1024x512 VRAM in BGR555 with the mask bit in bit 15
+    fn vram(&self) -> &[u16];
+
+    /// Replaces the VRAM, redrawing the framebuffer from it
+    ///
+    /// Arguments:
+    ///
+    /// * `data`: The 1024x512 VRAM in BGR555 with the mask bit in bit 15
+    fn load_vram(&mut self, data: &[u16]);
+
+    /// Uploads a rectangle of pixels into VRAM, wrapping around the VRAM edges
+    ///
+    /// The pixels are stored as they are, the mask bit settings have already been applied
+    ///
+    /// Arguments:
+    ///
+    /// * `x`: The left edge of the rectangle
+    /// * `y`: The top edge of the rectangle
+    /// * `width`: The width of the rectangle
+    /// * `height`: The height of the rectangle
+    /// * `data`: The rows of the rectangle in BGR555 with the mask bit in bit 15
+    fn upload_vram(&mut self, x: u16, y: u16, width: u16, height: u16, data: &[u16]);
+
+    /// Changes the factor primitives are rasterized at relative to the native resolution
+    ///
+    /// Arguments:
+    ///
+    /// * `scale`: The internal scale
+    fn set_internal_scale(&mut self, scale: u32);
+
+    /// Returns the factor the framebuffer is scaled by relative to the native resolution
+    fn internal_scale(&self) -> u32;
+
+    /// Changes how the mask bit of VRAM pixels is handled while drawing
+    ///
+    /// Arguments:
+    ///
+    /// * `set_mask`: If drawn pixels get the mask bit set
+    /// * `check_mask`: If pixels with the mask bit set are protected from drawing
+    fn set_mask_bit_setting(&mut self, set_mask: bool, check_mask: bool);
+
+    /// Changes the lines primitives skip, as they are being displayed
+    ///
+    /// Arguments:
+    ///
+    /// * `skipped_lines`: The displayed lines, none if every line is drawn
+    fn set_skipped_lines(&mut self, skipped_lines: Option<SkippedLines>);
+
     /// Draws a quad
     ///
     /// Arguments:
     ///
     /// * `positions`: Vertex positions
     /// * `colors`: Vertex colors
-    fn draw_quad(&mut self, positions: [Position; 4], colors: [Color; 4]);
+    /// * `blend_mode`: The blending of semi-transparent pixels, none if the quad is opaque
+    fn draw_quad(
+        &mut self,
+        positions: [Position; 4],
+        colors: [Color; 4],
+        blend_mode: Option<BlendMode>,
+    );
 
     /// Draws a triangle
     ///
@@ -52,5 +293,174 @@ pub(crate) trait Renderer {
     ///
     /// * `positions`: Vertex positions
     /// * `colors`: Vertex colors
-    fn draw_triangle(&mut self, positions: [Position; 3], colors: [Color; 3]);
+    /// * `blend_mode`: The blending of semi-transparent pixels, none if the triangle is opaque
+    fn draw_triangle(
+        &mut self,
+        positions: [Position; 3],
+        colors: [Color; 3],
+        blend_mode: Option<BlendMode>,
+    );
+
+    /// Draws a textured quad
+    ///
+    /// Arguments:
+    ///
+    /// * `positions`: Vertex positions
+    /// * `tex_coords`: Vertex texture coordinates
+    /// * `colors`: Vertex colors blended with the texture, none for raw textures
+    /// * `texture`: The texture to sample from
+    /// * `blend_mode`: The blending of texels with the semi-transparency bit, none if the quad is
+    ///   opaque
+    fn draw_textured_quad(
+        &mut self,
+        positions: [Position; 4],
+        tex_coords: [TexCoord; 4],
+        colors: Option<[Color; 4]>,
+        texture: Texture,
+        blend_mode: Option<BlendMode>,
+    );
+
+    /// Draws a textured triangle
+    ///
+    /// Arguments:
+    ///
+    /// * `positions`: Vertex positions
+    /// * `tex_coords`: Vertex texture coordinates
+    /// * `colors`: Vertex colors blended with the texture, none for raw textures
+    /// * `texture`: The texture to sample from
+    /// * `blend_mode`: The blending of texels with the semi-transparency bit, none if the
+    ///   triangle is opaque
+    fn draw_textured_triangle(
+        &mut self,
+        positions: [Position; 3],
+        tex_coords: [TexCoord; 3],
+        colors: Option<[Color; 3]>,
+        texture: Texture,
+        blend_mode: Option<BlendMode>,
+    );
+}
+
+/// A renderer discarding every draw call
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct NullRenderer;
+
+impl Renderer for NullRenderer {
+    fn render(&mut self, _display_area: Option<Rect>) {}
+
+    #[cfg(feature = "window")]
+    fn resize(&mut self, _size: Vector2<u32>) {}
+
+    fn set_scaling(&mut self, _scaling: Scaling) {}
+
+    fn frame(&self) -> &[u8] {
+        &[]
+    }
+
+    fn vram(&self) -> &[u16] {
+        &[]
+    }
+
+    fn load_vram(&mut self, _data: &[u16]) {}
+
+    fn upload_vram(&mut self, _x: u16, _y: u16, _width: u16, _height: u16, _data: &[u16]) {}
+
+    fn set_internal_scale(&mut self, _scale: u32) {}
+
+    fn internal_scale(&self) -> u32 {
+        1
+    }
+
+    fn set_mask_bit_setting(&mut self, _set_mask: bool, _check_mask: bool) {}
+
+    fn set_skipped_lines(&mut self, _skipped_lines: Option<SkippedLines>) {}
+
+    fn draw_quad(
+        &mut self,
+        _positions: [Position; 4],
+        _colors: [Color; 4],
+        _blend_mode: Option<BlendMode>,
+    ) {
+    }
+
+    fn draw_triangle(
+        &mut self,
+        _positions: [Position; 3],
+        _colors: [Color; 3],
+        _blend_mode: Option<BlendMode>,
+    ) {
+    }
+
+    fn draw_textured_quad(
+        &mut self,
+        _positions: [Position; 4],
+        _tex_coords: [TexCoord; 4],
+        _colors: Option<[Color; 4]>,
+        _texture: Texture,
+        _blend_mode: Option<BlendMode>,
+    ) {
+    }
+
+    fn draw_textured_triangle(
+        &mut self,
+        _positions: [Position; 3],
+        _tex_coords: [TexCoord; 3],
+        _colors: Option<[Color; 3]>,
+        _texture: Texture,
+        _blend_mode: Option<BlendMode>,
+    ) {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texture_window_without_mask_keeps_the_coordinate() {
+        let window = TextureWindow {
+            x_mask: 0,
+            y_mask: 0,
+            x_offset: 0x1f,
+            y_offset: 0x1f,
+        };
+
+        assert_eq!(
+            window.apply(TexCoord::new(0x3f, 0xc5)),
+            TexCoord::new(0x3f, 0xc5)
+        );
+    }
+
+    #[test]
+    fn texture_window_replaces_the_masked_bits_with_the_offset() {
+        let window = TextureWindow {
+            x_mask: 0x03,
+            y_mask: 0x1f,
+            x_offset: 0x02,
+            y_offset: 0x05,
+        };
+
+        assert_eq!(
+            window.apply(TexCoord::new(0x3f, 0xff)),
+            TexCoord::new(0x37, 0x2f)
+        );
+        assert_eq!(
+            window.apply(TexCoord::new(0x00, 0x00)),
+            TexCoord::new(0x10, 0x28)
+        );
+    }
+
+    #[test]
+    fn texture_window_ignores_offset_bits_outside_the_mask() {
+        let window = TextureWindow {
+            x_mask: 0x01,
+            y_mask: 0x00,
+            x_offset: 0x1e,
+            y_offset: 0x00,
+        };
+
+        assert_eq!(
+            window.apply(TexCoord::new(0x0f, 0x0f)),
+            TexCoord::new(0x07, 0x0f)
+        );
+    }
 }
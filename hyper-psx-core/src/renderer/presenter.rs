@@ -0,0 +1,178 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::renderer::{
+    scaling::{self, Rect, Scaling},
+    window::Window,
+};
+
+use cgmath::Vector2;
+use pixels::{Pixels, SurfaceTexture};
+
+/// The presenter scaling the display area of a framebuffer into a window
+#[derive(Debug)]
+pub(crate) struct Presenter {
+    /// The pixels framebuffer of the window
+    pixels: Pixels,
+
+    /// The current window size
+    size: Vector2<u32>,
+
+    /// How the display area is fitted into the window
+    scaling: Scaling,
+}
+
+impl Presenter {
+    /// Creates a new presenter
+    ///
+    /// Arguments:
+    ///
+    /// * `window`: The window to present into
+    pub(crate) fn new(window: &Window) -> Result<Self, pixels::Error> {
+        // The window gets scaled into in software, so the pixels buffer matches its size
+        let size = window.size();
+        let surface_texture = SurfaceTexture::new(size.x, size.y, window.internal());
+        let pixels = Pixels::new(size.x, size.y, surface_texture)?;
+
+        Ok(Self {
+            pixels,
+            size,
+            scaling: Scaling::default(),
+        })
+    }
+
+    /// Presents the display area of a framebuffer
+    ///
+    /// Arguments:
+    ///
+    /// * `frame`: The RGBA framebuffer
+    /// * `frame_size`: The size of the framebuffer
+    /// * `scale`: The internal scale of the framebuffer
+    /// * `display_area`: The area of the framebuffer shown on screen, none presents a black screen
+    pub(crate) fn present(
+        &mut self,
+        frame: &[u8],
+        frame_size: Vector2<u32>,
+        scale: u32,
+        display_area: Option<Rect>,
+    ) {
+        match display_area {
+            Some(display_area) => {
+                if display_area.width == 0 || display_area.height == 0 {
+                    return;
+                }
+
+                let display_size = Vector2 {
+                    x: display_area.width,
+                    y: display_area.height,
+                };
+                let destination = scaling::destination_rect(self.size, display_size, self.scaling);
+                if destination.width == 0 || destination.height == 0 {
+                    return;
+                }
+
+                Self::blit(
+                    frame,
+                    frame_size,
+                    scale,
+                    self.pixels.frame_mut(),
+                    self.size,
+                    display_area,
+                    destination,
+                );
+            }
+            None => {
+                for pixel in self.pixels.frame_mut().chunks_exact_mut(4) {
+                    pixel.copy_from_slice(&[0x00, 0x00, 0x00, 0xff]);
+                }
+            }
+        }
+
+        if let Err(error) = self.pixels.render() {
+            log::error!(target: "gpu", "Failed to present frame: {}", error);
+        }
+    }
+
+    /// Resizes the window buffer
+    ///
+    /// Arguments:
+    ///
+    /// * `size`: New window size
+    pub(crate) fn resize(&mut self, size: Vector2<u32>) {
+        // Minimized windows report a zero size, which the surface can't be resized to
+        if size.x == 0 || size.y == 0 {
+            return;
+        }
+
+        // The window buffer keeps its previous size if resizing fails, as the present rect is
+        // derived from it on every render
+        if let Err(error) = self.pixels.resize_surface(size.x, size.y) {
+            log::error!(target: "gpu", "Failed to resize surface: {}", error);
+            return;
+        }
+
+        if let Err(error) = self.pixels.resize_buffer(size.x, size.y) {
+            log::error!(target: "gpu", "Failed to resize window buffer: {}", error);
+            return;
+        }
+
+        self.size = size;
+    }
+
+    /// Changes how the display area is fitted into the window
+    ///
+    /// Arguments:
+    ///
+    /// * `scaling`: The new scaling mode
+    pub(crate) fn set_scaling(&mut self, scaling: Scaling) {
+        self.scaling = scaling;
+    }
+
+    /// Scales the display area of the frame into the destination of the window buffer
+    ///
+    /// Arguments:
+    ///
+    /// * `frame`: The RGBA framebuffer
+    /// * `frame_size`: The size of the framebuffer
+    /// * `scale`: The internal scale of the framebuffer
+    /// * `target`: The RGBA window buffer
+    /// * `target_size`: The size of the window buffer
+    /// * `display_area`: The area of the framebuffer shown on screen
+    /// * `destination`: The area of the window buffer to scale into
+    fn blit(
+        frame: &[u8],
+        frame_size: Vector2<u32>,
+        scale: u32,
+        target: &mut [u8],
+        target_size: Vector2<u32>,
+        display_area: Rect,
+        destination: Rect,
+    ) {
+        for pixel in target.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[0x00, 0x00, 0x00, 0xff]);
+        }
+
+        let width = destination.width.min(target_size.x - destination.x);
+        let height = destination.height.min(target_size.y - destination.y);
+        for y in 0..height {
+            let source_y = (display_area.y * scale
+                + y * display_area.height * scale / destination.height)
+                % frame_size.y;
+            let target_y = destination.y + y;
+
+            for x in 0..width {
+                let source_x = (display_area.x * scale
+                    + x * display_area.width * scale / destination.width)
+                    % frame_size.x;
+                let target_x = destination.x + x;
+
+                let source = ((source_y * frame_size.x + source_x) * 4) as usize;
+                let target_index = ((target_y * target_size.x + target_x) * 4) as usize;
+                target[target_index..target_index + 4].copy_from_slice(&frame[source..source + 4]);
+            }
+        }
+    }
+}
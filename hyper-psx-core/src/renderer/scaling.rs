@@ -0,0 +1,159 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+#[cfg(feature = "window")]
+use cgmath::Vector2;
+
+/// How the display area is fitted into the window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scaling {
+    /// Fills the whole window, distorting the image
+    Stretch,
+
+    /// Letterboxes the image to the 4:3 aspect of a TV
+    #[default]
+    Aspect,
+
+    /// Letterboxes to 4:3 with the height snapped to whole multiples of the display height
+    Integer,
+}
+
+/// A rectangle in pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Rect {
+    /// The left edge
+    pub(crate) x: u32,
+
+    /// The top edge
+    pub(crate) y: u32,
+
+    /// The width
+    pub(crate) width: u32,
+
+    /// The height
+    pub(crate) height: u32,
+}
+
+/// The aspect of the PSX video output, independent of the display resolution
+#[cfg(feature = "window")]
+const DISPLAY_ASPECT: (u32, u32) = (4, 3);
+
+/// Calculates where the display area is presented inside the window
+///
+/// The PSX pixels aren't square, so every resolution gets shown at 4:3
+///
+/// Arguments:
+///
+/// * `window_size`: The size of the window
+/// * `display_size`: The active display resolution
+/// * `scaling`: The scaling mode
+#[cfg(feature = "window")]
+pub(crate) fn destination_rect(
+    window_size: Vector2<u32>,
+    display_size: Vector2<u32>,
+    scaling: Scaling,
+) -> Rect {
+    let (aspect_width, aspect_height) = DISPLAY_ASPECT;
+
+    let (width, height) = match scaling {
+        Scaling::Stretch => (window_size.x, window_size.y),
+        Scaling::Aspect => {
+            // The widest 4:3 rectangle which still fits
+            let height = window_size
+                .y
+                .min(window_size.x * aspect_height / aspect_width);
+            (height * aspect_width / aspect_height, height)
+        }
+        Scaling::Integer => {
+            let fits = |factor: u32| {
+                let height = display_size.y * factor;
+                let width = height * aspect_width / aspect_height;
+                width <= window_size.x && height <= window_size.y
+            };
+
+            // Falls back to a factor of 1 for windows smaller than the display
+            let mut factor = (window_size.y / display_size.y.max(1)).max(1);
+            while factor > 1 && !fits(factor) {
+                factor -= 1;
+            }
+
+            let height = display_size.y * factor;
+            (height * aspect_width / aspect_height, height)
+        }
+    };
+
+    Rect {
+        x: window_size.x.saturating_sub(width) / 2,
+        y: window_size.y.saturating_sub(height) / 2,
+        width,
+        height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn stretch_fills_the_window() {
+        let rect = destination_rect(
+            Vector2::new(800, 600),
+            Vector2::new(320, 240),
+            Scaling::Stretch,
+        );
+
+        assert_eq!(rect, self::rect(0, 0, 800, 600));
+    }
+
+    #[test]
+    fn aspect_letterboxes_to_4_3() {
+        let wide = destination_rect(
+            Vector2::new(1280, 720),
+            Vector2::new(320, 240),
+            Scaling::Aspect,
+        );
+        let tall = destination_rect(
+            Vector2::new(640, 800),
+            Vector2::new(640, 480),
+            Scaling::Aspect,
+        );
+
+        assert_eq!(wide, rect(160, 0, 960, 720));
+        assert_eq!(tall, rect(0, 160, 640, 480));
+    }
+
+    #[test]
+    fn integer_snaps_to_whole_factors() {
+        let exact = destination_rect(
+            Vector2::new(1280, 720),
+            Vector2::new(320, 240),
+            Scaling::Integer,
+        );
+        let too_narrow = destination_rect(
+            Vector2::new(1000, 1000),
+            Vector2::new(640, 480),
+            Scaling::Integer,
+        );
+        let too_small = destination_rect(
+            Vector2::new(300, 200),
+            Vector2::new(320, 240),
+            Scaling::Integer,
+        );
+
+        assert_eq!(exact, rect(160, 0, 960, 720));
+        assert_eq!(too_narrow, rect(180, 260, 640, 480));
+        assert_eq!(too_small, rect(0, 0, 320, 240));
+    }
+}
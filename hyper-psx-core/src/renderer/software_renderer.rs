@@ -4,13 +4,20 @@
  * SPDX-License-Identifier: MIT
  */
 
-use crate::renderer::{window::Window, Color, Position, Renderer};
+#[cfg(feature = "window")]
+use crate::renderer::{presenter::Presenter, window::Window};
+use crate::renderer::{
+    scaling::{Rect, Scaling},
+    BlendMode, Color, Position, Renderer, SkippedLines, TexCoord, Texture, TextureDepth,
+};
 
-use cgmath::{Vector2, Vector3};
-use pixels::{Pixels, SurfaceTexture};
+use cgmath::Vector2;
+use std::mem;
+#[cfg(feature = "window")]
 use thiserror::Error;
 
 /// Creation error type for the software renderer
+#[cfg(feature = "window")]
 #[derive(Debug, Error)]
 pub enum CreationError {
     /// If the code failed to create a pixels framebuffer
@@ -18,150 +25,1032 @@ pub enum CreationError {
     PixelsCreationFailure(#[from] pixels::Error),
 }
 
+/// The barycentric weights of a pixel inside a triangle, in the order of its vertices
+#[derive(Clone, Copy, Debug)]
+struct Barycentric {
+    /// The weight of each vertex, summing up to the area
+    weights: [i64; 3],
+
+    /// Twice the area of the triangle
+    area: i64,
+}
+
+impl Barycentric {
+    /// Interpolates a vertex attribute, rounding towards zero
+    ///
+    /// Arguments:
+    ///
+    /// * `values`: The attribute of each vertex
+    fn interpolate(&self, values: [u8; 3]) -> u8 {
+        (self.weighted_sum(values) / self.area) as u8
+    }
+
+    /// Interpolates the vertex colors, rounding to the nearest value so gradients don't get
+    /// darkened by truncation
+    ///
+    /// Arguments:
+    ///
+    /// * `colors`: The color of each vertex
+    fn interpolate_color(&self, colors: [Color; 3]) -> Color {
+        let interpolate = |values: [u8; 3]| {
+            ((self.weighted_sum(values) + self.area / 2) / self.area).min(0xff) as u8
+        };
+
+        Color {
+            x: interpolate(colors.map(|color| color.x)),
+            y: interpolate(colors.map(|color| color.y)),
+            z: interpolate(colors.map(|color| color.z)),
+        }
+    }
+
+    /// Sums up the weighted attribute of each vertex
+    ///
+    /// Arguments:
+    ///
+    /// * `values`: The attribute of each vertex
+    fn weighted_sum(&self, values: [u8; 3]) -> i64 {
+        values
+            .iter()
+            .zip(self.weights)
+            .map(|(&value, weight)| value as i64 * weight)
+            .sum()
+    }
+}
+
 /// The software renderer
+///
+/// VRAM and the frame persist across frames and only change through drawing, so the display
+/// keeps showing earlier draws until they get overwritten. The frame mirrors VRAM at the internal
+/// scale, and the presenter regenerates the window buffer from the display area of the frame on
+/// each render.
 #[derive(Debug)]
 pub(crate) struct SoftwareRenderer {
-    /// The pixels framebuffer
-    pixels: Pixels,
+    /// The RGBA framebuffer the primitives are drawn into, scaled by the internal scale
+    frame: Vec<u8>,
+
+    /// The factor primitives are rasterized at relative to the native resolution
+    internal_scale: u32,
+
+    /// The VRAM writes of the primitive being rasterized, applied once it is done so that the
+    /// mask bit check of every sample sees VRAM as it was before the primitive
+    pending_vram_writes: Vec<(usize, u16)>,
+
+    /// The 16-bit VRAM in BGR555 with the mask bit in bit 15, kept alongside the frame
+    vram: Vec<u16>,
 
-    /// The current framebuffer size
-    size: Vector2<u32>,
+    /// If drawn pixels get the mask bit set
+    set_mask: bool,
+
+    /// If pixels with the mask bit set are protected from drawing
+    check_mask: bool,
+
+    /// The displayed lines primitives skip, none if every line is drawn
+    skipped_lines: Option<SkippedLines>,
+
+    /// The presenter showing the frame in a window, none if rendering offscreen
+    #[cfg(feature = "window")]
+    presenter: Option<Presenter>,
 }
 
 impl SoftwareRenderer {
+    /// The width of the framebuffer
+    const WIDTH: u32 = 1024;
+
+    /// The height of the framebuffer
+    const HEIGHT: u32 = 512;
+
+    /// The highest supported internal scale
+    const MAX_INTERNAL_SCALE: u32 = 8;
+
     /// Creates a new software renderer
     ///
     /// Arguments:
     ///
     /// * `window`: The corresponding window
+    #[cfg(feature = "window")]
     pub(crate) fn new(window: &Window) -> Result<Self, CreationError> {
-        let mut pixels = {
-            let window_size = window.size();
-            let surface_texture =
-                SurfaceTexture::new(window_size.x, window_size.y, window.internal());
-            Pixels::new(1024, 512, surface_texture)?
+        Ok(Self {
+            presenter: Some(Presenter::new(window)?),
+            ..Self::new_headless()
+        })
+    }
+
+    /// Creates a new software renderer which only renders offscreen
+    pub(crate) fn new_headless() -> Self {
+        Self {
+            frame: Self::create_frame(1),
+            internal_scale: 1,
+            pending_vram_writes: Vec::new(),
+            vram: vec![0; (Self::WIDTH * Self::HEIGHT) as usize],
+            set_mask: false,
+            check_mask: false,
+            skipped_lines: None,
+            #[cfg(feature = "window")]
+            presenter: None,
+        }
+    }
+
+    /// Calculates on which side of the edge from `a` to `b` the point `p` lies
+    ///
+    /// Arguments:
+    ///
+    /// * `a`: The start of the edge
+    /// * `b`: The end of the edge
+    /// * `p`: The point to test
+    fn edge_function(a: Vector2<i32>, b: Vector2<i32>, p: Vector2<i32>) -> i64 {
+        let (a_x, a_y) = (a.x as i64, a.y as i64);
+        let (b_x, b_y) = (b.x as i64, b.y as i64);
+        let (p_x, p_y) = (p.x as i64, p.y as i64);
+
+        (b_x - a_x) * (p_y - a_y) - (b_y - a_y) * (p_x - a_x)
+    }
+
+    /// Returns the bias of an edge for the top-left fill rule
+    ///
+    /// Arguments:
+    ///
+    /// * `a`: The start of the edge
+    /// * `b`: The end of the edge
+    fn fill_rule_bias(a: Vector2<i32>, b: Vector2<i32>) -> i64 {
+        let is_top = a.y == b.y && b.x > a.x;
+        let is_left = b.y < a.y;
+
+        if is_top || is_left {
+            0
+        } else {
+            -1
+        }
+    }
+
+    /// Rasterizes a triangle and writes the color the shader returns for each covered pixel
+    ///
+    /// Arguments:
+    ///
+    /// * `positions`: Vertex positions
+    /// * `blend_mode`: The blending of semi-transparent pixels, none if the triangle is opaque
+    /// * `shader`: Returns the color of a pixel and if it is semi-transparent or none to skip it,
+    ///   gets the barycentric weights of the pixel to interpolate vertex attributes with
+    fn rasterize<F>(
+        &mut self,
+        positions: [Position; 3],
+        blend_mode: Option<BlendMode>,
+        mut shader: F,
+    ) where
+        F: FnMut(&Self, &Barycentric) -> Option<(Color, bool)>,
+    {
+        // Vertices are placed at the scaled resolution, so each VRAM pixel gets several samples.
+        // Scaling happens in 32 bits, as off-screen vertices would overflow their 16 bits.
+        let scale = self.internal_scale as i32;
+        let [a, mut b, mut c] = positions.map(|position| Vector2 {
+            x: position.x as i32 * scale,
+            y: position.y as i32 * scale,
+        });
+
+        // Twice the signed area, its sign tells the winding of the vertices
+        let mut area = Self::edge_function(a, b, c);
+        if area == 0 {
+            return;
+        }
+
+        // GP0 polygons arrive in either winding, the fill rule expects a clockwise one
+        let swapped = area < 0;
+        if swapped {
+            mem::swap(&mut b, &mut c);
+            area = -area;
+        }
+
+        // The bounding box is clamped to the frame, so far off-screen vertices only cost the
+        // visible part of the triangle
+        let min_x = a.x.min(b.x).min(c.x).max(0);
+        let min_y = a.y.min(b.y).min(c.y).max(0);
+        let max_x =
+            a.x.max(b.x)
+                .max(c.x)
+                .min((Self::WIDTH * self.internal_scale) as i32 - 1);
+        let max_y =
+            a.y.max(b.y)
+                .max(c.y)
+                .min((Self::HEIGHT * self.internal_scale) as i32 - 1);
+        if min_x > max_x || min_y > max_y {
+            return;
+        }
+
+        // Pixels exactly on an edge are only covered by top or left edges
+        let bias_a = Self::fill_rule_bias(b, c);
+        let bias_b = Self::fill_rule_bias(c, a);
+        let bias_c = Self::fill_rule_bias(a, b);
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let p = Vector2 { x, y };
+
+                // Each weight belongs to the vertex opposite of the edge
+                let weight_a = Self::edge_function(b, c, p);
+                let mut weight_b = Self::edge_function(c, a, p);
+                let mut weight_c = Self::edge_function(a, b, p);
+
+                // The point lies outside of the triangle
+                if weight_a + bias_a < 0 || weight_b + bias_b < 0 || weight_c + bias_c < 0 {
+                    continue;
+                }
+
+                // The attributes are passed in the original vertex order
+                if swapped {
+                    mem::swap(&mut weight_b, &mut weight_c);
+                }
+
+                let barycentric = Barycentric {
+                    weights: [weight_a, weight_b, weight_c],
+                    area,
+                };
+
+                if let Some((color, semi_transparent)) = shader(self, &barycentric) {
+                    let color = match blend_mode {
+                        Some(blend_mode) if semi_transparent => {
+                            self.blend_pixel(x as u32, y as u32, color, blend_mode)
+                        }
+                        _ => color,
+                    };
+
+                    self.put_pixel(x as u32, y as u32, color);
+                }
+            }
+        }
+
+        for (index, pixel) in self.pending_vram_writes.drain(..) {
+            self.vram[index] = pixel;
+        }
+    }
+
+    /// Fetches the texel of a texture and converts it into a 24-bit color, along with its
+    /// semi-transparency bit, none if the texel is transparent
+    ///
+    /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#textures>
+    ///
+    /// Arguments:
+    ///
+    /// * `texture`: The texture to sample from
+    /// * `tex_coord`: The interpolated texture coordinate
+    fn sample_texture(&self, texture: Texture, tex_coord: TexCoord) -> Option<(Color, bool)> {
+        let tex_coord = texture.window.apply(tex_coord);
+        let (u, v) = (tex_coord.x as u32, tex_coord.y as u32);
+
+        let y = texture.page_y as u32 + v;
+        let texel = match texture.depth {
+            TextureDepth::Bit4 => {
+                let halfword = self.vram_pixel(texture.page_x as u32 + u / 4, y);
+                let index = (halfword >> ((u % 4) * 4)) & 0xf;
+                self.vram_pixel(texture.clut_x as u32 + index as u32, texture.clut_y as u32)
+            }
+            TextureDepth::Bit8 => {
+                let halfword = self.vram_pixel(texture.page_x as u32 + u / 2, y);
+                let index = (halfword >> ((u % 2) * 8)) & 0xff;
+                self.vram_pixel(texture.clut_x as u32 + index as u32, texture.clut_y as u32)
+            }
+            TextureDepth::Bit15 => self.vram_pixel(texture.page_x as u32 + u, y),
         };
 
-        for pixel in pixels.frame_mut().chunks_exact_mut(4) {
-            pixel[0] = 0x00;
-            pixel[1] = 0x00;
-            pixel[2] = 0x00;
-            pixel[3] = 0xff;
+        // Black without the semi-transparency bit is always fully transparent
+        if texel == 0x0000 {
+            return None;
         }
 
-        Ok(Self {
-            pixels,
-            size: window.size(),
-        })
+        let color = Color {
+            x: ((texel & 0x1f) << 3) as u8,
+            y: (((texel >> 5) & 0x1f) << 3) as u8,
+            z: (((texel >> 10) & 0x1f) << 3) as u8,
+        };
+
+        Some((color, texel & 0x8000 != 0))
+    }
+
+    /// Modulates a texel with a vertex color, where 0x80 leaves the texel unchanged
+    ///
+    /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#texture-blending>
+    ///
+    /// Arguments:
+    ///
+    /// * `texel`: The sampled texel
+    /// * `color`: The interpolated vertex color
+    fn blend_texel(texel: Color, color: Color) -> Color {
+        let blend = |texel: u8, color: u8| ((texel as u16 * color as u16) / 0x80).min(0xff) as u8;
+
+        Color {
+            x: blend(texel.x, color.x),
+            y: blend(texel.y, color.y),
+            z: blend(texel.z, color.z),
+        }
+    }
+
+    /// Blends a semi-transparent color with the sample it is drawn over
+    ///
+    /// <https://psx-spx.consoledev.net/graphicsprocessingunitgpu/#semi-transparency>
+    ///
+    /// Arguments:
+    ///
+    /// * `x`: The x coordinate of the sample at the internal scale
+    /// * `y`: The y coordinate of the sample at the internal scale
+    /// * `color`: The semi-transparent color
+    /// * `blend_mode`: How the colors are blended
+    fn blend_pixel(&self, x: u32, y: u32, color: Color, blend_mode: BlendMode) -> Color {
+        let frame_index = ((y * Self::WIDTH * self.internal_scale + x) * 4) as usize;
+        let blend = |background: u8, foreground: u8| {
+            let (background, foreground) = (background as i16, foreground as i16);
+            let value = match blend_mode {
+                BlendMode::Average => (background + foreground) / 2,
+                BlendMode::Add => background + foreground,
+                BlendMode::Subtract => background - foreground,
+                BlendMode::AddQuarter => background + foreground / 4,
+            };
+
+            value.clamp(0x00, 0xff) as u8
+        };
+
+        Color {
+            x: blend(self.frame[frame_index], color.x),
+            y: blend(self.frame[frame_index + 1], color.y),
+            z: blend(self.frame[frame_index + 2], color.z),
+        }
+    }
+
+    /// Reads a VRAM pixel, wrapping coordinates around the VRAM edges
+    ///
+    /// Arguments:
+    ///
+    /// * `x`: The x coordinate of the pixel
+    /// * `y`: The y coordinate of the pixel
+    fn vram_pixel(&self, x: u32, y: u32) -> u16 {
+        let x = x % Self::WIDTH;
+        let y = y % Self::HEIGHT;
+        self.vram[(y * Self::WIDTH + x) as usize]
+    }
+
+    /// Writes a sample into the frame and its pixel into VRAM, honoring the mask bit settings and
+    /// the skipped lines
+    ///
+    /// Arguments:
+    ///
+    /// * `x`: The x coordinate of the sample at the internal scale
+    /// * `y`: The y coordinate of the sample at the internal scale
+    /// * `color`: The 24-bit color of the sample
+    fn put_pixel(&mut self, x: u32, y: u32, color: Color) {
+        let scale = self.internal_scale;
+        if let Some(skipped_lines) = self.skipped_lines {
+            if skipped_lines.contains(y / scale) {
+                return;
+            }
+        }
+
+        let index = ((y / scale) * Self::WIDTH + x / scale) as usize;
+        if self.check_mask && self.vram[index] & 0x8000 != 0 {
+            return;
+        }
+
+        // Only the sample at the native pixel position reaches VRAM, like at native resolution
+        if (x % scale, y % scale) == (0, 0) {
+            let r = (color.x >> 3) as u16;
+            let g = (color.y >> 3) as u16;
+            let b = (color.z >> 3) as u16;
+            let mask = if self.set_mask { 0x8000 } else { 0x0000 };
+            self.pending_vram_writes
+                .push((index, mask | (b << 10) | (g << 5) | r));
+        }
+
+        let frame_index = ((y * Self::WIDTH * scale + x) * 4) as usize;
+        self.frame[frame_index] = color.x;
+        self.frame[frame_index + 1] = color.y;
+        self.frame[frame_index + 2] = color.z;
+    }
+
+    /// Regenerates an area of the frame from VRAM, for VRAM changes which were not rasterized
+    ///
+    /// Arguments:
+    ///
+    /// * `area`: The area of VRAM in native pixels
+    fn refresh_frame(&mut self, area: Rect) {
+        let scale = self.internal_scale;
+        for y in area.y * scale..(area.y + area.height) * scale {
+            for x in area.x * scale..(area.x + area.width) * scale {
+                let pixel = self.vram_pixel(x / scale, y / scale);
+                let index = ((y * Self::WIDTH * scale + x) * 4) as usize;
+                self.frame[index] = ((pixel & 0x1f) << 3) as u8;
+                self.frame[index + 1] = (((pixel >> 5) & 0x1f) << 3) as u8;
+                self.frame[index + 2] = (((pixel >> 10) & 0x1f) << 3) as u8;
+            }
+        }
+    }
+
+    /// Splits a span into the parts before and after it wraps around a VRAM edge
+    ///
+    /// Arguments:
+    ///
+    /// * `start`: The start of the span, inside the VRAM
+    /// * `length`: The length of the span
+    /// * `size`: The size of the VRAM along the span
+    fn wrapped_spans(start: u32, length: u32, size: u32) -> [(u32, u32); 2] {
+        let length = length.min(size);
+        let first = length.min(size - start);
+        [(start, first), (0, length - first)]
+    }
+
+    /// Creates a black RGBA framebuffer
+    ///
+    /// Arguments:
+    ///
+    /// * `scale`: The internal scale of the framebuffer
+    fn create_frame(scale: u32) -> Vec<u8> {
+        [0x00, 0x00, 0x00, 0xff].repeat((Self::WIDTH * Self::HEIGHT * scale * scale) as usize)
     }
 }
 
 impl Renderer for SoftwareRenderer {
-    fn render(&mut self) {
-        self.pixels.render().unwrap();
+    #[cfg_attr(not(feature = "window"), allow(unused_variables))]
+    fn render(&mut self, display_area: Option<Rect>) {
+        #[cfg(feature = "window")]
+        if let Some(presenter) = &mut self.presenter {
+            let frame_size = Vector2 {
+                x: Self::WIDTH * self.internal_scale,
+                y: Self::HEIGHT * self.internal_scale,
+            };
+            presenter.present(&self.frame, frame_size, self.internal_scale, display_area);
+        }
     }
 
+    #[cfg(feature = "window")]
     fn resize(&mut self, size: Vector2<u32>) {
-        self.pixels.resize_surface(size.x, size.y).unwrap();
-        self.size = size;
+        if let Some(presenter) = &mut self.presenter {
+            presenter.resize(size);
+        }
+    }
+
+    #[cfg_attr(not(feature = "window"), allow(unused_variables))]
+    fn set_scaling(&mut self, scaling: Scaling) {
+        #[cfg(feature = "window")]
+        if let Some(presenter) = &mut self.presenter {
+            presenter.set_scaling(scaling);
+        }
+    }
+
+    fn frame(&self) -> &[u8] {
+        &self.frame
+    }
+
+    fn vram(&self) -> &[u16] {
+        &self.vram
+    }
+
+    fn load_vram(&mut self, data: &[u16]) {
+        let length = data.len().min(self.vram.len());
+        self.vram[..length].copy_from_slice(&data[..length]);
+
+        self.refresh_frame(Rect {
+            x: 0,
+            y: 0,
+            width: Self::WIDTH,
+            height: Self::HEIGHT,
+        });
+    }
+
+    fn upload_vram(&mut self, x: u16, y: u16, width: u16, height: u16, data: &[u16]) {
+        let (x, y) = (x as u32 % Self::WIDTH, y as u32 % Self::HEIGHT);
+        let (width, height) = (width as u32, height as u32);
+        for (index, &pixel) in data.iter().take((width * height) as usize).enumerate() {
+            let row = index as u32 / width;
+            let column = index as u32 % width;
+            let vram_x = (x + column) % Self::WIDTH;
+            let vram_y = (y + row) % Self::HEIGHT;
+            self.vram[(vram_y * Self::WIDTH + vram_x) as usize] = pixel;
+        }
+
+        // A rectangle crossing the VRAM edges continues on the opposite side
+        let columns = Self::wrapped_spans(x, width, Self::WIDTH);
+        let rows = Self::wrapped_spans(y, height, Self::HEIGHT);
+        for (x, width) in columns {
+            for (y, height) in rows {
+                self.refresh_frame(Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                });
+            }
+        }
+    }
+
+    fn set_internal_scale(&mut self, scale: u32) {
+        let scale = scale.clamp(1, Self::MAX_INTERNAL_SCALE);
+        if scale == self.internal_scale {
+            return;
+        }
+
+        // The previous samples are lost, so the frame is rebuilt from VRAM
+        self.internal_scale = scale;
+        self.frame = Self::create_frame(scale);
+        self.refresh_frame(Rect {
+            x: 0,
+            y: 0,
+            width: Self::WIDTH,
+            height: Self::HEIGHT,
+        });
     }
 
-    fn draw_quad(&mut self, positions: [Position; 4], colors: [Color; 4]) {
+    fn internal_scale(&self) -> u32 {
+        self.internal_scale
+    }
+
+    fn set_mask_bit_setting(&mut self, set_mask: bool, check_mask: bool) {
+        self.set_mask = set_mask;
+        self.check_mask = check_mask;
+    }
+
+    fn set_skipped_lines(&mut self, skipped_lines: Option<SkippedLines>) {
+        self.skipped_lines = skipped_lines;
+    }
+
+    fn draw_quad(
+        &mut self,
+        positions: [Position; 4],
+        colors: [Color; 4],
+        blend_mode: Option<BlendMode>,
+    ) {
         self.draw_triangle(
             [positions[0], positions[2], positions[1]],
             [colors[0], colors[2], colors[1]],
+            blend_mode,
         );
         self.draw_triangle(
             [positions[1], positions[2], positions[3]],
             [colors[1], colors[2], colors[3]],
+            blend_mode,
         );
     }
 
-    fn draw_triangle(&mut self, positions: [Position; 3], colors: [Color; 3]) {
-        let mut bbox_min = Vector2 {
-            x: f32::MAX,
-            y: f32::MAX,
-        };
-        let mut bbox_max = Vector2 {
-            x: f32::MIN,
-            y: f32::MIN,
-        };
+    fn draw_triangle(
+        &mut self,
+        positions: [Position; 3],
+        colors: [Color; 3],
+        blend_mode: Option<BlendMode>,
+    ) {
+        self.rasterize(positions, blend_mode, |_, barycentric| {
+            Some((barycentric.interpolate_color(colors), true))
+        });
+    }
+
+    fn draw_textured_quad(
+        &mut self,
+        positions: [Position; 4],
+        tex_coords: [TexCoord; 4],
+        colors: Option<[Color; 4]>,
+        texture: Texture,
+        blend_mode: Option<BlendMode>,
+    ) {
+        self.draw_textured_triangle(
+            [positions[0], positions[2], positions[1]],
+            [tex_coords[0], tex_coords[2], tex_coords[1]],
+            colors.map(|colors| [colors[0], colors[2], colors[1]]),
+            texture,
+            blend_mode,
+        );
+        self.draw_textured_triangle(
+            [positions[1], positions[2], positions[3]],
+            [tex_coords[1], tex_coords[2], tex_coords[3]],
+            colors.map(|colors| [colors[1], colors[2], colors[3]]),
+            texture,
+            blend_mode,
+        );
+    }
+
+    fn draw_textured_triangle(
+        &mut self,
+        positions: [Position; 3],
+        tex_coords: [TexCoord; 3],
+        colors: Option<[Color; 3]>,
+        texture: Texture,
+        blend_mode: Option<BlendMode>,
+    ) {
+        let [tex_coord_a, tex_coord_b, tex_coord_c] = tex_coords;
+        self.rasterize(positions, blend_mode, |renderer, barycentric| {
+            let tex_coord = TexCoord {
+                x: barycentric.interpolate([tex_coord_a.x, tex_coord_b.x, tex_coord_c.x]),
+                y: barycentric.interpolate([tex_coord_a.y, tex_coord_b.y, tex_coord_c.y]),
+            };
+
+            let (texel, semi_transparent) = renderer.sample_texture(texture, tex_coord)?;
+            let Some(colors) = colors else {
+                return Some((texel, semi_transparent));
+            };
+
+            let color = barycentric.interpolate_color(colors);
+            Some((Self::blend_texel(texel, color), semi_transparent))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{TextureDepth, TextureWindow};
+
+    /// Returns the indices of the pixels a triangle covers
+    fn coverage(positions: [Position; 3]) -> Vec<usize> {
+        let mut renderer = SoftwareRenderer::new_headless();
+        renderer.draw_triangle(positions, [Color::new(0xff, 0xff, 0xff); 3], None);
+
+        renderer
+            .frame()
+            .chunks_exact(4)
+            .enumerate()
+            .filter(|(_, pixel)| pixel[0] != 0)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    #[test]
+    fn shared_edge_is_drawn_once() {
+        let top_left = Position::new(10, 10);
+        let top_right = Position::new(30, 10);
+        let bottom_left = Position::new(10, 30);
+        let bottom_right = Position::new(30, 30);
+
+        let first = coverage([top_left, bottom_left, top_right]);
+        let second = coverage([top_right, bottom_left, bottom_right]);
+
+        // No pixel of the diagonal is drawn twice and none is skipped
+        assert!(first.iter().all(|index| !second.contains(index)));
+        assert_eq!(first.len() + second.len(), 20 * 20);
+    }
+
+    #[test]
+    fn both_windings_cover_the_same_pixels() {
+        let a = Position::new(12, 3);
+        let b = Position::new(40, 21);
+        let c = Position::new(5, 33);
+        let colors = [
+            Color::new(0xff, 0x00, 0x00),
+            Color::new(0x00, 0xff, 0x00),
+            Color::new(0x00, 0x00, 0xff),
+        ];
+
+        let mut clockwise = SoftwareRenderer::new_headless();
+        clockwise.draw_triangle([a, b, c], colors, None);
+
+        let mut counter_clockwise = SoftwareRenderer::new_headless();
+        counter_clockwise.draw_triangle([a, c, b], [colors[0], colors[2], colors[1]], None);
+
+        assert!(clockwise.frame() == counter_clockwise.frame());
+        assert!(clockwise
+            .frame()
+            .chunks_exact(4)
+            .any(|pixel| pixel[..3] != [0, 0, 0]));
+    }
 
-        let clamp = Vector2 {
-            x: (self.size.x - 1) as f32,
-            y: (self.size.y - 1) as f32,
+    #[test]
+    fn shared_edge_is_drawn_once_with_mixed_windings() {
+        let top_left = Position::new(10, 10);
+        let top_right = Position::new(30, 10);
+        let bottom_left = Position::new(10, 30);
+        let bottom_right = Position::new(30, 30);
+
+        let first = coverage([top_left, bottom_left, top_right]);
+        let second = coverage([top_right, bottom_right, bottom_left]);
+
+        assert!(first.iter().all(|index| !second.contains(index)));
+        assert_eq!(first.len() + second.len(), 20 * 20);
+    }
+
+    #[test]
+    fn textured_triangle_samples_through_the_texture_window() {
+        let mut renderer = SoftwareRenderer::new_headless();
+
+        // Green texels in the first 8 columns of the texture page and red ones in the next 8
+        for y in 0..16 {
+            for x in 0..16 {
+                let texel = if x < 8 { 0x03e0 } else { 0x001f };
+                renderer.vram[(y * SoftwareRenderer::WIDTH + 64 + x) as usize] = texel;
+            }
+        }
+
+        // The window forces bit 3 of u on, so only the red columns get sampled
+        let texture = Texture {
+            page_x: 64,
+            page_y: 0,
+            depth: TextureDepth::Bit15,
+            clut_x: 0,
+            clut_y: 0,
+            window: TextureWindow {
+                x_mask: 0x01,
+                y_mask: 0x00,
+                x_offset: 0x01,
+                y_offset: 0x00,
+            },
         };
-        for position in positions {
-            bbox_min.x = 0.0f32.max(bbox_min.x.min(position.x as f32));
-            bbox_max.x = clamp.x.min(bbox_max.x.max(position.x as f32));
+        renderer.draw_textured_triangle(
+            [
+                Position::new(0, 0),
+                Position::new(8, 0),
+                Position::new(0, 8),
+            ],
+            [
+                TexCoord::new(0, 0),
+                TexCoord::new(8, 0),
+                TexCoord::new(0, 8),
+            ],
+            None,
+            texture,
+            None,
+        );
 
-            bbox_min.y = 0.0f32.max(bbox_min.y.min(position.y as f32));
-            bbox_max.y = clamp.y.min(bbox_max.y.max(position.y as f32));
+        let drawn = renderer
+            .frame()
+            .chunks_exact(4)
+            .filter(|pixel| pixel[..3] != [0, 0, 0])
+            .collect::<Vec<_>>();
+        assert!(!drawn.is_empty());
+        assert!(drawn.iter().all(|pixel| pixel[..3] == [0xf8, 0x00, 0x00]));
+    }
+
+    /// Red, green and blue
+    const PALETTE: [u16; 3] = [0x001f, 0x03e0, 0x7c00];
+
+    /// Returns the 24-bit color of a palette entry
+    fn palette_color(index: usize) -> Color {
+        [
+            Color::new(0xf8, 0x00, 0x00),
+            Color::new(0x00, 0xf8, 0x00),
+            Color::new(0x00, 0x00, 0xf8),
+        ][index]
+    }
+
+    /// Returns a texture at (64, 0) with its CLUT at (clut_x, 256)
+    fn clut_texture(depth: TextureDepth, clut_x: u16) -> Texture {
+        Texture {
+            page_x: 64,
+            page_y: 0,
+            depth,
+            clut_x,
+            clut_y: 256,
+            window: TextureWindow::default(),
         }
+    }
 
-        for x in (bbox_min.x as i32)..=(bbox_max.x as i32) {
-            for y in (bbox_min.y as i32)..=(bbox_max.y as i32) {
-                let a = Vector2 {
-                    x: positions[0].x as f32,
-                    y: positions[0].y as f32,
-                };
+    #[test]
+    fn four_bit_texels_index_the_clut() {
+        let mut renderer = SoftwareRenderer::new_headless();
 
-                let b = Vector2 {
-                    x: positions[1].x as f32,
-                    y: positions[1].y as f32,
-                };
+        // Indices 0, 1, 2 and 3, with index 0 being transparent black
+        renderer.vram[64] = 0x3210;
+        for (index, color) in PALETTE.into_iter().enumerate() {
+            renderer.vram[(256 * SoftwareRenderer::WIDTH + 16 + 1 + index as u32) as usize] = color;
+        }
 
-                let c = Vector2 {
-                    x: positions[2].x as f32,
-                    y: positions[2].y as f32,
-                };
+        let texture = clut_texture(TextureDepth::Bit4, 16);
+        assert_eq!(renderer.sample_texture(texture, TexCoord::new(0, 0)), None);
+        for index in 0..3 {
+            assert_eq!(
+                renderer.sample_texture(texture, TexCoord::new(index as u8 + 1, 0)),
+                Some((palette_color(index), false))
+            );
+        }
+    }
 
-                let p = Vector2 {
-                    x: x as f32,
-                    y: y as f32,
-                };
+    #[test]
+    fn eight_bit_texels_index_the_clut() {
+        let mut renderer = SoftwareRenderer::new_headless();
 
-                let v0 = b - a;
-                let v1 = c - a;
-                let v2 = p - a;
+        // Indices 0x80 and 0x82 in the first halfword, 0x81 in the second
+        renderer.vram[64] = 0x8280;
+        renderer.vram[65] = 0x0081;
+        for (index, color) in PALETTE.into_iter().enumerate() {
+            renderer.vram[(256 * SoftwareRenderer::WIDTH + 32 + 0x80 + index as u32) as usize] =
+                color;
+        }
 
-                let denominator = v0.x * v1.y - v1.x * v0.y;
+        let texture = clut_texture(TextureDepth::Bit8, 32);
+        assert_eq!(
+            renderer.sample_texture(texture, TexCoord::new(0, 0)),
+            Some((palette_color(0), false))
+        );
+        assert_eq!(
+            renderer.sample_texture(texture, TexCoord::new(1, 0)),
+            Some((palette_color(2), false))
+        );
+        assert_eq!(
+            renderer.sample_texture(texture, TexCoord::new(2, 0)),
+            Some((palette_color(1), false))
+        );
+    }
 
-                let v = (v2.x * v1.y - v1.x * v2.y) / denominator;
-                let w = (v0.x * v2.y - v2.x * v0.y) / denominator;
-                let u = 1.0 - v - w;
+    #[test]
+    fn transparent_texels_are_not_drawn() {
+        let mut renderer = SoftwareRenderer::new_headless();
 
-                // The point lies outside of the triangle
-                if v <= f32::EPSILON || w + f32::EPSILON < 0.0 || u + f32::EPSILON < 0.0 {
-                    continue;
-                }
+        // A CLUT whose every entry is transparent black
+        renderer.draw_textured_triangle(
+            [
+                Position::new(0, 0),
+                Position::new(16, 0),
+                Position::new(0, 16),
+            ],
+            [
+                TexCoord::new(0, 0),
+                TexCoord::new(16, 0),
+                TexCoord::new(0, 16),
+            ],
+            None,
+            clut_texture(TextureDepth::Bit4, 16),
+            None,
+        );
 
-                let a_color = Vector3 {
-                    x: colors[0].x as f32,
-                    y: colors[0].y as f32,
-                    z: colors[0].z as f32,
-                };
+        assert!(renderer
+            .frame()
+            .chunks_exact(4)
+            .all(|pixel| pixel[..3] == [0, 0, 0]));
+    }
 
-                let b_color = Vector3 {
-                    x: colors[1].x as f32,
-                    y: colors[1].y as f32,
-                    z: colors[1].z as f32,
-                };
+    #[test]
+    fn vertex_colors_modulate_the_texels() {
+        let mut renderer = SoftwareRenderer::new_headless();
 
-                let c_color = Vector3 {
-                    x: colors[2].x as f32,
-                    y: colors[2].y as f32,
-                    z: colors[2].z as f32,
-                };
+        // Mid-gray texels, which become 0x80 on every channel
+        for y in 0..16 {
+            for x in 0..16 {
+                renderer.vram[(y * SoftwareRenderer::WIDTH + 64 + x) as usize] = 0x4210;
+            }
+        }
+
+        let texture = Texture {
+            page_x: 64,
+            page_y: 0,
+            depth: TextureDepth::Bit15,
+            clut_x: 0,
+            clut_y: 0,
+            window: TextureWindow::default(),
+        };
+        renderer.draw_textured_triangle(
+            [
+                Position::new(0, 0),
+                Position::new(16, 0),
+                Position::new(0, 16),
+            ],
+            [
+                TexCoord::new(0, 0),
+                TexCoord::new(16, 0),
+                TexCoord::new(0, 16),
+            ],
+            Some([Color::new(0xff, 0x80, 0x40); 3]),
+            texture,
+            None,
+        );
+
+        // 0x80 leaves the texel unchanged, 0xff doubles it
+        let pixel = &renderer.frame()[(4 * SoftwareRenderer::WIDTH + 4) as usize * 4..][..3];
+        assert_eq!(pixel, [0xff, 0x80, 0x40]);
+    }
 
-                let color = b_color * v + a_color * u + c_color * w;
+    #[test]
+    fn blended_texels_saturate() {
+        let texel = Color::new(0xf8, 0x80, 0x00);
+        let color = Color::new(0xff, 0x00, 0xff);
 
-                let index = ((y as u32 * 1024 + x as u32) * 4) as usize;
-                let buffer = self.pixels.frame_mut();
-                buffer[index] = color.x as u8;
-                buffer[index + 1] = color.y as u8;
-                buffer[index + 2] = color.z as u8;
+        assert_eq!(
+            SoftwareRenderer::blend_texel(texel, color),
+            Color::new(0xff, 0x00, 0x00)
+        );
+    }
+
+    #[test]
+    fn raw_texels_ignore_the_vertex_colors() {
+        let draw = |colors| {
+            let mut renderer = SoftwareRenderer::new_headless();
+            for y in 0..16 {
+                for x in 0..16 {
+                    renderer.vram[(y * SoftwareRenderer::WIDTH + 64 + x) as usize] = 0x4210;
+                }
             }
+
+            let texture = Texture {
+                page_x: 64,
+                page_y: 0,
+                depth: TextureDepth::Bit15,
+                clut_x: 0,
+                clut_y: 0,
+                window: TextureWindow::default(),
+            };
+            renderer.draw_textured_triangle(
+                [
+                    Position::new(0, 0),
+                    Position::new(16, 0),
+                    Position::new(0, 16),
+                ],
+                [
+                    TexCoord::new(0, 0),
+                    TexCoord::new(16, 0),
+                    TexCoord::new(0, 16),
+                ],
+                colors,
+                texture,
+                None,
+            );
+
+            renderer.frame()[(4 * SoftwareRenderer::WIDTH + 4) as usize * 4..][..3].to_vec()
+        };
+
+        let color = Color::new(0x40, 0x40, 0x40);
+        assert_eq!(draw(None), [0x80, 0x80, 0x80]);
+        assert_eq!(draw(Some([color; 3])), [0x40, 0x40, 0x40]);
+    }
+
+    #[test]
+    fn semi_transparent_triangles_blend_with_the_background() {
+        let positions = [
+            Position::new(0, 0),
+            Position::new(16, 0),
+            Position::new(0, 16),
+        ];
+
+        for (blend_mode, expected) in [
+            (BlendMode::Average, [0x60, 0x80, 0xa0]),
+            (BlendMode::Add, [0xc0, 0xff, 0xff]),
+            (BlendMode::Subtract, [0x40, 0x00, 0x00]),
+            (BlendMode::AddQuarter, [0x90, 0xa0, 0xb0]),
+        ] {
+            let mut renderer = SoftwareRenderer::new_headless();
+            renderer.draw_triangle(positions, [Color::new(0x80, 0x80, 0x80); 3], None);
+            renderer.draw_triangle(
+                positions,
+                [Color::new(0x40, 0x80, 0xc0); 3],
+                Some(blend_mode),
+            );
+
+            let pixel = &renderer.frame()[(4 * SoftwareRenderer::WIDTH + 4) as usize * 4..][..3];
+            assert_eq!(pixel, expected, "{:?}", blend_mode);
+        }
+    }
+
+    #[test]
+    fn far_off_screen_vertices_only_touch_the_frame() {
+        let positions = [
+            Position::new(-5000, 3),
+            Position::new(2000, 200),
+            Position::new(-5000, 200),
+        ];
+
+        let covered = coverage(positions);
+        let index = |x: u32, y: u32| (y * SoftwareRenderer::WIDTH + x) as usize;
+        assert!(covered.contains(&index(0, 199)));
+        assert!(covered.contains(&index(1023, 190)));
+        assert!(!covered.contains(&index(1023, 150)));
+        assert!(covered
+            .iter()
+            .all(|&index| (3..=200).contains(&(index as u32 / SoftwareRenderer::WIDTH))));
+
+        // Scaled up, the vertices no longer fit into 16 bits
+        let mut renderer = SoftwareRenderer::new_headless();
+        renderer.set_internal_scale(SoftwareRenderer::MAX_INTERNAL_SCALE);
+        renderer.draw_triangle(positions, [Color::new(0xff, 0xff, 0xff); 3], None);
+
+        let scale = SoftwareRenderer::MAX_INTERNAL_SCALE;
+        let pixel = ((199 * scale * SoftwareRenderer::WIDTH * scale) * 4) as usize;
+        assert_eq!(renderer.frame()[pixel..pixel + 3], [0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn vertex_colors_are_exact_at_their_vertices() {
+        let red = Color::new(0xff, 0x00, 0x00);
+        let green = Color::new(0x00, 0xff, 0x00);
+        let blue = Color::new(0x00, 0x00, 0xff);
+
+        // All weight on one vertex yields its color unchanged
+        for (index, color) in [red, green, blue].into_iter().enumerate() {
+            let mut weights = [0; 3];
+            weights[index] = 64;
+            let barycentric = Barycentric { weights, area: 64 };
+            assert_eq!(barycentric.interpolate_color([red, green, blue]), color);
+        }
+
+        // The top left vertex is the only one covered by its own triangle, whichever position
+        // and winding it gets
+        let vertices = [
+            (Position::new(0, 0), red),
+            (Position::new(64, 0), green),
+            (Position::new(0, 64), blue),
+        ];
+        for [a, b, c] in [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ] {
+            let mut renderer = SoftwareRenderer::new_headless();
+            renderer.draw_triangle(
+                [vertices[a].0, vertices[b].0, vertices[c].0],
+                [vertices[a].1, vertices[b].1, vertices[c].1],
+                None,
+            );
+
+            assert_eq!(renderer.frame()[..3], [0xff, 0x00, 0x00]);
         }
     }
 }
@@ -4,6 +4,8 @@
  * SPDX-License-Identifier: MIT
  */
 
+use crate::sio::controller::Button;
+
 use cgmath::Vector2;
 use glfw::{Action, Context, Glfw, InitError, Key, WindowEvent, WindowMode};
 use std::sync::mpsc::Receiver;
@@ -77,6 +79,33 @@ impl Window {
         }
     }
 
+    /// Maps a keyboard key to the digital pad button
+    ///
+    /// Arguments:
+    ///
+    /// * `key`: The keyboard key
+    pub(crate) fn button(key: Key) -> Option<Button> {
+        let button = match key {
+            Key::Up => Button::Up,
+            Key::Down => Button::Down,
+            Key::Left => Button::Left,
+            Key::Right => Button::Right,
+            Key::Enter => Button::Start,
+            Key::Backspace => Button::Select,
+            Key::X => Button::Cross,
+            Key::D => Button::Circle,
+            Key::Z => Button::Square,
+            Key::S => Button::Triangle,
+            Key::Q => Button::L1,
+            Key::E => Button::R1,
+            Key::Num1 => Button::L2,
+            Key::Num3 => Button::R2,
+            _ => return None,
+        };
+
+        Some(button)
+    }
+
     /// Tells if the window should close
     pub(crate) fn should_close(&self) -> bool {
         self.window.should_close()
@@ -0,0 +1,138 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use serde::{Deserialize, Serialize};
+
+/// The buttons of the digital pad, the value is the bit in the button word
+///
+/// <https://psx-spx.consoledev.net/controllersandmemorycards/#standard-controllers>
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Button {
+    /// Select button
+    Select = 0,
+
+    /// Start button
+    Start = 3,
+
+    /// Joypad up
+    Up = 4,
+
+    /// Joypad right
+    Right = 5,
+
+    /// Joypad down
+    Down = 6,
+
+    /// Joypad left
+    Left = 7,
+
+    /// L2 button
+    L2 = 8,
+
+    /// R2 button
+    R2 = 9,
+
+    /// L1 button
+    L1 = 10,
+
+    /// R1 button
+    R1 = 11,
+
+    /// Triangle button
+    Triangle = 12,
+
+    /// Circle button
+    Circle = 13,
+
+    /// Cross button
+    Cross = 14,
+
+    /// Square button
+    Square = 15,
+}
+
+/// The communication sequence state of the pad
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum State {
+    /// Waiting for the address byte
+    #[default]
+    Idle,
+
+    /// Waiting for the read command
+    Command,
+
+    /// Sending the second ID byte
+    Id,
+
+    /// Sending the low byte of the buttons
+    ButtonsLow,
+
+    /// Sending the high byte of the buttons
+    ButtonsHigh,
+}
+
+/// The digital pad (SCPH-1080)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Controller {
+    /// The button word, a cleared bit means pressed
+    buttons: u16,
+
+    /// The communication sequence state
+    state: State,
+}
+
+impl Controller {
+    /// Creates a digital pad with no buttons pressed
+    pub(crate) fn new() -> Self {
+        Self {
+            buttons: 0xffff,
+            state: State::default(),
+        }
+    }
+
+    /// Presses or releases a button
+    ///
+    /// # Arguments:
+    ///
+    /// * `button`: The button
+    /// * `pressed`: If the button is held down
+    pub(crate) fn set_button(&mut self, button: Button, pressed: bool) {
+        let mask = 1 << button as u16;
+        if pressed {
+            self.buttons &= !mask;
+        } else {
+            self.buttons |= mask;
+        }
+    }
+
+    /// Ends the current communication sequence
+    pub(crate) fn deselect(&mut self) {
+        self.state = State::Idle;
+    }
+
+    /// Exchanges a byte with the pad
+    ///
+    /// # Arguments:
+    ///
+    /// * `value`: The byte sent by the console
+    ///
+    /// Returns the response byte and if the pad acknowledged to receive more bytes
+    pub(crate) fn transfer(&mut self, value: u8) -> (u8, bool) {
+        let (response, next_state) = match self.state {
+            State::Idle if value == 0x01 => (0xff, State::Command),
+            State::Idle => return (0xff, false),
+            State::Command if value == 0x42 => (0x41, State::Id),
+            State::Command => (0xff, State::Idle),
+            State::Id => (0x5a, State::ButtonsLow),
+            State::ButtonsLow => (self.buttons as u8, State::ButtonsHigh),
+            State::ButtonsHigh => ((self.buttons >> 8) as u8, State::Idle),
+        };
+
+        self.state = next_state;
+
+        (response, next_state != State::Idle)
+    }
+}
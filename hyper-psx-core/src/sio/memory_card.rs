@@ -0,0 +1,429 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+use thiserror::Error;
+
+/// The error type of the creation process of the memory card
+#[derive(Debug, Error)]
+pub enum CreationError {
+    /// If the memory card file failed to open
+    #[error("failed to open memory card: '{1}'")]
+    OpenFailure(#[source] io::Error, String),
+
+    /// If the memory card file failed to be read from
+    #[error("failed to read memory card: '{1}'")]
+    ReadingFailure(#[source] io::Error, String),
+
+    /// If the memory card file doesn't have the size of a memory card
+    #[error("invalid memory card size: {0} bytes, expected {1} bytes")]
+    InvalidSize(u64, usize),
+}
+
+/// The communication sequence state of the memory card
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum State {
+    /// Waiting for the address byte
+    #[default]
+    Idle,
+
+    /// Waiting for the read or write command
+    Command,
+
+    /// Sending the first ID byte
+    Id1,
+
+    /// Sending the second ID byte
+    Id2,
+
+    /// Receiving the high byte of the sector
+    AddressHigh,
+
+    /// Receiving the low byte of the sector
+    AddressLow,
+
+    /// Sending the first command acknowledge
+    ReadAck1,
+
+    /// Sending the second command acknowledge
+    ReadAck2,
+
+    /// Sending the high byte of the confirmed sector
+    ReadConfirmHigh,
+
+    /// Sending the low byte of the confirmed sector
+    ReadConfirmLow,
+
+    /// Sending the data of the sector
+    ReadData,
+
+    /// Sending the checksum
+    ReadChecksum,
+
+    /// Receiving the data of the sector
+    WriteData,
+
+    /// Receiving the checksum
+    WriteChecksum,
+
+    /// Sending the first command acknowledge
+    WriteAck1,
+
+    /// Sending the second command acknowledge
+    WriteAck2,
+
+    /// Sending the end byte
+    End,
+}
+
+/// The command of the current communication sequence
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Command {
+    /// Read a sector
+    #[default]
+    Read,
+
+    /// Write a sector
+    Write,
+}
+
+/// A memory card backed by a file
+///
+/// <https://psx-spx.consoledev.net/controllersandmemorycards/#memory-card-readwrite-commands>
+#[derive(Debug)]
+pub(crate) struct MemoryCard {
+    /// The contents of the memory card
+    data: Vec<u8>,
+
+    /// The backing file
+    file: File,
+
+    /// The FLAG byte, bit 3 is set until the first write
+    flag: u8,
+
+    /// The communication sequence state
+    state: State,
+
+    /// The command of the current communication sequence
+    command: Command,
+
+    /// The sector of the current command
+    sector: u16,
+
+    /// The index of the current data byte
+    index: usize,
+
+    /// The running checksum
+    checksum: u8,
+
+    /// The last received byte, echoed one byte later while writing
+    previous: u8,
+
+    /// The end byte reported after the command
+    end: u8,
+
+    /// The sector received by a write command
+    buffer: [u8; Self::SECTOR_SIZE],
+}
+
+impl MemoryCard {
+    /// The size of a sector
+    const SECTOR_SIZE: usize = 128;
+
+    /// The amount of sectors on a memory card
+    const SECTOR_COUNT: usize = 1024;
+
+    /// The size of a memory card (128KB)
+    const SIZE: usize = Self::SECTOR_SIZE * Self::SECTOR_COUNT;
+
+    /// Opens a memory card file, creating an empty one if it doesn't exist
+    ///
+    /// # Arguments:
+    ///
+    /// * `path`: The path of the memory card file
+    pub(crate) fn new<P: AsRef<Path>>(path: P) -> Result<Self, CreationError> {
+        let path_display = path.as_ref().display().to_string();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(|error| CreationError::OpenFailure(error, path_display.clone()))?;
+
+        let length = file
+            .metadata()
+            .map_err(|error| CreationError::ReadingFailure(error, path_display.clone()))?
+            .len();
+
+        let mut data = vec![0x00; Self::SIZE];
+        match length {
+            0 => {
+                file.set_len(Self::SIZE as u64)
+                    .map_err(|error| CreationError::OpenFailure(error, path_display.clone()))?;
+            }
+            length if length == Self::SIZE as u64 => {
+                file.read_exact(&mut data)
+                    .map_err(|error| CreationError::ReadingFailure(error, path_display.clone()))?;
+            }
+            length => return Err(CreationError::InvalidSize(length, Self::SIZE)),
+        }
+
+        log::info!("Loaded memory card from '{}'", path_display);
+
+        Ok(Self {
+            data,
+            file,
+            flag: 0x08,
+            state: State::default(),
+            command: Command::default(),
+            sector: 0,
+            index: 0,
+            checksum: 0,
+            previous: 0,
+            end: 0,
+            buffer: [0x00; Self::SECTOR_SIZE],
+        })
+    }
+
+    /// Ends the current communication sequence
+    pub(crate) fn deselect(&mut self) {
+        self.state = State::Idle;
+    }
+
+    /// Exchanges a byte with the memory card
+    ///
+    /// # Arguments:
+    ///
+    /// * `value`: The byte sent by the console
+    ///
+    /// Returns the response byte and if the memory card acknowledged to receive more bytes
+    pub(crate) fn transfer(&mut self, value: u8) -> (u8, bool) {
+        let (response, next_state) = match self.state {
+            State::Idle if value == 0x81 => (0xff, State::Command),
+            State::Idle => return (0xff, false),
+            State::Command => {
+                self.command = match value {
+                    b'R' => Command::Read,
+                    b'W' => Command::Write,
+                    _ => {
+                        log::warn!(target: "sio", "Unhandled memory card command {:#04x}", value);
+                        self.state = State::Idle;
+                        return (self.flag, false);
+                    }
+                };
+
+                (self.flag, State::Id1)
+            }
+            State::Id1 => (0x5a, State::Id2),
+            State::Id2 => (0x5d, State::AddressHigh),
+            State::AddressHigh => {
+                self.sector = (value as u16) << 8;
+                self.checksum = value;
+                self.previous = value;
+                (0x00, State::AddressLow)
+            }
+            State::AddressLow => {
+                self.sector |= value as u16;
+                self.checksum ^= value;
+                self.index = 0;
+
+                let response = self.previous;
+                self.previous = value;
+
+                match self.command {
+                    Command::Read => (response, State::ReadAck1),
+                    Command::Write => (response, State::WriteData),
+                }
+            }
+            State::ReadAck1 => (0x5c, State::ReadAck2),
+            State::ReadAck2 => (0x5d, State::ReadConfirmHigh),
+            State::ReadConfirmHigh => {
+                if self.sector as usize >= Self::SECTOR_COUNT {
+                    self.state = State::Idle;
+                    return (0xff, false);
+                }
+
+                ((self.sector >> 8) as u8, State::ReadConfirmLow)
+            }
+            State::ReadConfirmLow => (self.sector as u8, State::ReadData),
+            State::ReadData => {
+                let byte = self.data[self.sector as usize * Self::SECTOR_SIZE + self.index];
+                self.checksum ^= byte;
+                self.index += 1;
+
+                if self.index == Self::SECTOR_SIZE {
+                    (byte, State::ReadChecksum)
+                } else {
+                    (byte, State::ReadData)
+                }
+            }
+            State::ReadChecksum => {
+                self.end = 0x47;
+                (self.checksum, State::End)
+            }
+            State::WriteData => {
+                self.buffer[self.index] = value;
+                self.checksum ^= value;
+                self.index += 1;
+
+                let response = self.previous;
+                self.previous = value;
+
+                if self.index == Self::SECTOR_SIZE {
+                    (response, State::WriteChecksum)
+                } else {
+                    (response, State::WriteData)
+                }
+            }
+            State::WriteChecksum => {
+                self.end = if self.sector as usize >= Self::SECTOR_COUNT {
+                    0xff
+                } else if value != self.checksum {
+                    0x4e
+                } else {
+                    self.write_sector();
+                    0x47
+                };
+
+                (self.previous, State::WriteAck1)
+            }
+            State::WriteAck1 => (0x5c, State::WriteAck2),
+            State::WriteAck2 => (0x5d, State::End),
+            State::End => (self.end, State::Idle),
+        };
+
+        self.state = next_state;
+
+        (response, next_state != State::Idle)
+    }
+
+    /// Stores the received sector and flushes it to the backing file
+    fn write_sector(&mut self) {
+        let offset = self.sector as usize * Self::SECTOR_SIZE;
+        self.data[offset..offset + Self::SECTOR_SIZE].copy_from_slice(&self.buffer);
+        self.flag &= !0x08;
+
+        let result = self
+            .file
+            .seek(SeekFrom::Start(offset as u64))
+            .and_then(|_| self.file.write_all(&self.buffer))
+            .and_then(|_| self.file.flush());
+
+        if let Err(error) = result {
+            log::error!(
+                target: "sio",
+                "Failed to flush memory card sector {}: {}",
+                self.sector,
+                error
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{fs, path::PathBuf};
+
+    /// Returns the path of a memory card file which doesn't exist yet
+    fn card_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("hyper-psx-{}-{}.mcr", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    /// Exchanges a sequence of bytes, returning the responses
+    fn exchange(card: &mut MemoryCard, bytes: &[u8]) -> Vec<u8> {
+        bytes.iter().map(|&value| card.transfer(value).0).collect()
+    }
+
+    fn write_sector(card: &mut MemoryCard, sector: u16, data: &[u8; 128], checksum: u8) -> Vec<u8> {
+        let mut bytes = vec![0x81, b'W', 0x00, 0x00, (sector >> 8) as u8, sector as u8];
+        bytes.extend_from_slice(data);
+        bytes.extend_from_slice(&[checksum, 0x00, 0x00, 0x00]);
+        exchange(card, &bytes)
+    }
+
+    fn read_sector(card: &mut MemoryCard, sector: u16) -> Vec<u8> {
+        let mut bytes = vec![0x81, b'R', 0x00, 0x00, (sector >> 8) as u8, sector as u8];
+        bytes.extend_from_slice(&[0x00; 4 + 128 + 2]);
+        exchange(card, &bytes)
+    }
+
+    fn checksum(sector: u16, data: &[u8; 128]) -> u8 {
+        data.iter()
+            .fold((sector >> 8) as u8 ^ sector as u8, |checksum, byte| {
+                checksum ^ byte
+            })
+    }
+
+    #[test]
+    fn written_sector_reads_back() {
+        let path = card_path("write-read");
+        let mut card = MemoryCard::new(&path).unwrap();
+
+        let mut data = [0x00; 128];
+        for (index, byte) in data.iter_mut().enumerate() {
+            *byte = index as u8 ^ 0xa5;
+        }
+
+        let sector = 0x0123;
+        let response = write_sector(&mut card, sector, &data, checksum(sector, &data));
+        assert_eq!(&response[..6], [0xff, 0x08, 0x5a, 0x5d, 0x00, 0x01]);
+        assert_eq!(response[6], 0x23);
+        assert_eq!(&response[7..134], &data[..127]);
+        assert_eq!(&response[134..], [data[127], 0x5c, 0x5d, 0x47]);
+
+        let response = read_sector(&mut card, sector);
+        assert_eq!(
+            &response[..10],
+            [0xff, 0x00, 0x5a, 0x5d, 0x00, 0x01, 0x5c, 0x5d, 0x01, 0x23]
+        );
+        assert_eq!(&response[10..138], data);
+        assert_eq!(&response[138..], [checksum(sector, &data), 0x47]);
+
+        // The sector was flushed to the backing file
+        drop(card);
+        let mut card = MemoryCard::new(&path).unwrap();
+        assert_eq!(&read_sector(&mut card, sector)[10..138], data);
+        assert_eq!(fs::metadata(&path).unwrap().len(), MemoryCard::SIZE as u64);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn bad_checksum_is_rejected() {
+        let path = card_path("bad-checksum");
+        let mut card = MemoryCard::new(&path).unwrap();
+
+        let data = [0xff; 128];
+        let response = write_sector(&mut card, 0x0002, &data, !checksum(0x0002, &data));
+        assert_eq!(response.last(), Some(&0x4e));
+        assert_eq!(&read_sector(&mut card, 0x0002)[10..138], [0x00; 128]);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_size_is_rejected() {
+        let path = card_path("invalid-size");
+        fs::write(&path, [0x00; 100]).unwrap();
+
+        assert!(matches!(
+            MemoryCard::new(&path),
+            Err(CreationError::InvalidSize(100, MemoryCard::SIZE))
+        ));
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,366 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+pub(crate) mod controller;
+pub(crate) mod memory_card;
+
+use crate::sio::{
+    controller::{Button, Controller},
+    memory_card::MemoryCard,
+};
+
+use serde::{Deserialize, Serialize};
+use std::mem;
+
+/// The device of slot 1 taking part in the current communication sequence
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum Device {
+    /// No device was addressed yet
+    #[default]
+    None,
+
+    /// The digital pad, addressed by 0x01
+    Controller,
+
+    /// The memory card, addressed by 0x81
+    MemoryCard,
+}
+
+/// The controller and memory card serial port (SIO0)
+///
+/// <https://psx-spx.consoledev.net/controllersandmemorycards/#controller-and-memory-card-io-ports>
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Sio {
+    /// The last received byte
+    rx_data: Option<u8>,
+
+    /// JOY_MODE - Mode register
+    mode: u16,
+
+    /// JOY_CTRL - Control register
+    control: u16,
+
+    /// JOY_BAUD - Baudrate reload value
+    baud: u16,
+
+    /// If the interrupt request flag is set (JOY_STAT bit 9)
+    interrupt: bool,
+
+    /// If the /ACK input is low (JOY_STAT bit 7)
+    ack_input: bool,
+
+    /// The cycles until the acknowledge of the device raises the interrupt
+    ack_countdown: Option<u32>,
+
+    /// The device addressed by the current communication sequence
+    device: Device,
+
+    /// The digital pad in slot 1
+    controller: Controller,
+
+    /// The memory card in slot 1, the backing file is not part of save states
+    #[serde(skip)]
+    memory_card: Option<MemoryCard>,
+}
+
+impl Sio {
+    /// The cycles from writing a byte until the device acknowledges it
+    const ACK_DELAY: u32 = 1088;
+
+    /// Creates the serial port with a digital pad in slot 1
+    ///
+    /// # Arguments:
+    ///
+    /// * `memory_card`: The optional memory card in slot 1
+    pub(crate) fn new(memory_card: Option<MemoryCard>) -> Self {
+        Self {
+            rx_data: None,
+            mode: 0,
+            control: 0,
+            baud: 0,
+            interrupt: false,
+            ack_input: false,
+            ack_countdown: None,
+            device: Device::None,
+            controller: Controller::new(),
+            memory_card,
+        }
+    }
+
+    /// Moves the memory card from the previous serial port into this restored one
+    ///
+    /// # Arguments:
+    ///
+    /// * `previous`: The serial port which gets replaced
+    pub(crate) fn reattach(&mut self, previous: &mut Self) {
+        mem::swap(&mut self.memory_card, &mut previous.memory_card);
+    }
+
+    /// Executes a number of cycles
+    ///
+    /// Arguments:
+    ///
+    /// * `cycles`: The amount of cycles which passed
+    ///
+    /// Returns if the controller interrupt (IRQ7) should be requested
+    pub(crate) fn step(&mut self, cycles: u32) -> bool {
+        let Some(countdown) = self.ack_countdown else {
+            return false;
+        };
+
+        if countdown > cycles {
+            self.ack_countdown = Some(countdown - cycles);
+            return false;
+        }
+
+        self.ack_countdown = None;
+        self.ack_input = true;
+
+        // JOY_CTRL bit 12 enables the interrupt on /ACK
+        if self.control & (1 << 12) == 0 {
+            return false;
+        }
+
+        self.interrupt = true;
+        true
+    }
+
+    /// Presses or releases a button of the digital pad
+    ///
+    /// # Arguments:
+    ///
+    /// * `button`: The button
+    /// * `pressed`: If the button is held down
+    pub(crate) fn set_button(&mut self, button: Button, pressed: bool) {
+        self.controller.set_button(button, pressed);
+    }
+
+    /// Sends a byte to the selected device
+    ///
+    /// # Arguments:
+    ///
+    /// * `value`: The byte to be sent
+    fn transfer(&mut self, value: u8) {
+        // JOY_CTRL bit 1 selects the device and bit 13 picks slot 1 or 2
+        let selected = self.control & (1 << 1) != 0;
+        let slot_2 = self.control & (1 << 13) != 0;
+
+        if !selected || slot_2 {
+            self.rx_data = Some(0xff);
+            self.ack_input = false;
+            self.ack_countdown = None;
+            return;
+        }
+
+        // The first byte of a sequence addresses either the pad or the memory card
+        if self.device == Device::None {
+            self.device = match value {
+                0x01 => Device::Controller,
+                0x81 if self.memory_card.is_some() => Device::MemoryCard,
+                _ => Device::None,
+            };
+        }
+
+        let (response, ack) = match (self.device, &mut self.memory_card) {
+            (Device::Controller, _) => self.controller.transfer(value),
+            (Device::MemoryCard, Some(memory_card)) => memory_card.transfer(value),
+            _ => (0xff, false),
+        };
+
+        if !ack {
+            self.device = Device::None;
+        }
+
+        self.rx_data = Some(response);
+        self.ack_input = false;
+        self.ack_countdown = ack.then_some(Self::ACK_DELAY);
+    }
+
+    /// Returns the JOY_STAT register
+    fn status(&self) -> u32 {
+        let mut status = 0;
+
+        // TX ready flags 1 and 2, transfers complete immediately
+        status |= 1 << 0;
+        status |= (self.rx_data.is_some() as u32) << 1;
+        status |= 1 << 2;
+        status |= (self.ack_input as u32) << 7;
+        status |= (self.interrupt as u32) << 9;
+
+        status
+    }
+
+    /// Writes to the JOY_CTRL register
+    ///
+    /// # Arguments:
+    ///
+    /// * `value`: The new control value
+    fn write_control(&mut self, value: u16) {
+        let previously_selected = self.control & (1 << 1) != 0;
+
+        // Bit 4 acknowledges the interrupt and bit 6 resets the port, both are write-only
+        if value & (1 << 4) != 0 {
+            self.interrupt = false;
+        }
+
+        if value & (1 << 6) != 0 {
+            let previous = mem::replace(self, Self::new(None));
+            self.controller = previous.controller;
+            self.memory_card = previous.memory_card;
+        }
+
+        self.control = value & !((1 << 4) | (1 << 6));
+
+        if previously_selected && self.control & (1 << 1) == 0 {
+            self.controller.deselect();
+            if let Some(memory_card) = &mut self.memory_card {
+                memory_card.deselect();
+            }
+
+            self.device = Device::None;
+            self.ack_countdown = None;
+        }
+    }
+
+    /// Reads a byte from a SIO0 register
+    ///
+    /// Unlike regular memory, reading the RX data consumes it
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative address offset
+    pub(crate) fn read_u8(&mut self, offset: u32) -> u8 {
+        match offset {
+            0x00 => self.rx_data.take().unwrap_or(0xff),
+            // Wider reads only preview the further FIFO entries
+            0x01..=0x03 => 0xff,
+            0x04..=0x07 => (self.status() >> ((offset - 0x04) * 8)) as u8,
+            0x08..=0x09 => (self.mode >> ((offset - 0x08) * 8)) as u8,
+            0x0a..=0x0b => (self.control >> ((offset - 0x0a) * 8)) as u8,
+            0x0e..=0x0f => (self.baud >> ((offset - 0x0e) * 8)) as u8,
+            _ => {
+                log::warn!(target: "sio", "Unhandled read from sio at {:#04x}", offset);
+                0x00
+            }
+        }
+    }
+
+    /// Writes a byte to a SIO0 register
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative address offset
+    /// * `value`: The value to be written
+    pub(crate) fn write_u8(&mut self, offset: u32, value: u8) {
+        match offset {
+            0x00 => self.transfer(value),
+            0x01..=0x03 => {}
+            0x08 => self.mode = (self.mode & 0xff00) | value as u16,
+            0x09 => self.mode = (self.mode & 0x00ff) | ((value as u16) << 8),
+            0x0a => self.write_control((self.control & 0xff00) | value as u16),
+            0x0b => self.write_control((self.control & 0x00ff) | ((value as u16) << 8)),
+            0x0e => self.baud = (self.baud & 0xff00) | value as u16,
+            0x0f => self.baud = (self.baud & 0x00ff) | ((value as u16) << 8),
+            _ => {
+                log::warn!(
+                    target: "sio",
+                    "Unhandled write to sio at {:#04x} with value {:#04x}",
+                    offset,
+                    value
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(sio: &mut Sio, value: u8) -> u8 {
+        sio.write_u8(0x00, value);
+        sio.step(Sio::ACK_DELAY);
+        sio.read_u8(0x00)
+    }
+
+    /// Exchanges the bytes of a button read with the selected pad
+    fn poll_pad(sio: &mut Sio) -> Vec<u8> {
+        [0x01, 0x42, 0x00, 0x00, 0x00]
+            .into_iter()
+            .map(|value| exchange(sio, value))
+            .collect()
+    }
+
+    #[test]
+    fn digital_pad_reports_the_pressed_buttons() {
+        let mut sio = Sio::new(None);
+        sio.set_button(Button::Cross, true);
+        sio.set_button(Button::Start, true);
+
+        // Select slot 1 with the /ACK interrupt enabled
+        sio.write_u8(0x0a, 0x02);
+        sio.write_u8(0x0b, 0x10);
+
+        let response = poll_pad(&mut sio);
+        assert_eq!(response, [0xff, 0x41, 0x5a, 0xf7, 0xbf]);
+
+        // The last byte isn't acknowledged, the earlier ones raised the interrupt
+        assert_eq!(sio.read_u8(0x04) & 0x80, 0);
+        assert_eq!(sio.read_u8(0x05) & 0x02, 0x02);
+
+        sio.write_u8(0x0a, 0x12);
+        assert_eq!(sio.read_u8(0x05) & 0x02, 0);
+
+        // Releasing the buttons is visible on the next poll
+        sio.set_button(Button::Cross, false);
+        sio.write_u8(0x0a, 0x00);
+        sio.write_u8(0x0a, 0x02);
+
+        let response = poll_pad(&mut sio);
+        assert_eq!(response, [0xff, 0x41, 0x5a, 0xf7, 0xff]);
+    }
+
+    #[test]
+    fn unknown_command_ends_the_sequence() {
+        let mut sio = Sio::new(None);
+        sio.write_u8(0x0a, 0x02);
+
+        assert_eq!(exchange(&mut sio, 0x01), 0xff);
+        assert_eq!(exchange(&mut sio, 0x43), 0xff);
+        assert!(sio.ack_countdown.is_none());
+    }
+
+    #[test]
+    fn deselected_pad_does_not_answer() {
+        let mut sio = Sio::new(None);
+
+        assert_eq!(exchange(&mut sio, 0x01), 0xff);
+        assert!(sio.ack_countdown.is_none());
+    }
+
+    #[test]
+    fn registers_keep_their_values() {
+        let mut sio = Sio::new(None);
+
+        sio.write_u8(0x08, 0x0d);
+        sio.write_u8(0x09, 0x00);
+        sio.write_u8(0x0e, 0x88);
+        sio.write_u8(0x0f, 0x00);
+        sio.write_u8(0x0a, 0x02);
+        sio.write_u8(0x0b, 0x10);
+
+        assert_eq!(sio.read_u8(0x08), 0x0d);
+        assert_eq!(sio.read_u8(0x0e), 0x88);
+        assert_eq!(sio.read_u8(0x0a), 0x02);
+        assert_eq!(sio.read_u8(0x0b), 0x10);
+
+        // The acknowledge and reset bits are write-only, the reset clears the other registers
+        sio.write_u8(0x0a, 0x52);
+        assert_eq!(sio.read_u8(0x0a), 0x02);
+        assert_eq!(sio.read_u8(0x08), 0x00);
+        assert_eq!(sio.read_u8(0x0e), 0x00);
+    }
+}
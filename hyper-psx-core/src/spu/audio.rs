@@ -0,0 +1,160 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+#[cfg(feature = "window")]
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    BufferSize, BuildStreamError, OutputCallbackInfo, PlayStreamError, SampleRate, Stream,
+    StreamConfig,
+};
+#[cfg(feature = "window")]
+use std::{
+    collections::VecDeque,
+    fmt::{self, Formatter},
+};
+use std::{
+    fmt::Debug,
+    mem,
+    sync::{Arc, Mutex},
+};
+#[cfg(feature = "window")]
+use thiserror::Error;
+
+/// The error type for the audio output
+#[cfg(feature = "window")]
+#[derive(Debug, Error)]
+pub enum CreationError {
+    /// If no output device is available
+    #[error("failed to find an audio output device")]
+    MissingDevice,
+
+    /// If the output stream failed to build
+    #[error("failed to build audio stream")]
+    StreamBuildFailure(#[from] BuildStreamError),
+
+    /// If the output stream failed to start
+    #[error("failed to play audio stream")]
+    StreamPlayFailure(#[from] PlayStreamError),
+}
+
+/// A destination for the stereo samples mixed by the SPU
+pub trait AudioSink: Debug {
+    /// Receives the samples the SPU produced since the last push
+    ///
+    /// # Arguments:
+    ///
+    /// * `samples`: The left and right samples
+    fn push_samples(&mut self, samples: &[(i16, i16)]);
+}
+
+/// A sink discarding every sample
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NullSink;
+
+impl AudioSink for NullSink {
+    fn push_samples(&mut self, _samples: &[(i16, i16)]) {}
+}
+
+/// A sink recording every sample, clones share the recorded samples
+#[derive(Clone, Debug, Default)]
+pub struct BufferSink {
+    /// The recorded samples
+    samples: Arc<Mutex<Vec<(i16, i16)>>>,
+}
+
+impl BufferSink {
+    /// Creates a new empty buffer sink
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the samples recorded since the last call
+    pub fn take_samples(&self) -> Vec<(i16, i16)> {
+        mem::take(&mut self.samples.lock().unwrap())
+    }
+}
+
+impl AudioSink for BufferSink {
+    fn push_samples(&mut self, samples: &[(i16, i16)]) {
+        self.samples.lock().unwrap().extend_from_slice(samples);
+    }
+}
+
+/// The audio output playing the SPU samples
+#[cfg(feature = "window")]
+pub(crate) struct Audio {
+    /// The output stream, which stops once dropped
+    _stream: Stream,
+
+    /// The interleaved stereo samples waiting to be played
+    buffer: Arc<Mutex<VecDeque<i16>>>,
+}
+
+#[cfg(feature = "window")]
+impl Audio {
+    /// The output sample rate of the SPU
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// The most samples buffered before the oldest ones are dropped (100ms)
+    const MAX_BUFFERED_SAMPLES: usize = Self::SAMPLE_RATE as usize / 10 * 2;
+
+    /// Opens a stereo 44.1kHz stream on the default output device
+    pub(crate) fn new() -> Result<Self, CreationError> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or(CreationError::MissingDevice)?;
+
+        let config = StreamConfig {
+            channels: 2,
+            sample_rate: SampleRate(Self::SAMPLE_RATE),
+            buffer_size: BufferSize::Default,
+        };
+
+        let buffer = Arc::new(Mutex::new(VecDeque::new()));
+        let stream_buffer = Arc::clone(&buffer);
+
+        let stream = device.build_output_stream(
+            &config,
+            move |data: &mut [f32], _: &OutputCallbackInfo| {
+                let mut buffer = stream_buffer.lock().unwrap();
+                for output in data.iter_mut() {
+                    // Underruns play silence
+                    let sample = buffer.pop_front().unwrap_or(0);
+                    *output = sample as f32 / 32768.0;
+                }
+            },
+            |error| log::error!("Audio stream error: {}", error),
+            None,
+        )?;
+        stream.play()?;
+
+        Ok(Self {
+            _stream: stream,
+            buffer,
+        })
+    }
+}
+
+#[cfg(feature = "window")]
+impl AudioSink for Audio {
+    fn push_samples(&mut self, samples: &[(i16, i16)]) {
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.extend(samples.iter().flat_map(|&(left, right)| [left, right]));
+
+        // Keeps the latency bounded if the emulation runs ahead of the output
+        let excess = buffer.len().saturating_sub(Self::MAX_BUFFERED_SAMPLES);
+        buffer.drain(..excess & !0x1);
+    }
+}
+
+#[cfg(feature = "window")]
+impl Debug for Audio {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Audio")
+            .field("buffered", &self.buffer.lock().unwrap().len())
+            .finish()
+    }
+}
@@ -0,0 +1,627 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+pub(crate) mod audio;
+mod voice;
+
+use crate::spu::{
+    audio::{AudioSink, NullSink},
+    voice::Voice,
+};
+
+use serde::{Deserialize, Serialize};
+use std::mem;
+
+/// The transfer mode of the SPU RAM (SPUCNT bits 4-5)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum TransferMode {
+    /// No transfer
+    #[default]
+    Stop = 0x0,
+
+    /// Writes through the data transfer FIFO
+    ManualWrite = 0x1,
+
+    /// Writes through DMA channel 4
+    DmaWrite = 0x2,
+
+    /// Reads through DMA channel 4
+    DmaRead = 0x3,
+}
+
+/// The Sound Processing Unit
+///
+/// <https://psx-spx.consoledev.net/soundprocessingunitspu/>
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Spu {
+    /// The 24 voices
+    voices: [Voice; Self::VOICE_COUNT],
+
+    /// The SPU RAM
+    ram: Box<[u8]>,
+
+    /// Main volume left and right
+    main_volume: [u16; 2],
+
+    /// Reverb output volume left and right
+    reverb_volume: [u16; 2],
+
+    /// KON - The voices last keyed on
+    key_on: u32,
+
+    /// KOFF - The voices last keyed off
+    key_off: u32,
+
+    /// PMON - The voices using pitch modulation
+    pitch_modulation: u32,
+
+    /// NON - The voices playing noise instead of ADPCM
+    noise: u32,
+
+    /// EON - The voices feeding the reverb
+    reverb: u32,
+
+    /// ENDX - The voices which reached an ADPCM end flag
+    end: u32,
+
+    /// Reverb work area start address in units of 8 bytes
+    reverb_address: u16,
+
+    /// IRQ address in units of 8 bytes
+    irq_address: u16,
+
+    /// Transfer start address in units of 8 bytes
+    transfer_address: u16,
+
+    /// The current transfer address in bytes
+    current_transfer_address: u32,
+
+    /// SPUCNT - Control register
+    control: u16,
+
+    /// Transfer control register, normally 0x0004
+    transfer_control: u16,
+
+    /// CD audio input volume left and right
+    cd_volume: [u16; 2],
+
+    /// External audio input volume left and right
+    external_volume: [u16; 2],
+
+    /// The reverb configuration area
+    reverb_registers: [u16; 32],
+
+    /// The cycles passed since the last output sample
+    cycles: u32,
+
+    /// The stereo samples produced since they were last pushed to the sink
+    #[serde(skip)]
+    samples: Vec<(i16, i16)>,
+
+    /// The sink receiving the mixed samples
+    #[serde(skip, default = "Spu::detached_sink")]
+    sink: Box<dyn AudioSink>,
+}
+
+impl Spu {
+    /// The amount of voices
+    const VOICE_COUNT: usize = 24;
+
+    /// The size of the SPU RAM (512KB)
+    const RAM_SIZE: usize = 512 * 1024;
+
+    /// The CPU cycles per output sample at 44.1kHz
+    const CYCLES_PER_SAMPLE: u32 = 768;
+
+    /// Creates a SPU Component
+    pub(crate) fn new() -> Self {
+        Self {
+            voices: [Voice::default(); Self::VOICE_COUNT],
+            ram: vec![0x00; Self::RAM_SIZE].into_boxed_slice(),
+            main_volume: [0; 2],
+            reverb_volume: [0; 2],
+            key_on: 0,
+            key_off: 0,
+            pitch_modulation: 0,
+            noise: 0,
+            reverb: 0,
+            end: 0,
+            reverb_address: 0,
+            irq_address: 0,
+            transfer_address: 0,
+            current_transfer_address: 0,
+            control: 0,
+            transfer_control: 0,
+            cd_volume: [0; 2],
+            external_volume: [0; 2],
+            reverb_registers: [0; 32],
+            cycles: 0,
+            samples: Vec::new(),
+            sink: Self::detached_sink(),
+        }
+    }
+
+    /// The sink used by SPUs restored from a save state until the real one is reattached
+    fn detached_sink() -> Box<dyn AudioSink> {
+        Box::new(NullSink)
+    }
+
+    /// Moves the sink from the previous SPU into this restored one
+    ///
+    /// # Arguments:
+    ///
+    /// * `previous`: The SPU which gets replaced
+    pub(crate) fn reattach(&mut self, previous: &mut Self) {
+        mem::swap(&mut self.sink, &mut previous.sink);
+    }
+
+    /// Replaces the sink receiving the mixed samples
+    ///
+    /// Returns the previous sink
+    ///
+    /// # Arguments:
+    ///
+    /// * `sink`: The new sink
+    pub(crate) fn set_sink(&mut self, sink: Box<dyn AudioSink>) -> Box<dyn AudioSink> {
+        mem::replace(&mut self.sink, sink)
+    }
+
+    /// Executes a number of cycles, mixing a stereo sample every 768 cycles
+    ///
+    /// # Arguments:
+    ///
+    /// * `cycles`: The amount of cycles which passed
+    pub(crate) fn step(&mut self, cycles: u32) {
+        self.cycles += cycles;
+        while self.cycles >= Self::CYCLES_PER_SAMPLE {
+            self.cycles -= Self::CYCLES_PER_SAMPLE;
+            self.mix_sample();
+        }
+    }
+
+    /// Pushes the samples produced since the last call to the sink
+    pub(crate) fn flush_samples(&mut self) {
+        self.sink.push_samples(&self.samples);
+        self.samples.clear();
+    }
+
+    /// Mixes the voices into the next stereo sample
+    fn mix_sample(&mut self) {
+        let mut left = 0;
+        let mut right = 0;
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            let (voice_left, voice_right, reached_end) = voice.tick(&self.ram);
+            if reached_end {
+                self.end |= 1 << index;
+            }
+
+            left += voice_left;
+            right += voice_right;
+        }
+
+        // SPUCNT bit 15 enables the SPU and bit 14 unmutes it
+        if self.control & 0xc000 != 0xc000 {
+            left = 0;
+            right = 0;
+        }
+
+        let left = left.clamp(i16::MIN as i32, i16::MAX as i32);
+        let right = right.clamp(i16::MIN as i32, i16::MAX as i32);
+
+        let left = (left * Voice::volume(self.main_volume[0])) >> 15;
+        let right = (right * Voice::volume(self.main_volume[1])) >> 15;
+
+        self.samples.push((left as i16, right as i16));
+    }
+
+    /// Returns the transfer mode selected in SPUCNT
+    fn transfer_mode(&self) -> TransferMode {
+        match (self.control >> 4) & 0x3 {
+            0x0 => TransferMode::Stop,
+            0x1 => TransferMode::ManualWrite,
+            0x2 => TransferMode::DmaWrite,
+            0x3 => TransferMode::DmaRead,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the SPUSTAT register
+    fn status(&self) -> u16 {
+        let transfer_mode = self.transfer_mode();
+
+        // Bits 0-5 mirror the applied SPUCNT mode, transfers never appear busy
+        let mut status = self.control & 0x3f;
+        status |= ((self.control >> 5) & 0x1) << 7;
+        status |= ((transfer_mode == TransferMode::DmaWrite) as u16) << 8;
+        status |= ((transfer_mode == TransferMode::DmaRead) as u16) << 9;
+
+        status
+    }
+
+    /// Keys on the voices of the set bits
+    ///
+    /// # Arguments:
+    ///
+    /// * `voices`: The voice bits
+    fn key_on(&mut self, voices: u32) {
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            if voices & (1 << index) != 0 {
+                voice.key_on(&self.ram);
+                self.end &= !(1 << index);
+            }
+        }
+    }
+
+    /// Keys off the voices of the set bits
+    ///
+    /// # Arguments:
+    ///
+    /// * `voices`: The voice bits
+    fn key_off(&mut self, voices: u32) {
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            if voices & (1 << index) != 0 {
+                voice.key_off();
+            }
+        }
+    }
+
+    /// Writes a halfword to the SPU RAM at the current transfer address
+    ///
+    /// # Arguments:
+    ///
+    /// * `value`: The value to be written
+    fn write_transfer(&mut self, value: u16) {
+        let address = self.current_transfer_address as usize;
+        self.ram[address..address + 2].copy_from_slice(&value.to_le_bytes());
+
+        self.current_transfer_address = (self.current_transfer_address + 2) % Self::RAM_SIZE as u32;
+    }
+
+    /// Reads a halfword from the SPU RAM at the current transfer address
+    fn read_transfer(&mut self) -> u16 {
+        let address = self.current_transfer_address as usize;
+        let value = u16::from_le_bytes([self.ram[address], self.ram[address + 1]]);
+
+        self.current_transfer_address = (self.current_transfer_address + 2) % Self::RAM_SIZE as u32;
+
+        value
+    }
+
+    /// Receives a word from DMA channel 4
+    ///
+    /// # Arguments:
+    ///
+    /// * `value`: The word to be written into the SPU RAM
+    pub(crate) fn dma_write_word(&mut self, value: u32) {
+        if self.transfer_mode() != TransferMode::DmaWrite {
+            log::warn!(target: "spu", "DMA write while in {:?} mode", self.transfer_mode());
+        }
+
+        self.write_transfer(value as u16);
+        self.write_transfer((value >> 16) as u16);
+    }
+
+    /// Hands a word to DMA channel 4
+    pub(crate) fn dma_read_word(&mut self) -> u32 {
+        if self.transfer_mode() != TransferMode::DmaRead {
+            log::warn!(target: "spu", "DMA read while in {:?} mode", self.transfer_mode());
+        }
+
+        let low = self.read_transfer() as u32;
+        let high = self.read_transfer() as u32;
+
+        (high << 16) | low
+    }
+
+    /// Writes a halfword to a SPU register
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative address offset
+    /// * `value`: The value to be written
+    pub(crate) fn write_u16(&mut self, offset: u32, value: u16) {
+        match offset {
+            0x000..=0x17f => {
+                let voice = (offset / 0x10) as usize;
+                self.voices[voice].write_u16(offset % 0x10, value);
+            }
+            0x180 => self.main_volume[0] = value,
+            0x182 => self.main_volume[1] = value,
+            0x184 => self.reverb_volume[0] = value,
+            0x186 => self.reverb_volume[1] = value,
+            0x188 => {
+                self.key_on = (self.key_on & 0xffff0000) | value as u32;
+                self.key_on(value as u32);
+            }
+            0x18a => {
+                self.key_on = (self.key_on & 0x0000ffff) | ((value as u32) << 16);
+                self.key_on((value as u32) << 16);
+            }
+            0x18c => {
+                self.key_off = (self.key_off & 0xffff0000) | value as u32;
+                self.key_off(value as u32);
+            }
+            0x18e => {
+                self.key_off = (self.key_off & 0x0000ffff) | ((value as u32) << 16);
+                self.key_off((value as u32) << 16);
+            }
+            0x190 => self.pitch_modulation = (self.pitch_modulation & 0xffff0000) | value as u32,
+            0x192 => {
+                self.pitch_modulation =
+                    (self.pitch_modulation & 0x0000ffff) | ((value as u32) << 16)
+            }
+            0x194 => self.noise = (self.noise & 0xffff0000) | value as u32,
+            0x196 => self.noise = (self.noise & 0x0000ffff) | ((value as u32) << 16),
+            0x198 => self.reverb = (self.reverb & 0xffff0000) | value as u32,
+            0x19a => self.reverb = (self.reverb & 0x0000ffff) | ((value as u32) << 16),
+            // ENDX is read-only
+            0x19c..=0x19e => {}
+            0x1a2 => self.reverb_address = value,
+            0x1a4 => self.irq_address = value,
+            0x1a6 => {
+                self.transfer_address = value;
+                self.current_transfer_address = value as u32 * 8;
+            }
+            0x1a8 => {
+                if self.transfer_mode() != TransferMode::ManualWrite {
+                    log::debug!(target: "spu", "FIFO write while in {:?} mode", self.transfer_mode());
+                }
+
+                self.write_transfer(value);
+            }
+            0x1aa => self.control = value,
+            0x1ac => self.transfer_control = value,
+            // SPUSTAT is read-only
+            0x1ae => {}
+            0x1b0 => self.cd_volume[0] = value,
+            0x1b2 => self.cd_volume[1] = value,
+            0x1b4 => self.external_volume[0] = value,
+            0x1b6 => self.external_volume[1] = value,
+            0x1c0..=0x1ff => self.reverb_registers[((offset - 0x1c0) / 2) as usize] = value,
+            _ => {
+                log::warn!(
+                    target: "spu",
+                    "Unhandled write to spu at {:#05x} with value {:#06x}",
+                    offset,
+                    value
+                );
+            }
+        }
+    }
+
+    /// Reads a halfword from a SPU register
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative address offset
+    pub(crate) fn read_u16(&self, offset: u32) -> u16 {
+        match offset {
+            0x000..=0x17f => {
+                let voice = (offset / 0x10) as usize;
+                self.voices[voice].read_u16(offset % 0x10)
+            }
+            0x180 => self.main_volume[0],
+            0x182 => self.main_volume[1],
+            0x184 => self.reverb_volume[0],
+            0x186 => self.reverb_volume[1],
+            0x188 => self.key_on as u16,
+            0x18a => (self.key_on >> 16) as u16,
+            0x18c => self.key_off as u16,
+            0x18e => (self.key_off >> 16) as u16,
+            0x190 => self.pitch_modulation as u16,
+            0x192 => (self.pitch_modulation >> 16) as u16,
+            0x194 => self.noise as u16,
+            0x196 => (self.noise >> 16) as u16,
+            0x198 => self.reverb as u16,
+            0x19a => (self.reverb >> 16) as u16,
+            0x19c => self.end as u16,
+            0x19e => (self.end >> 16) as u16,
+            0x1a2 => self.reverb_address,
+            0x1a4 => self.irq_address,
+            0x1a6 => self.transfer_address,
+            0x1aa => self.control,
+            0x1ac => self.transfer_control,
+            0x1ae => self.status(),
+            0x1b0 => self.cd_volume[0],
+            0x1b2 => self.cd_volume[1],
+            0x1b4 => self.external_volume[0],
+            0x1b6 => self.external_volume[1],
+            // The current main volume equals the main volume without sweeps
+            0x1b8 => self.main_volume[0],
+            0x1ba => self.main_volume[1],
+            0x1c0..=0x1ff => self.reverb_registers[((offset - 0x1c0) / 2) as usize],
+            // The current voice volumes, sweeps are not emulated
+            0x200..=0x25f => {
+                let voice = &self.voices[((offset - 0x200) / 4) as usize];
+                let register = if offset & 0x2 == 0 { 0x0 } else { 0x2 };
+                (Voice::volume(voice.read_u16(register)) as i16) as u16
+            }
+            _ => {
+                log::warn!(target: "spu", "Unhandled read from spu at {:#05x}", offset);
+                0x0000
+            }
+        }
+    }
+
+    /// Writes a byte to a SPU register by merging it into the halfword
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative address offset
+    /// * `value`: The value to be written
+    pub(crate) fn write_u8(&mut self, offset: u32, value: u8) {
+        let halfword_offset = offset & !0x1;
+        let shift = (offset & 0x1) * 8;
+
+        let halfword = self.read_u16(halfword_offset);
+        let halfword = (halfword & !(0xff << shift)) | ((value as u16) << shift);
+        self.write_u16(halfword_offset, halfword);
+    }
+
+    /// Reads a byte from a SPU register
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The relative address offset
+    pub(crate) fn read_u8(&self, offset: u32) -> u8 {
+        let shift = (offset & 0x1) * 8;
+        (self.read_u16(offset & !0x1) >> shift) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spu::{audio::BufferSink, voice::AdsrPhase};
+    use crate::{
+        bus::{memory::Memory, ram::Ram},
+        cdrom::Cdrom,
+        dma::Dma,
+        gpu::Gpu,
+        mdec::Mdec,
+        region::Region,
+        renderer::mock_renderer::MockRenderer,
+    };
+
+    #[test]
+    fn registers_keep_their_values() {
+        let mut spu = Spu::new();
+
+        spu.write_u16(0x016, 0x1234);
+        spu.write_u16(0x180, 0x3fff);
+        spu.write_u16(0x1a2, 0xabcd);
+        spu.write_u16(0x1c4, 0x5678);
+        spu.write_u8(0x1b1, 0x7f);
+
+        assert_eq!(spu.read_u16(0x016), 0x1234);
+        assert_eq!(spu.voices[1].read_u16(0x6), 0x1234);
+        assert_eq!(spu.read_u16(0x180), 0x3fff);
+        assert_eq!(spu.read_u16(0x1b8), 0x3fff);
+        assert_eq!(spu.read_u16(0x1a2), 0xabcd);
+        assert_eq!(spu.read_u16(0x1c4), 0x5678);
+        assert_eq!(spu.read_u16(0x1b0), 0x7f00);
+    }
+
+    #[test]
+    fn key_on_and_key_off_update_the_voices() {
+        let mut spu = Spu::new();
+        spu.end = 0x0080_0001;
+
+        spu.write_u16(0x188, 0x0001);
+        spu.write_u16(0x18a, 0x0080);
+
+        assert_eq!(spu.read_u16(0x188), 0x0001);
+        assert_eq!(spu.read_u16(0x18a), 0x0080);
+        assert_eq!(spu.read_u16(0x19c), 0x0000);
+        assert_eq!(spu.read_u16(0x19e), 0x0000);
+        assert_eq!(spu.voices[0].adsr_phase, AdsrPhase::Attack);
+        assert_eq!(spu.voices[23].adsr_phase, AdsrPhase::Attack);
+
+        spu.write_u16(0x18c, 0x0003);
+
+        assert_eq!(spu.read_u16(0x18c), 0x0003);
+        assert_eq!(spu.voices[0].adsr_phase, AdsrPhase::Release);
+        assert_eq!(spu.voices[1].adsr_phase, AdsrPhase::Off);
+        assert_eq!(spu.voices[23].adsr_phase, AdsrPhase::Attack);
+    }
+
+    #[test]
+    fn status_mirrors_the_transfer_mode() {
+        let mut spu = Spu::new();
+
+        spu.write_u16(0x1aa, 0x8020);
+        assert_eq!(spu.read_u16(0x1ae), 0x01a0);
+
+        spu.write_u16(0x1aa, 0x8030);
+        assert_eq!(spu.read_u16(0x1ae), 0x02b0);
+    }
+
+    /// Pushes the samples produced so far through a buffer sink and returns them
+    fn take_samples(spu: &mut Spu) -> Vec<(i16, i16)> {
+        let sink = BufferSink::new();
+        let previous = spu.set_sink(Box::new(sink.clone()));
+        spu.flush_samples();
+        spu.set_sink(previous);
+        sink.take_samples()
+    }
+
+    #[test]
+    fn keyed_on_voice_is_mixed_at_44100hz() {
+        let mut spu = Spu::new();
+
+        // A single block of samples at 0x1000 with the loop end flag
+        spu.ram[0x1001] = 0x01;
+        spu.ram[0x1002..0x1010].fill(0x11);
+
+        spu.write_u16(0x000, 0x3fff);
+        spu.write_u16(0x002, 0x3fff);
+        spu.write_u16(0x004, 0x1000);
+        spu.write_u16(0x006, 0x1000 / 8);
+        spu.write_u16(0x180, 0x3fff);
+        spu.write_u16(0x182, 0x3fff);
+        spu.write_u16(0x1aa, 0xc000);
+        spu.write_u16(0x188, 0x0001);
+
+        // The envelope starts silent and rises by 0x3800 per sample
+        spu.step(Spu::CYCLES_PER_SAMPLE * 2 - 1);
+        assert_eq!(take_samples(&mut spu), [(0, 0)]);
+
+        spu.step(1);
+        assert_eq!(take_samples(&mut spu), [(1790, 1790)]);
+        assert_eq!(spu.read_u16(0x200), 0x7ffe);
+
+        // The block holds 28 samples
+        spu.step(Spu::CYCLES_PER_SAMPLE * 26);
+        assert_eq!(spu.read_u16(0x19c), 0x0001);
+        assert_eq!(take_samples(&mut spu).len(), 26);
+    }
+
+    #[test]
+    fn muted_spu_outputs_silence() {
+        let mut spu = Spu::new();
+        spu.ram[0x1002..0x1010].fill(0x77);
+
+        spu.write_u16(0x000, 0x3fff);
+        spu.write_u16(0x004, 0x1000);
+        spu.write_u16(0x006, 0x1000 / 8);
+        spu.write_u16(0x180, 0x3fff);
+        spu.write_u16(0x1aa, 0x8000);
+        spu.write_u16(0x188, 0x0001);
+
+        spu.step(Spu::CYCLES_PER_SAMPLE * 4);
+        assert_eq!(take_samples(&mut spu), [(0, 0); 4]);
+    }
+
+    #[test]
+    fn dma_uploads_into_spu_ram() {
+        let mut ram = Ram::with_size(2 * 1024 * 1024);
+        let (renderer, _) = MockRenderer::new();
+        let mut gpu = Gpu::new(Box::new(renderer), Region::Ntsc);
+        let mut cdrom = Cdrom::new(None);
+        let mut mdec = Mdec::new();
+        let mut spu = Spu::new();
+        let mut dma = Dma::new();
+
+        for index in 0..4 {
+            ram.write_u32(0x1000 + index * 4, 0x11223344 + index);
+        }
+
+        spu.write_u16(0x1a6, 0x0200);
+        spu.write_u16(0x1aa, 0x0020);
+
+        dma.write_u32(0x40, 0x0000_1000);
+        dma.write_u32(0x44, 0x0001_0004);
+        dma.write_u32(0x48, 0x0100_0201);
+        dma.step(&mut ram, &mut gpu, &mut cdrom, &mut mdec, &mut spu);
+
+        for index in 0..4u32 {
+            let address = 0x1000 + index as usize * 4;
+            let bytes = (0x11223344 + index).to_le_bytes();
+            assert_eq!(spu.ram[address..address + 4], bytes);
+        }
+        assert_eq!(spu.current_transfer_address, 0x1010);
+    }
+}
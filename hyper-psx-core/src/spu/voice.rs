@@ -0,0 +1,417 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use serde::{Deserialize, Serialize};
+
+/// The phase of the ADSR envelope
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) enum AdsrPhase {
+    /// The voice is silent
+    #[default]
+    Off,
+
+    /// The volume rises after key-on
+    Attack,
+
+    /// The volume falls towards the sustain level
+    Decay,
+
+    /// The volume is held until key-off
+    Sustain,
+
+    /// The volume fades out after key-off
+    Release,
+}
+
+/// One of the 24 SPU voices
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub(super) struct Voice {
+    /// Volume left
+    volume_left: u16,
+
+    /// Volume right
+    volume_right: u16,
+
+    /// ADPCM sample rate, 0x1000 plays at 44.1kHz
+    pitch: u16,
+
+    /// ADPCM start address in units of 8 bytes
+    start_address: u16,
+
+    /// Attack, decay, sustain and release settings
+    adsr: u32,
+
+    /// Current ADSR volume
+    adsr_volume: u16,
+
+    /// ADPCM repeat address in units of 8 bytes
+    repeat_address: u16,
+
+    /// The current ADPCM address in bytes
+    current_address: u32,
+
+    /// The phase of the ADSR envelope
+    pub(super) adsr_phase: AdsrPhase,
+
+    /// The samples remaining until the next envelope step
+    adsr_counter: u32,
+
+    /// The position inside the decoded block as 20.12 fixed-point
+    pitch_counter: u32,
+
+    /// The samples of the current ADPCM block
+    decoded: [i16; Self::SAMPLES_PER_BLOCK],
+
+    /// The last two decoded samples feeding the ADPCM filter
+    history: [i16; 2],
+}
+
+impl Voice {
+    /// The size of an ADPCM block
+    const BLOCK_SIZE: u32 = 16;
+
+    /// The amount of samples in an ADPCM block
+    const SAMPLES_PER_BLOCK: usize = 28;
+
+    /// The positive ADPCM filter coefficients
+    const POSITIVE_COEFFICIENTS: [i32; 5] = [0, 60, 115, 98, 122];
+
+    /// The negative ADPCM filter coefficients
+    const NEGATIVE_COEFFICIENTS: [i32; 5] = [0, 0, -52, -55, -60];
+
+    /// Starts the voice from its start address
+    ///
+    /// # Arguments:
+    ///
+    /// * `ram`: The SPU RAM
+    pub(super) fn key_on(&mut self, ram: &[u8]) {
+        self.current_address = self.start_address as u32 * 8;
+        self.pitch_counter = 0;
+        self.history = [0; 2];
+        self.decode_block(ram);
+
+        self.adsr_volume = 0;
+        self.adsr_counter = 0;
+        self.adsr_phase = AdsrPhase::Attack;
+    }
+
+    /// Releases the voice
+    pub(super) fn key_off(&mut self) {
+        if self.adsr_phase != AdsrPhase::Off {
+            self.adsr_phase = AdsrPhase::Release;
+            self.adsr_counter = 0;
+        }
+    }
+
+    /// Produces the next output sample at 44.1kHz
+    ///
+    /// # Arguments:
+    ///
+    /// * `ram`: The SPU RAM
+    ///
+    /// Returns the left and right sample and if an ADPCM end flag was reached
+    pub(super) fn tick(&mut self, ram: &[u8]) -> (i32, i32, bool) {
+        if self.adsr_phase == AdsrPhase::Off {
+            return (0, 0, false);
+        }
+
+        let sample = self.decoded[(self.pitch_counter >> 12) as usize] as i32;
+        let sample = (sample * self.adsr_volume as i32) >> 15;
+
+        let left = (sample * Self::volume(self.volume_left)) >> 15;
+        let right = (sample * Self::volume(self.volume_right)) >> 15;
+
+        self.step_envelope();
+
+        // Pitches above 0x4000 are clamped to four times the base rate
+        self.pitch_counter += self.pitch.min(0x4000) as u32;
+
+        let mut reached_end = false;
+        while (self.pitch_counter >> 12) as usize >= Self::SAMPLES_PER_BLOCK {
+            self.pitch_counter -= (Self::SAMPLES_PER_BLOCK as u32) << 12;
+            reached_end |= self.next_block(ram);
+        }
+
+        (left, right, reached_end)
+    }
+
+    /// Converts a volume register into a signed volume
+    ///
+    /// Sweeps are not emulated and play at full volume
+    ///
+    /// # Arguments:
+    ///
+    /// * `register`: The volume register
+    pub(super) fn volume(register: u16) -> i32 {
+        if register & 0x8000 != 0 {
+            return 0x7fff;
+        }
+
+        ((register << 1) as i16) as i32
+    }
+
+    /// Moves to the next ADPCM block, following the loop flags of the current one
+    ///
+    /// # Arguments:
+    ///
+    /// * `ram`: The SPU RAM
+    ///
+    /// Returns if the current block had the loop end flag
+    fn next_block(&mut self, ram: &[u8]) -> bool {
+        let flags = ram[self.current_address as usize + 1];
+
+        let loop_end = flags & 0x1 != 0;
+        if loop_end {
+            self.current_address = self.repeat_address as u32 * 8;
+
+            // Without the repeat flag the voice gets silenced
+            if flags & 0x2 == 0 {
+                self.adsr_phase = AdsrPhase::Off;
+                self.adsr_volume = 0;
+            }
+        } else {
+            self.current_address = (self.current_address + Self::BLOCK_SIZE) & 0x7fff0;
+        }
+
+        self.decode_block(ram);
+
+        loop_end
+    }
+
+    /// Decodes the ADPCM block at the current address
+    ///
+    /// # Arguments:
+    ///
+    /// * `ram`: The SPU RAM
+    fn decode_block(&mut self, ram: &[u8]) {
+        let address = (self.current_address & 0x7fff0) as usize;
+        let block = &ram[address..address + Self::BLOCK_SIZE as usize];
+
+        // The loop start flag marks the block to repeat from
+        if block[1] & 0x4 != 0 {
+            self.repeat_address = (address / 8) as u16;
+        }
+
+        self.history = decode_adpcm_block(block, self.history, &mut self.decoded);
+    }
+
+    /// Advances the ADSR envelope by one sample
+    fn step_envelope(&mut self) {
+        if self.adsr_counter > 0 {
+            self.adsr_counter -= 1;
+            return;
+        }
+
+        let (exponential, decreasing, shift, step) = match self.adsr_phase {
+            AdsrPhase::Off => return,
+            AdsrPhase::Attack => {
+                let exponential = self.adsr & (1 << 15) != 0;
+                let shift = (self.adsr >> 10) & 0x1f;
+                let step = 7 - ((self.adsr >> 8) & 0x3) as i32;
+                (exponential, false, shift, step)
+            }
+            AdsrPhase::Decay => {
+                let shift = (self.adsr >> 4) & 0xf;
+                (true, true, shift, -8)
+            }
+            AdsrPhase::Sustain => {
+                let exponential = self.adsr & (1 << 31) != 0;
+                let decreasing = self.adsr & (1 << 30) != 0;
+                let shift = (self.adsr >> 24) & 0x1f;
+                let step = ((self.adsr >> 22) & 0x3) as i32;
+                let step = if decreasing { -8 + step } else { 7 - step };
+                (exponential, decreasing, shift, step)
+            }
+            AdsrPhase::Release => {
+                let exponential = self.adsr & (1 << 21) != 0;
+                let shift = (self.adsr >> 16) & 0x1f;
+                (exponential, true, shift, -8)
+            }
+        };
+
+        let level = self.adsr_volume as i32;
+
+        let mut cycles = 1 << shift.saturating_sub(11);
+        let mut step = step << 11_u32.saturating_sub(shift);
+        if exponential && !decreasing && level > 0x6000 {
+            cycles *= 4;
+        }
+
+        if exponential && decreasing {
+            step = (step * level) >> 15;
+        }
+
+        self.adsr_counter = cycles - 1;
+
+        let level = (level + step).clamp(0, 0x7fff);
+        self.adsr_volume = level as u16;
+
+        let sustain_level = (((self.adsr & 0xf) + 1) * 0x800).min(0x7fff) as i32;
+        match self.adsr_phase {
+            AdsrPhase::Attack if level == 0x7fff => self.adsr_phase = AdsrPhase::Decay,
+            AdsrPhase::Decay if level <= sustain_level => self.adsr_phase = AdsrPhase::Sustain,
+            AdsrPhase::Release if level == 0 => self.adsr_phase = AdsrPhase::Off,
+            _ => {}
+        }
+    }
+
+    /// Writes a voice register
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The register offset inside the voice
+    /// * `value`: The value to be written
+    pub(super) fn write_u16(&mut self, offset: u32, value: u16) {
+        match offset {
+            0x0 => self.volume_left = value,
+            0x2 => self.volume_right = value,
+            0x4 => self.pitch = value,
+            0x6 => self.start_address = value,
+            0x8 => self.adsr = (self.adsr & 0xffff0000) | value as u32,
+            0xa => self.adsr = (self.adsr & 0x0000ffff) | ((value as u32) << 16),
+            0xc => self.adsr_volume = value,
+            0xe => self.repeat_address = value,
+            _ => unreachable!("write to spu voice at {:#03x}", offset),
+        }
+    }
+
+    /// Reads a voice register
+    ///
+    /// # Arguments:
+    ///
+    /// * `offset`: The register offset inside the voice
+    pub(super) fn read_u16(&self, offset: u32) -> u16 {
+        match offset {
+            0x0 => self.volume_left,
+            0x2 => self.volume_right,
+            0x4 => self.pitch,
+            0x6 => self.start_address,
+            0x8 => self.adsr as u16,
+            0xa => (self.adsr >> 16) as u16,
+            0xc => self.adsr_volume,
+            0xe => self.repeat_address,
+            _ => unreachable!("read from spu voice at {:#03x}", offset),
+        }
+    }
+}
+
+/// Decodes a 16-byte ADPCM block into 28 PCM samples
+///
+/// Byte 0 holds the shift and filter, byte 1 the loop flags and the remaining 14 bytes the
+/// 4-bit samples, low nibble first
+///
+/// # Arguments:
+///
+/// * `block`: The ADPCM block
+/// * `history`: The last two samples of the previous block, newest first
+/// * `output`: The decoded samples
+///
+/// Returns the history for the next block
+fn decode_adpcm_block(
+    block: &[u8],
+    history: [i16; 2],
+    output: &mut [i16; Voice::SAMPLES_PER_BLOCK],
+) -> [i16; 2] {
+    // Shifts of 13-15 behave like a shift of 9
+    let shift = match block[0] & 0xf {
+        shift @ 0..=12 => shift,
+        _ => 9,
+    };
+
+    let filter = ((block[0] >> 4) & 0x7).min(4) as usize;
+    let positive = Voice::POSITIVE_COEFFICIENTS[filter];
+    let negative = Voice::NEGATIVE_COEFFICIENTS[filter];
+
+    let [mut old, mut older] = history.map(|sample| sample as i32);
+    for (index, sample) in output.iter_mut().enumerate() {
+        let byte = block[2 + index / 2];
+        let nibble = if index % 2 == 0 {
+            byte & 0xf
+        } else {
+            byte >> 4
+        };
+
+        // Sign-extends the nibble from the top of a halfword
+        let value = (((nibble as u16) << 12) as i16 >> shift) as i32;
+        let value = value + ((old * positive + older * negative + 32) >> 6);
+        let value = value.clamp(i16::MIN as i32, i16::MAX as i32);
+
+        *sample = value as i16;
+        older = old;
+        old = value;
+    }
+
+    [old as i16, older as i16]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unfiltered_block_decodes_the_nibbles() {
+        let mut block = [0x00; 16];
+        block[2..5].copy_from_slice(&[0x21, 0x87, 0xf0]);
+
+        let mut output = [0; Voice::SAMPLES_PER_BLOCK];
+        let history = decode_adpcm_block(&block, [0; 2], &mut output);
+
+        assert_eq!(output[..6], [4096, 8192, 28672, -32768, 0, -4096]);
+        assert_eq!(output[6..], [0; 22]);
+        assert_eq!(history, [0, 0]);
+    }
+
+    #[test]
+    fn filtered_block_matches_the_reference() {
+        // Shift 8 and filter 2, continuing from a previous block
+        let mut block = [0x00; 16];
+        block[0] = 0x28;
+        block[2..4].copy_from_slice(&[0x71, 0x0f]);
+
+        let mut output = [0; Voice::SAMPLES_PER_BLOCK];
+        let history = decode_adpcm_block(&block, [100, 50], &mut output);
+
+        assert_eq!(
+            output,
+            [
+                155, 309, 413, 491, 547, 584, 605, 613, 610, 598, 579, 555, 527, 496, 463, 429,
+                395, 361, 328, 296, 265, 236, 209, 184, 161, 140, 121, 104
+            ]
+        );
+        assert_eq!(history, [104, 121]);
+    }
+
+    #[test]
+    fn reserved_shift_behaves_like_9() {
+        let mut block = [0x00; 16];
+        block[0] = 0x0d;
+        block[2] = 0x01;
+
+        let mut output = [0; Voice::SAMPLES_PER_BLOCK];
+        decode_adpcm_block(&block, [0; 2], &mut output);
+
+        assert_eq!(output[0], 0x1000 >> 9);
+    }
+
+    #[test]
+    fn loop_end_without_repeat_silences_the_voice() {
+        let mut ram = vec![0x00; 0x20];
+        ram[0x01] = 0x01;
+
+        let mut voice = Voice::default();
+        voice.write_u16(0x4, 0x1000);
+        voice.key_on(&ram);
+
+        let mut reached_end = false;
+        for _ in 0..Voice::SAMPLES_PER_BLOCK {
+            reached_end |= voice.tick(&ram).2;
+        }
+
+        assert!(reached_end);
+        assert_eq!(voice.adsr_phase, AdsrPhase::Off);
+        assert_eq!(voice.tick(&ram), (0, 0, false));
+    }
+}
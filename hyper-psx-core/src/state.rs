@@ -0,0 +1,53 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use crate::{bus::Bus, cpu::Cpu, dma::Dma, gpu::Gpu};
+
+use serde::{Deserialize, Serialize};
+
+/// The borrowed components written into a save state
+#[derive(Serialize)]
+pub(crate) struct SaveStateRef<'a> {
+    /// The CPU component
+    pub(crate) cpu: &'a Cpu,
+
+    /// The Bus component
+    pub(crate) bus: &'a Bus,
+
+    /// The DMA component
+    pub(crate) dma: &'a Dma,
+
+    /// The GPU component without its renderer
+    pub(crate) gpu: &'a Gpu,
+
+    /// The cycles `run_cycles` ran past its last budget
+    pub(crate) overrun_cycles: u64,
+
+    /// The VRAM, which is owned by the renderer
+    pub(crate) vram: Vec<u16>,
+}
+
+/// The components restored from a save state
+#[derive(Deserialize)]
+pub(crate) struct SaveState {
+    /// The CPU component
+    pub(crate) cpu: Cpu,
+
+    /// The Bus component
+    pub(crate) bus: Bus,
+
+    /// The DMA component
+    pub(crate) dma: Dma,
+
+    /// The GPU component without its renderer
+    pub(crate) gpu: Gpu,
+
+    /// The cycles `run_cycles` ran past its last budget
+    pub(crate) overrun_cycles: u64,
+
+    /// The VRAM, which is owned by the renderer
+    pub(crate) vram: Vec<u16>,
+}
@@ -0,0 +1,23 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use hyper_psx_core::Psx;
+
+use std::env;
+
+/// The variable holding the path to a real BIOS image
+const BIOS_VARIABLE: &str = "HYPER_PSX_BIOS";
+
+#[test]
+#[ignore = "needs a BIOS image, run with HYPER_PSX_BIOS=<path> cargo test -- --ignored"]
+fn bios_boots_to_shell() {
+    let bios_path = env::var(BIOS_VARIABLE)
+        .unwrap_or_else(|_| panic!("{} should point to a BIOS image", BIOS_VARIABLE));
+
+    let mut psx = Psx::new_headless(bios_path).unwrap();
+    assert!(psx.boot_to_shell(600));
+    assert!(!psx.take_tty_output().is_empty());
+}
@@ -0,0 +1,18 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use hyper_psx_core::Psx;
+
+/// Creates a PSX Emulator running a program from the start of the BIOS
+pub fn psx_with_program(program: &[u32]) -> Psx {
+    let mut bios = program
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .collect::<Vec<_>>();
+    bios.resize(512 * 1024, 0);
+
+    Psx::from_bios_bytes(bios).unwrap()
+}
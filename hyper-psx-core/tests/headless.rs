@@ -0,0 +1,88 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use hyper_psx_core::Psx;
+
+use std::{cell::RefCell, rc::Rc};
+
+/// Creates a headless PSX running the program from the reset vector
+fn psx(program: &[u32]) -> Psx {
+    let mut bios = vec![0; 512 * 1024];
+    for (index, word) in program.iter().enumerate() {
+        bios[index * 4..index * 4 + 4].copy_from_slice(&word.to_le_bytes());
+    }
+
+    Psx::from_bios_bytes(bios).unwrap()
+}
+
+#[test]
+fn run_headless_calls_back_once_per_frame() {
+    let mut psx = psx(&[
+        0x0bf00000, // j 0xbfc00000
+        0x00000000, // nop
+    ]);
+
+    let mut frames = 0;
+    psx.run_headless(3, |frame| {
+        assert!(!frame.is_empty());
+        frames += 1;
+    });
+
+    assert_eq!(frames, 3);
+}
+
+#[test]
+fn program_draws_through_the_bus_end_to_end() {
+    let mut psx = psx(&[
+        0x3c081f80, // lui $t0, 0x1f80
+        0x3c0d0300, // lui $t5, 0x0300
+        0xad0d1814, // sw $t5, 0x1814($t0)
+        0x3c0de300, // lui $t5, 0xe300
+        0xad0d1810, // sw $t5, 0x1810($t0)
+        0x3c0de403, // lui $t5, 0xe403
+        0x35adc3ff, // ori $t5, $t5, 0xc3ff
+        0xad0d1810, // sw $t5, 0x1810($t0)
+        0x3c093000, // lui $t1, 0x3000
+        0x352900ff, // ori $t1, $t1, 0x00ff
+        0x340a00ff, // ori $t2, $zero, 0x00ff
+        0x340b0040, // ori $t3, $zero, 0x0040
+        0x3c0c0040, // lui $t4, 0x0040
+        0xad091810, // sw $t1, 0x1810($t0)
+        0xad001810, // sw $zero, 0x1810($t0)
+        0xad0a1810, // sw $t2, 0x1810($t0)
+        0xad0b1810, // sw $t3, 0x1810($t0)
+        0xad0a1810, // sw $t2, 0x1810($t0)
+        0xad0c1810, // sw $t4, 0x1810($t0)
+        0x0bf00013, // j 0xbfc0004c
+        0x00000000, // nop
+    ]);
+    psx.run_headless(1, |_| {});
+
+    // The red shaded triangle covers the top left corner of the display area
+    let (width, _, pixels) = psx.capture_frame();
+    let index = (4 * width as usize + 4) * 4;
+    assert_eq!(pixels[index..index + 4], [0xff, 0x00, 0x00, 0xff]);
+}
+
+#[test]
+fn builder_hands_each_frame_to_on_frame() {
+    let frames = Rc::new(RefCell::new(Vec::new()));
+    let callback_frames = Rc::clone(&frames);
+
+    let mut psx = Psx::builder()
+        .bios_bytes(vec![0; 512 * 1024])
+        .on_frame(move |width, height, pixels| {
+            callback_frames
+                .borrow_mut()
+                .push((width, height, pixels.len()));
+        })
+        .build()
+        .unwrap();
+    psx.run_headless(2, |_| {});
+
+    // The display area after reset is 256x240
+    assert_eq!(*frames.borrow(), vec![(256, 240, 256 * 240 * 4); 2]);
+}
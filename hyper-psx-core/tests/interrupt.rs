@@ -0,0 +1,33 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+mod common;
+
+use common::psx_with_program;
+
+#[test]
+fn gpu_interrupt_is_raised_and_acknowledged() {
+    let mut psx = psx_with_program(&[
+        0x3c081f80, // lui $t0, 0x1f80
+        0x3c091f00, // lui $t1, 0x1f00
+        0xad091810, // sw $t1, 0x1810($t0) - GP0(1Fh)
+        0x00000000, // nop
+        0x8d0a1814, // lw $t2, 0x1814($t0) - GPUSTAT
+        0x8d0b1070, // lw $t3, 0x1070($t0) - I_STAT
+        0x3c090200, // lui $t1, 0x0200
+        0xad091814, // sw $t1, 0x1814($t0) - GP1(02h)
+        0x8d0c1814, // lw $t4, 0x1814($t0) - GPUSTAT
+        0x00000000, // nop
+        0x0bf0000a, // j 0xbfc00028
+        0x00000000, // nop
+    ]);
+    psx.run_cycles(100);
+
+    let registers = psx.cpu_registers();
+    assert_ne!(registers[10] & (1 << 24), 0, "GPUSTAT IRQ1 after GP0(1Fh)");
+    assert_ne!(registers[11] & (1 << 1), 0, "I_STAT IRQ1 after GP0(1Fh)");
+    assert_eq!(registers[12] & (1 << 24), 0, "GPUSTAT IRQ1 after GP1(02h)");
+}
@@ -0,0 +1,81 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+mod common;
+
+use common::psx_with_program;
+use hyper_psx_core::Psx;
+
+use std::{env, fs, process};
+
+/// Asserts the power-on values of the CPU
+fn assert_power_on(psx: &Psx) {
+    assert_eq!(psx.pc(), 0xbfc00000);
+    assert_eq!(psx.cpu_registers(), [0; 32]);
+    assert_eq!(psx.cop0_register(12), 0x00000000);
+    assert_eq!(psx.cop0_register(13), 0x00000000);
+    assert_eq!(psx.cop0_register(15), 0x00000002);
+}
+
+#[test]
+fn reset_restores_the_power_on_values() {
+    let mut psx = psx_with_program(&[
+        0x3c088000, // lui $t0, 0x8000
+        0x34091234, // ori $t1, $zero, 0x1234
+        0xad090100, // sw $t1, 0x100($t0)
+        0x0bf00003, // j 0xbfc0000c
+        0x00000000, // nop
+    ]);
+    psx.run_cycles(100);
+    assert_eq!(psx.cpu_registers()[9], 0x1234);
+
+    psx.reset_keeping_ram();
+    assert_power_on(&psx);
+    assert_eq!(psx.read_memory(0x80000100, 4), [0x34, 0x12, 0x00, 0x00]);
+
+    psx.reset();
+    assert_power_on(&psx);
+    assert_eq!(psx.read_memory(0x80000100, 4), [0x00; 4]);
+}
+
+#[test]
+fn reset_sideloads_the_exe_again() {
+    let text = [
+        0x340a0042, // ori $t2, $zero, 0x42
+        0x08004001, // j 0x80010004
+        0x00000000, // nop
+    ];
+
+    let mut exe = vec![0; 0x800];
+    exe[0x00..0x08].copy_from_slice(b"PS-X EXE");
+    exe[0x10..0x14].copy_from_slice(&0x80010000_u32.to_le_bytes());
+    exe[0x18..0x1c].copy_from_slice(&0x80010000_u32.to_le_bytes());
+    exe[0x1c..0x20].copy_from_slice(&0x800_u32.to_le_bytes());
+    exe.extend(text.iter().flat_map(|word: &u32| word.to_le_bytes()));
+    exe.resize(0x1000, 0);
+
+    let path = env::temp_dir().join(format!("hyper-psx-reset-{}.exe", process::id()));
+    fs::write(&path, exe).unwrap();
+
+    // The program jumps straight to the shell entry
+    let mut psx = psx_with_program(&[
+        0x3c088003, // lui $t0, 0x8003
+        0x01000008, // jr $t0
+        0x00000000, // nop
+    ]);
+    let loaded = psx.load_exe(&path);
+    fs::remove_file(&path).unwrap();
+    loaded.unwrap();
+
+    psx.run_cycles(100);
+    assert_eq!(psx.cpu_registers()[10], 0x42);
+
+    psx.reset();
+    assert_eq!(psx.cpu_registers()[10], 0x00);
+
+    psx.run_cycles(100);
+    assert_eq!(psx.cpu_registers()[10], 0x42);
+}
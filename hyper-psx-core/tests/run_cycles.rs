@@ -0,0 +1,47 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+mod common;
+
+use common::psx_with_program as psx;
+
+#[test]
+fn run_cycles_runs_an_exact_amount_of_instructions() {
+    let mut psx = psx(&[
+        0x25080001, // addiu $t0, $t0, 1
+        0x0bf00000, // j 0xbfc00000
+        0x00000000, // nop
+    ]);
+
+    // 100 loop iterations of 3 instructions with 2 cycles each
+    psx.run_cycles(600);
+
+    assert_eq!(psx.cpu_registers()[8], 100);
+    assert_eq!(psx.pc(), 0xbfc00000);
+}
+
+#[test]
+fn run_cycles_completes_dma_transfers_within_the_budget() {
+    let mut psx = psx(&[
+        0x3c081f80, // lui $t0, 0x1f80
+        0x3409100c, // ori $t1, $zero, 0x100c
+        0xad0910e0, // sw $t1, 0x10e0($t0) - D6_MADR
+        0x34090004, // ori $t1, $zero, 4
+        0xad0910e4, // sw $t1, 0x10e4($t0) - D6_BCR
+        0x3c091100, // lui $t1, 0x1100
+        0xad0910e8, // sw $t1, 0x10e8($t0) - D6_CHCR, start and trigger
+        0x3c0a8000, // lui $t2, 0x8000
+        0x8d4b100c, // lw $t3, 0x100c($t2)
+        0x00000000, // nop
+        0x0bf0000a, // j 0xbfc00028
+        0x00000000, // nop
+    ]);
+
+    psx.run_cycles(100);
+
+    // The ordering table entry points to the previous one
+    assert_eq!(psx.cpu_registers()[11], 0x00001008);
+}
@@ -0,0 +1,49 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use hyper_psx_core::Psx;
+
+/// Runs a number of odd cycle budgets, recording the program counter and registers after each
+fn trace(psx: &mut Psx, steps: usize) -> Vec<(u32, [u32; 32])> {
+    (0..steps)
+        .map(|_| {
+            psx.run_cycles(3);
+            (psx.pc(), psx.cpu_registers())
+        })
+        .collect()
+}
+
+#[test]
+fn load_state_replays_the_same_steps() {
+    let mut psx = Psx::from_bios_bytes(vec![0; 512 * 1024]).unwrap();
+
+    // An odd budget leaves the last instruction running past it
+    psx.run_cycles(3);
+
+    let state = psx.save_state();
+    let expected = trace(&mut psx, 1001);
+
+    psx.load_state(&state).unwrap();
+    let actual = trace(&mut psx, 1001);
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn load_state_restores_the_vram() {
+    let mut psx = Psx::from_bios_bytes(vec![0; 512 * 1024]).unwrap();
+
+    let vram = (0..1024 * 512)
+        .map(|index| index as u16)
+        .collect::<Vec<_>>();
+    psx.load_vram(&vram);
+    let state = psx.save_state();
+
+    psx.load_vram(&vec![0; 1024 * 512]);
+    psx.load_state(&state).unwrap();
+
+    assert_eq!(psx.dump_vram(), vram);
+}
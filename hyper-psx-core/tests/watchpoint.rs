@@ -0,0 +1,44 @@
+/*
+ * Copyright (c) 2023, SkillerRaptor
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+mod common;
+
+use common::psx_with_program;
+use hyper_psx_core::WatchKind;
+
+use std::{cell::RefCell, rc::Rc};
+
+#[test]
+fn write_watchpoint_matches_every_mirror() {
+    let mut psx = psx_with_program(&[
+        0x3c088020, // lui $t0, 0x8020
+        0x3c09a000, // lui $t1, 0xa000
+        0x340a0001, // ori $t2, $zero, 1
+        0xad0a0100, // sw $t2, 0x100($t0) - mirror of 0x100 behind 2MB RAM
+        0x340a0002, // ori $t2, $zero, 2
+        0xa52a0102, // sh $t2, 0x102($t1) - KSEG1
+        0xad0a0200, // sw $t2, 0x200($t0) - outside of the watched range
+        0x8d0b0100, // lw $t3, 0x100($t0) - loads are not watched
+        0x0bf00008, // j 0xbfc00020
+        0x00000000, // nop
+    ]);
+
+    let accesses = Rc::new(RefCell::new(Vec::new()));
+    let callback_accesses = Rc::clone(&accesses);
+    psx.set_watchpoint_callback(move |address, value, kind| {
+        callback_accesses.borrow_mut().push((address, value, kind));
+    });
+    psx.add_watchpoint(0x00000100, 4, false, true);
+    psx.run_cycles(100);
+
+    assert_eq!(
+        *accesses.borrow(),
+        [
+            (0x80200100, 1, WatchKind::Write),
+            (0xa0000102, 2, WatchKind::Write),
+        ]
+    );
+}
@@ -10,17 +10,19 @@ use chrono::Local;
 use color_eyre::Result;
 use fern::{
     colors::{Color, ColoredLevelConfig},
-    Dispatch,
+    Dispatch, FormatCallback,
 };
-use log::LevelFilter;
-use std::{fs::OpenOptions, io};
+use log::{LevelFilter, Record};
+use std::{fmt::Arguments, fs::OpenOptions, io};
 
 /// Initializes the global logger
 ///
 /// # Arguments:
 ///
 /// * `verbosity`: The verbosity the logger should operate on
-pub(crate) fn init(verbosity: usize, debug: Debug) -> Result<()> {
+/// * `debug`: The components whose debug logging is enabled
+/// * `debug_log_files`: If the debug logging of each component is also written into its own file
+pub(crate) fn init(verbosity: usize, debug: &[Debug], debug_log_files: bool) -> Result<()> {
     let mut logger = Dispatch::new();
 
     let color_logger = create_color_logger(verbosity, debug);
@@ -29,12 +31,51 @@ pub(crate) fn init(verbosity: usize, debug: Debug) -> Result<()> {
     let file_logger = create_file_logger(verbosity, debug)?;
     logger = logger.chain(file_logger);
 
+    if debug_log_files {
+        for &component in debug {
+            let target_logger = create_target_logger(component)?;
+            logger = logger.chain(target_logger);
+        }
+    }
+
     logger.apply()?;
 
     Ok(())
 }
 
-fn create_color_logger(verbosity: usize, debug: Debug) -> Dispatch {
+/// Returns the log target of a component
+///
+/// # Arguments:
+///
+/// * `debug`: The component
+fn target(debug: Debug) -> &'static str {
+    match debug {
+        Debug::Bios => "bios",
+        Debug::Bus => "bus",
+        Debug::Cdrom => "cdrom",
+        Debug::Cpu => "cpu",
+        Debug::Dma => "dma",
+        Debug::Gpu => "gpu",
+    }
+}
+
+/// Formats a record without colors for the log files
+fn format_plain(out: FormatCallback, message: &Arguments, record: &Record) {
+    let time = {
+        let current_time = Local::now().format("%Y-%m-%d %H:%M:%S");
+        format!("[{current_time}]")
+    };
+
+    let level = {
+        let current_level = record.level();
+        format!("{current_level:<5}")
+    };
+
+    let message = format!("{message}");
+    out.finish(format_args!("{time} {level} {message}",))
+}
+
+fn create_color_logger(verbosity: usize, debug: &[Debug]) -> Dispatch {
     let mut logger = Dispatch::new();
 
     let levels = ColoredLevelConfig::new()
@@ -77,36 +118,19 @@ fn create_color_logger(verbosity: usize, debug: Debug) -> Dispatch {
         _ => logger.level(LevelFilter::Error),
     };
 
-    logger = match debug {
-        Debug::None => logger,
-        Debug::Bus => logger.level_for("bus", LevelFilter::Debug),
-        Debug::Cpu => logger.level_for("cpu", LevelFilter::Debug),
-        Debug::Dma => logger.level_for("dma", LevelFilter::Debug),
-        Debug::Gpu => logger.level_for("gpu", LevelFilter::Debug),
-    };
+    for &component in debug {
+        logger = logger.level_for(target(component), LevelFilter::Debug);
+    }
 
     logger = logger.chain(io::stdout());
 
     logger
 }
 
-fn create_file_logger(verbosity: usize, debug: Debug) -> Result<Dispatch> {
+fn create_file_logger(verbosity: usize, debug: &[Debug]) -> Result<Dispatch> {
     let mut logger = Dispatch::new();
 
-    logger = logger.format(move |out, message, record| {
-        let time = {
-            let current_time = Local::now().format("%Y-%m-%d %H:%M:%S");
-            format!("[{current_time}]")
-        };
-
-        let level = {
-            let current_level = record.level();
-            format!("{current_level:<5}")
-        };
-
-        let message = format!("{message}");
-        out.finish(format_args!("{time} {level} {message}",))
-    });
+    logger = logger.format(format_plain);
 
     logger = logger.level_for("naga", LevelFilter::Off);
 
@@ -122,13 +146,9 @@ fn create_file_logger(verbosity: usize, debug: Debug) -> Result<Dispatch> {
         _ => logger.level(LevelFilter::Error),
     };
 
-    logger = match debug {
-        Debug::None => logger,
-        Debug::Bus => logger.level_for("bus", LevelFilter::Debug),
-        Debug::Cpu => logger.level_for("cpu", LevelFilter::Debug),
-        Debug::Dma => logger.level_for("dma", LevelFilter::Debug),
-        Debug::Gpu => logger.level_for("gpu", LevelFilter::Debug),
-    };
+    for &component in debug {
+        logger = logger.level_for(target(component), LevelFilter::Debug);
+    }
 
     logger = logger.chain(
         OpenOptions::new()
@@ -142,3 +162,25 @@ fn create_file_logger(verbosity: usize, debug: Debug) -> Result<Dispatch> {
 
     Ok(logger)
 }
+
+fn create_target_logger(debug: Debug) -> Result<Dispatch> {
+    let target = target(debug);
+
+    let mut logger = Dispatch::new();
+
+    logger = logger.format(format_plain);
+
+    logger = logger.level(LevelFilter::Off);
+
+    logger = logger.level_for(target, LevelFilter::Debug);
+
+    logger = logger.chain(
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(format!("{target}.log"))?,
+    );
+
+    Ok(logger)
+}
@@ -8,7 +8,7 @@
 
 mod logger;
 
-use hyper_psx_core::Psx;
+use hyper_psx_core::{Psx, RamSize, Region, RendererKind, Scaling};
 
 use clap::{Parser, ValueEnum};
 use color_eyre::Result;
@@ -23,11 +23,41 @@ enum Verbosity {
     Trace,
 }
 
-/// Debugger
+/// Installed RAM
+#[derive(Clone, Copy, ValueEnum)]
+enum Memory {
+    Retail,
+    DevKit,
+}
+
+/// Console Region
+#[derive(Clone, Copy, ValueEnum)]
+enum VideoRegion {
+    Ntsc,
+    Pal,
+}
+
+/// Window Scaling
+#[derive(Clone, Copy, ValueEnum)]
+enum WindowScaling {
+    Stretch,
+    Aspect,
+    Integer,
+}
+
+/// Rendering Backend
 #[derive(Clone, Copy, ValueEnum)]
+enum Renderer {
+    Software,
+    Hardware,
+}
+
+/// Debugger
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub(crate) enum Debug {
-    None,
+    Bios,
     Bus,
+    Cdrom,
     Cpu,
     Dma,
     Gpu,
@@ -44,9 +74,61 @@ struct Arguments {
     #[arg(long, default_value_t = String::from("./data/SCPH1001.BIN"))]
     bios_path: String,
 
-    /// Enable debug mode
-    #[arg(long, value_enum, default_value_t = Debug::None)]
-    debug: Debug,
+    /// Path to a CUE sheet or BIN disc image
+    #[arg(long)]
+    disc_path: Option<String>,
+
+    /// Path to the memory card file of slot 1, created if missing
+    #[arg(long = "memcard")]
+    memory_card_path: Option<String>,
+
+    /// Amount of installed RAM, 2MB for retail or 8MB for development units
+    #[arg(long, value_enum, default_value_t = Memory::Retail)]
+    memory: Memory,
+
+    /// Path to a PSX-EXE which is sideloaded after the BIOS intro
+    #[arg(long = "exe")]
+    exe_path: Option<String>,
+
+    /// Region of the console, NTSC runs at 59.94Hz and PAL at 50Hz
+    #[arg(long, value_enum, default_value_t = VideoRegion::Ntsc)]
+    region: VideoRegion,
+
+    /// Path to a file receiving each executed instruction with the registers it changed
+    #[arg(long)]
+    trace_file: Option<String>,
+
+    /// How the image is fitted into the window
+    #[arg(long, value_enum, default_value_t = WindowScaling::Aspect)]
+    scaling: WindowScaling,
+
+    /// Factor the primitives are rasterized at relative to the native resolution
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=8))]
+    scale: u32,
+
+    /// Backend drawing the GPU primitives
+    #[arg(long, value_enum, default_value_t = Renderer::Software)]
+    renderer: Renderer,
+
+    /// Disable the audio output
+    #[arg(long)]
+    mute: bool,
+
+    /// Disable the frame limiter
+    #[arg(long)]
+    uncapped: bool,
+
+    /// Skip the BIOS intro and boot the disc directly
+    #[arg(long)]
+    fast_boot: bool,
+
+    /// Enable debug logging for a comma-separated list of components
+    #[arg(long, value_enum, value_delimiter = ',')]
+    debug: Vec<Debug>,
+
+    /// Write the debug logging of each component into its own `<component>.log` file
+    #[arg(long)]
+    debug_log_files: bool,
 }
 
 fn main() -> Result<()> {
@@ -62,14 +144,56 @@ fn main() -> Result<()> {
     };
     let debug = arguments.debug;
 
-    logger::init(verbosity, debug)?;
+    logger::init(verbosity, &debug, arguments.debug_log_files)?;
 
     log::info!(" _     _ __   __  _____  _______  ______      _____  _______ _     _");
     log::info!(" |_____|   \\_/   |_____] |______ |_____/ ___ |_____] |______  \\___/ ");
     log::info!(" |     |    |    |       |______ |    \\_     |       ______| _/   \\_");
     log::info!("");
 
-    let mut psx = Psx::new(arguments.bios_path)?;
+    let ram_size = match arguments.memory {
+        Memory::Retail => RamSize::Retail,
+        Memory::DevKit => RamSize::DevKit,
+    };
+
+    let renderer_kind = match arguments.renderer {
+        Renderer::Software => RendererKind::Software,
+        Renderer::Hardware => RendererKind::Hardware,
+    };
+
+    let region = match arguments.region {
+        VideoRegion::Ntsc => Region::Ntsc,
+        VideoRegion::Pal => Region::Pal,
+    };
+
+    let mut psx = Psx::new(
+        arguments.bios_path,
+        arguments.disc_path,
+        arguments.memory_card_path,
+        ram_size,
+        renderer_kind,
+        region,
+    )?;
+    if let Some(exe_path) = arguments.exe_path {
+        psx.load_exe(exe_path)?;
+    }
+    if let Some(trace_file) = arguments.trace_file {
+        psx.set_trace_file(trace_file)?;
+    }
+
+    let scaling = match arguments.scaling {
+        WindowScaling::Stretch => Scaling::Stretch,
+        WindowScaling::Aspect => Scaling::Aspect,
+        WindowScaling::Integer => Scaling::Integer,
+    };
+
+    psx.set_scaling(scaling);
+    psx.set_internal_scale(arguments.scale);
+    psx.set_muted(arguments.mute);
+    psx.set_uncapped(arguments.uncapped);
+    psx.set_fast_boot(arguments.fast_boot);
+    psx.set_bios_call_logging(debug.contains(&Debug::Bios));
+
     psx.run();
 
     Ok(())